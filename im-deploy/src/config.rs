@@ -1,19 +1,94 @@
-use crate::constants::{openstack as os_constants, terraform as tf_constants};
+use crate::constants::{
+    dns as dns_constants, env_vars, files as file_constants, openstack as os_constants, terraform as tf_constants,
+};
+use crate::domain::cluster::IpFamily;
+use crate::domain::log_analysis::LogFilter;
+use crate::domain::monitor::{MonitorLogs, MonitorPhase};
 use crate::errors::{ConfigError, Result, TerraformError};
+use crate::hooks::Hooks;
+use crate::progress_events::EventSink;
+use crate::prompt::{AutoConfirm, Prompter, TerminalPrompter};
+use crate::runner::{CommandRunner, SystemRunner};
+use crate::terraform::{InitOptions, VersionConstraint};
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tracing::{debug, info};
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub terraform_dir: PathBuf,
     pub terraform_bin: String,
+    /// Pinned terraform/OpenTofu version constraint, e.g. ">= 1.6, < 2.0"
+    pub terraform_required_version: Option<VersionConstraint>,
+    /// Default timeout for a single terraform destroy run, overridable with `destroy --timeout`
+    pub destroy_timeout_mins: u64,
+    /// Address patterns of resources removed from state before destroy so they survive it
+    pub preserve_on_destroy: Vec<String>,
+    /// Flags for the automatic `terraform init`
+    pub terraform_init: InitOptions,
+    /// Value for TF_PLUGIN_CACHE_DIR so providers are downloaded once for all workspaces
+    pub plugin_cache_dir: Option<PathBuf>,
+    /// TF_VAR_<name> for every tfvar overridden from the environment, set on each terraform run
+    pub terraform_env: BTreeMap<String, String>,
     pub cluster_name: String,
+    /// Only kept to redact it from output; cloud-init logs echo the install command
+    pub k3s_token: Option<String>,
+    /// Private key matching `ssh_key_path` from terraform.tfvars
+    pub ssh_identity_file: Option<PathBuf>,
+    /// Address family used for dual-stack nodes
+    pub ip_family: IpFamily,
     pub tailscale: Option<TailscaleConfig>,
     pub openstack: Option<OpenStackConfig>,
     pub dry_run: bool,
+    /// Print terraform progress as newline-delimited JSON events when not attached to a terminal
+    pub json_output: bool,
+    /// Receives deploy and monitor progress events, from `--events-file`
+    pub events: EventSink,
+    /// Harmless error lines per remote log file name; "*" applies to every log
+    pub log_ignore_patterns: BTreeMap<String, Vec<String>>,
+    pub hooks: Hooks,
+    /// A record pointed at the API load balancer after deploy
+    pub dns: Option<DnsConfig>,
+    /// Log files and markers the monitor follows
+    pub monitor_logs: MonitorLogs,
+    /// Runs terraform, ssh and kubectl; replaced with a scripted runner in tests
+    pub runner: Arc<dyn CommandRunner>,
+    /// Answers questions raised while checking prerequisites, e.g. switching Tailscale accounts
+    pub prompter: Arc<dyn Prompter>,
+    /// `--manage-tailscale`: start Tailscale and switch its account without asking
+    pub manage_tailscale: bool,
+}
+
+impl Config {
+    /// Credentials that must never show up in output or logs
+    pub fn secrets(&self) -> Vec<&str> {
+        let mut secrets = Vec::new();
+        secrets.extend(self.k3s_token.as_deref());
+        secrets.extend(self.tailscale.as_ref().map(|ts| ts.api_key.as_str()));
+        secrets.extend(self.openstack.as_ref().map(|os| os.password.as_str()));
+        if let Some(DnsConfig {
+            provider: DnsProvider::Cloudflare { api_token, .. },
+            ..
+        }) = &self.dns
+        {
+            secrets.push(api_token);
+        }
+        secrets
+    }
+
+    /// Failure detection for one remote log, e.g. "k3s-server.log"
+    pub fn log_filter(&self, log_name: &str) -> LogFilter {
+        let ignore = ["*", log_name]
+            .iter()
+            .filter_map(|key| self.log_ignore_patterns.get(*key))
+            .flatten()
+            .cloned()
+            .collect();
+        LogFilter::new(ignore)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -21,6 +96,13 @@ pub struct TailscaleConfig {
     pub api_key: String,
     pub tailnet: String,
     pub account_name: String,
+    /// Connect to tailnet nodes with `tailscale ssh` instead of OpenSSH and the cluster key
+    pub ssh: bool,
+    /// Turn off node key expiry of the cluster's devices once they joined
+    pub disable_key_expiry: bool,
+    /// Nodes are named `<prefix>-server-N` and `<prefix>-agent-N`: the
+    /// `tailscale_hostname_prefix` tfvar, or the module's cluster name `<cluster>-openstack`
+    pub hostname_prefix: String,
 }
 
 #[derive(Debug, Clone)]
@@ -29,9 +111,35 @@ pub struct OpenStackConfig {
     pub username: String,
     pub password: String,
     pub project_name: String,
+    pub user_domain: String,
+    pub project_domain: String,
     pub region: String,
+    /// Service catalog interface the endpoints are picked from
+    pub interface: String,
     pub cacert_file: Option<String>,
     pub insecure: bool,
+    /// Flavors set in terraform.tfvars, by variable name
+    pub flavors: BTreeMap<String, String>,
+    /// External network terraform allocates floating IPs from
+    pub floating_ip_pool: String,
+    /// Floating IPs terraform allocates: one each for the bastion and the API load balancer,
+    /// and one per node with enable_node_floating_ips
+    pub floating_ips_needed: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct DnsConfig {
+    /// Fully qualified record name, e.g. k3s.example.com
+    pub name: String,
+    pub ttl: u32,
+    pub provider: DnsProvider,
+}
+
+#[derive(Debug, Clone)]
+pub enum DnsProvider {
+    Cloudflare { zone_id: String, api_token: String },
+    /// Dynamic update through nsupdate
+    Rfc2136 { server: String, zone: Option<String>, key_file: Option<PathBuf> },
 }
 
 impl TailscaleConfig {
@@ -46,16 +154,372 @@ impl TailscaleConfig {
 #[derive(Debug, Deserialize)]
 struct TerraformVars {
     cluster_name: Option<String>,
+    k3s_token: Option<String>,
+    ssh_key_path: Option<String>,
     user_name: Option<String>,
     user_password: Option<String>,
     tenant_name: Option<String>,
     openstack_auth_url: Option<String>,
     openstack_region: Option<String>,
+    openstack_user_domain: Option<String>,
+    openstack_project_domain: Option<String>,
     openstack_cacert_file: Option<String>,
     openstack_insecure: Option<bool>,
+    openstack_server_flavor: Option<String>,
+    openstack_agent_flavor: Option<String>,
+    openstack_bastion_flavor: Option<String>,
+    openstack_floating_ip_pool: Option<String>,
+    openstack_server_count: Option<u32>,
+    openstack_agent_count: Option<u32>,
+    enable_bastion: Option<bool>,
+    enable_load_balancer: Option<bool>,
+    enable_node_floating_ips: Option<bool>,
     enable_tailscale: Option<bool>,
     tailscale_api_key: Option<String>,
     tailscale_tailnet: Option<String>,
+    tailscale_hostname_prefix: Option<String>,
+}
+
+/// Optional tool settings read from im-deploy.toml
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    terraform_dir: Option<PathBuf>,
+    terraform_bin: Option<String>,
+    terraform_required_version: Option<String>,
+    destroy_timeout_minutes: Option<u64>,
+    /// Replaces the default list, the Longhorn backup container; `*` matches any text
+    preserve_on_destroy: Option<Vec<String>>,
+    /// Pass -upgrade whenever terraform init runs
+    init_upgrade: Option<bool>,
+    plugin_cache_dir: Option<PathBuf>,
+    /// "ipv4" or "ipv6"
+    ip_family: Option<IpFamily>,
+    /// Use `tailscale ssh` for nodes on the tailnet
+    tailscale_ssh: Option<bool>,
+    /// Keep cluster nodes on the tailnet past the node key expiry (on by default)
+    tailscale_disable_key_expiry: Option<bool>,
+    #[serde(default)]
+    log_ignore_patterns: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    hooks: Hooks,
+    dns: Option<FileDnsConfig>,
+    #[serde(default)]
+    monitor: FileMonitorConfig,
+}
+
+/// `[dns]` table of im-deploy.toml
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileDnsConfig {
+    name: String,
+    /// "cloudflare" or "rfc2136"
+    provider: String,
+    ttl: Option<u32>,
+    cloudflare_zone_id: Option<String>,
+    rfc2136_server: Option<String>,
+    rfc2136_zone: Option<String>,
+    rfc2136_key_file: Option<PathBuf>,
+}
+
+impl FileDnsConfig {
+    fn resolve(self) -> Result<DnsConfig> {
+        let provider = match self.provider.as_str() {
+            "cloudflare" => DnsProvider::Cloudflare {
+                zone_id: self
+                    .cloudflare_zone_id
+                    .ok_or_else(|| ConfigError::MissingField("dns.cloudflare_zone_id".to_string()))?,
+                api_token: env_override(env_vars::CLOUDFLARE_API_TOKEN)
+                    .ok_or_else(|| ConfigError::MissingField(env_vars::CLOUDFLARE_API_TOKEN.to_string()))?,
+            },
+            "rfc2136" => DnsProvider::Rfc2136 {
+                server: self
+                    .rfc2136_server
+                    .ok_or_else(|| ConfigError::MissingField("dns.rfc2136_server".to_string()))?,
+                zone: self.rfc2136_zone,
+                key_file: self.rfc2136_key_file,
+            },
+            other => {
+                return Err(ConfigError::InvalidValue {
+                    field: "dns.provider".to_string(),
+                    reason: format!("expected 'cloudflare' or 'rfc2136', got '{}'", other),
+                }
+                .into());
+            }
+        };
+
+        Ok(DnsConfig {
+            name: self.name,
+            ttl: self.ttl.unwrap_or(dns_constants::DEFAULT_TTL),
+            provider,
+        })
+    }
+}
+
+/// `[monitor]` table of im-deploy.toml, for templates that log elsewhere than the bundled ones
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileMonitorConfig {
+    server_log: Option<String>,
+    /// Keyed by phase name, e.g. `[monitor.phases.gpu]`; unset fields keep the defaults
+    #[serde(default)]
+    phases: BTreeMap<String, FileLogPhase>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileLogPhase {
+    log_file: Option<String>,
+    start_marker: Option<String>,
+    success_marker: Option<String>,
+    summary_marker: Option<String>,
+}
+
+impl FileMonitorConfig {
+    fn resolve(self) -> Result<MonitorLogs> {
+        let mut logs = MonitorLogs::default();
+        if let Some(server_log) = self.server_log {
+            logs.server_log = server_log;
+        }
+        for (name, file_phase) in self.phases {
+            let field = format!("monitor.phases.{}", name);
+            let phase: MonitorPhase = name
+                .parse()
+                .map_err(|reason| ConfigError::InvalidValue { field: field.clone(), reason })?;
+            let Some(log_phase) = logs.phases.get_mut(&phase) else {
+                return Err(ConfigError::InvalidValue {
+                    field,
+                    reason: format!("the {} phase is not followed through a log file", phase),
+                }
+                .into());
+            };
+            if let Some(log_file) = file_phase.log_file {
+                log_phase.log_file = log_file;
+            }
+            if let Some(start_marker) = file_phase.start_marker {
+                log_phase.start_marker = start_marker;
+            }
+            if let Some(success_marker) = file_phase.success_marker {
+                log_phase.success_marker = success_marker;
+            }
+            if let Some(summary_marker) = file_phase.summary_marker {
+                log_phase.summary_marker = Some(summary_marker).filter(|marker| !marker.is_empty());
+            }
+        }
+        Ok(logs)
+    }
+}
+
+/// Values passed on the command line, taking precedence over environment and config file
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    pub terraform_dir: Option<PathBuf>,
+    pub terraform_bin: Option<String>,
+    pub json_output: bool,
+    pub events_file: Option<PathBuf>,
+    /// `--yes`: confirm prompts without asking
+    pub auto_confirm: bool,
+    /// `--manage-tailscale`
+    pub manage_tailscale: bool,
+}
+
+/// Locate im-deploy.toml in the current directory or its parent
+fn find_config_file() -> Result<Option<PathBuf>> {
+    let current_dir = std::env::current_dir()?;
+
+    let candidates = std::iter::once(current_dir.as_path()).chain(current_dir.parent());
+    for dir in candidates {
+        let path = dir.join(file_constants::CONFIG_FILE);
+        if path.is_file() {
+            debug!("Found config file at {:?}", path);
+            return Ok(Some(path));
+        }
+    }
+
+    Ok(None)
+}
+
+fn load_file_config() -> Result<FileConfig> {
+    let Some(path) = find_config_file()? else {
+        return Ok(FileConfig::default());
+    };
+
+    let content = fs::read_to_string(&path)?;
+    let mut file_config: FileConfig = toml::from_str(&content).map_err(|e| ConfigError::InvalidValue {
+        field: path.display().to_string(),
+        reason: e.to_string(),
+    })?;
+
+    // Relative paths in the config file are relative to the file itself
+    if let Some(base) = path.parent() {
+        file_config.hooks.working_dir = Some(base.to_path_buf());
+        let dns_key_file = file_config.dns.as_mut().and_then(|dns| dns.rfc2136_key_file.as_mut());
+        for dir in [file_config.terraform_dir.as_mut(), file_config.plugin_cache_dir.as_mut(), dns_key_file]
+            .into_iter()
+            .flatten()
+        {
+            if dir.is_relative() {
+                *dir = base.join(&*dir);
+            }
+        }
+        if let Some(bin) = file_config.terraform_bin.as_mut() {
+            *bin = absolute_binary_path(bin, base);
+        }
+    }
+
+    Ok(file_config)
+}
+
+/// Parse the version pin from the environment or im-deploy.toml, in that order
+fn resolve_required_version(file: Option<&str>) -> Result<Option<VersionConstraint>> {
+    let (field, raw) = match env_override(env_vars::TERRAFORM_REQUIRED_VERSION) {
+        Some(raw) => (env_vars::TERRAFORM_REQUIRED_VERSION, raw),
+        None => match file {
+            Some(raw) => ("terraform_required_version", raw.to_string()),
+            None => return Ok(None),
+        },
+    };
+
+    VersionConstraint::parse(&raw)
+        .map(Some)
+        .map_err(|reason| ConfigError::InvalidValue { field: field.to_string(), reason }.into())
+}
+
+/// Resolve the provider plugin cache: TF_PLUGIN_CACHE_DIR, config file, then ~/.terraform.d/plugin-cache
+fn resolve_plugin_cache_dir(file: Option<&PathBuf>) -> Option<PathBuf> {
+    env_override(env_vars::TF_PLUGIN_CACHE_DIR)
+        .map(PathBuf::from)
+        .or_else(|| file.cloned())
+        .or_else(|| env_override("HOME").map(|home| PathBuf::from(home).join(tf_constants::PLUGIN_CACHE_DIR)))
+}
+
+impl TerraformVars {
+    /// Apply environment variable overrides on top of the values parsed from terraform.tfvars,
+    /// so CI can inject credentials without writing them into the checkout. Returns the
+    /// TF_VAR_<name> variables that hand the same values to terraform.
+    fn apply_env_overrides(&mut self) -> Result<BTreeMap<String, String>> {
+        let mut terraform_env = BTreeMap::new();
+        let mut export = |tfvar: &str, value: Option<String>| {
+            if let Some(value) = value {
+                terraform_env.insert(format!("TF_VAR_{}", tfvar), value);
+            }
+        };
+        export("cluster_name", override_string(&mut self.cluster_name, env_vars::CLUSTER_NAME));
+        export("openstack_auth_url", override_string(&mut self.openstack_auth_url, env_vars::OS_AUTH_URL));
+        export("user_name", override_string(&mut self.user_name, env_vars::OS_USERNAME));
+        export("user_password", override_string(&mut self.user_password, env_vars::OS_PASSWORD));
+        export("tenant_name", override_string(&mut self.tenant_name, env_vars::OS_PROJECT_NAME));
+        export("openstack_region", override_string(&mut self.openstack_region, env_vars::OS_REGION_NAME));
+        export(
+            "openstack_user_domain",
+            override_string(&mut self.openstack_user_domain, env_vars::OS_USER_DOMAIN_NAME),
+        );
+        export(
+            "openstack_project_domain",
+            override_string(&mut self.openstack_project_domain, env_vars::OS_PROJECT_DOMAIN_NAME),
+        );
+        export("openstack_cacert_file", override_string(&mut self.openstack_cacert_file, env_vars::OS_CACERT));
+        export(
+            "openstack_insecure",
+            override_bool(&mut self.openstack_insecure, env_vars::OS_INSECURE)?.map(|v| v.to_string()),
+        );
+        export(
+            "enable_tailscale",
+            override_bool(&mut self.enable_tailscale, env_vars::ENABLE_TAILSCALE)?.map(|v| v.to_string()),
+        );
+        export("tailscale_api_key", override_string(&mut self.tailscale_api_key, env_vars::TS_API_KEY));
+        export("tailscale_tailnet", override_string(&mut self.tailscale_tailnet, env_vars::TS_TAILNET));
+        Ok(terraform_env)
+    }
+}
+
+/// Set a string variable in terraform.tfvars, keeping the rest of the file as it is
+pub fn set_tfvar(terraform_dir: &Path, name: &str, value: &str) -> Result<()> {
+    let path = terraform_dir.join(tf_constants::TFVARS_FILE);
+    let content = fs::read_to_string(&path)?;
+    // JSON string escapes are valid in both HCL and TOML
+    let assignment = format!("{} = {}", name, serde_json::Value::from(value));
+
+    let mut replaced = false;
+    let mut lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            let assigns = line
+                .trim_start()
+                .strip_prefix(name)
+                .is_some_and(|rest| rest.trim_start().starts_with('='));
+            if assigns && !replaced {
+                replaced = true;
+                assignment.clone()
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    if !replaced {
+        lines.push(assignment);
+    }
+    fs::write(&path, lines.join("\n") + "\n")?;
+    Ok(())
+}
+
+/// Event stream of `--events-file`; fails early so a typo doesn't go unnoticed until the end
+fn open_event_sink(path: Option<&Path>) -> Result<EventSink> {
+    let Some(path) = path else {
+        return Ok(EventSink::default());
+    };
+    EventSink::open(path).map_err(|e| {
+        ConfigError::InvalidValue {
+            field: "--events-file".to_string(),
+            reason: format!("{}: {}", path.display(), e),
+        }
+        .into()
+    })
+}
+
+/// Private key for the public key terraform installs on the servers
+fn resolve_identity_file(ssh_key_path: Option<&str>) -> Option<PathBuf> {
+    let public_key = match ssh_key_path?.strip_prefix("~/") {
+        Some(rest) => PathBuf::from(env_override("HOME")?).join(rest),
+        None => PathBuf::from(ssh_key_path?),
+    };
+    public_key
+        .extension()
+        .is_some_and(|ext| ext == "pub")
+        .then(|| public_key.with_extension(""))
+}
+
+/// Read an environment variable, treating empty values as unset
+fn env_override(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// Replace `field` with the environment variable `name` when it is set; returns the new value
+fn override_string(field: &mut Option<String>, name: &str) -> Option<String> {
+    let value = env_override(name)?;
+    debug!("Using {} from environment", name);
+    *field = Some(value.clone());
+    Some(value)
+}
+
+fn override_bool(field: &mut Option<bool>, name: &str) -> Result<Option<bool>> {
+    if let Some(value) = env_override(name) {
+        debug!("Using {} from environment", name);
+        let parsed = match value.to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => true,
+            "0" | "false" | "no" | "off" => false,
+            _ => {
+                return Err(ConfigError::InvalidValue {
+                    field: name.to_string(),
+                    reason: format!("expected a boolean, got '{}'", value),
+                }
+                .into());
+            }
+        };
+        *field = Some(parsed);
+        return Ok(Some(parsed));
+    }
+    Ok(None)
 }
 
 pub fn detect_terraform_dir() -> Result<PathBuf> {
@@ -80,27 +544,80 @@ pub fn detect_terraform_dir() -> Result<PathBuf> {
     Err(ConfigError::TerraformDirNotFound.into())
 }
 
+/// Resolve the terraform directory: CLI flag, environment, config file, then auto-detection
+fn resolve_terraform_dir(cli: Option<&PathBuf>, file: Option<&PathBuf>) -> Result<PathBuf> {
+    let explicit = cli
+        .cloned()
+        .or_else(|| env_override(env_vars::TERRAFORM_DIR).map(PathBuf::from))
+        .or_else(|| file.cloned());
+
+    match explicit {
+        Some(dir) => {
+            if !dir.join(tf_constants::MAIN_TF_FILE).exists() {
+                return Err(TerraformError::DirectoryNotFound(dir).into());
+            }
+            debug!("Using configured terraform directory: {:?}", dir);
+            Ok(dir)
+        }
+        None => detect_terraform_dir(),
+    }
+}
+
+/// Resolve the terraform binary: CLI flag, environment, config file, then tofu/terraform on PATH
+fn resolve_terraform_binary(cli: Option<&str>, file: Option<&str>) -> Result<String> {
+    let explicit = cli
+        .map(|s| s.to_string())
+        .or_else(|| env_override(env_vars::TERRAFORM_BIN))
+        .or_else(|| file.map(|s| s.to_string()));
+
+    match explicit {
+        Some(bin) => {
+            if !binary_exists(&bin) {
+                return Err(ConfigError::InvalidValue {
+                    field: "terraform_bin".to_string(),
+                    reason: format!("'{}' not found or not a file", bin),
+                }
+                .into());
+            }
+            let bin = absolute_binary_path(&bin, &std::env::current_dir()?);
+            debug!("Using configured terraform binary: {}", bin);
+            Ok(bin)
+        }
+        None => find_terraform_binary(),
+    }
+}
+
+/// Terraform runs in the terraform directory, so a relative path like `./bin/tofu` is
+/// resolved against `base` first: the working directory for the flag and the environment,
+/// the file's directory for im-deploy.toml. Names looked up on PATH are kept as they are.
+fn absolute_binary_path(bin: &str, base: &Path) -> String {
+    if bin.contains(['/', std::path::MAIN_SEPARATOR]) && Path::new(bin).is_relative() {
+        base.join(bin).display().to_string()
+    } else {
+        bin.to_string()
+    }
+}
+
+/// Check a binary given either as a path or as a name on PATH
+fn binary_exists(bin: &str) -> bool {
+    if bin.contains(['/', std::path::MAIN_SEPARATOR]) {
+        return PathBuf::from(bin).is_file();
+    }
+
+    which::which(bin).is_ok()
+}
+
 pub fn find_terraform_binary() -> Result<String> {
     debug!("Looking for terraform/tofu binary");
 
     // Try tofu first
-    if Command::new("which")
-        .arg("tofu")
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
-    {
+    if which::which("tofu").is_ok() {
         debug!("Using tofu binary");
         return Ok("tofu".to_string());
     }
 
     // Fallback to terraform
-    if Command::new("which")
-        .arg("terraform")
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
-    {
+    if which::which("terraform").is_ok() {
         debug!("Using terraform binary");
         return Ok("terraform".to_string());
     }
@@ -109,18 +626,33 @@ pub fn find_terraform_binary() -> Result<String> {
 }
 
 pub fn load_config(dry_run: bool) -> Result<Config> {
+    load_config_with_overrides(dry_run, &ConfigOverrides::default())
+}
+
+pub fn load_config_with_overrides(dry_run: bool, overrides: &ConfigOverrides) -> Result<Config> {
     debug!("Loading configuration");
 
-    let terraform_dir = detect_terraform_dir()?;
-    let terraform_bin = find_terraform_binary()?;
+    let file_config = load_file_config()?;
+
+    let terraform_dir = resolve_terraform_dir(
+        overrides.terraform_dir.as_ref(),
+        file_config.terraform_dir.as_ref(),
+    )?;
+    let terraform_bin = resolve_terraform_binary(
+        overrides.terraform_bin.as_deref(),
+        file_config.terraform_bin.as_deref(),
+    )?;
+    let terraform_required_version =
+        resolve_required_version(file_config.terraform_required_version.as_deref())?;
 
     // Parse terraform.tfvars
     let tfvars_path = terraform_dir.join(tf_constants::TFVARS_FILE);
     let tfvars_content = fs::read_to_string(&tfvars_path)
         .map_err(|e| ConfigError::TfVarsParseFailed(format!("Could not read {}: {}", tfvars_path.display(), e)))?;
 
-    let vars: TerraformVars = toml::from_str(&tfvars_content)
+    let mut vars: TerraformVars = toml::from_str(&tfvars_content)
         .map_err(|e| ConfigError::TfVarsParseFailed(e.to_string()))?;
+    let terraform_env = vars.apply_env_overrides()?;
 
     let cluster_name = vars.cluster_name
         .unwrap_or_else(|| "k3s-multicloud".to_string());
@@ -141,6 +673,12 @@ pub fn load_config(dry_run: bool) -> Result<Config> {
             api_key,
             tailnet,
             account_name,
+            ssh: file_config.tailscale_ssh.unwrap_or(false),
+            disable_key_expiry: file_config.tailscale_disable_key_expiry.unwrap_or(true),
+            hostname_prefix: vars
+                .tailscale_hostname_prefix
+                .filter(|prefix| !prefix.is_empty())
+                .unwrap_or_else(|| format!("{}-openstack", cluster_name)),
         })
     } else {
         debug!("Tailscale disabled");
@@ -150,6 +688,14 @@ pub fn load_config(dry_run: bool) -> Result<Config> {
     // Build OpenStack config
     let openstack = if vars.user_name.is_some() && vars.user_password.is_some() {
         debug!("OpenStack credentials found");
+        let flavors = [
+            ("openstack_server_flavor", vars.openstack_server_flavor),
+            ("openstack_agent_flavor", vars.openstack_agent_flavor),
+            ("openstack_bastion_flavor", vars.openstack_bastion_flavor),
+        ]
+        .into_iter()
+        .filter_map(|(var, flavor)| Some((var.to_string(), flavor?)))
+        .collect();
         Some(OpenStackConfig {
             auth_url: vars.openstack_auth_url
                 .unwrap_or_else(|| os_constants::DEFAULT_AUTH_URL.to_string()),
@@ -159,10 +705,29 @@ pub fn load_config(dry_run: bool) -> Result<Config> {
                 .ok_or_else(|| ConfigError::MissingField("user_password".to_string()))?,
             project_name: vars.tenant_name
                 .ok_or_else(|| ConfigError::MissingField("tenant_name".to_string()))?,
+            user_domain: vars.openstack_user_domain
+                .unwrap_or_else(|| os_constants::DEFAULT_DOMAIN.to_string()),
+            project_domain: vars.openstack_project_domain
+                .unwrap_or_else(|| os_constants::DEFAULT_DOMAIN.to_string()),
             region: vars.openstack_region
                 .unwrap_or_else(|| os_constants::DEFAULT_REGION.to_string()),
+            interface: env_override(env_vars::OS_INTERFACE)
+                .unwrap_or_else(|| os_constants::DEFAULT_INTERFACE.to_string()),
             cacert_file: vars.openstack_cacert_file,
             insecure: vars.openstack_insecure.unwrap_or(true),
+            flavors,
+            floating_ip_pool: vars.openstack_floating_ip_pool
+                .unwrap_or_else(|| os_constants::DEFAULT_FLOATING_IP_POOL.to_string()),
+            floating_ips_needed: [vars.enable_bastion, vars.enable_load_balancer]
+                .into_iter()
+                .filter(|enabled| enabled.unwrap_or(true))
+                .count() as u32
+                + if vars.enable_node_floating_ips.unwrap_or(false) {
+                    vars.openstack_server_count.unwrap_or(os_constants::DEFAULT_SERVER_COUNT)
+                        + vars.openstack_agent_count.unwrap_or(os_constants::DEFAULT_AGENT_COUNT)
+                } else {
+                    0
+                },
         })
     } else {
         debug!("OpenStack credentials not found");
@@ -176,10 +741,35 @@ pub fn load_config(dry_run: bool) -> Result<Config> {
     Ok(Config {
         terraform_dir,
         terraform_bin,
+        terraform_required_version,
+        destroy_timeout_mins: file_config
+            .destroy_timeout_minutes
+            .unwrap_or(tf_constants::DESTROY_TIMEOUT_MINS),
+        preserve_on_destroy: file_config
+            .preserve_on_destroy
+            .unwrap_or_else(|| tf_constants::PRESERVE_ON_DESTROY.map(str::to_string).to_vec()),
+        terraform_init: InitOptions {
+            upgrade: file_config.init_upgrade.unwrap_or(false),
+            ..Default::default()
+        },
+        plugin_cache_dir: resolve_plugin_cache_dir(file_config.plugin_cache_dir.as_ref()),
+        terraform_env,
         cluster_name,
+        k3s_token: vars.k3s_token,
+        ssh_identity_file: resolve_identity_file(vars.ssh_key_path.as_deref()),
+        ip_family: file_config.ip_family.unwrap_or_default(),
         tailscale,
         openstack,
         dry_run,
+        json_output: overrides.json_output,
+        events: open_event_sink(overrides.events_file.as_deref())?,
+        log_ignore_patterns: file_config.log_ignore_patterns,
+        hooks: file_config.hooks,
+        dns: file_config.dns.map(FileDnsConfig::resolve).transpose()?,
+        monitor_logs: file_config.monitor.resolve()?,
+        runner: Arc::new(SystemRunner),
+        prompter: if overrides.auto_confirm { Arc::new(AutoConfirm) } else { Arc::new(TerminalPrompter) },
+        manage_tailscale: overrides.manage_tailscale,
     })
 }
 
@@ -202,6 +792,38 @@ mod tests {
         assert_eq!(account3, "plain-name");
     }
 
+    #[test]
+    fn test_resolve_identity_file() {
+        assert_eq!(
+            resolve_identity_file(Some("/keys/id_ed25519.pub")),
+            Some(PathBuf::from("/keys/id_ed25519"))
+        );
+        assert_eq!(resolve_identity_file(Some("/keys/authorized")), None);
+        assert_eq!(resolve_identity_file(None), None);
+    }
+
+    #[test]
+    fn test_set_tfvar_keeps_other_lines() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(tf_constants::TFVARS_FILE);
+        fs::write(&path, "# SSH\nssh_key_path = \"~/.ssh/id_ed25519.pub\"\nssh_key_path_backup = \"x\"\n").unwrap();
+
+        set_tfvar(dir.path(), "ssh_key_path", "/keys/new \"key\".pub").unwrap();
+        set_tfvar(dir.path(), "cluster_name", "demo").unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "# SSH\nssh_key_path = \"/keys/new \\\"key\\\".pub\"\nssh_key_path_backup = \"x\"\ncluster_name = \"demo\"\n"
+        );
+    }
+
+    #[test]
+    fn test_absolute_binary_path() {
+        let cwd = Path::new("/work/cluster");
+        assert_eq!(absolute_binary_path("bin/tofu", cwd), "/work/cluster/bin/tofu");
+        assert_eq!(absolute_binary_path("/usr/bin/tofu", cwd), "/usr/bin/tofu");
+        assert_eq!(absolute_binary_path("tofu", cwd), "tofu");
+    }
+
     #[test]
     fn test_find_terraform_binary() {
         // Result depends on what's installed, so we just check if it doesn't panic
@@ -3,6 +3,17 @@ pub mod ssh {
     pub const SSH_PORT: u16 = 22;
     pub const SSH_USER: &str = "ubuntu";
     pub const SSH_STRICT_HOST_KEY_CHECKING: &str = "StrictHostKeyChecking=no";
+    /// Reachability probe before the first command: TCP and SSH connect timeout
+    pub const PROBE_CONNECT_TIMEOUT_SECS: u64 = 10;
+    pub const PROBE_MAX_ATTEMPTS: u32 = 8;
+    pub const PROBE_INITIAL_DELAY_SECS: u64 = 2;
+    pub const PROBE_MAX_DELAY_SECS: u64 = 30;
+    /// Exit code of the ssh client when it cannot connect, as opposed to the remote command's
+    pub const CONNECTION_FAILED_EXIT_CODE: i32 = 255;
+    /// Exported host entries, relative to the home directory
+    pub const CONFIG_DIR: &str = ".ssh/config.d";
+    /// Local port of the SOCKS proxy opened by `im-deploy tunnel`
+    pub const TUNNEL_SOCKS_PORT: u16 = 1080;
 }
 
 /// Network timeouts and retry settings
@@ -18,27 +29,226 @@ pub mod network {
 pub mod openstack {
     pub const DEFAULT_AUTH_URL: &str = "https://private-cloud.informatik.hs-fulda.de:5000/v3";
     pub const DEFAULT_REGION: &str = "RegionOne";
+    /// Keystone domain of the user and project unless tfvars name another
     pub const DEFAULT_DOMAIN: &str = "Default";
+    /// Catalog endpoint interface used unless OS_INTERFACE says otherwise
+    pub const DEFAULT_INTERFACE: &str = "public";
+    /// Default of the openstack_floating_ip_pool variable
+    pub const DEFAULT_FLOATING_IP_POOL: &str = "ext_net";
+    /// Defaults of the openstack_server_count and openstack_agent_count variables
+    pub const DEFAULT_SERVER_COUNT: u32 = 3;
+    pub const DEFAULT_AGENT_COUNT: u32 = 3;
     pub const LOADBALANCER_DELETION_TIMEOUT_SECS: u64 = 120;
     pub const LOADBALANCER_POLL_INTERVAL_SECS: u64 = 5;
+    /// Amphora failover rebuilds the VM, which takes a few minutes
+    pub const LOADBALANCER_FAILOVER_TIMEOUT_SECS: u64 = 300;
+    /// Key of the `cluster=<name>` tag terraform sets on Neutron and Octavia resources
+    pub const CLUSTER_TAG_KEY: &str = "cluster";
+    /// Snapshots of each node `snapshot --prune` keeps unless --keep says otherwise
+    pub const SNAPSHOT_KEEP: usize = 3;
+}
+
+/// DNS record for the API endpoint
+pub mod dns {
+    pub const CLOUDFLARE_API_URL: &str = "https://api.cloudflare.com/client/v4";
+    pub const DEFAULT_TTL: u32 = 300;
+}
+
+/// Tailscale client constants
+pub mod tailscale {
+    /// CLI inside the macOS app bundle, which does not put `tailscale` on PATH
+    pub const MACOS_APP_CLI: &str = "/Applications/Tailscale.app/Contents/MacOS/Tailscale";
+    /// Wait for the Running state after `tailscale up`, e.g. while the login is approved
+    pub const UP_TIMEOUT_SECS: u64 = 300;
+    pub const UP_POLL_INTERVAL_SECS: u64 = 2;
+    /// Pause between device deletes so large clusters stay under the API rate limit
+    pub const DELETE_INTERVAL_MILLIS: u64 = 250;
+    /// Re-lists after deleting, for deletes the API has not caught up with yet
+    pub const DELETE_CHECKS: u32 = 3;
+    pub const DELETE_CHECK_INTERVAL_SECS: u64 = 2;
+    /// Retries of a rate limited (HTTP 429) request
+    pub const RATE_LIMIT_RETRIES: u32 = 5;
+    /// Wait after a 429 without Retry-After, doubled on each retry
+    pub const RATE_LIMIT_BACKOFF_SECS: u64 = 2;
+    /// Longest wait honoured from Retry-After
+    pub const RATE_LIMIT_MAX_WAIT_SECS: u64 = 60;
 }
 
 /// Kubernetes API endpoint constants
 pub mod kubernetes {
     pub const API_SERVER_PORT: u16 = 6443;
+    /// CA that signs the API server certificate, as embedded in the k3s kubeconfig
+    pub const SERVER_CA_FILE: &str = "/var/lib/rancher/k3s/server/tls/server-ca.crt";
+    /// Certificate the API server presents; k3s adds `--tls-san` entries to it
+    pub const SERVING_CERT_FILE: &str = "/var/lib/rancher/k3s/server/tls/serving-kube-apiserver.crt";
+    /// Client endpoint and certificates of the embedded etcd on each server
+    pub const ETCD_ENDPOINT: &str = "https://127.0.0.1:2379";
+    pub const ETCD_CA_FILE: &str = "/var/lib/rancher/k3s/server/tls/etcd/server-ca.crt";
+    pub const ETCD_CLIENT_CERT: &str = "/var/lib/rancher/k3s/server/tls/etcd/client.crt";
+    pub const ETCD_CLIENT_KEY: &str = "/var/lib/rancher/k3s/server/tls/etcd/client.key";
+    /// Certificates k3s generates for the control plane, including the admin client certificate
+    pub const SERVER_TLS_DIR: &str = "/var/lib/rancher/k3s/server/tls";
+    /// k3s renews certificates that expire within this many days when it restarts
+    pub const CERT_RENEWAL_DAYS: u64 = 90;
+    /// Where `k3s etcd-snapshot save` writes snapshots on a server
+    pub const ETCD_SNAPSHOT_DIR: &str = "/var/lib/rancher/k3s/server/db/snapshots";
+    /// Datastore backups `etcd-backup --prune` keeps, locally and on the server
+    pub const DATASTORE_BACKUP_KEEP: usize = 5;
+    /// Environment files the k3s install script writes K3S_TOKEN to
+    pub const SERVER_ENV_FILE: &str = "/etc/systemd/system/k3s.service.env";
+    pub const AGENT_ENV_FILE: &str = "/etc/systemd/system/k3s-agent.service.env";
+    /// Admin kubeconfig k3s writes on servers; `sudo kubectl` uses it, helm has to be told
+    pub const SERVER_KUBECONFIG: &str = "/etc/rancher/k3s/k3s.yaml";
+    /// The k3s helm controller installs a HelmChart release with a job of this prefix
+    pub const HELM_INSTALL_JOB_PREFIX: &str = "helm-install-";
+}
+
+/// NVIDIA GPU Operator constants
+pub mod gpu {
+    pub const OPERATOR_NAMESPACE: &str = "gpu-operator";
+    pub const DEVICE_PLUGIN_DAEMONSET: &str = "nvidia-device-plugin-daemonset";
+    pub const RESOURCE_NAME: &str = "nvidia.com/gpu";
+    pub const NVIDIA_SMI_QUERY: &str = "nvidia-smi --query-gpu=name,driver_version,memory.total --format=csv,noheader";
+    /// The operator's driver container keeps nvidia-smi under this root instead of the host
+    pub const DRIVER_ROOT: &str = "/run/nvidia/driver";
+    pub const CUDA_TEST_POD: &str = "im-deploy-cuda-vectoradd";
+    pub const CUDA_TEST_IMAGE: &str = "nvcr.io/nvidia/k8s/cuda-sample:vectoradd-cuda12.5.0";
+    pub const CUDA_TEST_TIMEOUT_SECS: u64 = 300;
+}
+
+/// Applications synced by ArgoCD
+pub mod apps {
+    pub const ARGOCD_NAMESPACE: &str = "argocd";
+    pub const READY_TIMEOUT_SECS: u64 = 900;
 }
 
 /// Cluster monitoring constants
 pub mod monitoring {
     pub const CHECK_INTERVAL_SECS: u64 = 10;
     pub const NODE_READY_TIMEOUT_SECS: u64 = 600;
+    /// Upper bound for the first server's cloud-init (packages, k3s install) to finish
+    pub const CLOUD_INIT_TIMEOUT_SECS: u64 = 1200;
+    pub const CLOUD_INIT_OUTPUT_LOG: &str = "/var/log/cloud-init-output.log";
+    /// Log of the k3s server install script; it announces the add-on installs
+    pub const SERVER_LOG: &str = "/var/log/k3s-server.log";
+    pub const API_PROBE_TIMEOUT_SECS: u64 = 5;
+    /// How long to keep polling the API load balancer once all nodes are Ready
+    pub const API_LB_TIMEOUT_SECS: u64 = 300;
+    /// How long core workloads (coredns, traefik, ...) get to become Ready once nodes are
+    pub const WORKLOADS_TIMEOUT_SECS: u64 = 600;
+    /// Limit of an add-on phase without its own `timeout_secs`
+    pub const ADDON_TIMEOUT_SECS: u64 = 1800;
+    /// Lines shown before and after the first error of a failed install log
+    pub const LOG_CONTEXT_LINES: usize = 8;
+    /// Console log lines printed per node when nodes never become Ready
+    pub const CONSOLE_TAIL_LINES: usize = 30;
+    /// Default refresh of `top`; metrics-server samples every 15s
+    pub const TOP_REFRESH_SECS: u64 = 5;
+    /// First wait before reconnecting to a server that dropped off SSH; doubles up to the max
+    pub const RECONNECT_INITIAL_DELAY_SECS: u64 = 2;
+    pub const RECONNECT_MAX_DELAY_SECS: u64 = 30;
+    /// How long the polled server may stay unreachable (a reboot) before monitoring moves on
+    /// to another server
+    pub const FAILOVER_AFTER_SECS: u64 = 90;
+    /// Monitoring fails once no server has been reachable for this long
+    pub const RECONNECT_TIMEOUT_SECS: u64 = 600;
+    /// Columns of the timeline chart printed after monitoring
+    pub const TIMELINE_WIDTH: usize = 50;
+    /// A monitor starting this long after apply finished is not part of the deploy's timeline
+    pub const TIMELINE_APPLY_GAP_SECS: u64 = 600;
+    /// Milestones shifted less than this against the previous deploy are not reported
+    pub const TIMELINE_SHIFT_SECS: u64 = 60;
 }
 
 /// Terraform constants
 pub mod terraform {
     pub const STATE_DIR: &str = ".terraform";
     pub const TFVARS_FILE: &str = "terraform.tfvars";
+    /// Documented settings, extended by `scaffold provider`
+    pub const TFVARS_EXAMPLE_FILE: &str = "terraform.tfvars.example";
     pub const MAIN_TF_FILE: &str = "main.tf";
+    /// Default upper bound for a single terraform destroy run
+    pub const DESTROY_TIMEOUT_MINS: u64 = 30;
+    /// How long terraform gets to stop after SIGINT before it is killed
+    pub const INTERRUPT_GRACE_SECS: u64 = 60;
+    /// Provider plugin cache shared by all workspaces, relative to the home directory
+    pub const PLUGIN_CACHE_DIR: &str = ".terraform.d/plugin-cache";
+    /// Resources listed in the deploy summary and kept in history
+    pub const SLOWEST_RESOURCES: usize = 10;
+    /// Keypair resource `rotate-ssh-key` re-applies after changing ssh_key_path
+    pub const KEYPAIR_ADDRESS: &str = "module.openstack_k3s[0].openstack_compute_keypair_v2.keypair";
+    /// Resources removed from state before destroy so they outlive the cluster, unless
+    /// im-deploy.toml sets `preserve_on_destroy`: the Swift container of the Longhorn backups
+    pub const PRESERVE_ON_DESTROY: [&str; 2] = [
+        "module.*.openstack_objectstorage_container_v1.longhorn_backup",
+        "module.*.openstack_objectstorage_container_v1.longhorn_backup[*]",
+    ];
+}
+
+/// Files read by im-deploy itself
+pub mod files {
+    pub const CONFIG_FILE: &str = "im-deploy.toml";
+    /// Per-deployment data directory inside the terraform directory
+    pub const DATA_DIR: &str = ".im-deploy";
+    pub const HISTORY_FILE: &str = "history.json";
+    pub const NODE_STATUS_FILE: &str = "node-status.json";
+    pub const MONITOR_PROGRESS_FILE: &str = "monitor-progress.json";
+    /// Cluster metadata from the last readable terraform outputs, for when state is offline
+    pub const CLUSTER_CACHE_FILE: &str = "cluster.json";
+    /// Extra monitor phases shipped with the terraform module, inside the terraform directory
+    pub const MONITOR_ADDONS_FILE: &str = "monitor-addons.toml";
+    /// Kubeconfig used by `im-deploy kubectl`, inside DATA_DIR
+    pub const KUBECONFIG_FILE: &str = "kubeconfig";
+    /// Application database dumps and datastore backups, inside DATA_DIR
+    pub const BACKUP_DIR: &str = "backups";
+    /// Nova console logs captured when nodes never become Ready, inside DATA_DIR
+    pub const CONSOLE_DIR: &str = "console";
+}
+
+/// Process exit codes, so pipelines can branch on the kind of failure. Scripts depend on
+/// these: never renumber them.
+pub mod exit_code {
+    pub const FAILURE: i32 = 1;
+    /// Invalid configuration; clap uses the same code for invalid flags
+    pub const CONFIG: i32 = 2;
+    pub const TERRAFORM: i32 = 3;
+    /// The command finished but cleanup left resources behind
+    pub const CLEANUP_INCOMPLETE: i32 = 4;
+    pub const MONITOR_TIMEOUT: i32 = 5;
+    pub const CANCELLED: i32 = 6;
+}
+
+/// Environment variables that override values parsed from terraform.tfvars
+pub mod env_vars {
+    pub const TERRAFORM_BIN: &str = "IM_DEPLOY_TERRAFORM_BIN";
+    pub const TERRAFORM_DIR: &str = "IM_DEPLOY_TERRAFORM_DIR";
+    pub const TERRAFORM_REQUIRED_VERSION: &str = "IM_DEPLOY_TERRAFORM_REQUIRED_VERSION";
+    /// Read by terraform itself; im-deploy only fills in a default
+    pub const TF_PLUGIN_CACHE_DIR: &str = "TF_PLUGIN_CACHE_DIR";
+    pub const CLUSTER_NAME: &str = "IM_DEPLOY_CLUSTER_NAME";
+    pub const OS_AUTH_URL: &str = "OS_AUTH_URL";
+    pub const OS_USERNAME: &str = "OS_USERNAME";
+    pub const OS_PASSWORD: &str = "OS_PASSWORD";
+    pub const OS_PROJECT_NAME: &str = "OS_PROJECT_NAME";
+    pub const OS_REGION_NAME: &str = "OS_REGION_NAME";
+    pub const OS_USER_DOMAIN_NAME: &str = "OS_USER_DOMAIN_NAME";
+    pub const OS_PROJECT_DOMAIN_NAME: &str = "OS_PROJECT_DOMAIN_NAME";
+    /// Service catalog interface (public, internal or admin); not a terraform variable
+    pub const OS_INTERFACE: &str = "OS_INTERFACE";
+    pub const OS_CACERT: &str = "OS_CACERT";
+    pub const OS_INSECURE: &str = "OS_INSECURE";
+    pub const ENABLE_TAILSCALE: &str = "IM_DEPLOY_ENABLE_TAILSCALE";
+    pub const TS_API_KEY: &str = "TS_API_KEY";
+    pub const TS_TAILNET: &str = "TS_TAILNET";
+    /// Kept out of im-deploy.toml, which is usually committed
+    pub const CLOUDFLARE_API_TOKEN: &str = "CLOUDFLARE_API_TOKEN";
+    /// Set for hook commands, along with CLUSTER_NAME and TERRAFORM_DIR
+    pub const HOOK: &str = "IM_DEPLOY_HOOK";
+    pub const LB_IP: &str = "IM_DEPLOY_LB_IP";
+    pub const KUBECONFIG: &str = "IM_DEPLOY_KUBECONFIG";
+    pub const EXPIRES_AT: &str = "IM_DEPLOY_EXPIRES_AT";
+    /// Set by GitHub Actions: file the job summary markdown is appended to
+    pub const GITHUB_STEP_SUMMARY: &str = "GITHUB_STEP_SUMMARY";
 }
 
 #[cfg(test)]
@@ -84,6 +294,10 @@ mod tests {
             openstack::LOADBALANCER_DELETION_TIMEOUT_SECS % openstack::LOADBALANCER_POLL_INTERVAL_SECS,
             0
         );
+        assert_eq!(
+            openstack::LOADBALANCER_FAILOVER_TIMEOUT_SECS % openstack::LOADBALANCER_POLL_INTERVAL_SECS,
+            0
+        );
     }
 
     #[test]
@@ -0,0 +1,181 @@
+use crate::config::{DnsConfig, DnsProvider};
+use crate::constants::{dns as dns_constants, network};
+use crate::errors::{DnsError, Result};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use serde_json::json;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tracing::{debug, info};
+
+#[derive(Debug, Deserialize)]
+struct CloudflareRecord {
+    id: String,
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CloudflareResponse<T> {
+    success: bool,
+    #[serde(default)]
+    errors: Vec<serde_json::Value>,
+    result: Option<T>,
+}
+
+/// Point the configured name at `ip`, creating the A record or updating it in place
+pub fn upsert_a_record(dns: &DnsConfig, ip: &str) -> Result<()> {
+    match &dns.provider {
+        DnsProvider::Cloudflare { zone_id, api_token } => {
+            let cloudflare = Cloudflare::new(zone_id, api_token)?;
+            let records = cloudflare.a_records(&dns.name)?;
+            let body = json!({"type": "A", "name": dns.name, "content": ip, "ttl": dns.ttl, "proxied": false});
+
+            match records.first() {
+                Some(record) if record.content == ip => {
+                    info!("DNS record {} already points at {}", dns.name, ip);
+                }
+                Some(record) => {
+                    cloudflare.execute(cloudflare.client.put(cloudflare.record_url(Some(&record.id))).json(&body))?;
+                }
+                None => {
+                    cloudflare.execute(cloudflare.client.post(cloudflare.record_url(None)).json(&body))?;
+                }
+            }
+            // Stale duplicates would make the name resolve to old load balancers too
+            for record in records.iter().skip(1) {
+                cloudflare.execute(cloudflare.client.delete(cloudflare.record_url(Some(&record.id))))?;
+            }
+            Ok(())
+        }
+        DnsProvider::Rfc2136 { server, zone, key_file } => nsupdate(
+            key_file.as_deref(),
+            &nsupdate_script(server, zone.as_deref(), &dns.name, Some((dns.ttl, ip))),
+        ),
+    }
+}
+
+/// Remove all A records of the configured name
+pub fn delete_a_record(dns: &DnsConfig) -> Result<()> {
+    match &dns.provider {
+        DnsProvider::Cloudflare { zone_id, api_token } => {
+            let cloudflare = Cloudflare::new(zone_id, api_token)?;
+            for record in cloudflare.a_records(&dns.name)? {
+                cloudflare.execute(cloudflare.client.delete(cloudflare.record_url(Some(&record.id))))?;
+            }
+            Ok(())
+        }
+        DnsProvider::Rfc2136 { server, zone, key_file } => nsupdate(
+            key_file.as_deref(),
+            &nsupdate_script(server, zone.as_deref(), &dns.name, None),
+        ),
+    }
+}
+
+struct Cloudflare<'a> {
+    client: Client,
+    zone_id: &'a str,
+    api_token: &'a str,
+}
+
+impl<'a> Cloudflare<'a> {
+    fn new(zone_id: &'a str, api_token: &'a str) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(std::time::Duration::from_secs(network::HTTP_TIMEOUT_SECS))
+            .build()
+            .map_err(|e| DnsError::ApiError(e.to_string()))?;
+        Ok(Self { client, zone_id, api_token })
+    }
+
+    fn record_url(&self, id: Option<&str>) -> String {
+        let base = format!("{}/zones/{}/dns_records", dns_constants::CLOUDFLARE_API_URL, self.zone_id);
+        match id {
+            Some(id) => format!("{}/{}", base, id),
+            None => base,
+        }
+    }
+
+    fn a_records(&self, name: &str) -> Result<Vec<CloudflareRecord>> {
+        let request = self.client.get(self.record_url(None)).query(&[("type", "A"), ("name", name)]);
+        let records: Option<Vec<CloudflareRecord>> = self.send(request)?;
+        Ok(records.unwrap_or_default())
+    }
+
+    /// Send a request whose result is not needed
+    fn execute(&self, request: reqwest::blocking::RequestBuilder) -> Result<()> {
+        self.send::<serde_json::Value>(request).map(|_| ())
+    }
+
+    fn send<T: serde::de::DeserializeOwned>(&self, request: reqwest::blocking::RequestBuilder) -> Result<Option<T>> {
+        let response = request
+            .bearer_auth(self.api_token)
+            .send()
+            .map_err(|e| DnsError::ApiError(e.to_string()))?;
+        let status = response.status();
+        let text = response.text().map_err(|e| DnsError::ApiError(e.to_string()))?;
+        debug!("Cloudflare API returned {}: {}", status, text);
+
+        let parsed: CloudflareResponse<T> = serde_json::from_str(&text)
+            .map_err(|e| DnsError::ApiError(format!("API returned {}: {}: {}", status, e, text)))?;
+        if !status.is_success() || !parsed.success {
+            let errors: Vec<String> = parsed.errors.iter().map(|e| e.to_string()).collect();
+            return Err(DnsError::ApiError(format!("API returned {}: {}", status, errors.join(", "))).into());
+        }
+        Ok(parsed.result)
+    }
+}
+
+/// Dynamic update for nsupdate; `record` is (ttl, ip), or None to only delete
+fn nsupdate_script(server: &str, zone: Option<&str>, name: &str, record: Option<(u32, &str)>) -> String {
+    let name = format!("{}.", name.trim_end_matches('.'));
+    let mut script = format!("server {}\n", server);
+    if let Some(zone) = zone {
+        script.push_str(&format!("zone {}\n", zone));
+    }
+    script.push_str(&format!("update delete {} A\n", name));
+    if let Some((ttl, ip)) = record {
+        script.push_str(&format!("update add {} {} A {}\n", name, ttl, ip));
+    }
+    script.push_str("send\n");
+    script
+}
+
+fn nsupdate(key_file: Option<&Path>, script: &str) -> Result<()> {
+    let mut command = Command::new("nsupdate");
+    if let Some(key_file) = key_file {
+        command.arg("-k").arg(key_file);
+    }
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| DnsError::UpdateFailed(format!("could not run nsupdate: {}", e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(script.as_bytes())?;
+    }
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(DnsError::UpdateFailed(String::from_utf8_lossy(&output.stderr).trim().to_string()).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nsupdate_script() {
+        assert_eq!(
+            nsupdate_script("ns1.example.com", Some("example.com"), "k3s.example.com", Some((300, "203.0.113.7"))),
+            "server ns1.example.com\nzone example.com\nupdate delete k3s.example.com. A\n\
+             update add k3s.example.com. 300 A 203.0.113.7\nsend\n"
+        );
+        assert_eq!(
+            nsupdate_script("10.0.0.53", None, "k3s.example.com.", None),
+            "server 10.0.0.53\nupdate delete k3s.example.com. A\nsend\n"
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use serde_json::Value;
+
+/// How a component's readiness is read from the cluster
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkloadKind {
+    Deployment,
+    DaemonSet,
+    /// CloudNativePG `Cluster`
+    PostgresCluster,
+}
+
+impl WorkloadKind {
+    /// Resource name for `kubectl get`
+    pub fn resource(&self) -> &'static str {
+        match self {
+            WorkloadKind::Deployment => "deployment",
+            WorkloadKind::DaemonSet => "daemonset",
+            WorkloadKind::PostgresCluster => "clusters.postgresql.cnpg.io",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Component {
+    pub label: &'static str,
+    pub kind: WorkloadKind,
+    pub name: &'static str,
+}
+
+/// PostgreSQL database managed by CloudNativePG
+#[derive(Debug, Clone, Copy)]
+pub struct DatabaseSpec {
+    /// CNPG `Cluster` name
+    pub cluster: &'static str,
+    pub name: &'static str,
+}
+
+impl DatabaseSpec {
+    /// Label selector for the current primary instance
+    pub fn primary_selector(&self) -> String {
+        format!("cnpg.io/cluster={},cnpg.io/instanceRole=primary", self.cluster)
+    }
+}
+
+/// An application shipped in `apps/` and synced by ArgoCD
+#[derive(Debug, Clone, Copy)]
+pub struct AppSpec {
+    pub name: &'static str,
+    /// ArgoCD Application in the `argocd` namespace
+    pub argocd_app: &'static str,
+    /// Manifest that creates the Application when the root app has not yet
+    pub application_manifest: &'static str,
+    pub namespace: &'static str,
+    pub components: &'static [Component],
+    /// Tailscale ingress hostname serving the app
+    pub ingress_hostname: &'static str,
+    pub database: Option<DatabaseSpec>,
+}
+
+pub const IMMICH: AppSpec = AppSpec {
+    name: "immich",
+    argocd_app: "immich",
+    application_manifest: include_str!("../../../apps/root/immich-application.yaml"),
+    namespace: "immich",
+    components: &[
+        Component { label: "server", kind: WorkloadKind::Deployment, name: "immich-server" },
+        Component { label: "machine-learning", kind: WorkloadKind::Deployment, name: "immich-machine-learning" },
+        Component { label: "valkey", kind: WorkloadKind::Deployment, name: "immich-valkey" },
+        Component { label: "database", kind: WorkloadKind::PostgresCluster, name: "immich-database" },
+    ],
+    ingress_hostname: "immich",
+    // CNPG initdb creates the `app` database by default
+    database: Some(DatabaseSpec { cluster: "immich-database", name: "app" }),
+};
+
+pub const APPS: [AppSpec; 1] = [IMMICH];
+
+pub fn find_app(name: &str) -> Option<&'static AppSpec> {
+    APPS.iter().find(|app| app.name.eq_ignore_ascii_case(name))
+}
+
+/// Sync and health of an ArgoCD Application from `kubectl get application -o json`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgoAppStatus {
+    pub sync: String,
+    pub health: String,
+}
+
+impl ArgoAppStatus {
+    pub fn from_json(application: &Value) -> Self {
+        let status = application.get("status");
+        let field = |section: &str| {
+            status
+                .and_then(|s| s.get(section))
+                .and_then(|s| s.get("status"))
+                .and_then(|s| s.as_str())
+                .unwrap_or("Unknown")
+                .to_string()
+        };
+        Self {
+            sync: field("sync"),
+            health: field("health"),
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.sync == "Synced" && self.health == "Healthy"
+    }
+}
+
+/// Ready vs desired replicas (or CNPG instances) of a component
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Readiness {
+    pub ready: u64,
+    pub desired: u64,
+}
+
+impl Readiness {
+    pub fn from_json(kind: WorkloadKind, workload: &Value) -> Self {
+        let number = |section: &str, key: &str| {
+            workload
+                .get(section)
+                .and_then(|s| s.get(key))
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0)
+        };
+        match kind {
+            WorkloadKind::Deployment => Self {
+                ready: number("status", "readyReplicas"),
+                desired: workload
+                    .get("spec")
+                    .and_then(|s| s.get("replicas"))
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(1),
+            },
+            WorkloadKind::DaemonSet => Self {
+                ready: number("status", "numberReady"),
+                desired: number("status", "desiredNumberScheduled"),
+            },
+            WorkloadKind::PostgresCluster => Self {
+                ready: number("status", "readyInstances"),
+                desired: number("spec", "instances"),
+            },
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.desired > 0 && self.ready >= self.desired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_find_app() {
+        assert_eq!(find_app("immich").unwrap().namespace, "immich");
+        assert_eq!(find_app("Immich").unwrap().argocd_app, "immich");
+        assert!(find_app("nextcloud").is_none());
+    }
+
+    #[test]
+    fn test_immich_application_manifest() {
+        // The embedded manifest must create the Application the status checks look for
+        assert!(IMMICH.application_manifest.contains("kind: Application"));
+        assert!(IMMICH.application_manifest.contains(&format!("name: {}", IMMICH.argocd_app)));
+    }
+
+    #[test]
+    fn test_database_primary_selector() {
+        let database = IMMICH.database.unwrap();
+        assert_eq!(
+            database.primary_selector(),
+            "cnpg.io/cluster=immich-database,cnpg.io/instanceRole=primary"
+        );
+        // The database must be one of the components whose readiness is checked
+        assert!(IMMICH.components.iter().any(|c| c.name == database.cluster));
+    }
+
+    #[test]
+    fn test_argo_app_status() {
+        let app = json!({"status": {"sync": {"status": "Synced"}, "health": {"status": "Healthy"}}});
+        assert!(ArgoAppStatus::from_json(&app).is_healthy());
+
+        let progressing = json!({"status": {"sync": {"status": "Synced"}, "health": {"status": "Progressing"}}});
+        assert!(!ArgoAppStatus::from_json(&progressing).is_healthy());
+
+        let fresh = ArgoAppStatus::from_json(&json!({"metadata": {"name": "immich"}}));
+        assert_eq!(fresh.sync, "Unknown");
+        assert_eq!(fresh.health, "Unknown");
+    }
+
+    #[test]
+    fn test_readiness() {
+        let deployment = json!({"spec": {"replicas": 2}, "status": {"readyReplicas": 2}});
+        assert!(Readiness::from_json(WorkloadKind::Deployment, &deployment).is_ready());
+
+        // A rolling-out deployment reports no readyReplicas yet
+        let starting = json!({"spec": {"replicas": 1}, "status": {}});
+        assert_eq!(
+            Readiness::from_json(WorkloadKind::Deployment, &starting),
+            Readiness { ready: 0, desired: 1 }
+        );
+
+        let database = json!({"spec": {"instances": 3}, "status": {"readyInstances": 2}});
+        let daemonset = json!({"status": {"desiredNumberScheduled": 3, "numberReady": 2}});
+        assert_eq!(
+            Readiness::from_json(WorkloadKind::DaemonSet, &daemonset),
+            Readiness { ready: 2, desired: 3 }
+        );
+
+        let readiness = Readiness::from_json(WorkloadKind::PostgresCluster, &database);
+        assert_eq!(readiness, Readiness { ready: 2, desired: 3 });
+        assert!(!readiness.is_ready());
+    }
+}
@@ -0,0 +1,244 @@
+use crate::history::parse_rfc3339;
+use base64::Engine;
+
+/// Entry of a certificate's Subject Alternative Name extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubjectAltName {
+    Dns(String),
+    Ip(String),
+}
+
+/// Parse the SAN list from `openssl x509 -noout -text` output
+pub fn parse_subject_alt_names(openssl_text: &str) -> Vec<SubjectAltName> {
+    let mut lines = openssl_text.lines();
+    let Some(list) = lines
+        .by_ref()
+        .find(|line| line.trim().starts_with("X509v3 Subject Alternative Name"))
+        .and_then(|_| lines.next())
+    else {
+        return Vec::new();
+    };
+
+    list.split(',')
+        .filter_map(|entry| {
+            let (kind, value) = entry.trim().split_once(':')?;
+            match kind {
+                "DNS" => Some(SubjectAltName::Dns(value.to_string())),
+                "IP Address" => Some(SubjectAltName::Ip(value.to_string())),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Whether a certificate with these SANs is valid for `host`, an IP address or DNS name
+pub fn covers(names: &[SubjectAltName], host: &str) -> bool {
+    names.iter().any(|name| match name {
+        SubjectAltName::Ip(ip) => ip == host,
+        SubjectAltName::Dns(dns) => match dns.strip_prefix("*.") {
+            Some(domain) => host
+                .split_once('.')
+                .is_some_and(|(_, rest)| rest.eq_ignore_ascii_case(domain)),
+            None => dns.eq_ignore_ascii_case(host),
+        },
+    })
+}
+
+/// Expiry of a certificate file on a node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertExpiry {
+    pub path: String,
+    /// Unix time of notAfter
+    pub not_after: u64,
+}
+
+impl CertExpiry {
+    pub fn file_name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+/// Renews the server's certificates; k3s has to be stopped for it. Prints the node's time once
+/// k3s runs again.
+pub const ROTATE_COMMAND: &str =
+    "sudo systemctl stop k3s && sudo k3s certificate rotate && sudo systemctl start k3s && date +%s";
+
+/// Agents renew their client certificates on restart
+pub const AGENT_RESTART_COMMAND: &str = "sudo systemctl restart k3s-agent && date +%s";
+
+/// Remote command printing `<path> notAfter=<date>` for each certificate in `dir` and below
+pub fn expiry_listing_command(dir: &str) -> String {
+    format!(
+        "sudo find {} -name '*.crt' -exec sh -c 'echo \"$1 $(openssl x509 -noout -enddate -in \"$1\")\"' _ {{}} \\;",
+        dir
+    )
+}
+
+/// Parse lines of `<path> notAfter=<date>`, as printed by running
+/// `openssl x509 -noout -enddate` for each certificate file
+pub fn parse_expiry_listing(output: &str) -> Vec<CertExpiry> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (path, enddate) = line.trim().split_once(' ')?;
+            Some(CertExpiry {
+                path: path.to_string(),
+                not_after: parse_openssl_enddate(enddate)?,
+            })
+        })
+        .collect()
+}
+
+/// Unix time of openssl's `notAfter=Jan  2 03:04:05 2026 GMT`
+pub fn parse_openssl_enddate(text: &str) -> Option<u64> {
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let mut parts = text.trim().strip_prefix("notAfter=")?.split_whitespace();
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|m| *m == month)? + 1;
+    let day: u32 = parts.next()?.parse().ok()?;
+    let time = parts.next()?;
+    let year: u32 = parts.next()?.parse().ok()?;
+    parse_rfc3339(&format!("{:04}-{:02}-{:02}T{}Z", year, month, day, time))
+}
+
+/// notAfter of the first certificate in a PEM bundle, e.g. a kubeconfig's decoded
+/// client-certificate-data
+pub fn pem_not_after(pem: &str) -> Option<u64> {
+    let body: String = pem
+        .lines()
+        .skip_while(|line| !line.starts_with("-----BEGIN CERTIFICATE-----"))
+        .skip(1)
+        .take_while(|line| !line.starts_with("-----END"))
+        .collect();
+    let der = base64::engine::general_purpose::STANDARD.decode(body).ok()?;
+
+    // Certificate ::= SEQUENCE { tbsCertificate SEQUENCE { [0] version OPTIONAL, serialNumber,
+    // signature, issuer, validity SEQUENCE { notBefore, notAfter }, ... }, ... }
+    let (_, certificate, _) = der_element(&der)?;
+    let (_, tbs, _) = der_element(certificate)?;
+    let (tag, _, mut rest) = der_element(tbs)?;
+    if tag == 0xa0 {
+        rest = der_element(rest)?.2;
+    }
+    for _ in 0..2 {
+        rest = der_element(rest)?.2;
+    }
+    let (_, validity, _) = der_element(rest)?;
+    let (_, _, after_not_before) = der_element(validity)?;
+    let (tag, not_after, _) = der_element(after_not_before)?;
+    der_time(tag, not_after)
+}
+
+/// Unix time of a DER UTCTime or GeneralizedTime; malformed values give `None`
+fn der_time(tag: u8, value: &[u8]) -> Option<u64> {
+    let text = std::str::from_utf8(value).ok()?.strip_suffix('Z')?;
+    if !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let full = match tag {
+        // UTCTime YYMMDDHHMMSS, years 1950-2049
+        0x17 => format!("{}{}", if text.get(..2)?.parse::<u32>().ok()? < 50 { "20" } else { "19" }, text),
+        // GeneralizedTime YYYYMMDDHHMMSS
+        0x18 => text.to_string(),
+        _ => return None,
+    };
+    let digits = full.get(..14)?;
+    parse_rfc3339(&format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        &digits[..4],
+        &digits[4..6],
+        &digits[6..8],
+        &digits[8..10],
+        &digits[10..12],
+        &digits[12..14]
+    ))
+}
+
+/// Tag, content and the bytes after a DER element
+fn der_element(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, data) = data.split_first()?;
+    let (&first, mut data) = data.split_first()?;
+    let length = if first < 0x80 {
+        first as usize
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || data.len() < count {
+            return None;
+        }
+        let (bytes, rest) = data.split_at(count);
+        data = rest;
+        bytes.iter().fold(0, |length, &byte| (length << 8) | byte as usize)
+    };
+    (data.len() >= length).then(|| (tag, &data[..length], &data[length..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Self-signed, valid until 2026-01-02T03:04:05Z
+    const CERT_PEM: &str = "-----BEGIN CERTIFICATE-----
+MIIBgjCCASmgAwIBAgIUZP7X/j+Vy56/omXqa11705OZQTUwCgYIKoZIzj0EAwIw
+FzEVMBMGA1UEAwwMc3lzdGVtOmFkbWluMB4XDTI1MDEwMTAwMDAwMFoXDTI2MDEw
+MjAzMDQwNVowFzEVMBMGA1UEAwwMc3lzdGVtOmFkbWluMFkwEwYHKoZIzj0CAQYI
+KoZIzj0DAQcDQgAEs4a4lT6op0q85QyWR/GsDNjA4vBW0jbBe0jkTjSb+SeIl/XS
+V5FGr2Lj5PFir4j4id++IcLPQmbUhcjARLm7BKNTMFEwHQYDVR0OBBYEFGy7l7O+
+eMKCCkt1yId7GRmaJQDLMB8GA1UdIwQYMBaAFGy7l7O+eMKCCkt1yId7GRmaJQDL
+MA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDRwAwRAIgGxkzfe6YBxfWld5o
+GEkSjw3U+SQVAFE2P3fxVQmTmQkCIEBXF4HuksxJyxcFwS0z+84VG8t/yyKKpt0r
+diRy/UIQ
+-----END CERTIFICATE-----
+";
+
+    const OPENSSL_TEXT: &str = "\
+        X509v3 extensions:
+            X509v3 Key Usage: critical
+                Digital Signature, Key Encipherment
+            X509v3 Subject Alternative Name:
+                DNS:kubernetes, DNS:kubernetes.default, DNS:*.k3s.example.com, IP Address:10.0.0.10, IP Address:203.0.113.7
+    Signature Algorithm: ecdsa-with-SHA256";
+
+    #[test]
+    fn test_parse_subject_alt_names() {
+        let names = parse_subject_alt_names(OPENSSL_TEXT);
+        assert_eq!(names.len(), 5);
+        assert_eq!(names[0], SubjectAltName::Dns("kubernetes".to_string()));
+        assert_eq!(names[4], SubjectAltName::Ip("203.0.113.7".to_string()));
+        assert!(parse_subject_alt_names("Certificate:\n    Data:\n").is_empty());
+    }
+
+    #[test]
+    fn test_covers() {
+        let names = parse_subject_alt_names(OPENSSL_TEXT);
+        assert!(covers(&names, "203.0.113.7"));
+        assert!(covers(&names, "api.k3s.example.com"));
+        assert!(!covers(&names, "203.0.113.8"));
+        assert!(!covers(&names, "a.b.k3s.example.com"));
+    }
+
+    #[test]
+    fn test_certificate_expiry() {
+        assert_eq!(pem_not_after(CERT_PEM), Some(1767323045));
+        assert_eq!(pem_not_after("not a certificate"), None);
+
+        let listing = "/var/lib/rancher/k3s/server/tls/client-admin.crt notAfter=Jan  2 03:04:05 2026 GMT\n\
+                       /var/lib/rancher/k3s/server/tls/broken.crt unable to load certificate\n\
+                       /var/lib/rancher/k3s/server/tls/server-ca.crt notAfter=Dec 30 03:04:05 2034 GMT\n";
+        let expiries = parse_expiry_listing(listing);
+        assert_eq!(expiries.len(), 2);
+        assert_eq!(expiries[0].file_name(), "client-admin.crt");
+        assert_eq!(expiries[0].not_after, 1767323045);
+        assert_eq!(expiries[1].not_after, 2051060645);
+    }
+
+    #[test]
+    fn test_der_time() {
+        assert_eq!(der_time(0x17, b"260102030405Z"), Some(1767323045));
+        assert_eq!(der_time(0x18, b"20260102030405Z"), Some(1767323045));
+        // Too short or not digits, as in a corrupt certificate
+        assert_eq!(der_time(0x17, b"Z"), None);
+        assert_eq!(der_time(0x17, b"2Z"), None);
+        assert_eq!(der_time(0x17, "\u{e9}60102030405Z".as_bytes()), None);
+        assert_eq!(der_time(0x02, b"260102030405Z"), None);
+    }
+}
@@ -0,0 +1,127 @@
+use serde_json::Value;
+
+/// Overall state reported by `cloud-init status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloudInitState {
+    NotStarted,
+    Running,
+    Done,
+    Error,
+    Disabled,
+}
+
+impl CloudInitState {
+    fn parse(status: &str) -> Option<Self> {
+        match status.trim() {
+            "not started" | "not run" => Some(Self::NotStarted),
+            "running" => Some(Self::Running),
+            // `degraded done` finished with recoverable errors; the boot itself succeeded
+            "done" | "degraded done" => Some(Self::Done),
+            "error" | "degraded error" => Some(Self::Error),
+            "disabled" => Some(Self::Disabled),
+            _ => None,
+        }
+    }
+
+    /// Whether cloud-init will not make further progress
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Done | Self::Error | Self::Disabled)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloudInitStatus {
+    pub state: CloudInitState,
+    /// Current stage or module, e.g. "DataSourceOpenStackLocal" or "modules:final"
+    pub detail: Option<String>,
+    pub errors: Vec<String>,
+}
+
+impl CloudInitStatus {
+    /// Parse `cloud-init status --format=json`, falling back to the plain `status: done`
+    /// output of releases without JSON support
+    pub fn parse(output: &str) -> Option<Self> {
+        if let Ok(json) = serde_json::from_str::<Value>(output) {
+            return Self::from_json(&json);
+        }
+
+        let status = output
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("status:"))?;
+        Some(Self {
+            state: CloudInitState::parse(status)?,
+            detail: None,
+            errors: Vec::new(),
+        })
+    }
+
+    fn from_json(json: &Value) -> Option<Self> {
+        // Newer releases report the combined state in `extended_status`
+        let state = ["extended_status", "status"]
+            .iter()
+            .filter_map(|key| json.get(key).and_then(|s| s.as_str()))
+            .find_map(CloudInitState::parse)?;
+
+        let detail = json
+            .get("detail")
+            .and_then(|d| d.as_str())
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .map(str::to_string);
+        let errors = json
+            .get("errors")
+            .and_then(|e| e.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.as_str())
+            .map(str::to_string)
+            .collect();
+
+        Some(Self { state, detail, errors })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_json_status() {
+        let running = CloudInitStatus::parse(
+            r#"{"boot_status_code": "enabled-by-generator", "datasource": "openstack", "detail": "DataSourceOpenStackLocal", "errors": [], "status": "running"}"#,
+        )
+        .unwrap();
+        assert_eq!(running.state, CloudInitState::Running);
+        assert_eq!(running.detail.as_deref(), Some("DataSourceOpenStackLocal"));
+        assert!(!running.state.is_finished());
+
+        let failed = CloudInitStatus::parse(
+            r#"{"status": "error", "extended_status": "error", "errors": ["('scripts_user', RuntimeError('Runparts: 1 failures'))"]}"#,
+        )
+        .unwrap();
+        assert_eq!(failed.state, CloudInitState::Error);
+        assert_eq!(failed.errors.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_degraded_status() {
+        let degraded = CloudInitStatus::parse(
+            r#"{"status": "done", "extended_status": "degraded done", "errors": [], "recoverable_errors": {"WARNING": ["x"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(degraded.state, CloudInitState::Done);
+    }
+
+    #[test]
+    fn test_parse_plain_status() {
+        assert_eq!(
+            CloudInitStatus::parse("\nstatus: done\n").unwrap().state,
+            CloudInitState::Done
+        );
+        assert_eq!(
+            CloudInitStatus::parse("status: running").unwrap().state,
+            CloudInitState::Running
+        );
+        assert!(CloudInitStatus::parse("command not found").is_none());
+    }
+}
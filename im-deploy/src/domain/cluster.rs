@@ -1,14 +1,69 @@
+use crate::constants::ssh;
+use crate::domain::apps::{Readiness, WorkloadKind};
+use crate::errors::TerraformError;
+use crate::history::parse_rfc3339;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use tracing::warn;
+
+/// Address family nodes with both an IPv4 and an IPv6 address are reached over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IpFamily {
+    #[default]
+    Ipv4,
+    Ipv6,
+}
+
+impl IpFamily {
+    pub fn of(address: &str) -> Self {
+        if address.contains(':') { IpFamily::Ipv6 } else { IpFamily::Ipv4 }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerInfo {
     pub name: String,
+    /// Address connections go to, the IPv4 one unless IPv6 is preferred or the only one
     pub ip: String,
+    /// Every address of a dual-stack node, `ip` included; empty when `ip` is the only one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub addresses: Vec<String>,
     pub cloud_provider: String,
     pub tailscale_hostname: Option<String>,
+    /// Tailscale IP to connect to instead of `tailscale_hostname` when the hostname does not
+    /// resolve here; the hostname stays the node's name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tailscale_ip: Option<String>,
+    /// Login of the node's image when its role runs a different OS than the default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_user: Option<String>,
+    /// Floating IP of a node reachable from outside, for direct SSH without bastion or Tailscale
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_ip: Option<String>,
+    /// Connect with `tailscale ssh` instead of OpenSSH
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub tailscale_ssh: bool,
+    /// Connect to `ip` through a Tailscale subnet router instead of the Tailscale hostname
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub via_subnet_router: bool,
 }
 
 impl ServerInfo {
+    pub fn login_user(&self) -> &str {
+        self.ssh_user.as_deref().unwrap_or(ssh::SSH_USER)
+    }
+
+    /// The node's address of `family`, if it has one
+    pub fn address(&self, family: IpFamily) -> Option<&str> {
+        std::iter::once(&self.ip)
+            .chain(&self.addresses)
+            .map(String::as_str)
+            .find(|address| IpFamily::of(address) == family)
+    }
+
     pub fn is_server(&self) -> bool {
         self.name.contains("server")
     }
@@ -16,6 +71,216 @@ impl ServerInfo {
     pub fn is_agent(&self) -> bool {
         self.name.contains("agent")
     }
+
+    /// Whether a Kubernetes node name refers to this server. Nodes are named
+    /// `<prefix>-server-0` by terraform while ServerInfo uses `k3s-server-0`.
+    pub fn matches_node_name(&self, node_name: &str) -> bool {
+        let suffix = self.name.strip_prefix("k3s-").unwrap_or(&self.name);
+        node_name == self.name
+            || node_name.ends_with(&format!("-{}", suffix))
+            || self.tailscale_hostname.as_deref() == Some(node_name)
+    }
+}
+
+/// Nova instance name of a server given as `k3s-server-0`, `server-0` or its full name.
+/// Terraform names instances `<prefix>-server-0`, where the prefix is the module's cluster name.
+pub fn instance_name(prefix: &str, server: &str) -> String {
+    if server.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('-')) {
+        return server.to_string();
+    }
+    let suffix = server.strip_prefix("k3s-").unwrap_or(server);
+    format!("{}-{}", prefix, suffix)
+}
+
+/// Parse `kubectl get nodes --no-headers` output into node name -> status
+pub fn parse_node_statuses(output: &str) -> BTreeMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+            Some((columns.next()?.to_string(), columns.next()?.to_string()))
+        })
+        .collect()
+}
+
+/// Parse `kubectl get nodes -o json` into node name -> whether its Ready condition is True
+pub fn parse_nodes_json(json: &Value) -> BTreeMap<String, bool> {
+    json.get("items")
+        .and_then(|items| items.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|node| {
+            let name = node.pointer("/metadata/name")?.as_str()?;
+            let ready = node
+                .pointer("/status/conditions")
+                .and_then(|c| c.as_array())
+                .into_iter()
+                .flatten()
+                .any(|c| c.get("type").and_then(|t| t.as_str()) == Some("Ready")
+                    && c.get("status").and_then(|s| s.as_str()) == Some("True"));
+            Some((name.to_string(), ready))
+        })
+        .collect()
+}
+
+/// Parse `kubectl get nodes -o json` into Ready node name -> Unix time it last became Ready
+pub fn parse_ready_since(json: &Value) -> BTreeMap<String, u64> {
+    ready_condition_times(json, "lastTransitionTime")
+}
+
+/// Parse `kubectl get nodes -o json` into Ready node name -> Unix time its kubelet last
+/// reported status. A restarted kubelet reports right away, without the node leaving Ready.
+pub fn parse_ready_heartbeats(json: &Value) -> BTreeMap<String, u64> {
+    ready_condition_times(json, "lastHeartbeatTime")
+}
+
+fn ready_condition_times(json: &Value, field: &str) -> BTreeMap<String, u64> {
+    json.get("items")
+        .and_then(|items| items.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|node| {
+            let name = node.pointer("/metadata/name")?.as_str()?;
+            let ready = node
+                .pointer("/status/conditions")?
+                .as_array()?
+                .iter()
+                .find(|c| c.get("type").and_then(|t| t.as_str()) == Some("Ready"))?;
+            if ready.get("status").and_then(|s| s.as_str()) != Some("True") {
+                return None;
+            }
+            let time = parse_rfc3339(ready.get(field)?.as_str()?)?;
+            Some((name.to_string(), time))
+        })
+        .collect()
+}
+
+/// Registered nodes compared against the servers terraform created
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeDiff {
+    /// Expected servers with no registered node
+    pub missing: Vec<String>,
+    /// Registered nodes that match no expected server
+    pub unexpected: Vec<String>,
+    /// Expected servers with several registered nodes, with the node names
+    pub duplicated: Vec<(String, Vec<String>)>,
+    /// Expected servers whose node is registered but not Ready
+    pub not_ready: Vec<String>,
+}
+
+impl NodeDiff {
+    pub fn compare(expected: &[ServerInfo], nodes: &BTreeMap<String, bool>) -> Self {
+        let mut diff = NodeDiff::default();
+
+        for server in expected {
+            let matching: Vec<&String> = nodes.keys().filter(|name| server.matches_node_name(name)).collect();
+            match matching.as_slice() {
+                [] => diff.missing.push(server.name.clone()),
+                [node] => {
+                    if !nodes[*node] {
+                        diff.not_ready.push(server.name.clone());
+                    }
+                }
+                several => diff
+                    .duplicated
+                    .push((server.name.clone(), several.iter().map(|n| n.to_string()).collect())),
+            }
+        }
+
+        diff.unexpected = nodes
+            .keys()
+            .filter(|name| !expected.iter().any(|server| server.matches_node_name(name)))
+            .cloned()
+            .collect();
+        diff
+    }
+
+    /// Every expected server registered exactly once and Ready, with no strangers
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty() && self.duplicated.is_empty() && self.not_ready.is_empty()
+    }
+}
+
+/// A workload k3s or the cluster templates install, needed before the cluster is usable
+#[derive(Debug, Clone, Copy)]
+pub struct CoreWorkload {
+    pub namespace: &'static str,
+    pub kind: WorkloadKind,
+    pub name: &'static str,
+    /// Only checked when installed, e.g. Longhorn
+    pub optional: bool,
+}
+
+pub const CORE_WORKLOADS: [CoreWorkload; 6] = [
+    CoreWorkload { namespace: "kube-system", kind: WorkloadKind::Deployment, name: "coredns", optional: false },
+    CoreWorkload { namespace: "kube-system", kind: WorkloadKind::Deployment, name: "traefik", optional: false },
+    CoreWorkload { namespace: "kube-system", kind: WorkloadKind::Deployment, name: "metrics-server", optional: false },
+    CoreWorkload {
+        namespace: "kube-system",
+        kind: WorkloadKind::Deployment,
+        name: "local-path-provisioner",
+        optional: false,
+    },
+    CoreWorkload { namespace: "longhorn-system", kind: WorkloadKind::DaemonSet, name: "longhorn-manager", optional: true },
+    CoreWorkload {
+        namespace: "longhorn-system",
+        kind: WorkloadKind::Deployment,
+        name: "longhorn-driver-deployer",
+        optional: true,
+    },
+];
+
+/// Whether every required workload is Ready and every installed optional one is too;
+/// `None` means the workload was not found
+pub fn core_workloads_ready(statuses: &[(CoreWorkload, Option<Readiness>)]) -> bool {
+    statuses.iter().all(|(workload, readiness)| match readiness {
+        Some(readiness) => readiness.is_ready(),
+        None => workload.optional,
+    })
+}
+
+/// One row of `kubectl get pods -A --no-headers`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PodHealth {
+    pub namespace: String,
+    pub name: String,
+    pub ready: u32,
+    pub containers: u32,
+    pub status: String,
+    pub restarts: u32,
+}
+
+impl PodHealth {
+    /// Running with every container ready, or a job pod that finished
+    pub fn is_healthy(&self) -> bool {
+        match self.status.as_str() {
+            "Completed" | "Succeeded" => true,
+            "Running" => self.ready == self.containers,
+            _ => false,
+        }
+    }
+}
+
+/// Parse `kubectl get pods -A --no-headers`; restarts may read `3 (5m ago)`
+pub fn parse_pod_health(output: &str) -> Vec<PodHealth> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let [namespace, name, ready, status, restarts, ..] = columns.as_slice() else {
+                return None;
+            };
+            let (ready, containers) = ready.split_once('/')?;
+            Some(PodHealth {
+                namespace: namespace.to_string(),
+                name: name.to_string(),
+                ready: ready.parse().ok()?,
+                containers: containers.parse().ok()?,
+                status: status.to_string(),
+                restarts: restarts.parse().ok()?,
+            })
+        })
+        .collect()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +289,9 @@ pub struct CloudProvider {
     pub bastion_ip: Option<String>,
     pub tailscale_enabled: bool,
     pub servers: Vec<ServerInfo>,
+    /// Private subnets a Tailscale subnet router advertises, e.g. `10.0.1.0/24`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subnet_routes: Vec<String>,
 }
 
 impl CloudProvider {
@@ -35,6 +303,65 @@ impl CloudProvider {
         self.servers.iter().filter(|s| s.is_agent()).count()
     }
 
+    /// Whether a subnet router makes `ip` reachable over the tailnet
+    pub fn routes_over_tailnet(&self, ip: &str) -> bool {
+        self.subnet_routes.iter().any(|route| cidr_contains(route, ip))
+    }
+
+    /// Drop the subnet routes no tailnet node serves, e.g. when the server that should
+    /// advertise them was created before the subnet router was turned on. Returns the routes
+    /// dropped.
+    pub fn keep_served_routes(&mut self, served: &[String]) -> Vec<String> {
+        let (kept, dropped) = self.subnet_routes.drain(..).partition(|route| served.contains(route));
+        self.subnet_routes = kept;
+        dropped
+    }
+
+    /// Reach nodes by their private IP through the subnet router, e.g. when MagicDNS does
+    /// not resolve their Tailscale hostnames
+    pub fn use_subnet_router(&mut self) {
+        let routes = &self.subnet_routes;
+        for server in &mut self.servers {
+            server.via_subnet_router = routes.iter().any(|route| cidr_contains(route, &server.ip));
+        }
+    }
+
+    /// Reach tailnet nodes whose hostname does not `resolve` here, e.g. with MagicDNS off
+    /// locally, by their Tailscale IP from `peers`. Nodes behind the subnet router keep their
+    /// private IP. Returns the names of the nodes changed.
+    pub fn use_tailscale_ips(&mut self, peers: &BTreeMap<String, String>, resolves: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut changed = Vec::new();
+        for server in self.servers.iter_mut().filter(|s| !s.via_subnet_router) {
+            let Some(hostname) = server.tailscale_hostname.as_deref() else {
+                continue;
+            };
+            if let Some(ip) = peers.get(hostname)
+                && !resolves(hostname)
+            {
+                server.tailscale_ip = Some(ip.clone());
+                changed.push(server.name.clone());
+            }
+        }
+        changed
+    }
+
+    /// Reach nodes on the tailnet with `tailscale ssh`; nodes without a Tailscale hostname keep
+    /// the bastion or their public IP
+    pub fn use_tailscale_ssh(&mut self) {
+        for server in &mut self.servers {
+            server.tailscale_ssh = server.tailscale_hostname.is_some();
+        }
+    }
+
+    /// Connect to dual-stack nodes over `family`; nodes without such an address keep theirs
+    pub fn prefer_ip_family(&mut self, family: IpFamily) {
+        for server in &mut self.servers {
+            if let Some(address) = server.address(family) {
+                server.ip = address.to_string();
+            }
+        }
+    }
+
     pub fn total_nodes(&self) -> usize {
         self.servers.len()
     }
@@ -42,18 +369,148 @@ impl CloudProvider {
     pub fn get_first_server(&self) -> Option<&ServerInfo> {
         self.servers.iter().find(|s| s.is_server())
     }
+
+    /// Node to run kubectl and fetch the kubeconfig on. A `kubeconfig_source` output wins,
+    /// naming either one of our nodes or a host outside terraform (an externally managed
+    /// control plane); otherwise the first server, and without servers any node at all.
+    pub fn control_node(&self, kubeconfig_source: Option<&str>) -> Option<ServerInfo> {
+        if let Some(source) = kubeconfig_source {
+            let known = self.servers.iter().find(|s| {
+                s.name == source || s.ip == source || s.tailscale_hostname.as_deref() == Some(source)
+            });
+            return Some(known.cloned().unwrap_or_else(|| ServerInfo {
+                name: source.to_string(),
+                ip: source.to_string(),
+                addresses: Vec::new(),
+                cloud_provider: self.name.to_lowercase(),
+                tailscale_hostname: self.tailscale_enabled.then(|| source.to_string()),
+                tailscale_ip: None,
+                ssh_user: None,
+                public_ip: None,
+                tailscale_ssh: false,
+                via_subnet_router: false,
+            }));
+        }
+        self.get_first_server().or_else(|| self.servers.first()).cloned()
+    }
 }
 
-#[derive(Debug, Clone)]
+/// An output a command can't do without
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequiredOutput {
+    /// Nodes of at least one cloud, from its `<cloud>_cluster` output
+    Nodes,
+    LoadBalancerIp,
+    /// The aggregated `all_server_ips` and `all_agent_ips`
+    NodeIps,
+    LonghornBackupContainer,
+}
+
+impl RequiredOutput {
+    pub fn name(self) -> &'static str {
+        match self {
+            RequiredOutput::Nodes => "<cloud>_cluster (server_ips and agent_ips)",
+            RequiredOutput::LoadBalancerIp => "openstack_cluster.loadbalancer_ip",
+            RequiredOutput::NodeIps => "all_server_ips / all_agent_ips",
+            RequiredOutput::LonghornBackupContainer => "longhorn_backup_info.container_name",
+        }
+    }
+
+    /// How to make the terraform code expose the output
+    pub fn hint(self) -> &'static str {
+        match self {
+            RequiredOutput::Nodes => {
+                "set enable_openstack = true, or expose another cloud's nodes in a <cloud>_cluster output (see 'im-deploy scaffold provider')"
+            }
+            RequiredOutput::LoadBalancerIp => {
+                "set enable_load_balancer = true, or set the primary_api_endpoint output to the API server address"
+            }
+            RequiredOutput::NodeIps => "add each module's server_ips and agent_ips to all_server_ips and all_agent_ips in outputs.tf",
+            RequiredOutput::LonghornBackupContainer => "set enable_longhorn = true and enable_longhorn_backup = true",
+        }
+    }
+}
+
+/// Typed view of `terraform output -json`. Every output may be missing, e.g. when a cloud or
+/// feature is disabled, so commands ask for the ones they need with `require`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ClusterInfo {
-    pub cluster_name: String,
+    /// Whether terraform reported any output at all
+    pub deployed: bool,
+    pub cluster_name: Option<String>,
     pub providers: Vec<CloudProvider>,
     pub primary_api_endpoint: Option<String>,
     pub gpu_enabled: bool,
     pub argocd_enabled: bool,
+    /// Host to fetch the kubeconfig from when the control plane is not among our servers
+    pub kubeconfig_source: Option<String>,
+    pub network_id: Option<String>,
+    pub loadbalancer_ip: Option<String>,
+    pub all_server_ips: Option<Vec<String>>,
+    pub all_agent_ips: Option<Vec<String>>,
+    pub longhorn_backup_container: Option<String>,
 }
 
 impl ClusterInfo {
+    /// Everything `terraform output -json` tells about the cluster
+    pub fn from_terraform_outputs(outputs: &Value) -> Self {
+        let flag = |name: &str| output_value(outputs, name).and_then(|v| v.as_bool()).unwrap_or(false);
+        let text = |value: Option<&Value>| value.and_then(|v| v.as_str()).map(str::to_string);
+        let openstack = |field: &str| text(output_value(outputs, "openstack_cluster").and_then(|cluster| cluster.get(field)));
+        let ips = |name: &str| {
+            output_value(outputs, name)
+                .and_then(|v| v.as_array())
+                .map(|ips| ips.iter().filter_map(|ip| ip.as_str()).map(str::to_string).collect())
+        };
+        Self {
+            deployed: outputs.as_object().is_some_and(|outputs| !outputs.is_empty()),
+            cluster_name: openstack("cluster_name"),
+            providers: cloud_providers_from_outputs(outputs),
+            primary_api_endpoint: output_value(outputs, "primary_api_endpoint")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            gpu_enabled: flag("enable_nvidia_gpu_operator"),
+            argocd_enabled: flag("enable_argocd"),
+            kubeconfig_source: output_value(outputs, "kubeconfig_source")
+                .and_then(|v| v.as_str())
+                .filter(|source| !source.is_empty())
+                .map(str::to_string),
+            network_id: openstack("network_id"),
+            loadbalancer_ip: openstack("loadbalancer_ip"),
+            all_server_ips: ips("all_server_ips"),
+            all_agent_ips: ips("all_agent_ips"),
+            longhorn_backup_container: text(
+                output_value(outputs, "longhorn_backup_info").and_then(|info| info.get("container_name")),
+            ),
+        }
+    }
+
+    /// `value` of an output `needed_for` can't do without, or an error naming the output and
+    /// how to expose it
+    pub fn require<'a, T: ?Sized>(
+        &self,
+        value: Option<&'a T>,
+        output: RequiredOutput,
+        needed_for: &str,
+    ) -> std::result::Result<&'a T, TerraformError> {
+        value.ok_or_else(|| self.missing(output, needed_for))
+    }
+
+    /// Error for a missing output; without any outputs the cluster isn't deployed at all
+    pub fn missing(&self, output: RequiredOutput, needed_for: &str) -> TerraformError {
+        if !self.deployed {
+            return TerraformError::NotDeployed {
+                needed_for: needed_for.to_string(),
+            };
+        }
+        TerraformError::MissingOutput {
+            output: output.name().to_string(),
+            needed_for: needed_for.to_string(),
+            hint: output.hint().to_string(),
+        }
+    }
+
     pub fn total_expected_nodes(&self) -> usize {
         self.providers.iter().map(|p| p.total_nodes()).sum()
     }
@@ -63,6 +520,272 @@ impl ClusterInfo {
     }
 }
 
+/// `value` of a terraform output, None when the output is missing or null
+fn output_value<'a>(outputs: &'a Value, name: &str) -> Option<&'a Value> {
+    outputs.get(name).and_then(|v| v.get("value")).filter(|v| !v.is_null())
+}
+
+const NODE_ROLES: [&str; 2] = ["server", "agent"];
+
+/// Index of the node a Tailscale hostname names: terraform uses `<prefix>-<role>-<i>`, so
+/// `demo-server-0.tailnet.ts.net`, `k3s-server-0` and `server-0` all name server 0
+fn hostname_node_index(hostname: &str, role: &str) -> Option<usize> {
+    let label = hostname.split('.').next().unwrap_or_default();
+    let index = match label.rsplit_once(&format!("-{}-", role)) {
+        Some((_, index)) => index,
+        None => label.strip_prefix(role)?.strip_prefix('-')?,
+    };
+    if index.is_empty() || !index.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    index.parse().ok()
+}
+
+/// Tailscale hostnames of the `node_count` nodes of a role, in node order. Hostnames are
+/// matched by the node they name, since terraform may list them in another order than the
+/// IPs; only a list that names no node at all is taken by position, and only when it covers
+/// every node. Whatever cannot be matched is left out with a warning.
+pub fn assign_tailscale_hostnames(hostnames: &[&str], role: &str, node_count: usize) -> Vec<Option<String>> {
+    if hostnames.is_empty() {
+        return vec![None; node_count];
+    }
+
+    let indexed: Vec<(usize, &str)> = hostnames
+        .iter()
+        .filter_map(|hostname| Some((hostname_node_index(hostname, role)?, *hostname)))
+        .collect();
+    if indexed.is_empty() {
+        let names_other_role = hostnames
+            .iter()
+            .any(|hostname| NODE_ROLES.iter().any(|other| hostname_node_index(hostname, other).is_some()));
+        if !names_other_role && hostnames.len() == node_count {
+            return hostnames.iter().map(|hostname| Some(hostname.to_string())).collect();
+        }
+        warn!(
+            "{} Tailscale {} hostname(s) for {} node(s) and none names its node; not using them",
+            hostnames.len(),
+            role,
+            node_count
+        );
+        return vec![None; node_count];
+    }
+
+    let assigned: Vec<Option<String>> = (0..node_count)
+        .map(|i| indexed.iter().find(|(index, _)| *index == i).map(|(_, hostname)| hostname.to_string()))
+        .collect();
+
+    if indexed.iter().enumerate().any(|(position, (index, _))| position != *index) {
+        warn!("Tailscale {} hostnames are not in node order; matched them by name", role);
+    }
+    for (i, hostname) in assigned.iter().enumerate() {
+        if hostname.is_none() {
+            warn!("No Tailscale hostname for k3s-{}-{}", role, i);
+        }
+    }
+    for hostname in hostnames.iter().filter(|hostname| !assigned.iter().flatten().any(|a| a == *hostname)) {
+        warn!("Tailscale hostname {} matches no {} node", hostname, role);
+    }
+    assigned
+}
+
+/// Suffix of the output each cloud's root module exposes its nodes in, e.g. `openstack_cluster`
+pub const CLUSTER_OUTPUT_SUFFIX: &str = "_cluster";
+
+/// Servers and agents of every cloud in `terraform output -json`, with their Tailscale
+/// hostnames when Tailscale is enabled. Each `<cloud>_cluster` output is a cloud; OpenStack
+/// comes first since monitoring and the kubeconfig use the first one.
+pub fn cloud_providers_from_outputs(outputs: &Value) -> Vec<CloudProvider> {
+    let tailscale_enabled = output_value(outputs, "tailscale_enabled")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let mut clouds: Vec<&str> = outputs
+        .as_object()
+        .into_iter()
+        .flat_map(|outputs| outputs.keys())
+        .filter_map(|key| key.strip_suffix(CLUSTER_OUTPUT_SUFFIX))
+        .filter(|cloud| !cloud.is_empty())
+        .collect();
+    clouds.sort_by_key(|cloud| (*cloud != "openstack", *cloud));
+
+    clouds
+        .into_iter()
+        .filter_map(|cloud| {
+            let cluster = output_value(outputs, &format!("{}{}", cloud, CLUSTER_OUTPUT_SUFFIX))?;
+            cloud_provider_from_output(outputs, cloud, cluster, tailscale_enabled)
+        })
+        .filter(|provider| !provider.servers.is_empty())
+        .collect()
+}
+
+/// Name of a node in im-deploy, e.g. `k3s-server-0`. Clouds other than OpenStack add their
+/// name so nodes of several clouds stay apart, e.g. `k3s-hetzner-server-0`.
+pub fn node_name(cloud: &str, role: &str, index: usize) -> String {
+    if cloud == "openstack" {
+        format!("k3s-{}-{}", role, index)
+    } else {
+        format!("k3s-{}-{}-{}", cloud, role, index)
+    }
+}
+
+fn cloud_provider_from_output(outputs: &Value, cloud: &str, cluster: &Value, tailscale_enabled: bool) -> Option<CloudProvider> {
+    cluster.as_object()?;
+    // Hostnames in the cloud's own output, or in the shared `tailscale_hostnames` output
+    // as `<cloud>_servers` / `<cloud>_agents`
+    let tailscale_hostnames = |role: &str| -> Vec<&str> {
+        if !tailscale_enabled {
+            return Vec::new();
+        }
+        cluster
+            .pointer(&format!("/tailscale_hostnames/{}", role))
+            .or_else(|| output_value(outputs, "tailscale_hostnames").and_then(|v| v.get(format!("{}_{}s", cloud, role))))
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str())
+            .collect()
+    };
+
+    let bastion_ip = cluster
+        .get("bastion_ip")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    let mut servers = Vec::new();
+    for role in NODE_ROLES {
+        let ips: Vec<&str> = cluster
+            .get(format!("{}_ips", role))
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str())
+            .collect();
+        // access_ip_v6 is empty for nodes without an IPv6 address
+        let ipv6s: Vec<&str> = cluster
+            .get(format!("{}_ipv6s", role))
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .map(|v| v.as_str().unwrap_or_default())
+            .collect();
+        // Empty for nodes without a floating IP, like the IPv6 addresses
+        let public_ips: Vec<&str> = cluster
+            .get(format!("{}_public_ips", role))
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .map(|v| v.as_str().unwrap_or_default())
+            .collect();
+        let hostnames = assign_tailscale_hostnames(&tailscale_hostnames(role), role, ips.len());
+        let ssh_user = cluster
+            .pointer(&format!("/ssh_users/{}", role))
+            .and_then(|v| v.as_str())
+            .filter(|user| *user != ssh::SSH_USER)
+            .map(|s| s.to_string());
+        for (i, (ip, tailscale_hostname)) in ips.iter().zip(hostnames).enumerate() {
+            let addresses: Vec<String> = [*ip, ipv6s.get(i).copied().unwrap_or_default()]
+                .into_iter()
+                .filter(|address| !address.is_empty())
+                .map(str::to_string)
+                .collect();
+            servers.push(ServerInfo {
+                name: node_name(cloud, role, i),
+                ip: addresses.first().cloned().unwrap_or_default(),
+                addresses: if addresses.len() > 1 { addresses } else { Vec::new() },
+                cloud_provider: cloud.to_string(),
+                tailscale_hostname,
+                tailscale_ip: None,
+                ssh_user: ssh_user.clone(),
+                public_ip: public_ips.get(i).filter(|ip| !ip.is_empty()).map(|ip| ip.to_string()),
+                tailscale_ssh: false,
+                via_subnet_router: false,
+            });
+        }
+    }
+
+    let name = match cluster.get("display_name").and_then(|v| v.as_str()) {
+        Some(display_name) => display_name.to_string(),
+        None if cloud == "openstack" => "OpenStack".to_string(),
+        None => cloud.to_string(),
+    };
+    let subnet_routes = cluster
+        .get("tailscale_subnet_routes")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str())
+        .filter(|_| tailscale_enabled)
+        .map(|s| s.to_string())
+        .collect();
+    Some(CloudProvider {
+        name,
+        bastion_ip,
+        tailscale_enabled,
+        servers,
+        subnet_routes,
+    })
+}
+
+/// Whether `ip` lies in `cidr`, e.g. `10.0.1.0/24`; false when either does not parse
+fn cidr_contains(cidr: &str, ip: &str) -> bool {
+    let Some((network, prefix)) = cidr.split_once('/') else {
+        return false;
+    };
+    let (Ok(network), Ok(prefix), Ok(ip)) = (network.parse::<IpAddr>(), prefix.parse::<u32>(), ip.parse::<IpAddr>()) else {
+        return false;
+    };
+    match (network, ip) {
+        (IpAddr::V4(network), IpAddr::V4(ip)) if prefix <= 32 => {
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            u32::from(network) & mask == u32::from(ip) & mask
+        }
+        (IpAddr::V6(network), IpAddr::V6(ip)) if prefix <= 128 => {
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            u128::from(network) & mask == u128::from(ip) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Result of requesting the API server's `/livez` through the load balancer from outside
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiProbe {
+    Live,
+    /// The API server answered, e.g. 401 when anonymous auth is disabled
+    Responding(u16),
+    /// The API server answered but reports itself unhealthy
+    Unhealthy(u16),
+    /// The load balancer answered 503 itself: no healthy backend behind it yet
+    NoBackends,
+    Unreachable(String),
+}
+
+impl ApiProbe {
+    pub fn from_status(code: u16) -> Self {
+        match code {
+            200 => ApiProbe::Live,
+            503 => ApiProbe::NoBackends,
+            500..=599 => ApiProbe::Unhealthy(code),
+            _ => ApiProbe::Responding(code),
+        }
+    }
+
+    /// Whether requests reach an API server through the load balancer
+    pub fn passes_traffic(&self) -> bool {
+        matches!(self, ApiProbe::Live | ApiProbe::Responding(_) | ApiProbe::Unhealthy(_))
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            ApiProbe::Live => "live".to_string(),
+            ApiProbe::Responding(code) => format!("responding (HTTP {})", code),
+            ApiProbe::Unhealthy(code) => format!("API server unhealthy (HTTP {})", code),
+            ApiProbe::NoBackends => "no healthy backend yet (HTTP 503)".to_string(),
+            ApiProbe::Unreachable(reason) => format!("unreachable ({})", reason),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -72,25 +795,105 @@ mod tests {
         let server = ServerInfo {
             name: "k3s-server-0".to_string(),
             ip: "10.0.0.1".to_string(),
+            addresses: Vec::new(),
             cloud_provider: "openstack".to_string(),
             tailscale_hostname: None,
+            tailscale_ip: None,
+            ssh_user: None,
+            public_ip: None,
+            tailscale_ssh: false,
+            via_subnet_router: false,
         };
         assert!(server.is_server());
         assert!(!server.is_agent());
     }
 
+    #[test]
+    fn test_instance_name() {
+        assert_eq!(instance_name("demo-openstack", "k3s-server-0"), "demo-openstack-server-0");
+        assert_eq!(instance_name("demo-openstack", "agent-1"), "demo-openstack-agent-1");
+        assert_eq!(instance_name("demo-openstack", "demo-openstack-bastion"), "demo-openstack-bastion");
+    }
+
+    #[test]
+    fn test_assign_tailscale_hostnames() {
+        let names = |hostnames: Vec<Option<String>>| -> Vec<String> {
+            hostnames.into_iter().map(|h| h.unwrap_or_else(|| "-".to_string())).collect()
+        };
+        assert_eq!(
+            names(assign_tailscale_hostnames(&["demo-server-2", "demo-server-0", "demo-server-1"], "server", 3)),
+            ["demo-server-0", "demo-server-1", "demo-server-2"]
+        );
+        assert_eq!(
+            names(assign_tailscale_hostnames(&["k3s-agent-1.tail1234.ts.net", "agent-0"], "agent", 3)),
+            ["agent-0", "k3s-agent-1.tail1234.ts.net", "-"]
+        );
+        // Agent hostnames never name servers
+        assert_eq!(names(assign_tailscale_hostnames(&["demo-agent-0"], "server", 1)), ["-"]);
+        // Other naming schemes only by position, and only when complete
+        assert_eq!(names(assign_tailscale_hostnames(&["alpha", "beta"], "server", 2)), ["alpha", "beta"]);
+        assert_eq!(names(assign_tailscale_hostnames(&["alpha"], "server", 2)), ["-", "-"]);
+        assert_eq!(names(assign_tailscale_hostnames(&[], "server", 2)), ["-", "-"]);
+    }
+
     #[test]
     fn test_server_info_is_agent() {
         let agent = ServerInfo {
             name: "k3s-agent-0".to_string(),
             ip: "10.0.0.2".to_string(),
+            addresses: Vec::new(),
             cloud_provider: "openstack".to_string(),
             tailscale_hostname: None,
+            tailscale_ip: None,
+            ssh_user: None,
+            public_ip: None,
+            tailscale_ssh: false,
+            via_subnet_router: false,
         };
         assert!(!agent.is_server());
         assert!(agent.is_agent());
     }
 
+    #[test]
+    fn test_server_info_matches_node_name() {
+        let server = ServerInfo {
+            name: "k3s-server-1".to_string(),
+            ip: "10.0.0.1".to_string(),
+            addresses: Vec::new(),
+            cloud_provider: "openstack".to_string(),
+            tailscale_hostname: Some("ts-k3s-server-1".to_string()),
+            tailscale_ip: None,
+            ssh_user: None,
+            public_ip: None,
+            tailscale_ssh: false,
+            via_subnet_router: false,
+        };
+        assert!(server.matches_node_name("prod-k3s-server-1"));
+        assert!(server.matches_node_name("ts-k3s-server-1"));
+        assert!(!server.matches_node_name("prod-k3s-server-11"));
+        assert!(!server.matches_node_name("prod-k3s-agent-1"));
+    }
+
+    #[test]
+    fn test_api_probe_from_status() {
+        assert_eq!(ApiProbe::from_status(200), ApiProbe::Live);
+        assert!(ApiProbe::from_status(401).passes_traffic());
+        assert!(ApiProbe::from_status(500).passes_traffic());
+        assert_eq!(ApiProbe::from_status(503), ApiProbe::NoBackends);
+        assert!(!ApiProbe::NoBackends.passes_traffic());
+        assert!(!ApiProbe::Unreachable("timed out".to_string()).passes_traffic());
+    }
+
+    #[test]
+    fn test_parse_node_statuses() {
+        let output = "prod-server-0   Ready      control-plane,etcd,master   5m   v1.30.4+k3s1\n\
+                      prod-agent-0    NotReady   <none>                      1m   v1.30.4+k3s1\n";
+        let statuses = parse_node_statuses(output);
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(statuses["prod-server-0"], "Ready");
+        assert_eq!(statuses["prod-agent-0"], "NotReady");
+    }
+
     #[test]
     fn test_cloud_provider_counts() {
         let provider = CloudProvider {
@@ -101,22 +904,41 @@ mod tests {
                 ServerInfo {
                     name: "k3s-server-0".to_string(),
                     ip: "10.0.0.1".to_string(),
+                    addresses: Vec::new(),
                     cloud_provider: "openstack".to_string(),
                     tailscale_hostname: None,
+                    tailscale_ip: None,
+                    ssh_user: None,
+                    public_ip: None,
+                    tailscale_ssh: false,
+                    via_subnet_router: false,
                 },
                 ServerInfo {
                     name: "k3s-agent-0".to_string(),
                     ip: "10.0.0.2".to_string(),
+                    addresses: Vec::new(),
                     cloud_provider: "openstack".to_string(),
                     tailscale_hostname: None,
+                    tailscale_ip: None,
+                    ssh_user: None,
+                    public_ip: None,
+                    tailscale_ssh: false,
+                    via_subnet_router: false,
                 },
                 ServerInfo {
                     name: "k3s-agent-1".to_string(),
                     ip: "10.0.0.3".to_string(),
+                    addresses: Vec::new(),
                     cloud_provider: "openstack".to_string(),
                     tailscale_hostname: None,
+                    tailscale_ip: None,
+                    ssh_user: None,
+                    public_ip: None,
+                    tailscale_ssh: false,
+                    via_subnet_router: false,
                 },
             ],
+            subnet_routes: Vec::new(),
         };
 
         assert_eq!(provider.server_count(), 1);
@@ -134,16 +956,29 @@ mod tests {
                 ServerInfo {
                     name: "k3s-agent-0".to_string(),
                     ip: "10.0.0.2".to_string(),
+                    addresses: Vec::new(),
                     cloud_provider: "openstack".to_string(),
                     tailscale_hostname: None,
+                    tailscale_ip: None,
+                    ssh_user: None,
+                    public_ip: None,
+                    tailscale_ssh: false,
+                    via_subnet_router: false,
                 },
                 ServerInfo {
                     name: "k3s-server-0".to_string(),
                     ip: "10.0.0.1".to_string(),
+                    addresses: Vec::new(),
                     cloud_provider: "openstack".to_string(),
                     tailscale_hostname: Some("server-0.tailscale.net".to_string()),
+                    tailscale_ip: None,
+                    ssh_user: None,
+                    public_ip: None,
+                    tailscale_ssh: false,
+                    via_subnet_router: false,
                 },
             ],
+            subnet_routes: Vec::new(),
         };
 
         let first_server = provider.get_first_server();
@@ -154,7 +989,7 @@ mod tests {
     #[test]
     fn test_cluster_info_total_nodes_multiple_providers() {
         let cluster_info = ClusterInfo {
-            cluster_name: "multi-cloud".to_string(),
+            cluster_name: Some("multi-cloud".to_string()),
             providers: vec![
                 CloudProvider {
                     name: "OpenStack".to_string(),
@@ -164,16 +999,29 @@ mod tests {
                         ServerInfo {
                             name: "k3s-server-0".to_string(),
                             ip: "10.0.0.1".to_string(),
+                            addresses: Vec::new(),
                             cloud_provider: "openstack".to_string(),
                             tailscale_hostname: None,
+                            tailscale_ip: None,
+                            ssh_user: None,
+                            public_ip: None,
+                            tailscale_ssh: false,
+                            via_subnet_router: false,
                         },
                         ServerInfo {
                             name: "k3s-agent-0".to_string(),
                             ip: "10.0.0.2".to_string(),
+                            addresses: Vec::new(),
                             cloud_provider: "openstack".to_string(),
                             tailscale_hostname: None,
+                            tailscale_ip: None,
+                            ssh_user: None,
+                            public_ip: None,
+                            tailscale_ssh: false,
+                            via_subnet_router: false,
                         },
                     ],
+                    subnet_routes: Vec::new(),
                 },
                 CloudProvider {
                     name: "AWS".to_string(),
@@ -182,14 +1030,19 @@ mod tests {
                     servers: vec![ServerInfo {
                         name: "k3s-agent-1".to_string(),
                         ip: "172.16.0.1".to_string(),
+                        addresses: Vec::new(),
                         cloud_provider: "aws".to_string(),
                         tailscale_hostname: None,
+                        tailscale_ip: None,
+                        ssh_user: None,
+                        public_ip: None,
+                        tailscale_ssh: false,
+                        via_subnet_router: false,
                     }],
+                    subnet_routes: Vec::new(),
                 },
             ],
-            primary_api_endpoint: None,
-            gpu_enabled: false,
-            argocd_enabled: false,
+            ..Default::default()
         };
 
         assert_eq!(cluster_info.total_expected_nodes(), 3);
@@ -198,24 +1051,24 @@ mod tests {
     #[test]
     fn test_cluster_info_primary_provider_returns_first() {
         let cluster_info = ClusterInfo {
-            cluster_name: "test".to_string(),
+            cluster_name: Some("test".to_string()),
             providers: vec![
                 CloudProvider {
                     name: "Provider1".to_string(),
                     bastion_ip: None,
                     tailscale_enabled: false,
                     servers: vec![],
+                    subnet_routes: Vec::new(),
                 },
                 CloudProvider {
                     name: "Provider2".to_string(),
                     bastion_ip: None,
                     tailscale_enabled: false,
                     servers: vec![],
+                    subnet_routes: Vec::new(),
                 },
             ],
-            primary_api_endpoint: None,
-            gpu_enabled: false,
-            argocd_enabled: false,
+            ..Default::default()
         };
 
         let primary = cluster_info.primary_provider();
@@ -223,6 +1076,28 @@ mod tests {
         assert_eq!(primary.unwrap().name, "Provider1");
     }
 
+    #[test]
+    fn test_cluster_info_require_names_missing_output() {
+        let cluster_info = ClusterInfo {
+            deployed: true,
+            loadbalancer_ip: Some("5.6.7.8".to_string()),
+            ..Default::default()
+        };
+        let ip = cluster_info.require(cluster_info.loadbalancer_ip.as_deref(), RequiredOutput::LoadBalancerIp, "the kubeconfig");
+        assert_eq!(ip.unwrap(), "5.6.7.8");
+
+        let err = cluster_info
+            .require(cluster_info.longhorn_backup_container.as_deref(), RequiredOutput::LonghornBackupContainer, "the Swift upload")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Terraform output longhorn_backup_info.container_name is missing, the Swift upload needs it; set enable_longhorn = true and enable_longhorn_backup = true"
+        );
+
+        let not_deployed = ClusterInfo::default().missing(RequiredOutput::Nodes, "'im-deploy info'");
+        assert!(matches!(not_deployed, TerraformError::NotDeployed { .. }));
+    }
+
     #[test]
     fn test_cloud_provider_empty_servers() {
         let provider = CloudProvider {
@@ -230,6 +1105,7 @@ mod tests {
             bastion_ip: None,
             tailscale_enabled: false,
             servers: vec![],
+            subnet_routes: Vec::new(),
         };
 
         assert_eq!(provider.server_count(), 0);
@@ -238,13 +1114,130 @@ mod tests {
         assert!(provider.get_first_server().is_none());
     }
 
+    #[test]
+    fn test_control_node_without_servers() {
+        let agent = |i: usize| ServerInfo {
+            name: format!("k3s-agent-{}", i),
+            ip: format!("10.0.0.{}", 10 + i),
+            addresses: Vec::new(),
+            cloud_provider: "openstack".to_string(),
+            tailscale_hostname: Some(format!("demo-agent-{}", i)),
+            tailscale_ip: None,
+            ssh_user: None,
+            public_ip: None,
+            tailscale_ssh: false,
+            via_subnet_router: false,
+        };
+        let provider = CloudProvider {
+            name: "OpenStack".to_string(),
+            bastion_ip: None,
+            tailscale_enabled: true,
+            servers: vec![agent(0), agent(1)],
+            subnet_routes: Vec::new(),
+        };
+
+        assert_eq!(provider.control_node(None).unwrap().name, "k3s-agent-0");
+        assert_eq!(provider.control_node(Some("demo-agent-1")).unwrap().name, "k3s-agent-1");
+        assert_eq!(provider.control_node(Some("10.0.0.11")).unwrap().name, "k3s-agent-1");
+
+        let external = provider.control_node(Some("cp.example.ts.net")).unwrap();
+        assert_eq!(external.name, "cp.example.ts.net");
+        assert_eq!(external.tailscale_hostname.as_deref(), Some("cp.example.ts.net"));
+
+        let empty = CloudProvider { servers: vec![], ..provider };
+        assert!(empty.control_node(None).is_none());
+        assert!(empty.control_node(Some("10.1.0.5")).is_some());
+    }
+
+    #[test]
+    fn test_subnet_router_reaches_private_ips() {
+        assert!(cidr_contains("10.0.1.0/24", "10.0.1.42"));
+        assert!(!cidr_contains("10.0.1.0/24", "10.0.2.42"));
+        assert!(cidr_contains("0.0.0.0/0", "192.0.2.1"));
+        assert!(cidr_contains("fd00:1::/64", "fd00:1::10"));
+        assert!(!cidr_contains("10.0.1.0/24", "fd00:1::10"));
+        assert!(!cidr_contains("10.0.1.0/33", "10.0.1.42"));
+        assert!(!cidr_contains("not a cidr", "10.0.1.42"));
+
+        let outputs = serde_json::json!({
+            "tailscale_enabled": {"value": true},
+            "openstack_cluster": {"value": {
+                "server_ips": ["10.0.1.10"],
+                "agent_ips": ["10.0.2.20"],
+                "tailscale_hostnames": {"server": ["demo-server-0"], "agent": ["demo-agent-0"]},
+                "tailscale_subnet_routes": ["10.0.1.0/24"]
+            }}
+        });
+        let mut provider = cloud_providers_from_outputs(&outputs).remove(0);
+        assert_eq!(provider.subnet_routes, ["10.0.1.0/24"]);
+        assert!(provider.routes_over_tailnet("10.0.1.10"));
+
+        provider.use_subnet_router();
+        let routed: Vec<bool> = provider.servers.iter().map(|s| s.via_subnet_router).collect();
+        assert_eq!(routed, [true, false]);
+    }
+
+    #[test]
+    fn test_unserved_subnet_routes_are_dropped() {
+        let outputs = serde_json::json!({
+            "tailscale_enabled": {"value": true},
+            "openstack_cluster": {"value": {
+                "server_ips": ["10.0.1.10"],
+                "agent_ips": [],
+                "tailscale_hostnames": {"server": ["demo-server-0"], "agent": []},
+                "tailscale_subnet_routes": ["10.0.1.0/24"]
+            }}
+        });
+        let mut provider = cloud_providers_from_outputs(&outputs).remove(0);
+        assert_eq!(provider.keep_served_routes(&["10.0.1.0/24".to_string()]), Vec::<String>::new());
+        assert_eq!(provider.subnet_routes, ["10.0.1.0/24"]);
+
+        // A server created before the router was turned on never advertises the route
+        assert_eq!(provider.keep_served_routes(&[]), ["10.0.1.0/24"]);
+        provider.use_subnet_router();
+        assert!(!provider.servers[0].via_subnet_router);
+        assert!(!provider.routes_over_tailnet("10.0.1.10"));
+    }
+
+    #[test]
+    fn test_unresolved_tailscale_hostnames_use_peer_ips() {
+        let outputs = serde_json::json!({
+            "tailscale_enabled": {"value": true},
+            "openstack_cluster": {"value": {
+                "server_ips": ["10.0.1.10", "10.0.1.11"],
+                "agent_ips": ["10.0.2.20"],
+                "tailscale_hostnames": {"server": ["demo-server-0", "demo-server-1"], "agent": ["demo-agent-0"]}
+            }}
+        });
+        let mut provider = cloud_providers_from_outputs(&outputs).remove(0);
+        let peers: BTreeMap<String, String> = [("demo-server-0", "100.64.0.1"), ("demo-agent-0", "100.64.0.3")]
+            .into_iter()
+            .map(|(name, ip)| (name.to_string(), ip.to_string()))
+            .collect();
+
+        // demo-agent-0 resolves and demo-server-1 is not a peer, both keep their hostname
+        let changed = provider.use_tailscale_ips(&peers, |hostname| hostname == "demo-agent-0");
+        assert_eq!(changed, [provider.servers[0].name.clone()]);
+        let ips: Vec<Option<&str>> = provider.servers.iter().map(|s| s.tailscale_ip.as_deref()).collect();
+        assert_eq!(ips, [Some("100.64.0.1"), None, None]);
+        // The hostname stays, so the node is still found by its name
+        assert_eq!(provider.servers[0].tailscale_hostname.as_deref(), Some("demo-server-0"));
+        assert!(provider.servers[0].matches_node_name("demo-server-0"));
+    }
+
     #[test]
     fn test_server_info_serialization() {
         let server = ServerInfo {
             name: "test-server".to_string(),
             ip: "192.168.1.1".to_string(),
+            addresses: Vec::new(),
             cloud_provider: "test-cloud".to_string(),
             tailscale_hostname: Some("test.ts.net".to_string()),
+            tailscale_ip: None,
+            ssh_user: None,
+            public_ip: None,
+            tailscale_ssh: false,
+            via_subnet_router: false,
         };
 
         // Serialize to JSON
@@ -259,6 +1252,106 @@ mod tests {
         assert_eq!(deserialized.ip, server.ip);
         assert_eq!(deserialized.tailscale_hostname, server.tailscale_hostname);
     }
-}
 
+    #[test]
+    fn test_parse_pod_health() {
+        let output = "\
+kube-system   coredns-6799fbcd5-x2x9q                   1/1   Running     0             12m
+kube-system   helm-install-traefik-8fkx2                0/1   Completed   1             12m
+kube-system   svclb-traefik-4c1b2a-7xk2p                1/2   Running     3 (2m ago)    11m
+argocd        argocd-repo-server-5d9f8b6c4-abcde        0/1   ImagePullBackOff   0      5m
+";
+        let pods = parse_pod_health(output);
+        assert_eq!(pods.len(), 4);
+        assert!(pods[0].is_healthy());
+        assert!(pods[1].is_healthy());
+        assert!(!pods[2].is_healthy());
+        assert_eq!(pods[2].restarts, 3);
+        assert_eq!(pods[3].namespace, "argocd");
+        assert!(!pods[3].is_healthy());
+    }
+
+    fn server(name: &str) -> ServerInfo {
+        ServerInfo {
+            name: name.to_string(),
+            ip: "10.0.0.1".to_string(),
+            addresses: Vec::new(),
+            cloud_provider: "openstack".to_string(),
+            tailscale_hostname: None,
+            tailscale_ip: None,
+            ssh_user: None,
+            public_ip: None,
+            tailscale_ssh: false,
+            via_subnet_router: false,
+        }
+    }
+
+    #[test]
+    fn test_parse_nodes_json() {
+        let json: Value = serde_json::from_str(
+            r#"{"items": [
+                {"metadata": {"name": "demo-server-0"}, "status": {"conditions": [
+                    {"type": "MemoryPressure", "status": "False"}, {"type": "Ready", "status": "True"}]}},
+                {"metadata": {"name": "demo-agent-0"}, "status": {"conditions": [{"type": "Ready", "status": "Unknown"}]}}
+            ]}"#,
+        )
+        .unwrap();
+        let nodes = parse_nodes_json(&json);
+        assert!(nodes["demo-server-0"]);
+        assert!(!nodes["demo-agent-0"]);
+    }
 
+    #[test]
+    fn test_parse_ready_since() {
+        let json: Value = serde_json::from_str(
+            r#"{"items": [
+                {"metadata": {"name": "demo-server-0"}, "status": {"conditions": [
+                    {"type": "Ready", "status": "True", "lastTransitionTime": "2025-01-02T03:04:05Z",
+                     "lastHeartbeatTime": "2025-01-02T04:00:00Z"}]}},
+                {"metadata": {"name": "demo-agent-0"}, "status": {"conditions": [
+                    {"type": "Ready", "status": "Unknown", "lastTransitionTime": "2025-01-02T03:04:05Z"}]}}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(parse_ready_since(&json), BTreeMap::from([("demo-server-0".to_string(), 1735787045)]));
+        assert_eq!(parse_ready_heartbeats(&json), BTreeMap::from([("demo-server-0".to_string(), 1735790400)]));
+    }
+
+    #[test]
+    fn test_node_diff() {
+        let expected = [server("k3s-server-0"), server("k3s-agent-0"), server("k3s-agent-1")];
+        let complete = BTreeMap::from([
+            ("demo-server-0".to_string(), true),
+            ("demo-agent-0".to_string(), true),
+            ("demo-agent-1".to_string(), true),
+        ]);
+        assert!(NodeDiff::compare(&expected, &complete).is_complete());
+
+        // Same count of Ready nodes, but agent-1 never joined and agent-0 registered twice
+        let nodes = BTreeMap::from([
+            ("demo-server-0".to_string(), true),
+            ("demo-agent-0".to_string(), true),
+            ("old-demo-agent-0".to_string(), true),
+            ("stray".to_string(), false),
+        ]);
+        let diff = NodeDiff::compare(&expected, &nodes);
+        assert!(!diff.is_complete());
+        assert_eq!(diff.missing, vec!["k3s-agent-1"]);
+        assert_eq!(diff.unexpected, vec!["stray"]);
+        assert_eq!(diff.duplicated[0].0, "k3s-agent-0");
+        assert_eq!(diff.duplicated[0].1.len(), 2);
+        assert!(diff.not_ready.is_empty());
+    }
+
+    #[test]
+    fn test_core_workloads_ready() {
+        let ready = Some(Readiness { ready: 1, desired: 1 });
+        let starting = Some(Readiness { ready: 0, desired: 1 });
+        let [coredns, _, _, _, longhorn, _] = CORE_WORKLOADS;
+
+        assert!(core_workloads_ready(&[(coredns, ready), (longhorn, None)]));
+        assert!(!core_workloads_ready(&[(coredns, starting), (longhorn, None)]));
+        assert!(!core_workloads_ready(&[(coredns, None)]));
+        assert!(!core_workloads_ready(&[(coredns, ready), (longhorn, starting)]));
+    }
+}
@@ -1,67 +1,237 @@
-use crate::constants::ssh;
-use crate::domain::cluster::ServerInfo;
+use crate::constants::{ssh, tailscale as ts};
+use crate::domain::cluster::{IpFamily, ServerInfo};
 use crate::errors::{Result, SshError};
+use crate::runner::CommandRunner;
+use std::fs::File;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-use tracing::debug;
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, warn};
 
 #[derive(Debug, Clone)]
 pub enum ConnectionStrategy {
-    Tailscale { hostname: String },
-    Bastion { bastion_ip: String, target_ip: String },
+    /// `user` is the login of the node's image; the bastion always runs the default image
+    Tailscale { hostname: String, user: String },
+    /// `tailscale ssh` to the node, authorized by the tailnet's SSH policy instead of keys
+    TailscaleSsh { hostname: String, user: String },
+    Bastion { bastion_ip: String, target_ip: String, user: String },
+    /// The host itself at an address this machine reaches: the bastion, a node's floating
+    /// IP, or its private IP through a Tailscale subnet router
+    Direct { host: String, user: String },
 }
 
 impl ConnectionStrategy {
     pub fn from_server(server: &ServerInfo, bastion_ip: Option<&str>) -> Result<Self> {
-        if let Some(ref hostname) = server.tailscale_hostname {
-            Ok(ConnectionStrategy::Tailscale {
-                hostname: hostname.clone(),
+        if server.via_subnet_router {
+            Ok(ConnectionStrategy::Direct {
+                host: server.ip.clone(),
+                user: server.login_user().to_string(),
             })
+        } else if let Some(hostname) = server.tailscale_ip.as_ref().or(server.tailscale_hostname.as_ref()) {
+            let hostname = hostname.clone();
+            let user = server.login_user().to_string();
+            if server.tailscale_ssh {
+                Ok(ConnectionStrategy::TailscaleSsh { hostname, user })
+            } else {
+                Ok(ConnectionStrategy::Tailscale { hostname, user })
+            }
         } else if let Some(bastion) = bastion_ip {
             Ok(ConnectionStrategy::Bastion {
                 bastion_ip: bastion.to_string(),
                 target_ip: server.ip.clone(),
+                user: server.login_user().to_string(),
+            })
+        } else if let Some(ref public_ip) = server.public_ip {
+            Ok(ConnectionStrategy::Direct {
+                host: public_ip.clone(),
+                user: server.login_user().to_string(),
             })
         } else {
             Err(SshError::NoConnectionMethod.into())
         }
     }
 
+    /// Arguments of the client: OpenSSH options and the destination, or `ssh <destination>`
+    /// for the Tailscale CLI
     pub fn build_ssh_args(&self) -> Vec<String> {
         match self {
-            ConnectionStrategy::Tailscale { hostname } => {
+            ConnectionStrategy::TailscaleSsh { hostname, user } => {
+                vec!["ssh".to_string(), format!("{}@{}", user, hostname)]
+            }
+            ConnectionStrategy::Tailscale { hostname: host, user } | ConnectionStrategy::Direct { host, user } => {
                 vec![
                     "-o".to_string(),
                     ssh::SSH_STRICT_HOST_KEY_CHECKING.to_string(),
-                    format!("{}@{}", ssh::SSH_USER, hostname),
+                    format!("{}@{}", user, host),
                 ]
             }
             ConnectionStrategy::Bastion {
                 bastion_ip,
                 target_ip,
+                user,
             } => {
-                vec![
-                    "-J".to_string(),
-                    format!("{}@{}", ssh::SSH_USER, bastion_ip),
+                let proxy_ssh = cfg!(windows).then(ssh_program);
+                let mut args = jump_args(bastion_ip, target_ip, proxy_ssh.as_deref());
+                args.extend([
                     "-o".to_string(),
                     ssh::SSH_STRICT_HOST_KEY_CHECKING.to_string(),
-                    format!("{}@{}", ssh::SSH_USER, target_ip),
-                ]
+                    format!("{}@{}", user, target_ip),
+                ]);
+                args
             }
         }
     }
 
-    pub fn execute_interactive(&self) -> Result<()> {
+    /// SSH arguments that log in with `identity` only, on the bastion too, e.g. to check a new
+    /// key before the old one is removed
+    pub fn build_ssh_args_with_identity(&self, identity: &Path) -> Vec<String> {
+        let only_identity = format!("-i \"{}\" -o IdentitiesOnly=yes", identity.display());
+        let mut args = vec![
+            "-i".to_string(),
+            identity.display().to_string(),
+            "-o".to_string(),
+            "IdentitiesOnly=yes".to_string(),
+        ];
+        match self {
+            // A -J hop would log in to the bastion with the default keys
+            ConnectionStrategy::Bastion { bastion_ip, target_ip, user } => args.extend([
+                "-o".to_string(),
+                format!(
+                    "ProxyCommand=\"{}\" {} -W {} {}@{}",
+                    ssh_program().display(),
+                    only_identity,
+                    forward_target(target_ip),
+                    ssh::SSH_USER,
+                    bastion_ip
+                ),
+                "-o".to_string(),
+                ssh::SSH_STRICT_HOST_KEY_CHECKING.to_string(),
+                format!("{}@{}", user, target_ip),
+            ]),
+            // Keys only matter to OpenSSH, so check them without `tailscale ssh`
+            ConnectionStrategy::TailscaleSsh { hostname, user } => args.extend([
+                "-o".to_string(),
+                ssh::SSH_STRICT_HOST_KEY_CHECKING.to_string(),
+                format!("{}@{}", user, hostname),
+            ]),
+            _ => args.extend(self.build_ssh_args()),
+        }
+        args
+    }
+
+    /// Client arguments with OpenSSH `options`. `tailscale ssh` takes them after the
+    /// destination and hands them on to OpenSSH, which accepts options there too.
+    fn ssh_args(&self, options: Vec<String>) -> Vec<String> {
+        match self {
+            ConnectionStrategy::TailscaleSsh { .. } => {
+                let mut args = self.build_ssh_args();
+                args.extend(options);
+                args
+            }
+            _ => {
+                let mut args = options;
+                args.extend(self.build_ssh_args());
+                args
+            }
+        }
+    }
+
+    /// OpenSSH, or the Tailscale CLI for Tailscale SSH
+    fn command(&self) -> Command {
+        match self {
+            ConnectionStrategy::TailscaleSsh { .. } => Command::new(tailscale_program()),
+            _ => ssh_command(),
+        }
+    }
+
+    /// Host the SSH connection is opened to: the Tailscale node, the bastion or the public address
+    pub fn first_hop(&self) -> &str {
+        match self {
+            ConnectionStrategy::Tailscale { hostname, .. } | ConnectionStrategy::TailscaleSsh { hostname, .. } => hostname,
+            ConnectionStrategy::Bastion { bastion_ip, .. } => bastion_ip,
+            ConnectionStrategy::Direct { host, .. } => host,
+        }
+    }
+
+    /// Wait until SSH works end to end, retrying with backoff. Right after apply the bastion
+    /// or Tailscale node often does not accept connections yet.
+    pub fn wait_until_reachable(&self, runner: &dyn CommandRunner) -> Result<()> {
+        let delays = probe_delays();
+        let mut attempt = 1;
+
+        loop {
+            let reason = match self.probe(runner) {
+                Ok(()) => return Ok(()),
+                Err(reason) => reason,
+            };
+            let Some(delay) = delays.get(attempt as usize - 1) else {
+                return Err(SshError::Unreachable {
+                    host: self.first_hop().to_string(),
+                    attempts: attempt,
+                    reason,
+                }
+                .into());
+            };
+
+            warn!(
+                "SSH to {} not ready ({}), retrying in {}s (attempt {}/{})",
+                self.first_hop(),
+                reason,
+                delay.as_secs(),
+                attempt,
+                ssh::PROBE_MAX_ATTEMPTS
+            );
+            thread::sleep(*delay);
+            attempt += 1;
+        }
+    }
+
+    /// Dial port 22 of the first hop, then run `true` over the full connection
+    fn probe(&self, runner: &dyn CommandRunner) -> std::result::Result<(), String> {
+        let mut args = self.ssh_args(vec![
+            "-o".to_string(),
+            "BatchMode=yes".to_string(),
+            "-o".to_string(),
+            format!("ConnectTimeout={}", ssh::PROBE_CONNECT_TIMEOUT_SECS),
+        ]);
+        args.push("true".to_string());
+
+        let output = runner
+            .output(self.command().args(&args).stdin(Stdio::null()))
+            .map_err(|e| e.to_string())?;
+        if output.status.success() {
+            return Ok(());
+        }
+
+        // Name the network problem where there is one, ssh's own complaint otherwise
+        let host = self.first_hop();
+        let timeout = Duration::from_secs(ssh::PROBE_CONNECT_TIMEOUT_SECS);
+        let addrs = (host, ssh::SSH_PORT)
+            .to_socket_addrs()
+            .map_err(|e| format!("cannot resolve {}: {}", host, e))?;
+        if !addrs.into_iter().any(|addr| TcpStream::connect_timeout(&addr, timeout).is_ok()) {
+            return Err(format!("port {} closed", ssh::SSH_PORT));
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(stderr.lines().last().unwrap_or("ssh failed").trim().to_string())
+    }
+
+    pub fn execute_interactive(&self, runner: &dyn CommandRunner) -> Result<()> {
         debug!("Establishing SSH connection: {:?}", self);
 
         let args = self.build_ssh_args();
-        debug!("SSH command: ssh {}", args.join(" "));
-
-        let status = Command::new("ssh")
-            .args(&args)
-            .stdin(Stdio::inherit())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .status()
+        debug!("SSH command: {:?} {}", self.command().get_program(), args.join(" "));
+
+        let status = runner
+            .status(
+                self.command()
+                    .args(&args)
+                    .stdin(Stdio::inherit())
+                    .stdout(Stdio::inherit())
+                    .stderr(Stdio::inherit()),
+            )
             .map_err(|e| SshError::ConnectionFailed(e.to_string()))?;
 
         if !status.success() {
@@ -75,41 +245,253 @@ impl ConnectionStrategy {
         Ok(())
     }
 
-    pub fn execute_command(&self, command: &str) -> Result<std::process::Output> {
+    /// Host a SOCKS proxy should exit from: a Tailscale or public node as is, the bastion
+    /// itself instead of a node behind it, since it sits in the private network too
+    pub fn tunnel_endpoint(self) -> Self {
+        match self {
+            ConnectionStrategy::Bastion { bastion_ip, .. } => ConnectionStrategy::Direct {
+                host: bastion_ip,
+                user: ssh::SSH_USER.to_string(),
+            },
+            other => other,
+        }
+    }
+
+    /// SSH arguments that open a dynamic SOCKS proxy on the local `port` and run nothing else
+    pub fn build_tunnel_args(&self, port: u16) -> Vec<String> {
+        self.ssh_args(vec![
+            "-N".to_string(),
+            "-D".to_string(),
+            format!("127.0.0.1:{}", port),
+            "-o".to_string(),
+            "ExitOnForwardFailure=yes".to_string(),
+        ])
+    }
+
+    /// Keep a SOCKS proxy open until SSH exits, e.g. on Ctrl+C
+    pub fn execute_tunnel(&self, runner: &dyn CommandRunner, port: u16) -> Result<()> {
+        let args = self.build_tunnel_args(port);
+        debug!("SSH command: {:?} {}", self.command().get_program(), args.join(" "));
+
+        let status = runner
+            .status(
+                self.command()
+                    .args(&args)
+                    .stdin(Stdio::inherit())
+                    .stdout(Stdio::inherit())
+                    .stderr(Stdio::inherit()),
+            )
+            .map_err(|e| SshError::ConnectionFailed(e.to_string()))?;
+
+        // 130 is the exit of an interrupted ssh, the usual way to close the tunnel
+        if !status.success() && status.code() != Some(130) {
+            return Err(SshError::ConnectionFailed(format!(
+                "SSH tunnel exited with code {:?}",
+                status.code()
+            ))
+            .into());
+        }
+
+        Ok(())
+    }
+
+    pub fn execute_command(&self, runner: &dyn CommandRunner, command: &str) -> Result<std::process::Output> {
         debug!("Executing command over SSH: {}", command);
+        run_command(runner, self.command(), self.build_ssh_args(), command)
+    }
+
+    /// Run a command that must not hang on an unreachable node: no prompts, and a bounded
+    /// connect time
+    pub fn execute_probe(&self, runner: &dyn CommandRunner, command: &str) -> Result<std::process::Output> {
+        debug!("Probing over SSH: {}", command);
+        let args = self.ssh_args(vec![
+            "-o".to_string(),
+            "BatchMode=yes".to_string(),
+            "-o".to_string(),
+            format!("ConnectTimeout={}", ssh::PROBE_CONNECT_TIMEOUT_SECS),
+        ]);
+        run_command(runner, self.command(), args, command)
+    }
+
+    /// Run a command logging in with `identity` only
+    pub fn execute_command_with_identity(
+        &self,
+        runner: &dyn CommandRunner,
+        identity: &Path,
+        command: &str,
+    ) -> Result<std::process::Output> {
+        debug!("Executing command over SSH with {}: {}", identity.display(), command);
+        let mut args = vec!["-o".to_string(), "BatchMode=yes".to_string()];
+        args.extend(self.build_ssh_args_with_identity(identity));
+        run_command(runner, ssh_command(), args, command)
+    }
+
+    /// Run a command over SSH with `file` as its stdin, e.g. to upload a backup
+    pub fn execute_command_from_file(&self, runner: &dyn CommandRunner, command: &str, file: File) -> Result<()> {
+        debug!("Executing command over SSH from file: {}", command);
 
         let mut args = self.build_ssh_args();
         args.push(command.to_string());
 
-        debug!("SSH command: ssh {}", args.join(" "));
+        let status = runner
+            .status(
+                self.command()
+                    .args(&args)
+                    .stdin(Stdio::from(file))
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::inherit()),
+            )
+            .map_err(|e| SshError::ConnectionFailed(e.to_string()))?;
+
+        if !status.success() {
+            return Err(SshError::CommandFailed {
+                command: command.to_string(),
+            }
+            .into());
+        }
 
-        let output = Command::new("ssh")
-            .args(&args)
-            .output()
+        Ok(())
+    }
+
+    /// Run a command over SSH and stream its stdout into `file`, e.g. for large dumps
+    pub fn execute_command_to_file(&self, runner: &dyn CommandRunner, command: &str, file: File) -> Result<()> {
+        debug!("Executing command over SSH into file: {}", command);
+
+        let mut args = self.build_ssh_args();
+        args.push(command.to_string());
+
+        let status = runner
+            .status(
+                self.command()
+                    .args(&args)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::from(file))
+                    .stderr(Stdio::inherit()),
+            )
             .map_err(|e| SshError::ConnectionFailed(e.to_string()))?;
 
-        if !output.status.success() {
+        if !status.success() {
             return Err(SshError::CommandFailed {
                 command: command.to_string(),
             }
             .into());
         }
 
-        Ok(output)
+        Ok(())
+    }
+}
+
+fn run_command(runner: &dyn CommandRunner, mut client: Command, mut args: Vec<String>, command: &str) -> Result<std::process::Output> {
+    args.push(command.to_string());
+    debug!("SSH command: {:?} {}", client.get_program(), args.join(" "));
+
+    let output = runner
+        .output(client.args(&args))
+        .map_err(|e| SshError::ConnectionFailed(e.to_string()))?;
+
+    if !output.status.success() {
+        return Err(SshError::CommandFailed {
+            command: command.to_string(),
+        }
+        .into());
+    }
+
+    Ok(output)
+}
+
+/// `host` as `-J`, `-W` and scp-style `host:path` targets expect it, IPv6 addresses in
+/// brackets. Plain ssh destinations take IPv6 addresses as they are.
+pub fn bracket_host(host: &str) -> String {
+    if IpFamily::of(host) == IpFamily::Ipv6 && !host.starts_with('[') {
+        format!("[{}]", host)
+    } else {
+        host.to_string()
+    }
+}
+
+/// `-W` argument forwarding to the target through the bastion
+fn forward_target(target_ip: &str) -> &'static str {
+    match IpFamily::of(target_ip) {
+        IpFamily::Ipv4 => "%h:%p",
+        IpFamily::Ipv6 => "[%h]:%p",
+    }
+}
+
+/// Quote text as one argument of a remote shell command
+pub fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// The ssh client. Resolved up front so Windows finds `ssh.exe` even where the bare name
+/// does not reach the OpenSSH install.
+fn ssh_program() -> PathBuf {
+    which::which("ssh").unwrap_or_else(|_| PathBuf::from("ssh"))
+}
+
+fn ssh_command() -> Command {
+    Command::new(ssh_program())
+}
+
+/// The Tailscale CLI for `tailscale ssh`: on PATH, or inside the app bundle on macOS
+fn tailscale_program() -> PathBuf {
+    which::which("tailscale").unwrap_or_else(|_| {
+        let app_cli = Path::new(ts::MACOS_APP_CLI);
+        if cfg!(target_os = "macos") && app_cli.is_file() {
+            app_cli.to_path_buf()
+        } else {
+            PathBuf::from("tailscale")
+        }
+    })
+}
+
+/// Hop through the bastion. Windows OpenSSH starts the ProxyJump connection with a bare
+/// `ssh`, which it cannot find on its own, so there the jump is spelled out as a
+/// ProxyCommand with the full path.
+fn jump_args(bastion_ip: &str, target_ip: &str, proxy_ssh: Option<&Path>) -> Vec<String> {
+    match proxy_ssh {
+        Some(program) => vec![
+            "-o".to_string(),
+            format!(
+                "ProxyCommand=\"{}\" -W {} {}@{}",
+                program.display(),
+                forward_target(target_ip),
+                ssh::SSH_USER,
+                bastion_ip
+            ),
+        ],
+        None => vec!["-J".to_string(), format!("{}@{}", ssh::SSH_USER, bracket_host(bastion_ip))],
     }
 }
 
+/// Delays between reachability probes: exponential backoff, capped
+fn probe_delays() -> Vec<Duration> {
+    let mut delay = ssh::PROBE_INITIAL_DELAY_SECS;
+    (1..ssh::PROBE_MAX_ATTEMPTS)
+        .map(|_| {
+            let current = Duration::from_secs(delay);
+            delay = (delay * 2).min(ssh::PROBE_MAX_DELAY_SECS);
+            current
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::cluster::ServerInfo;
+    use crate::domain::cluster::{CloudProvider, ServerInfo};
 
     fn create_test_server(name: &str, ip: &str, tailscale_hostname: Option<&str>) -> ServerInfo {
         ServerInfo {
             name: name.to_string(),
             ip: ip.to_string(),
+            addresses: Vec::new(),
             cloud_provider: "openstack".to_string(),
             tailscale_hostname: tailscale_hostname.map(|s| s.to_string()),
+            tailscale_ip: None,
+            ssh_user: None,
+            public_ip: None,
+            tailscale_ssh: false,
+            via_subnet_router: false,
         }
     }
 
@@ -117,6 +499,7 @@ mod tests {
     fn test_connection_strategy_tailscale_builds_correct_args() {
         let strategy = ConnectionStrategy::Tailscale {
             hostname: "server-0.tailnet.ts.net".to_string(),
+            user: "ubuntu".to_string(),
         };
 
         let args = strategy.build_ssh_args();
@@ -132,6 +515,7 @@ mod tests {
         let strategy = ConnectionStrategy::Bastion {
             bastion_ip: "1.2.3.4".to_string(),
             target_ip: "10.0.0.5".to_string(),
+            user: "ubuntu".to_string(),
         };
 
         let args = strategy.build_ssh_args();
@@ -144,6 +528,63 @@ mod tests {
         assert_eq!(args[4], "ubuntu@10.0.0.5");
     }
 
+    #[test]
+    fn test_identity_args_apply_to_the_bastion_hop() {
+        let identity = Path::new("/keys/new");
+        let direct = ConnectionStrategy::Direct {
+            host: "1.2.3.4".to_string(),
+            user: "ubuntu".to_string(),
+        };
+        assert_eq!(
+            direct.build_ssh_args_with_identity(identity),
+            ["-i", "/keys/new", "-o", "IdentitiesOnly=yes", "-o", "StrictHostKeyChecking=no", "ubuntu@1.2.3.4"]
+        );
+
+        let bastion = ConnectionStrategy::Bastion {
+            bastion_ip: "1.2.3.4".to_string(),
+            target_ip: "10.0.0.5".to_string(),
+            user: "ubuntu".to_string(),
+        };
+        let args = bastion.build_ssh_args_with_identity(identity);
+        assert!(!args.contains(&"-J".to_string()));
+        assert!(args[5].ends_with("ssh\" -i \"/keys/new\" -o IdentitiesOnly=yes -W %h:%p ubuntu@1.2.3.4"), "{}", args[5]);
+        assert_eq!(args.last().unwrap(), "ubuntu@10.0.0.5");
+    }
+
+    #[test]
+    fn test_jump_args_spell_out_proxy_command_for_windows() {
+        assert_eq!(jump_args("1.2.3.4", "10.0.0.5", None), ["-J", "ubuntu@1.2.3.4"]);
+
+        let program = Path::new(r"C:\Windows\System32\OpenSSH\ssh.exe");
+        assert_eq!(
+            jump_args("1.2.3.4", "10.0.0.5", Some(program)),
+            ["-o", r#"ProxyCommand="C:\Windows\System32\OpenSSH\ssh.exe" -W %h:%p ubuntu@1.2.3.4"#]
+        );
+    }
+
+    #[test]
+    fn test_ipv6_targets_are_bracketed_where_ssh_expects_it() {
+        assert_eq!(jump_args("2001:db8::1", "fd00::5", None), ["-J", "ubuntu@[2001:db8::1]"]);
+        assert!(jump_args("1.2.3.4", "fd00::5", Some(Path::new("ssh")))[1].contains("-W [%h]:%p ubuntu@1.2.3.4"));
+
+        let mut server = create_test_server("k3s-server-0", "10.0.0.10", None);
+        server.addresses = vec!["10.0.0.10".to_string(), "fd00::10".to_string()];
+        let mut provider = CloudProvider {
+            name: "OpenStack".to_string(),
+            bastion_ip: Some("2001:db8::1".to_string()),
+            tailscale_enabled: false,
+            servers: vec![server],
+            subnet_routes: Vec::new(),
+        };
+        provider.prefer_ip_family(IpFamily::Ipv6);
+        let args = ConnectionStrategy::from_server(&provider.servers[0], provider.bastion_ip.as_deref())
+            .unwrap()
+            .build_ssh_args();
+        assert_eq!(args, ["-J", "ubuntu@[2001:db8::1]", "-o", "StrictHostKeyChecking=no", "ubuntu@fd00::10"]);
+        assert_eq!(provider.servers[0].address(IpFamily::Ipv4), Some("10.0.0.10"));
+        assert_eq!(bracket_host("[fd00::10]"), "[fd00::10]");
+    }
+
     #[test]
     fn test_connection_strategy_from_server_prefers_tailscale() {
         let server = create_test_server(
@@ -155,13 +596,25 @@ mod tests {
         let strategy = ConnectionStrategy::from_server(&server, Some("1.2.3.4")).unwrap();
 
         match strategy {
-            ConnectionStrategy::Tailscale { hostname } => {
+            ConnectionStrategy::Tailscale { hostname, user } => {
                 assert_eq!(hostname, "server-0.tailnet.ts.net");
+                assert_eq!(user, "ubuntu");
             }
             _ => panic!("Expected Tailscale strategy"),
         }
     }
 
+    #[test]
+    fn test_connection_strategy_connects_to_the_tailscale_ip() {
+        let mut server = create_test_server("k3s-server-0", "10.0.0.10", Some("server-0"));
+        server.tailscale_ip = Some("100.64.0.1".to_string());
+
+        match ConnectionStrategy::from_server(&server, None).unwrap() {
+            ConnectionStrategy::Tailscale { hostname, .. } => assert_eq!(hostname, "100.64.0.1"),
+            _ => panic!("Expected Tailscale strategy"),
+        }
+    }
+
     #[test]
     fn test_connection_strategy_from_server_falls_back_to_bastion() {
         let server = create_test_server("k3s-server-0", "10.0.0.10", None);
@@ -172,6 +625,7 @@ mod tests {
             ConnectionStrategy::Bastion {
                 bastion_ip,
                 target_ip,
+                ..
             } => {
                 assert_eq!(bastion_ip, "1.2.3.4");
                 assert_eq!(target_ip, "10.0.0.10");
@@ -180,6 +634,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_connection_strategy_uses_the_role_login() {
+        let mut agent = create_test_server("k3s-agent-0", "10.0.0.20", None);
+        agent.ssh_user = Some("rocky".to_string());
+
+        let args = ConnectionStrategy::from_server(&agent, Some("1.2.3.4")).unwrap().build_ssh_args();
+
+        assert_eq!(args, ["-J", "ubuntu@1.2.3.4", "-o", "StrictHostKeyChecking=no", "rocky@10.0.0.20"]);
+    }
+
     #[test]
     fn test_connection_strategy_from_server_no_method_errors() {
         let server = create_test_server("k3s-server-0", "10.0.0.10", None);
@@ -188,14 +652,99 @@ mod tests {
 
         assert!(result.is_err());
         let err = result.unwrap_err();
-        // SshError::NoConnectionMethod message is "Neither Tailscale nor bastion host available"
+        // SshError::NoConnectionMethod message is "Neither Tailscale, bastion host nor public IP available"
         assert!(err.to_string().contains("Neither") || err.to_string().contains("bastion"));
     }
 
+    #[test]
+    fn test_tailscale_ssh_takes_options_after_the_destination() {
+        let mut server = create_test_server("k3s-server-0", "10.0.0.10", Some("server-0.tailnet.ts.net"));
+        server.tailscale_ssh = true;
+
+        let strategy = ConnectionStrategy::from_server(&server, Some("1.2.3.4")).unwrap();
+        assert_eq!(strategy.build_ssh_args(), ["ssh", "ubuntu@server-0.tailnet.ts.net"]);
+        assert_eq!(
+            strategy.build_tunnel_args(1080),
+            ["ssh", "ubuntu@server-0.tailnet.ts.net", "-N", "-D", "127.0.0.1:1080", "-o", "ExitOnForwardFailure=yes"]
+        );
+        assert!(strategy.command().get_program().to_string_lossy().contains("ailscale"));
+
+        // The key check of a rotation needs OpenSSH
+        let args = strategy.build_ssh_args_with_identity(Path::new("/keys/new"));
+        assert_eq!(args.last().unwrap(), "ubuntu@server-0.tailnet.ts.net");
+        assert!(!args.contains(&"ssh".to_string()));
+    }
+
+    #[test]
+    fn test_subnet_router_connects_to_private_ip() {
+        let mut server = create_test_server("k3s-server-0", "10.0.0.10", Some("server-0.tailnet.ts.net"));
+        server.via_subnet_router = true;
+
+        let strategy = ConnectionStrategy::from_server(&server, Some("1.2.3.4")).unwrap();
+
+        assert_eq!(strategy.first_hop(), "10.0.0.10");
+        assert_eq!(strategy.build_ssh_args(), ["-o", "StrictHostKeyChecking=no", "ubuntu@10.0.0.10"]);
+    }
+
+    #[test]
+    fn test_tunnel_exits_from_the_bastion() {
+        let server = create_test_server("k3s-server-0", "10.0.0.10", None);
+        let strategy = ConnectionStrategy::from_server(&server, Some("1.2.3.4")).unwrap().tunnel_endpoint();
+
+        assert_eq!(
+            strategy.build_tunnel_args(1080),
+            ["-N", "-D", "127.0.0.1:1080", "-o", "ExitOnForwardFailure=yes", "-o", "StrictHostKeyChecking=no", "ubuntu@1.2.3.4"]
+        );
+
+        let server = create_test_server("k3s-server-0", "10.0.0.10", Some("server-0.tailnet.ts.net"));
+        let strategy = ConnectionStrategy::from_server(&server, Some("1.2.3.4")).unwrap().tunnel_endpoint();
+        assert_eq!(strategy.first_hop(), "server-0.tailnet.ts.net");
+    }
+
+    #[test]
+    fn test_connection_strategy_direct_to_public_ip() {
+        let mut server = create_test_server("k3s-agent-0", "10.0.0.20", None);
+        server.public_ip = Some("203.0.113.20".to_string());
+        server.ssh_user = Some("rocky".to_string());
+
+        let strategy = ConnectionStrategy::from_server(&server, None).unwrap();
+        assert_eq!(strategy.first_hop(), "203.0.113.20");
+        assert_eq!(strategy.build_ssh_args(), ["-o", "StrictHostKeyChecking=no", "rocky@203.0.113.20"]);
+
+        // A configured bastion still wins, so the public address is only the fallback
+        let strategy = ConnectionStrategy::from_server(&server, Some("1.2.3.4")).unwrap();
+        assert!(matches!(strategy, ConnectionStrategy::Bastion { .. }));
+    }
+
+    #[test]
+    fn test_first_hop() {
+        let tailscale = ConnectionStrategy::Tailscale {
+            hostname: "server-0.tailnet.ts.net".to_string(),
+            user: "ubuntu".to_string(),
+        };
+        assert_eq!(tailscale.first_hop(), "server-0.tailnet.ts.net");
+
+        let bastion = ConnectionStrategy::Bastion {
+            bastion_ip: "1.2.3.4".to_string(),
+            target_ip: "10.0.0.5".to_string(),
+            user: "ubuntu".to_string(),
+        };
+        assert_eq!(bastion.first_hop(), "1.2.3.4");
+    }
+
+    #[test]
+    fn test_probe_delays_back_off_and_cap() {
+        let delays: Vec<u64> = probe_delays().iter().map(|d| d.as_secs()).collect();
+        assert_eq!(delays.len() as u32, ssh::PROBE_MAX_ATTEMPTS - 1);
+        assert_eq!(&delays[..4], &[2, 4, 8, 16]);
+        assert!(delays.iter().all(|d| *d <= ssh::PROBE_MAX_DELAY_SECS));
+    }
+
     #[test]
     fn test_connection_strategy_debug_format() {
         let strategy = ConnectionStrategy::Tailscale {
             hostname: "test.ts.net".to_string(),
+            user: "ubuntu".to_string(),
         };
 
         let debug_str = format!("{:?}", strategy);
@@ -0,0 +1,236 @@
+use serde_json::Value;
+
+/// Readyz of a server's local API. kubectl exits non-zero when a check fails; the verbose
+/// body is still wanted.
+pub const READYZ_COMMAND: &str = "sudo kubectl get --raw '/readyz?verbose' 2>&1 || true";
+
+/// Result of `kubectl get --raw '/readyz?verbose'` against one server's local API
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadyzReport {
+    pub passed: bool,
+    /// Names of checks reported as `[-]name failed`
+    pub failed_checks: Vec<String>,
+}
+
+impl ReadyzReport {
+    /// None when the output holds no check results, i.e. the API server did not answer
+    pub fn parse(output: &str) -> Option<Self> {
+        let failed_checks: Vec<String> = output
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("[-]"))
+            .map(|check| check.split_whitespace().next().unwrap_or(check).to_string())
+            .collect();
+        let passed = output
+            .lines()
+            .any(|line| matches!(line.trim(), "ok" | "readyz check passed"));
+        let answered = passed || output.lines().any(|line| line.trim().starts_with("[+]")) || !failed_checks.is_empty();
+
+        answered.then_some(Self {
+            passed: passed && failed_checks.is_empty(),
+            failed_checks,
+        })
+    }
+}
+
+/// A member of the embedded etcd cluster
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EtcdMember {
+    /// Empty until the member has started; k3s names members `<hostname>-<suffix>`
+    pub name: String,
+    pub peer_urls: Vec<String>,
+    /// Joined but not yet promoted to a voting member
+    pub is_learner: bool,
+}
+
+impl EtcdMember {
+    pub fn is_started(&self) -> bool {
+        !self.name.is_empty()
+    }
+}
+
+/// Parse the etcd gateway's `/v3/cluster/member/list` response
+pub fn parse_member_list(json: &Value) -> Vec<EtcdMember> {
+    json.get("members")
+        .and_then(|m| m.as_array())
+        .into_iter()
+        .flatten()
+        .map(|member| EtcdMember {
+            name: member.get("name").and_then(|n| n.as_str()).unwrap_or_default().to_string(),
+            peer_urls: member
+                .get("peerURLs")
+                .and_then(|p| p.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|url| url.as_str())
+                .map(str::to_string)
+                .collect(),
+            is_learner: member.get("isLearner").and_then(|l| l.as_bool()).unwrap_or(false),
+        })
+        .collect()
+}
+
+/// Parse etcd's `/health` response, `{"health":"true","reason":""}`
+pub fn parse_etcd_health(json: &Value) -> bool {
+    json.get("health").and_then(|h| h.as_str()) == Some("true")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerHealth {
+    pub server: String,
+    /// None when the API server did not answer at all
+    pub readyz: Option<ReadyzReport>,
+    /// None when etcd could not be queried, e.g. a server without embedded etcd
+    pub etcd_healthy: Option<bool>,
+}
+
+impl ServerHealth {
+    pub fn api_ready(&self) -> bool {
+        self.readyz.as_ref().is_some_and(|readyz| readyz.passed)
+    }
+}
+
+/// Server to run kubectl against: the first whose API passes readyz, else the first whose API
+/// answers at all. None when no API answers.
+pub fn healthiest(servers: &[ServerHealth]) -> Option<&ServerHealth> {
+    servers
+        .iter()
+        .find(|health| health.api_ready())
+        .or_else(|| servers.iter().find(|health| health.readyz.is_some()))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlPlaneReport {
+    pub servers: Vec<ServerHealth>,
+    pub members: Option<Vec<EtcdMember>>,
+}
+
+impl ControlPlaneReport {
+    /// Everything that makes the control plane less available than deployed
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        for health in &self.servers {
+            match &health.readyz {
+                None => problems.push(format!("{}: API server not responding", health.server)),
+                Some(readyz) if !readyz.passed => problems.push(format!(
+                    "{}: readyz failed ({})",
+                    health.server,
+                    if readyz.failed_checks.is_empty() {
+                        "no details".to_string()
+                    } else {
+                        readyz.failed_checks.join(", ")
+                    }
+                )),
+                Some(_) => {}
+            }
+            if health.etcd_healthy == Some(false) {
+                problems.push(format!("{}: etcd member unhealthy", health.server));
+            }
+        }
+
+        if let Some(members) = &self.members {
+            if members.len() < self.servers.len() {
+                problems.push(format!(
+                    "etcd has {} of {} members; a server did not join",
+                    members.len(),
+                    self.servers.len()
+                ));
+            }
+            for member in members {
+                if !member.is_started() {
+                    problems.push(format!("etcd member {} has not started", member.peer_urls.join(", ")));
+                } else if member.is_learner {
+                    problems.push(format!("etcd member {} is still a learner", member.name));
+                }
+            }
+        }
+
+        problems
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.problems().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_readyz() {
+        let passed = ReadyzReport::parse("[+]ping ok\n[+]etcd ok\nreadyz check passed\n").unwrap();
+        assert!(passed.passed);
+
+        let failed = ReadyzReport::parse(
+            "Error from server (InternalError): an error on the server\n[+]ping ok\n[-]etcd failed: reason withheld\n[+]log ok\nreadyz check failed\n",
+        )
+        .unwrap();
+        assert!(!failed.passed);
+        assert_eq!(failed.failed_checks, vec!["etcd"]);
+
+        assert!(ReadyzReport::parse("The connection to the server 127.0.0.1:6443 was refused").is_none());
+    }
+
+    #[test]
+    fn test_parse_members_and_health() {
+        let members = parse_member_list(&json!({"members": [
+            {"ID": "1", "name": "demo-server-0-1a2b", "peerURLs": ["https://10.0.0.10:2380"]},
+            {"ID": "2", "name": "demo-server-1-3c4d", "peerURLs": ["https://10.0.0.11:2380"], "isLearner": true},
+            {"ID": "3", "peerURLs": ["https://10.0.0.12:2380"]}
+        ]}));
+        assert_eq!(members.len(), 3);
+        assert!(members[1].is_learner);
+        assert!(!members[2].is_started());
+
+        assert!(parse_etcd_health(&json!({"health": "true", "reason": ""})));
+        assert!(!parse_etcd_health(&json!({"health": "false", "reason": "RAFT NO LEADER"})));
+    }
+
+    #[test]
+    fn test_report_problems() {
+        let healthy_server = |name: &str| ServerHealth {
+            server: name.to_string(),
+            readyz: Some(ReadyzReport { passed: true, failed_checks: Vec::new() }),
+            etcd_healthy: Some(true),
+        };
+        let member = |name: &str| EtcdMember {
+            name: name.to_string(),
+            peer_urls: vec!["https://10.0.0.10:2380".to_string()],
+            is_learner: false,
+        };
+
+        let healthy = ControlPlaneReport {
+            servers: vec![healthy_server("k3s-server-0"), healthy_server("k3s-server-1")],
+            members: Some(vec![member("a"), member("b")]),
+        };
+        assert!(healthy.is_healthy());
+
+        let degraded = ControlPlaneReport {
+            servers: vec![
+                healthy_server("k3s-server-0"),
+                ServerHealth { server: "k3s-server-1".to_string(), readyz: None, etcd_healthy: None },
+            ],
+            members: Some(vec![member("a")]),
+        };
+        let problems = degraded.problems();
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].contains("k3s-server-1"));
+        assert!(problems[1].contains("1 of 2"));
+    }
+
+    #[test]
+    fn test_healthiest_prefers_ready_servers() {
+        let health = |name: &str, readyz: Option<bool>| ServerHealth {
+            server: name.to_string(),
+            readyz: readyz.map(|passed| ReadyzReport { passed, failed_checks: Vec::new() }),
+            etcd_healthy: None,
+        };
+
+        let servers = [health("k3s-server-0", None), health("k3s-server-1", Some(false)), health("k3s-server-2", Some(true))];
+        assert_eq!(healthiest(&servers).map(|h| h.server.as_str()), Some("k3s-server-2"));
+        assert_eq!(healthiest(&servers[..2]).map(|h| h.server.as_str()), Some("k3s-server-1"));
+        assert_eq!(healthiest(&servers[..1]), None);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::constants::kubernetes;
+use crate::domain::connection::shell_quote;
+use std::path::{Path, PathBuf};
+
+/// Prefix of the etcd snapshots im-deploy takes; k3s appends the node name and a timestamp
+pub const SNAPSHOT_NAME: &str = "im-deploy";
+
+/// Where a k3s server keeps the cluster state: embedded etcd with `--cluster-init`, sqlite
+/// for a single server without it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Datastore {
+    Etcd,
+    Sqlite,
+}
+
+impl Datastore {
+    /// Prints "etcd" or "sqlite" on a server
+    pub const DETECT_COMMAND: &str =
+        "if sudo test -d /var/lib/rancher/k3s/server/db/etcd; then echo etcd; else echo sqlite; fi";
+
+    pub fn parse(output: &str) -> Option<Self> {
+        match output.trim() {
+            "etcd" => Some(Datastore::Etcd),
+            "sqlite" => Some(Datastore::Sqlite),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Datastore::Etcd => "etcd",
+            Datastore::Sqlite => "sqlite",
+        }
+    }
+
+    /// Datastore a backup file was taken from, by the extension `etcd-backup` gave it
+    pub fn of_backup(path: &Path) -> Self {
+        if path.to_string_lossy().ends_with(".tar.gz") {
+            Datastore::Sqlite
+        } else {
+            Datastore::Etcd
+        }
+    }
+
+    /// Extension of downloaded backups
+    pub fn extension(self) -> &'static str {
+        match self {
+            Datastore::Etcd => "db",
+            Datastore::Sqlite => "tar.gz",
+        }
+    }
+}
+
+/// Take an etcd snapshot and print its path on the server
+pub fn save_command() -> String {
+    format!(
+        "sudo k3s etcd-snapshot save --name {name} >/dev/null && sudo sh -c 'ls -1t {dir}/{name}-* | head -n 1'",
+        name = SNAPSHOT_NAME,
+        dir = kubernetes::ETCD_SNAPSHOT_DIR
+    )
+}
+
+/// Streams a copy of the sqlite database as gzipped tar of `db/state.db`. k3s keeps writing
+/// while it runs, so the copy comes from sqlite's online backup, which folds in the
+/// write-ahead log, instead of the live files.
+pub const SQLITE_ARCHIVE_COMMAND: &str = "sudo sh -c 'command -v sqlite3 >/dev/null || { echo \"sqlite3 is not installed on the server\" >&2; exit 1; }; \
+     tmp=$(mktemp -d) && mkdir \"$tmp/db\" && \
+     sqlite3 /var/lib/rancher/k3s/server/db/state.db \".backup $tmp/db/state.db\" && \
+     tar -czf - -C \"$tmp\" db/state.db; status=$?; rm -rf \"$tmp\"; exit $status'";
+
+/// Delete all but the newest `keep` im-deploy snapshots on a server
+pub fn prune_command(keep: usize) -> String {
+    format!("sudo k3s etcd-snapshot prune --name {} --snapshot-retention {}", SNAPSHOT_NAME, keep)
+}
+
+/// Where a backup is uploaded on the server before restoring it
+pub fn restore_path(file_name: &str) -> String {
+    format!("{}/{}-restore-{}", kubernetes::ETCD_SNAPSHOT_DIR, SNAPSHOT_NAME, file_name)
+}
+
+/// Write stdin to `path` on the server
+pub fn upload_command(path: &str) -> String {
+    format!(
+        "sudo mkdir -p {} && sudo sh -c {}",
+        kubernetes::ETCD_SNAPSHOT_DIR,
+        shell_quote(&format!("cat > {}", shell_quote(path)))
+    )
+}
+
+/// Reset the first server's etcd to a single member restored from `path`. k3s exits once the
+/// reset is done.
+pub fn cluster_reset_command(path: &str, token: Option<&str>) -> String {
+    let mut command = format!(
+        "sudo systemctl stop k3s && sudo k3s server --cluster-reset --cluster-reset-restore-path={}",
+        shell_quote(path)
+    );
+    if let Some(token) = token {
+        command.push_str(&format!(" --token {}", shell_quote(token)));
+    }
+    command
+}
+
+/// Replace the server's sqlite database with the archive at `path`
+pub fn sqlite_restore_command(path: &str) -> String {
+    format!(
+        "sudo systemctl stop k3s && sudo sh -c 'rm -f /var/lib/rancher/k3s/server/db/state.db*' && \
+         sudo tar -xzf {} -C /var/lib/rancher/k3s/server",
+        shell_quote(path)
+    )
+}
+
+/// Start k3s on the restored server, printing the node's time once it runs
+pub const START_COMMAND: &str = "sudo systemctl start k3s && date +%s";
+
+/// The other servers drop their etcd data and join the restored member as new members
+pub const REJOIN_COMMAND: &str =
+    "sudo systemctl stop k3s && sudo rm -rf /var/lib/rancher/k3s/server/db && sudo systemctl start k3s && date +%s";
+
+/// Local backups of a cluster in `dir`, newest first. Names start with the cluster name and
+/// sort by their timestamp.
+pub fn local_backups(dir: &Path, cluster: &str) -> Vec<PathBuf> {
+    let prefix = format!("{}-datastore-", cluster);
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&prefix))
+        })
+        .collect();
+    backups.sort_by(|a, b| b.file_name().cmp(&a.file_name()));
+    backups
+}
+
+/// File name of a backup taken at `stamp`, e.g. `demo-datastore-20250102T030405Z.db`
+pub fn backup_file_name(cluster: &str, stamp: &str, datastore: Datastore) -> String {
+    format!("{}-datastore-{}.{}", cluster, stamp, datastore.extension())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_backups_newest_first() {
+        let dir = tempfile::TempDir::new().unwrap();
+        for name in [
+            backup_file_name("demo", "20250102T030405Z", Datastore::Etcd),
+            backup_file_name("demo", "20250301T000000Z", Datastore::Sqlite),
+            backup_file_name("other", "20250401T000000Z", Datastore::Etcd),
+            "notes.txt".to_string(),
+        ] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+
+        let names: Vec<String> = local_backups(dir.path(), "demo")
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["demo-datastore-20250301T000000Z.tar.gz", "demo-datastore-20250102T030405Z.db"]);
+        assert!(local_backups(&dir.path().join("missing"), "demo").is_empty());
+    }
+
+    #[test]
+    fn test_datastore_commands() {
+        assert_eq!(Datastore::parse("etcd\n"), Some(Datastore::Etcd));
+        assert_eq!(Datastore::parse(""), None);
+        assert!(save_command().ends_with("sudo sh -c 'ls -1t /var/lib/rancher/k3s/server/db/snapshots/im-deploy-* | head -n 1'"));
+        assert_eq!(prune_command(3), "sudo k3s etcd-snapshot prune --name im-deploy --snapshot-retention 3");
+        assert!(SQLITE_ARCHIVE_COMMAND.contains("sqlite3 /var/lib/rancher/k3s/server/db/state.db \".backup $tmp/db/state.db\""));
+        assert!(SQLITE_ARCHIVE_COMMAND.contains("tar -czf - -C \"$tmp\" db/state.db;"));
+        assert!(!SQLITE_ARCHIVE_COMMAND.contains("state.db-wal"));
+    }
+
+    #[test]
+    fn test_restore_commands() {
+        assert_eq!(Datastore::of_backup(Path::new("demo-datastore-20250102T030405Z.tar.gz")), Datastore::Sqlite);
+        assert_eq!(Datastore::of_backup(Path::new("etcd-snapshot-k3s-server-0-1735787045")), Datastore::Etcd);
+
+        let path = restore_path("demo.db");
+        assert_eq!(path, "/var/lib/rancher/k3s/server/db/snapshots/im-deploy-restore-demo.db");
+        assert!(upload_command(&path).starts_with("sudo mkdir -p /var/lib/rancher/k3s/server/db/snapshots && sudo sh -c 'cat > "));
+        assert!(cluster_reset_command(&path, Some("secret")).ends_with(
+            "--cluster-reset --cluster-reset-restore-path='/var/lib/rancher/k3s/server/db/snapshots/im-deploy-restore-demo.db' --token 'secret'"
+        ));
+    }
+}
@@ -0,0 +1,138 @@
+use crate::history::parse_rfc3339;
+use serde_json::Value;
+
+/// A Kubernetes event as listed by `kubectl get events -o json`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KubeEvent {
+    pub uid: String,
+    pub namespace: String,
+    /// "Normal" or "Warning"
+    pub event_type: String,
+    pub reason: String,
+    /// e.g. "Pod/immich-server-7d9f"
+    pub object: String,
+    pub message: String,
+    pub count: u64,
+    /// Unix time the event was last seen
+    pub last_seen: Option<u64>,
+}
+
+impl KubeEvent {
+    pub fn is_warning(&self) -> bool {
+        self.event_type == "Warning"
+    }
+}
+
+/// Event type shown by the events view
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TypeFilter {
+    #[default]
+    All,
+    Warning,
+    Normal,
+}
+
+impl TypeFilter {
+    pub fn next(self) -> Self {
+        match self {
+            TypeFilter::All => TypeFilter::Warning,
+            TypeFilter::Warning => TypeFilter::Normal,
+            TypeFilter::Normal => TypeFilter::All,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TypeFilter::All => "all",
+            TypeFilter::Warning => "warnings",
+            TypeFilter::Normal => "normal",
+        }
+    }
+}
+
+/// Type and reason an event has to match to be shown
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    pub event_type: TypeFilter,
+    /// Case-insensitive part of the reason, e.g. "backoff"
+    pub reason: String,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &KubeEvent) -> bool {
+        let type_matches = match self.event_type {
+            TypeFilter::All => true,
+            TypeFilter::Warning => event.is_warning(),
+            TypeFilter::Normal => !event.is_warning(),
+        };
+        type_matches && event.reason.to_lowercase().contains(&self.reason.to_lowercase())
+    }
+}
+
+/// Events of a `kubectl get events -o json` list, warnings first and newest first within each
+pub fn parse_events(json: &Value) -> Vec<KubeEvent> {
+    let mut events: Vec<KubeEvent> = json
+        .get("items")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|item| {
+            let text = |pointer: &str| item.pointer(pointer).and_then(Value::as_str).unwrap_or_default().to_string();
+            // Events created through events.k8s.io only carry eventTime
+            let last_seen = ["/lastTimestamp", "/series/lastObservedTime", "/eventTime", "/metadata/creationTimestamp"]
+                .iter()
+                .find_map(|pointer| item.pointer(pointer).and_then(Value::as_str).and_then(parse_rfc3339));
+            KubeEvent {
+                uid: text("/metadata/uid"),
+                namespace: text("/metadata/namespace"),
+                event_type: text("/type"),
+                reason: text("/reason"),
+                object: format!("{}/{}", text("/involvedObject/kind"), text("/involvedObject/name")),
+                message: text("/message").trim().to_string(),
+                count: item.get("count").and_then(Value::as_u64).unwrap_or(1),
+                last_seen,
+            }
+        })
+        .collect();
+    events.sort_by(|a, b| b.is_warning().cmp(&a.is_warning()).then(b.last_seen.cmp(&a.last_seen)));
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_events_warnings_first() {
+        let list = json!({"items": [
+            {
+                "metadata": {"uid": "a", "namespace": "immich", "creationTimestamp": "2025-01-02T03:00:00Z"},
+                "type": "Normal", "reason": "Pulled", "message": "Image pulled\n",
+                "involvedObject": {"kind": "Pod", "name": "immich-server-0"},
+                "lastTimestamp": "2025-01-02T03:04:05Z", "count": 1
+            },
+            {
+                "metadata": {"uid": "b", "namespace": "immich"},
+                "type": "Warning", "reason": "BackOff", "message": "Back-off restarting failed container",
+                "involvedObject": {"kind": "Pod", "name": "immich-ml-0"},
+                "lastTimestamp": null, "eventTime": "2025-01-02T03:00:00.000000Z", "count": 12
+            }
+        ]});
+
+        let events = parse_events(&list);
+        assert_eq!(events[0].reason, "BackOff");
+        assert_eq!(events[0].object, "Pod/immich-ml-0");
+        assert_eq!(events[0].count, 12);
+        assert_eq!(events[0].last_seen, parse_rfc3339("2025-01-02T03:00:00Z"));
+        assert_eq!(events[1].message, "Image pulled");
+
+        let mut filter = EventFilter::default();
+        assert_eq!(events.iter().filter(|e| filter.matches(e)).count(), 2);
+        filter.event_type = TypeFilter::Warning;
+        assert_eq!(events.iter().filter(|e| filter.matches(e)).count(), 1);
+        filter.event_type = TypeFilter::All;
+        filter.reason = "pull".to_string();
+        assert_eq!(events.iter().filter(|e| filter.matches(e)).count(), 1);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::constants::gpu;
+use serde_json::Value;
+
+/// One GPU as reported by `nvidia-smi --query-gpu=name,driver_version,memory.total --format=csv,noheader`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpuInfo {
+    pub model: String,
+    pub driver_version: String,
+    pub memory: String,
+}
+
+pub fn parse_nvidia_smi(output: &str) -> Vec<GpuInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(',').map(str::trim);
+            let model = fields.next().filter(|m| !m.is_empty())?;
+            Some(GpuInfo {
+                model: model.to_string(),
+                driver_version: fields.next()?.to_string(),
+                memory: fields.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// A Kubernetes node advertising GPUs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpuNode {
+    pub name: String,
+    /// `nvidia.com/gpu` capacity; time-slicing multiplies the physical count
+    pub gpu_capacity: u64,
+}
+
+/// Nodes with `nvidia.com/gpu` capacity or the `nvidia.com/gpu.present` label from `kubectl get nodes -o json`
+pub fn gpu_nodes(nodes: &Value) -> Vec<GpuNode> {
+    nodes
+        .get("items")
+        .and_then(|i| i.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|node| {
+            let name = node.get("metadata")?.get("name")?.as_str()?;
+            let gpu_capacity = node
+                .get("status")
+                .and_then(|s| s.get("capacity"))
+                .and_then(|c| c.get(gpu::RESOURCE_NAME))
+                .and_then(|g| g.as_str())
+                .and_then(|g| g.parse().ok())
+                .unwrap_or(0);
+            let labelled = node
+                .get("metadata")
+                .and_then(|m| m.get("labels"))
+                .and_then(|l| l.get("nvidia.com/gpu.present"))
+                .and_then(|p| p.as_str())
+                == Some("true");
+
+            (gpu_capacity > 0 || labelled).then(|| GpuNode {
+                name: name.to_string(),
+                gpu_capacity,
+            })
+        })
+        .collect()
+}
+
+/// Scheduling state of a DaemonSet from `kubectl get daemonset -o json`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DaemonSetStatus {
+    pub desired: u64,
+    pub ready: u64,
+}
+
+impl DaemonSetStatus {
+    pub fn from_json(daemonset: &Value) -> Option<Self> {
+        let status = daemonset.get("status")?;
+        let field = |key: &str| status.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+        Some(Self {
+            desired: field("desiredNumberScheduled"),
+            ready: field("numberReady"),
+        })
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.desired > 0 && self.ready == self.desired
+    }
+}
+
+/// Pod manifest for the CUDA vector-add sample, which prints "Test PASSED" on success
+pub fn cuda_test_pod_manifest() -> String {
+    format!(
+        r#"apiVersion: v1
+kind: Pod
+metadata:
+  name: {name}
+  namespace: {namespace}
+spec:
+  restartPolicy: Never
+  containers:
+    - name: vectoradd
+      image: {image}
+      resources:
+        limits:
+          {resource}: 1
+"#,
+        name = gpu::CUDA_TEST_POD,
+        namespace = gpu::OPERATOR_NAMESPACE,
+        image = gpu::CUDA_TEST_IMAGE,
+        resource = gpu::RESOURCE_NAME,
+    )
+}
+
+pub fn cuda_test_passed(logs: &str) -> bool {
+    logs.contains("Test PASSED")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_nvidia_smi() {
+        let output = "NVIDIA A100-PCIE-40GB, 535.104.05, 40960 MiB\nTesla T4, 535.104.05, 15360 MiB\n\n";
+        let gpus = parse_nvidia_smi(output);
+        assert_eq!(gpus.len(), 2);
+        assert_eq!(gpus[0].model, "NVIDIA A100-PCIE-40GB");
+        assert_eq!(gpus[0].driver_version, "535.104.05");
+        assert_eq!(gpus[1].memory, "15360 MiB");
+
+        assert!(parse_nvidia_smi("").is_empty());
+        assert!(parse_nvidia_smi("NVIDIA-SMI has failed").is_empty());
+    }
+
+    #[test]
+    fn test_gpu_nodes() {
+        let nodes = json!({
+            "items": [
+                {
+                    "metadata": {"name": "prod-agent-0", "labels": {"nvidia.com/gpu.present": "true"}},
+                    "status": {"capacity": {"nvidia.com/gpu": "4", "cpu": "8"}}
+                },
+                {
+                    "metadata": {"name": "prod-agent-1", "labels": {"nvidia.com/gpu.present": "true"}},
+                    "status": {"capacity": {"cpu": "8"}}
+                },
+                {
+                    "metadata": {"name": "prod-server-0", "labels": {}},
+                    "status": {"capacity": {"cpu": "4"}}
+                }
+            ]
+        });
+
+        let found = gpu_nodes(&nodes);
+        assert_eq!(
+            found,
+            vec![
+                GpuNode { name: "prod-agent-0".to_string(), gpu_capacity: 4 },
+                // Labelled but the device plugin has not registered GPUs yet
+                GpuNode { name: "prod-agent-1".to_string(), gpu_capacity: 0 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_daemonset_status() {
+        let ready = DaemonSetStatus::from_json(&json!({"status": {"desiredNumberScheduled": 2, "numberReady": 2}}));
+        assert!(ready.unwrap().is_ready());
+
+        let partial = DaemonSetStatus::from_json(&json!({"status": {"desiredNumberScheduled": 2, "numberReady": 1}}));
+        assert!(!partial.unwrap().is_ready());
+
+        // Nothing scheduled means no GPU node is running the plugin
+        let empty = DaemonSetStatus::from_json(&json!({"status": {"desiredNumberScheduled": 0}}));
+        assert!(!empty.unwrap().is_ready());
+
+        assert!(DaemonSetStatus::from_json(&json!({})).is_none());
+    }
+
+    #[test]
+    fn test_cuda_test_pod() {
+        let manifest = cuda_test_pod_manifest();
+        assert!(manifest.contains("nvidia.com/gpu: 1"));
+        assert!(manifest.contains(gpu::CUDA_TEST_IMAGE));
+
+        assert!(cuda_test_passed("[Vector addition of 50000 elements]\n...\nTest PASSED\nDone\n"));
+        assert!(!cuda_test_passed("Failed to allocate device vector A"));
+    }
+}
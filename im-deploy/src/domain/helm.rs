@@ -0,0 +1,129 @@
+use crate::constants::kubernetes;
+use serde_json::Value;
+use std::fmt;
+
+/// Where the install of a Helm release stands
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReleaseState {
+    Deployed,
+    /// Still installing, with the status helm or the install job reports
+    Pending(String),
+    Failed(String),
+    /// Not listed yet
+    Missing,
+}
+
+impl fmt::Display for ReleaseState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReleaseState::Deployed => write!(f, "deployed"),
+            ReleaseState::Pending(status) => write!(f, "{}", status),
+            ReleaseState::Failed(reason) => write!(f, "failed: {}", reason),
+            ReleaseState::Missing => write!(f, "not installed yet"),
+        }
+    }
+}
+
+/// State of `release` in the output of `helm ls -A -a -o json`; `namespace` narrows the
+/// match when the same name is installed twice
+pub fn release_state(helm_ls: &Value, release: &str, namespace: Option<&str>) -> ReleaseState {
+    let found = helm_ls.as_array().into_iter().flatten().find(|entry| {
+        entry.get("name").and_then(Value::as_str) == Some(release)
+            && namespace.is_none_or(|ns| entry.get("namespace").and_then(Value::as_str) == Some(ns))
+    });
+    let Some(entry) = found else {
+        return ReleaseState::Missing;
+    };
+    match entry.get("status").and_then(Value::as_str).unwrap_or("unknown") {
+        "deployed" => ReleaseState::Deployed,
+        "failed" => ReleaseState::Failed(format!(
+            "revision {} of {}",
+            entry.get("revision").and_then(Value::as_str).unwrap_or("?"),
+            entry.get("chart").and_then(Value::as_str).unwrap_or(release)
+        )),
+        other => ReleaseState::Pending(other.to_string()),
+    }
+}
+
+/// State of the k3s helm controller job installing `release`, from `kubectl get jobs -A -o json`.
+/// Used when the helm CLI is not on the server. A failed attempt is retried by the
+/// controller, so only a job marked Failed counts as failure.
+pub fn install_job_state(jobs: &Value, release: &str) -> ReleaseState {
+    let job_name = format!("{}{}", kubernetes::HELM_INSTALL_JOB_PREFIX, release);
+    let found = jobs
+        .get("items")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .find(|job| job.pointer("/metadata/name").and_then(Value::as_str) == Some(job_name.as_str()));
+    let Some(job) = found else {
+        return ReleaseState::Missing;
+    };
+    let status = job.get("status");
+    let count = |key: &str| status.and_then(|s| s.get(key)).and_then(Value::as_u64).unwrap_or(0);
+    if count("succeeded") > 0 {
+        return ReleaseState::Deployed;
+    }
+    let failed = status
+        .and_then(|s| s.get("conditions"))
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .find(|condition| {
+            condition.get("type").and_then(Value::as_str) == Some("Failed")
+                && condition.get("status").and_then(Value::as_str) == Some("True")
+        });
+    if let Some(condition) = failed {
+        let reason = condition
+            .get("message")
+            .or_else(|| condition.get("reason"))
+            .and_then(Value::as_str)
+            .unwrap_or("job failed");
+        return ReleaseState::Failed(format!("{}: {}", job_name, reason));
+    }
+    ReleaseState::Pending(format!("{} running, {} failed attempts", job_name, count("failed")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_release_state_from_helm_ls() {
+        let releases = json!([
+            {"name": "longhorn", "namespace": "longhorn-system", "revision": "1", "status": "deployed", "chart": "longhorn-1.7.2"},
+            {"name": "velero", "namespace": "velero", "revision": "2", "status": "failed", "chart": "velero-8.0.0"},
+            {"name": "traefik", "namespace": "kube-system", "revision": "1", "status": "pending-install", "chart": "traefik-27.0.0"},
+        ]);
+        assert_eq!(release_state(&releases, "longhorn", None), ReleaseState::Deployed);
+        assert_eq!(release_state(&releases, "longhorn", Some("default")), ReleaseState::Missing);
+        assert_eq!(
+            release_state(&releases, "velero", Some("velero")),
+            ReleaseState::Failed("revision 2 of velero-8.0.0".to_string())
+        );
+        assert_eq!(release_state(&releases, "traefik", None), ReleaseState::Pending("pending-install".to_string()));
+        assert_eq!(release_state(&json!([]), "longhorn", None), ReleaseState::Missing);
+    }
+
+    #[test]
+    fn test_install_job_state() {
+        let jobs = json!({"items": [
+            {"metadata": {"name": "helm-install-longhorn"}, "status": {"succeeded": 1}},
+            {"metadata": {"name": "helm-install-velero"}, "status": {"failed": 6, "conditions": [
+                {"type": "Failed", "status": "True", "reason": "BackoffLimitExceeded", "message": "Job has reached the specified backoff limit"}
+            ]}},
+            {"metadata": {"name": "helm-install-traefik"}, "status": {"active": 1, "failed": 2}},
+        ]});
+        assert_eq!(install_job_state(&jobs, "longhorn"), ReleaseState::Deployed);
+        assert_eq!(
+            install_job_state(&jobs, "velero"),
+            ReleaseState::Failed("helm-install-velero: Job has reached the specified backoff limit".to_string())
+        );
+        assert_eq!(
+            install_job_state(&jobs, "traefik").to_string(),
+            "helm-install-traefik running, 2 failed attempts"
+        );
+        assert_eq!(install_job_state(&jobs, "cert-manager"), ReleaseState::Missing);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::domain::inventory::{AuditEntry, Finding, ResourceKind};
+use serde_json::Value;
+
+/// A resource `terraform plan` would create because state has nothing at its address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedCreate {
+    pub address: String,
+    pub resource_type: String,
+    /// e.g. `k3s_server` for `openstack_compute_instance_v2.k3s_server[0]`
+    pub resource_name: String,
+    /// count index or for_each key
+    pub key: Option<String>,
+}
+
+/// Resources planned for creation in the `-json` output of `terraform plan`
+pub fn planned_creates(plan_output: &str) -> Vec<PlannedCreate> {
+    plan_output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|line| line.get("type").and_then(Value::as_str) == Some("planned_change"))
+        .filter_map(|line| {
+            let change = line.get("change")?;
+            if change.get("action").and_then(Value::as_str) != Some("create") {
+                return None;
+            }
+            let resource = change.get("resource")?;
+            let text = |key: &str| resource.get(key).and_then(Value::as_str).map(str::to_string);
+            Some(PlannedCreate {
+                address: text("addr")?,
+                resource_type: text("resource_type")?,
+                resource_name: text("resource_name")?,
+                key: match resource.get("resource_key") {
+                    Some(Value::String(key)) => Some(key.clone()),
+                    Some(Value::Number(index)) => Some(index.to_string()),
+                    _ => None,
+                },
+            })
+        })
+        .collect()
+}
+
+/// An unmanaged cloud resource and the address `terraform import` would adopt it at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportCandidate {
+    pub kind: ResourceKind,
+    pub id: String,
+    pub name: String,
+    pub address: String,
+    /// The name shares a word with the address and agrees on its index; otherwise the pair
+    /// is a guess, e.g. a lone floating IP, that `--yes` leaves for review
+    pub exact: bool,
+}
+
+/// Pair resources the inventory found outside of state with the planned creates they most
+/// likely are. The resource types must fit, an index in the cloud name must agree with the
+/// terraform one, and the names must share a word, e.g. `demo-openstack-server-1` and
+/// `k3s_server[1]`. Floating IPs and ports rarely have telling names, so a lone one pairs
+/// up with a lone planned create of its type regardless of words, as a guess.
+pub fn match_candidates(entries: &[AuditEntry], planned: &[PlannedCreate]) -> Vec<ImportCandidate> {
+    let untracked: Vec<&AuditEntry> = entries
+        .iter()
+        .filter(|entry| matches!(entry.finding, Finding::Unmanaged | Finding::Orphaned))
+        .filter(|entry| !entry.kind.terraform_types().is_empty())
+        .collect();
+
+    let mut pairs: Vec<(usize, bool, &AuditEntry, &PlannedCreate)> = Vec::new();
+    for entry in &untracked {
+        let of_type: Vec<&PlannedCreate> = planned
+            .iter()
+            .filter(|create| entry.kind.terraform_types().contains(&create.resource_type.as_str()))
+            .collect();
+        let lone = matches!(entry.kind, ResourceKind::FloatingIp | ResourceKind::Port)
+            && of_type.len() == 1
+            && untracked.iter().filter(|other| other.kind == entry.kind).count() == 1;
+        for create in of_type {
+            if let Some((shared, score)) = name_score(&entry.name, create)
+                && (shared > 0 || lone)
+            {
+                let index_agrees = create.key.is_none() || score > shared;
+                pairs.push((score, shared > 0 && index_agrees, entry, create));
+            }
+        }
+    }
+
+    // Best matches first, each resource and address used once
+    pairs.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.3.address.cmp(&b.3.address)));
+    let mut candidates: Vec<ImportCandidate> = Vec::new();
+    for (_, exact, entry, create) in pairs {
+        if candidates.iter().any(|c| c.id == entry.id || c.address == create.address) {
+            continue;
+        }
+        candidates.push(ImportCandidate {
+            kind: entry.kind,
+            id: entry.id.clone(),
+            name: entry.name.clone(),
+            address: create.address.clone(),
+            exact,
+        });
+    }
+    candidates.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.address.cmp(&b.address)));
+    candidates
+}
+
+/// Number of words the names share, and the score of the pair: those words plus two for a
+/// matching index. None when the name carries another index than the address.
+fn name_score(name: &str, create: &PlannedCreate) -> Option<(usize, usize)> {
+    let words: Vec<String> = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let shared = create
+        .resource_name
+        .split('_')
+        .filter(|word| words.iter().any(|w| w == &word.to_ascii_lowercase()))
+        .count();
+    let index = words.iter().rev().find(|word| word.chars().all(|c| c.is_ascii_digit()));
+    match (&create.key, index) {
+        (Some(key), Some(index)) if key == index => Some((shared, shared + 2)),
+        (Some(_), Some(_)) => None,
+        _ => Some((shared, shared)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn planned(resource_type: &str, resource_name: &str, key: Option<&str>) -> PlannedCreate {
+        let index = key.map(|key| format!("[{}]", key)).unwrap_or_default();
+        PlannedCreate {
+            address: format!("module.openstack_k3s[0].{}.{}{}", resource_type, resource_name, index),
+            resource_type: resource_type.to_string(),
+            resource_name: resource_name.to_string(),
+            key: key.map(str::to_string),
+        }
+    }
+
+    fn entry(kind: ResourceKind, id: &str, name: &str, finding: Finding) -> AuditEntry {
+        AuditEntry {
+            kind,
+            id: id.to_string(),
+            name: name.to_string(),
+            status: "ACTIVE".to_string(),
+            finding,
+        }
+    }
+
+    #[test]
+    fn test_planned_creates_from_plan_json() {
+        let plan = r#"{"@level":"info","@message":"Terraform 1.9.8","type":"version"}
+{"@level":"info","type":"planned_change","change":{"resource":{"addr":"module.openstack_k3s[0].openstack_compute_instance_v2.k3s_server[1]","resource_type":"openstack_compute_instance_v2","resource_name":"k3s_server","resource_key":1},"action":"create"}}
+{"@level":"info","type":"planned_change","change":{"resource":{"addr":"module.openstack_k3s[0].openstack_compute_instance_v2.k3s_agent[0]","resource_type":"openstack_compute_instance_v2","resource_name":"k3s_agent","resource_key":0},"action":"update"}}
+{"@level":"info","type":"planned_change","change":{"resource":{"addr":"tailscale_tailnet_key.keys[\"servers\"]","resource_type":"tailscale_tailnet_key","resource_name":"keys","resource_key":"servers"},"action":"create"}}
+not json"#;
+        let creates = planned_creates(plan);
+        assert_eq!(creates.len(), 2);
+        assert_eq!(creates[0].resource_name, "k3s_server");
+        assert_eq!(creates[0].key.as_deref(), Some("1"));
+        assert_eq!(creates[1].key.as_deref(), Some("servers"));
+    }
+
+    #[test]
+    fn test_match_candidates_by_name_and_index() {
+        let entries = vec![
+            entry(ResourceKind::Server, "srv-0", "demo-openstack-server-0", Finding::Managed("x".to_string())),
+            entry(ResourceKind::Server, "srv-1", "demo-openstack-server-1", Finding::Unmanaged),
+            entry(ResourceKind::Server, "srv-a2", "demo-openstack-agent-2", Finding::Unmanaged),
+            entry(ResourceKind::Server, "srv-a", "demo-openstack-agent", Finding::Unmanaged),
+            entry(ResourceKind::LoadBalancer, "lb-k8s", "kube_service_default_web", Finding::Unmanaged),
+            entry(ResourceKind::Volume, "vol-1", "demo-openstack-agent-1-longhorn", Finding::Orphaned),
+            entry(ResourceKind::FloatingIp, "fip-1", "203.0.113.7", Finding::Orphaned),
+        ];
+        let planned = [
+            planned("openstack_compute_instance_v2", "k3s_agent", Some("1")),
+            planned("openstack_compute_instance_v2", "k3s_server", Some("1")),
+            planned("openstack_lb_loadbalancer_v2", "k3s_api", None),
+            planned("openstack_blockstorage_volume_v3", "longhorn", Some("0")),
+            planned("openstack_networking_floatingip_v2", "fip_bastion", None),
+        ];
+
+        let candidates = match_candidates(&entries, &planned);
+        let pairs: Vec<(&str, &str, bool)> =
+            candidates.iter().map(|c| (c.id.as_str(), c.address.as_str(), c.exact)).collect();
+        assert_eq!(
+            pairs,
+            [
+                // Without an index in its name the agent may be any of them
+                ("srv-a", "module.openstack_k3s[0].openstack_compute_instance_v2.k3s_agent[1]", false),
+                ("srv-1", "module.openstack_k3s[0].openstack_compute_instance_v2.k3s_server[1]", true),
+                // The volume is named after agent 1, not 0, and the Kubernetes load balancer
+                // shares no word with k3s_api; the lone floating IP needs no telling name but
+                // is only a guess
+                ("fip-1", "module.openstack_k3s[0].openstack_networking_floatingip_v2.fip_bastion", false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use crate::terraform::{resource_type, StateRef};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+/// Kind of cloud resource listed by `im-deploy inventory`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ResourceKind {
+    Server,
+    Volume,
+    LoadBalancer,
+    /// Listener, pool, member or health monitor of a load balancer
+    LbComponent,
+    FloatingIp,
+    Port,
+    SecurityGroup,
+    /// Router, subnet or network
+    Network,
+    TailscaleDevice,
+}
+
+impl ResourceKind {
+    pub const ALL: [ResourceKind; 9] = [
+        ResourceKind::Server,
+        ResourceKind::Volume,
+        ResourceKind::LoadBalancer,
+        ResourceKind::LbComponent,
+        ResourceKind::FloatingIp,
+        ResourceKind::Port,
+        ResourceKind::SecurityGroup,
+        ResourceKind::Network,
+        ResourceKind::TailscaleDevice,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ResourceKind::Server => "Servers",
+            ResourceKind::Volume => "Volumes",
+            ResourceKind::LoadBalancer => "Load balancers",
+            ResourceKind::LbComponent => "Load balancer components",
+            ResourceKind::FloatingIp => "Floating IPs",
+            ResourceKind::Port => "Ports",
+            ResourceKind::SecurityGroup => "Security groups",
+            ResourceKind::Network => "Networks",
+            ResourceKind::TailscaleDevice => "Tailscale devices",
+        }
+    }
+
+    /// Terraform resource types whose IDs identify resources of this kind
+    pub fn terraform_types(&self) -> &'static [&'static str] {
+        match self {
+            ResourceKind::Server => &["openstack_compute_instance_v2"],
+            ResourceKind::Volume => &["openstack_blockstorage_volume_v3"],
+            ResourceKind::LoadBalancer => &["openstack_lb_loadbalancer_v2"],
+            // Not listed by the inventory, so they must not show up as missing
+            ResourceKind::LbComponent => &[],
+            ResourceKind::FloatingIp => &["openstack_networking_floatingip_v2"],
+            // Router interfaces use the ID of the port they create
+            ResourceKind::Port => &["openstack_networking_port_v2", "openstack_networking_router_interface_v2"],
+            ResourceKind::SecurityGroup => &["openstack_networking_secgroup_v2"],
+            // Only listed by `cleanup --deep`
+            ResourceKind::Network => &[],
+            ResourceKind::TailscaleDevice => &[],
+        }
+    }
+
+    /// Kind of the resource behind a state ID: a load balancer's VIP port is a port
+    fn of_state(state: &StateRef) -> Option<ResourceKind> {
+        if state.attribute == "vip_port_id" {
+            return Some(ResourceKind::Port);
+        }
+        let resource_type = resource_type(&state.address)?;
+        ResourceKind::ALL.into_iter().find(|kind| kind.terraform_types().contains(&resource_type))
+    }
+}
+
+/// A resource found in OpenStack or the tailnet
+#[derive(Debug, Clone)]
+pub struct InventoryItem {
+    pub kind: ResourceKind,
+    pub id: String,
+    pub name: String,
+    pub status: String,
+    /// Would be an orphan if terraform does not track it, e.g. a detached volume,
+    /// an unassociated floating IP or a node device without a server
+    pub orphan_candidate: bool,
+}
+
+/// What the inventory listed
+#[derive(Debug, Clone, Default)]
+pub struct Inventory {
+    pub items: Vec<InventoryItem>,
+    /// Kinds whose listing was skipped or failed, e.g. volumes without a cinder endpoint
+    pub unchecked: Vec<ResourceKind>,
+}
+
+impl Inventory {
+    pub fn extend(&mut self, other: Inventory) {
+        self.items.extend(other.items);
+        self.unchecked.extend(other.unchecked);
+    }
+}
+
+/// How a resource relates to terraform state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Finding {
+    /// Tracked in terraform state under this address
+    Managed(String),
+    /// Not tracked by terraform but in use, e.g. created by Kubernetes
+    Unmanaged,
+    /// Not tracked by terraform and apparently unused; what the cleanup routines remove
+    Orphaned,
+    /// Tracked in terraform state but not found in the cloud
+    Missing,
+    /// Tracked in terraform state, but resources of its kind were not listed
+    NotChecked,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Finding::Managed(_) => write!(f, "managed"),
+            Finding::Unmanaged => write!(f, "unmanaged"),
+            Finding::Orphaned => write!(f, "orphaned"),
+            Finding::Missing => write!(f, "missing"),
+            Finding::NotChecked => write!(f, "not checked"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub kind: ResourceKind,
+    /// Cloud ID, or the terraform ID for missing resources
+    pub id: String,
+    pub name: String,
+    pub status: String,
+    pub finding: Finding,
+}
+
+/// Cross-reference cloud resources against terraform state.
+///
+/// `state_ids` maps resource IDs to where state has them. Tailscale devices are never in
+/// state, so they count as managed when a managed server has the same hostname.
+pub fn audit(inventory: &Inventory, state_ids: &BTreeMap<String, StateRef>) -> Vec<AuditEntry> {
+    let items = &inventory.items;
+    let mut entries: Vec<AuditEntry> = Vec::new();
+
+    let managed_servers: BTreeMap<&str, &String> = items
+        .iter()
+        .filter(|i| i.kind == ResourceKind::Server)
+        .filter_map(|i| state_ids.get(&i.id).map(|state| (i.name.as_str(), &state.address)))
+        .collect();
+
+    for item in items {
+        let tracked = if item.kind == ResourceKind::TailscaleDevice {
+            managed_servers.get(item.name.as_str()).map(|a| (*a).clone())
+        } else {
+            state_ids.get(&item.id).map(|state| state.address.clone())
+        };
+
+        let finding = match tracked {
+            Some(address) => Finding::Managed(address),
+            None if item.orphan_candidate => Finding::Orphaned,
+            None => Finding::Unmanaged,
+        };
+
+        entries.push(AuditEntry {
+            kind: item.kind,
+            id: item.id.clone(),
+            name: item.name.clone(),
+            status: item.status.clone(),
+            finding,
+        });
+    }
+
+    // State entries of the listed kinds that the cloud no longer has
+    let found_ids: HashSet<&str> = items.iter().map(|i| i.id.as_str()).collect();
+    for (id, state) in state_ids {
+        let Some(kind) = ResourceKind::of_state(state) else {
+            continue;
+        };
+        if !found_ids.contains(id.as_str()) {
+            entries.push(AuditEntry {
+                kind,
+                id: id.clone(),
+                name: state.address.clone(),
+                status: "-".to_string(),
+                finding: if inventory.unchecked.contains(&kind) { Finding::NotChecked } else { Finding::Missing },
+            });
+        }
+    }
+
+    entries.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.name.cmp(&b.name)));
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(kind: ResourceKind, id: &str, name: &str, orphan_candidate: bool) -> InventoryItem {
+        InventoryItem {
+            kind,
+            id: id.to_string(),
+            name: name.to_string(),
+            status: "ACTIVE".to_string(),
+            orphan_candidate,
+        }
+    }
+
+    fn state(address: &str) -> StateRef {
+        StateRef {
+            address: address.to_string(),
+            attribute: "id",
+        }
+    }
+
+    fn listed(items: Vec<InventoryItem>) -> Inventory {
+        Inventory { items, unchecked: Vec::new() }
+    }
+
+    fn finding_of<'a>(entries: &'a [AuditEntry], id: &str) -> &'a Finding {
+        &entries.iter().find(|e| e.id == id).unwrap().finding
+    }
+
+    #[test]
+    fn test_audit_classifies_resources() {
+        let items = vec![
+            item(ResourceKind::Server, "srv-1", "prod-server-0", true),
+            item(ResourceKind::Volume, "vol-1", "prod-agent-0-longhorn", true),
+            item(ResourceKind::LoadBalancer, "lb-k8s", "kube_service_default_web", false),
+            item(ResourceKind::FloatingIp, "fip-free", "203.0.113.7", true),
+        ];
+        let state_ids = BTreeMap::from([(
+            "srv-1".to_string(),
+            state("module.openstack_k3s[0].openstack_compute_instance_v2.k3s_server[0]"),
+        )]);
+
+        let entries = audit(&listed(items), &state_ids);
+        assert_eq!(
+            finding_of(&entries, "srv-1"),
+            &Finding::Managed("module.openstack_k3s[0].openstack_compute_instance_v2.k3s_server[0]".to_string())
+        );
+        assert_eq!(finding_of(&entries, "vol-1"), &Finding::Orphaned);
+        assert_eq!(finding_of(&entries, "lb-k8s"), &Finding::Unmanaged);
+        assert_eq!(finding_of(&entries, "fip-free"), &Finding::Orphaned);
+    }
+
+    #[test]
+    fn test_audit_reports_missing_state_resources() {
+        let state_ids = BTreeMap::from([
+            ("vol-gone".to_string(), state("module.k3s.openstack_blockstorage_volume_v3.data[0]")),
+            // Kinds that are not listed from the cloud are never reported as missing
+            ("net-1".to_string(), state("module.k3s.openstack_networking_network_v2.network")),
+        ]);
+
+        let entries = audit(&Inventory::default(), &state_ids);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].kind, ResourceKind::Volume);
+        assert_eq!(entries[0].finding, Finding::Missing);
+    }
+
+    #[test]
+    fn test_audit_matches_tailscale_devices_to_servers() {
+        let items = vec![
+            item(ResourceKind::Server, "srv-1", "prod-server-0", true),
+            item(ResourceKind::TailscaleDevice, "ts-1", "prod-server-0", true),
+            item(ResourceKind::TailscaleDevice, "ts-2", "prod-server-1", true),
+            item(ResourceKind::TailscaleDevice, "ts-3", "immich-ingress", false),
+        ];
+        let state_ids = BTreeMap::from([(
+            "srv-1".to_string(),
+            state("module.k3s.openstack_compute_instance_v2.k3s_server[0]"),
+        )]);
+
+        let entries = audit(&listed(items), &state_ids);
+        assert!(matches!(finding_of(&entries, "ts-1"), Finding::Managed(_)));
+        assert_eq!(finding_of(&entries, "ts-2"), &Finding::Orphaned);
+        assert_eq!(finding_of(&entries, "ts-3"), &Finding::Unmanaged);
+    }
+
+    #[test]
+    fn test_audit_skipped_listings_are_not_checked() {
+        let state_ids = BTreeMap::from([
+            ("vol-1".to_string(), state("module.k3s.openstack_blockstorage_volume_v3.data[0]")),
+            ("fip-1".to_string(), state("module.k3s.openstack_networking_floatingip_v2.fip[0]")),
+            ("srv-gone".to_string(), state("module.k3s.openstack_compute_instance_v2.k3s_server[1]")),
+        ]);
+        let inventory = Inventory {
+            items: Vec::new(),
+            unchecked: vec![ResourceKind::Volume, ResourceKind::FloatingIp],
+        };
+
+        let entries = audit(&inventory, &state_ids);
+        assert_eq!(finding_of(&entries, "vol-1"), &Finding::NotChecked);
+        assert_eq!(finding_of(&entries, "fip-1"), &Finding::NotChecked);
+        assert_eq!(finding_of(&entries, "srv-gone"), &Finding::Missing);
+    }
+
+    #[test]
+    fn test_audit_load_balancer_vip_port_is_a_port() {
+        let address = "module.k3s.openstack_lb_loadbalancer_v2.k3s_lb[0]";
+        let state_ids = BTreeMap::from([
+            ("lb-1".to_string(), state(address)),
+            (
+                "port-vip".to_string(),
+                StateRef {
+                    address: address.to_string(),
+                    attribute: "vip_port_id",
+                },
+            ),
+        ]);
+
+        let entries = audit(&listed(vec![item(ResourceKind::LoadBalancer, "lb-1", "demo-lb", false)]), &state_ids);
+        let vip = entries.iter().find(|e| e.id == "port-vip").unwrap();
+        assert_eq!(vip.kind, ResourceKind::Port);
+        assert_eq!(vip.finding, Finding::Missing);
+
+        let with_port = listed(vec![
+            item(ResourceKind::LoadBalancer, "lb-1", "demo-lb", false),
+            item(ResourceKind::Port, "port-vip", "octavia-lb-1", false),
+        ]);
+        let entries = audit(&with_port, &state_ids);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(finding_of(&entries, "port-vip"), &Finding::Managed(address.to_string()));
+    }
+}
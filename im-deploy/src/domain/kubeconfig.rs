@@ -0,0 +1,225 @@
+use crate::domain::connection::bracket_host;
+use base64::Engine;
+use serde_yaml::Value;
+
+/// A kubeconfig edited structurally. Fields im-deploy does not know are kept as they are.
+#[derive(Debug, Clone)]
+pub struct Kubeconfig(Value);
+
+impl Kubeconfig {
+    pub fn parse(yaml: &str) -> Result<Self, serde_yaml::Error> {
+        serde_yaml::from_str(yaml).map(Self)
+    }
+
+    pub fn to_yaml(&self) -> Result<String, serde_yaml::Error> {
+        serde_yaml::to_string(&self.0)
+    }
+
+    /// Point every cluster at `host`, keeping scheme, port and path of its server URL
+    pub fn set_server_host(&mut self, host: &str) {
+        for cluster in self.entries_mut("clusters") {
+            if let Some(server) = cluster.get_mut("cluster").and_then(|c| c.get_mut("server"))
+                && let Some(url) = server.as_str()
+            {
+                *server = Value::from(replace_url_host(url, host));
+            }
+        }
+    }
+
+    /// Turn off server certificate verification. kubectl refuses a CA together with
+    /// `insecure-skip-tls-verify`, so the CA is removed.
+    pub fn skip_tls_verify(&mut self) {
+        for cluster in self.entries_mut("clusters") {
+            if let Some(Value::Mapping(cluster)) = cluster.get_mut("cluster") {
+                cluster.remove("certificate-authority-data");
+                cluster.remove("certificate-authority");
+                cluster.insert(Value::from("insecure-skip-tls-verify"), Value::from(true));
+            }
+        }
+    }
+
+    /// Rename clusters, users and contexts after the cluster instead of k3s' "default", so the
+    /// file can be merged with other kubeconfigs. With several entries the old name is kept
+    /// as suffix.
+    pub fn rename(&mut self, name: &str) {
+        let clusters = self.rename_entries("clusters", name);
+        let users = self.rename_entries("users", name);
+        let contexts = self.rename_entries("contexts", name);
+
+        for context in self.entries_mut("contexts") {
+            let Some(Value::Mapping(context)) = context.get_mut("context") else {
+                continue;
+            };
+            for (key, renamed) in [("cluster", &clusters), ("user", &users)] {
+                if let Some(reference) = context.get_mut(key) {
+                    rename_reference(reference, renamed);
+                }
+            }
+        }
+        if let Some(current) = self.0.get_mut("current-context") {
+            rename_reference(current, &contexts);
+        }
+    }
+
+    /// Server URLs of all clusters
+    pub fn servers(&self) -> Vec<&str> {
+        self.0
+            .get("clusters")
+            .and_then(Value::as_sequence)
+            .into_iter()
+            .flatten()
+            .filter_map(|cluster| cluster.get("cluster")?.get("server")?.as_str())
+            .collect()
+    }
+
+    /// User name and decoded PEM of every embedded client certificate
+    pub fn client_certificates(&self) -> Vec<(String, String)> {
+        self.0
+            .get("users")
+            .and_then(Value::as_sequence)
+            .into_iter()
+            .flatten()
+            .filter_map(|user| {
+                let data = user.get("user")?.get("client-certificate-data")?.as_str()?;
+                let pem = base64::engine::general_purpose::STANDARD.decode(data.trim()).ok()?;
+                Some((user.get("name")?.as_str()?.to_string(), String::from_utf8(pem).ok()?))
+            })
+            .collect()
+    }
+
+    fn entries_mut(&mut self, list: &str) -> impl Iterator<Item = &mut Value> {
+        self.0
+            .get_mut(list)
+            .and_then(Value::as_sequence_mut)
+            .into_iter()
+            .flatten()
+    }
+
+    /// Rename the entries of a list, returning (old, new) pairs
+    fn rename_entries(&mut self, list: &str, name: &str) -> Vec<(String, String)> {
+        let entries: Vec<&mut Value> = self.entries_mut(list).collect();
+        let single = entries.len() == 1;
+        let mut renamed = Vec::new();
+
+        for entry in entries {
+            let Some(old) = entry.get("name").and_then(Value::as_str).map(str::to_string) else {
+                continue;
+            };
+            let new = if single { name.to_string() } else { format!("{}-{}", name, old) };
+            if let Value::Mapping(entry) = entry {
+                entry.insert(Value::from("name"), Value::from(new.clone()));
+            }
+            renamed.push((old, new));
+        }
+        renamed
+    }
+}
+
+fn rename_reference(reference: &mut Value, renamed: &[(String, String)]) {
+    if let Some((_, new)) = renamed.iter().find(|(old, _)| reference.as_str() == Some(old)) {
+        *reference = Value::from(new.as_str());
+    }
+}
+
+/// Replace the host of a URL like `https://127.0.0.1:6443/prefix`, including bracketed IPv6 hosts
+fn replace_url_host(url: &str, host: &str) -> String {
+    let (scheme, rest) = url.split_once("://").unwrap_or(("https", url));
+    let (authority, path) = match rest.find('/') {
+        Some(slash) => rest.split_at(slash),
+        None => (rest, ""),
+    };
+    let port = match authority.rfind(']') {
+        Some(bracket) => &authority[bracket + 1..],
+        None => authority.rfind(':').map_or("", |colon| &authority[colon..]),
+    };
+    format!("{}://{}{}{}", scheme, bracket_host(host), port, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const K3S_KUBECONFIG: &str = "\
+apiVersion: v1
+clusters:
+- cluster:
+    certificate-authority-data: LS0tLS1CRUdJTi
+    server: https://127.0.0.1:6443
+  name: default
+contexts:
+- context:
+    cluster: default
+    user: default
+  name: default
+current-context: default
+kind: Config
+preferences: {}
+users:
+- name: default
+  user:
+    client-certificate-data: LS0tLS1DRVJU
+    client-key-data: LS0tLS1LRVk
+";
+
+    #[test]
+    fn test_set_server_host_and_rename() {
+        let mut kubeconfig = Kubeconfig::parse(K3S_KUBECONFIG).unwrap();
+        kubeconfig.set_server_host("203.0.113.7");
+        kubeconfig.rename("demo");
+
+        let yaml = kubeconfig.to_yaml().unwrap();
+        let reparsed = Kubeconfig::parse(&yaml).unwrap();
+        assert_eq!(reparsed.servers(), vec!["https://203.0.113.7:6443"]);
+        assert!(yaml.contains("current-context: demo"));
+        assert!(yaml.contains("cluster: demo\n    user: demo"));
+        assert!(yaml.contains("client-key-data: LS0tLS1LRVk"));
+        assert!(!yaml.contains("default"));
+        assert_eq!(reparsed.client_certificates(), [("demo".to_string(), "-----CERT".to_string())]);
+    }
+
+    #[test]
+    fn test_multi_cluster_kubeconfig() {
+        let yaml = "\
+clusters:
+- cluster: {server: 'https://10.0.0.10:6443'}
+  name: a
+- cluster: {server: 'https://10.0.0.11:16443/k8s'}
+  name: b
+contexts:
+- context: {cluster: b, user: b}
+  name: b
+users:
+- name: b
+current-context: b
+";
+        let mut kubeconfig = Kubeconfig::parse(yaml).unwrap();
+        kubeconfig.set_server_host("k3s.example.com");
+        kubeconfig.rename("demo");
+
+        assert_eq!(
+            kubeconfig.servers(),
+            vec!["https://k3s.example.com:6443", "https://k3s.example.com:16443/k8s"]
+        );
+        let yaml = kubeconfig.to_yaml().unwrap();
+        assert!(yaml.contains("name: demo-a"));
+        assert!(yaml.contains("cluster: demo-b"));
+        assert!(yaml.contains("user: demo\n"));
+        assert!(yaml.contains("current-context: demo\n"));
+    }
+
+    #[test]
+    fn test_skip_tls_verify() {
+        let mut kubeconfig = Kubeconfig::parse(K3S_KUBECONFIG).unwrap();
+        kubeconfig.skip_tls_verify();
+        let yaml = kubeconfig.to_yaml().unwrap();
+        assert!(yaml.contains("insecure-skip-tls-verify: true"));
+        assert!(!yaml.contains("certificate-authority-data"));
+    }
+
+    #[test]
+    fn test_replace_url_host() {
+        assert_eq!(replace_url_host("https://[::1]:6443", "203.0.113.7"), "https://203.0.113.7:6443");
+        assert_eq!(replace_url_host("https://127.0.0.1:6443", "2001:db8::1"), "https://[2001:db8::1]:6443");
+        assert_eq!(replace_url_host("https://kubernetes", "10.0.0.1"), "https://10.0.0.1");
+    }
+}
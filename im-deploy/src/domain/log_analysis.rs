@@ -0,0 +1,303 @@
+/// Severity of a log line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warning,
+    Error,
+    Fatal,
+}
+
+impl LogLevel {
+    fn from_tag(tag: &str) -> Option<Self> {
+        match tag.to_ascii_uppercase().as_str() {
+            "DEBUG" | "TRACE" => Some(Self::Debug),
+            "INFO" | "NOTICE" => Some(Self::Info),
+            "WARN" | "WARNING" => Some(Self::Warning),
+            "ERROR" | "ERR" => Some(Self::Error),
+            "FATAL" | "PANIC" | "CRITICAL" => Some(Self::Fatal),
+            _ => None,
+        }
+    }
+
+    /// klog prefix such as `E1017` (level letter followed by month and day)
+    fn from_klog(token: &str) -> Option<Self> {
+        let (letter, date) = token.split_at_checked(1)?;
+        if date.len() != 4 || !date.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        match letter {
+            "I" => Some(Self::Info),
+            "W" => Some(Self::Warning),
+            "E" => Some(Self::Error),
+            "F" => Some(Self::Fatal),
+            _ => None,
+        }
+    }
+}
+
+/// Level of a log line from its leading tag (`ERROR:`, `[warn]`, `E1017`), optionally
+/// after a timestamp, or from a `level=error` field. Words like "errors" in the
+/// message itself do not count.
+pub fn parse_level(line: &str) -> Option<LogLevel> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+
+    if let Some(value) = tokens.iter().find_map(|t| t.strip_prefix("level=")) {
+        return LogLevel::from_tag(value.trim_matches('"'));
+    }
+
+    let is_timestamp = |t: &&&str| t.trim_start_matches('[').starts_with(|c: char| c.is_ascii_digit());
+    let token = tokens.iter().find(|t| !is_timestamp(t))?;
+
+    if let Some(level) = LogLevel::from_klog(token) {
+        return Some(level);
+    }
+    let tagged = token.ends_with(':') || (token.starts_with('[') && token.ends_with(']'));
+    let tag = token.trim_start_matches('[').trim_end_matches([':', ']']);
+    // Bare words only count when shouted, so "Error handling enabled" is not an error
+    if tagged || tag.bytes().all(|b| b.is_ascii_uppercase()) {
+        LogLevel::from_tag(tag)
+    } else {
+        None
+    }
+}
+
+/// Decides which lines of one log count as failures
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    /// Substrings of error lines known to be harmless
+    ignore: Vec<String>,
+    /// Substrings marking failures in lines without an error level
+    errors: Vec<String>,
+}
+
+impl LogFilter {
+    pub fn new(ignore: Vec<String>) -> Self {
+        Self { ignore, errors: Vec::new() }
+    }
+
+    /// Also count lines containing one of `errors` as failures
+    pub fn with_errors(mut self, errors: Vec<String>) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    pub fn is_failure(&self, line: &str) -> bool {
+        (parse_level(line).is_some_and(|level| level >= LogLevel::Error)
+            || self.errors.iter().any(|pattern| line.contains(pattern.as_str())))
+            && !self.is_ignored(line)
+    }
+
+    fn is_ignored(&self, line: &str) -> bool {
+        self.ignore.iter().any(|pattern| line.contains(pattern.as_str()))
+    }
+
+    pub fn has_failure(&self, log: &str) -> bool {
+        log.lines().any(|line| self.is_failure(line))
+    }
+
+    pub fn has_warning(&self, log: &str) -> bool {
+        log.lines().any(|line| parse_level(line) == Some(LogLevel::Warning) && !self.is_ignored(line))
+    }
+}
+
+/// Known failure causes found in cloud-init and install logs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    ImagePull,
+    TokenMismatch,
+    DiskPressure,
+    ApiUnreachable,
+}
+
+impl FailureKind {
+    const ALL: [FailureKind; 4] = [
+        FailureKind::ImagePull,
+        FailureKind::TokenMismatch,
+        FailureKind::DiskPressure,
+        FailureKind::ApiUnreachable,
+    ];
+
+    /// Lowercase substrings that identify this failure
+    fn patterns(&self) -> &'static [&'static str] {
+        match self {
+            FailureKind::ImagePull => &[
+                "errimagepull",
+                "imagepullbackoff",
+                "failed to pull image",
+                "pull access denied",
+                "manifest unknown",
+            ],
+            FailureKind::TokenMismatch => &[
+                "token ca hash does not match",
+                "failed to validate token",
+                "node password rejected",
+                "invalid token",
+            ],
+            FailureKind::DiskPressure => &["diskpressure", "no space left on device", "evicted"],
+            FailureKind::ApiUnreachable => &[
+                "6443: connect: connection refused",
+                "the connection to the server",
+                "context deadline exceeded",
+            ],
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            FailureKind::ImagePull => "Container image could not be pulled",
+            FailureKind::TokenMismatch => "Node rejected by the cluster token",
+            FailureKind::DiskPressure => "Node is running out of disk space",
+            FailureKind::ApiUnreachable => "Kubernetes API server not reachable",
+        }
+    }
+
+    pub fn remediation(&self) -> &'static str {
+        match self {
+            FailureKind::ImagePull => {
+                "Check the image name and tag, registry credentials and outbound internet access from the nodes"
+            }
+            FailureKind::TokenMismatch => {
+                "Make sure all nodes share the same k3s token; for rebuilt nodes delete the stale \
+                 <node>.node-password.k3s secret in kube-system"
+            }
+            FailureKind::DiskPressure => {
+                "Increase the node volume size or prune unused images with 'sudo k3s crictl rmi --prune'"
+            }
+            FailureKind::ApiUnreachable => {
+                "Check that k3s is running on the servers ('sudo systemctl status k3s') and that port 6443 is open"
+            }
+        }
+    }
+}
+
+/// Lines around the first error in a log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogExcerpt {
+    /// 1-based line number of the first excerpt line
+    pub first_line: usize,
+    /// 1-based line number of the error
+    pub error_line: usize,
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogAnalysis {
+    pub excerpt: Option<LogExcerpt>,
+    /// Error lines in the log besides the one in the excerpt
+    pub more_errors: usize,
+    pub failures: Vec<FailureKind>,
+}
+
+/// Find the first error with `context` lines on either side and classify known failures
+pub fn analyze(log: &str, context: usize, filter: &LogFilter) -> LogAnalysis {
+    let lines: Vec<&str> = log.lines().collect();
+    let error_indices: Vec<usize> = (0..lines.len()).filter(|&i| filter.is_failure(lines[i])).collect();
+
+    let excerpt = error_indices.first().map(|&index| {
+        let start = index.saturating_sub(context);
+        let end = (index + context + 1).min(lines.len());
+        LogExcerpt {
+            first_line: start + 1,
+            error_line: index + 1,
+            lines: lines[start..end].iter().map(|l| l.to_string()).collect(),
+        }
+    });
+
+    let lowercase = log.to_lowercase();
+    let failures = FailureKind::ALL
+        .into_iter()
+        .filter(|kind| kind.patterns().iter().any(|p| lowercase.contains(p)))
+        .collect();
+
+    LogAnalysis {
+        excerpt,
+        more_errors: error_indices.len().saturating_sub(1),
+        failures,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "\
+Installing k3s...
+Waiting for node
+Pulling images
+ERROR: Failed to pull image \"nvcr.io/nvidia/gpu-operator:v99\": manifest unknown
+retrying
+ERROR: giving up
+done";
+
+    #[test]
+    fn test_analyze_extracts_first_error_with_context() {
+        let analysis = analyze(LOG, 1, &LogFilter::default());
+        let excerpt = analysis.excerpt.unwrap();
+        assert_eq!(excerpt.first_line, 3);
+        assert_eq!(excerpt.error_line, 4);
+        assert_eq!(excerpt.lines.len(), 3);
+        assert_eq!(excerpt.lines[2], "retrying");
+        assert_eq!(analysis.more_errors, 1);
+        assert_eq!(analysis.failures, vec![FailureKind::ImagePull]);
+    }
+
+    #[test]
+    fn test_analyze_context_clamped_to_log() {
+        let analysis = analyze("FATAL: no space left on device\nbye", 5, &LogFilter::default());
+        let excerpt = analysis.excerpt.unwrap();
+        assert_eq!(excerpt.first_line, 1);
+        assert_eq!(excerpt.lines.len(), 2);
+        assert_eq!(analysis.failures, vec![FailureKind::DiskPressure]);
+    }
+
+    #[test]
+    fn test_filter_error_patterns() {
+        let filter = LogFilter::new(vec!["retrying".to_string()]).with_errors(vec!["release failed".to_string()]);
+        assert!(filter.is_failure("helm: release failed: timed out"));
+        assert!(!filter.is_failure("helm: release failed, retrying"));
+        assert!(filter.is_failure("ERROR: anything else"));
+        assert!(!LogFilter::default().is_failure("helm: release failed"));
+    }
+
+    #[test]
+    fn test_analyze_clean_log() {
+        let analysis = analyze("all good\nstill good", 3, &LogFilter::default());
+        assert!(analysis.excerpt.is_none());
+        assert!(analysis.failures.is_empty());
+    }
+
+    #[test]
+    fn test_parse_level_tags() {
+        assert_eq!(parse_level("ERROR: K3s did not become ready in time"), Some(LogLevel::Error));
+        assert_eq!(parse_level("WARNING: Not all nodes became ready"), Some(LogLevel::Warning));
+        assert_eq!(parse_level("2025-01-02T03:04:05Z [error] dial failed"), Some(LogLevel::Error));
+        assert_eq!(parse_level("Error: INSTALLATION FAILED: timed out"), Some(LogLevel::Error));
+        assert_eq!(
+            parse_level("E1017 12:00:01.123456 1234 reflector.go:138] watch failed"),
+            Some(LogLevel::Error)
+        );
+        assert_eq!(
+            parse_level("time=\"2025-01-02T03:04:05Z\" level=fatal msg=\"bind: address in use\""),
+            Some(LogLevel::Fatal)
+        );
+        assert_eq!(parse_level("Installing ArgoCD..."), None);
+    }
+
+    #[test]
+    fn test_parse_level_ignores_words_in_message() {
+        assert_eq!(parse_level("Helm finished with 0 errors"), None);
+        assert_eq!(parse_level("Error handling middleware enabled"), None);
+        assert_eq!(parse_level("Checking for ERROR conditions"), None);
+    }
+
+    #[test]
+    fn test_filter_ignore_patterns() {
+        let filter = LogFilter::new(vec!["metrics-server not yet available".to_string()]);
+        assert!(!filter.is_failure("ERROR: metrics-server not yet available, retrying"));
+        assert!(filter.is_failure("ERROR: helm install failed"));
+        assert!(filter.has_warning("info\nWARNING: slow"));
+        assert!(!filter.has_warning("WARNING: metrics-server not yet available"));
+        assert!(!filter.has_failure("INFO: 0 errors"));
+    }
+}
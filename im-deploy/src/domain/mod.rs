@@ -1,4 +1,26 @@
+pub mod apps;
+pub mod certificates;
+pub mod cloud_init;
 pub mod cluster;
 pub mod connection;
+pub mod control_plane;
+pub mod datastore;
+pub mod events;
+pub mod gpu;
+pub mod helm;
+pub mod import;
+pub mod inventory;
+pub mod kubeconfig;
+pub mod log_analysis;
+pub mod monitor;
+pub mod outputs_schema;
+pub mod resource_usage;
+pub mod scaffold;
 pub mod services;
+pub mod ssh_config;
+pub mod ssh_keys;
+pub mod step_summary;
+pub mod tailscale_acl;
+pub mod timeline;
+pub mod token_rotation;
 
@@ -0,0 +1,512 @@
+use crate::constants::monitoring;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Steps of `im-deploy monitor`, in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MonitorPhase {
+    CloudInit,
+    Nodes,
+    ApiLoadBalancer,
+    ControlPlane,
+    Workloads,
+    Gpu,
+    Argocd,
+    ArgocdServe,
+}
+
+impl MonitorPhase {
+    pub const ALL: [MonitorPhase; 8] = [
+        MonitorPhase::CloudInit,
+        MonitorPhase::Nodes,
+        MonitorPhase::ApiLoadBalancer,
+        MonitorPhase::ControlPlane,
+        MonitorPhase::Workloads,
+        MonitorPhase::Gpu,
+        MonitorPhase::Argocd,
+        MonitorPhase::ArgocdServe,
+    ];
+
+    /// Name used on the command line and in the saved monitor progress
+    pub fn name(&self) -> &'static str {
+        match self {
+            MonitorPhase::CloudInit => "cloud-init",
+            MonitorPhase::Nodes => "nodes",
+            MonitorPhase::ApiLoadBalancer => "api",
+            MonitorPhase::ControlPlane => "control-plane",
+            MonitorPhase::Workloads => "workloads",
+            MonitorPhase::Gpu => "gpu",
+            MonitorPhase::Argocd => "argocd",
+            MonitorPhase::ArgocdServe => "argocd-serve",
+        }
+    }
+}
+
+impl fmt::Display for MonitorPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for MonitorPhase {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MonitorPhase::ALL
+            .into_iter()
+            .find(|p| p.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<&str> = MonitorPhase::ALL.iter().map(|p| p.name()).collect();
+                format!("unknown phase '{}', expected one of: {}", s, names.join(", "))
+            })
+    }
+}
+
+/// A phase followed through the logs cloud-init writes on the first server: the server log
+/// announces it with `start_marker`, then its own log says `success_marker` when done
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogPhase {
+    /// e.g. "GPU Operator installation"
+    pub title: String,
+    pub log_file: String,
+    pub start_marker: String,
+    pub success_marker: String,
+    /// Lines from this marker on are shown once the phase succeeded, e.g. access URLs
+    pub summary_marker: Option<String>,
+    /// Lines containing one of these fail the phase even without an error level
+    pub error_patterns: Vec<String>,
+    /// Give up when the phase takes longer; None waits as long as the monitor runs
+    pub timeout: Option<Duration>,
+}
+
+impl LogPhase {
+    /// Definition matching the terraform templates; None for phases not driven by logs
+    pub fn default_for(phase: MonitorPhase) -> Option<LogPhase> {
+        let (title, log_file, start_marker, success_marker, summary_marker) = match phase {
+            MonitorPhase::Gpu => (
+                "GPU Operator installation",
+                "/var/log/gpu-operator-install.log",
+                "Installing NVIDIA GPU Operator...",
+                "GPU Operator installation complete!",
+                None,
+            ),
+            MonitorPhase::Argocd => (
+                "ArgoCD installation",
+                "/var/log/argocd-install.log",
+                "Installing ArgoCD...",
+                "ArgoCD installation complete!",
+                None,
+            ),
+            MonitorPhase::ArgocdServe => (
+                "Tailscale ArgoCD Serve setup",
+                "/var/log/tailscale-argocd-serve.log",
+                "Setting up Tailscale Serve for ArgoCD...",
+                "Tailscale Serve configured successfully for ArgoCD",
+                Some("===================================================================="),
+            ),
+            _ => return None,
+        };
+        Some(LogPhase {
+            title: title.to_string(),
+            log_file: log_file.to_string(),
+            start_marker: start_marker.to_string(),
+            success_marker: success_marker.to_string(),
+            summary_marker: summary_marker.map(str::to_string),
+            error_patterns: Vec::new(),
+            timeout: None,
+        })
+    }
+
+    /// File name keying `log_ignore_patterns`, e.g. "gpu-operator-install.log"
+    pub fn log_name(&self) -> &str {
+        log_name(&self.log_file)
+    }
+}
+
+/// File name of a remote log path
+pub fn log_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Extra phase from the add-on file of the terraform module, run after the built-in phases
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddonPhase {
+    /// Key in the saved monitor progress and the timeline
+    pub name: String,
+    pub check: AddonCheck,
+    /// Bool terraform output telling whether the component is deployed at all
+    pub enabled_output: Option<String>,
+}
+
+impl AddonPhase {
+    /// Whether the component is deployed: always without `enabled_output`, otherwise when
+    /// that output is `true`
+    pub fn enabled(&self, outputs: &Value) -> bool {
+        self.enabled_output.as_ref().is_none_or(|name| {
+            outputs.get(name).and_then(|output| output.get("value")).and_then(Value::as_bool) == Some(true)
+        })
+    }
+
+    pub fn title(&self) -> &str {
+        match &self.check {
+            AddonCheck::Log(log) => &log.title,
+            AddonCheck::Helm(helm) => &helm.title,
+        }
+    }
+}
+
+/// How an add-on phase tells it is done
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddonCheck {
+    /// Markers in a log file, like the built-in phases
+    Log(LogPhase),
+    /// Status of a Helm release, for charts whose logs have no stable markers
+    Helm(HelmPhase),
+}
+
+/// A Helm release waited for until it is deployed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HelmPhase {
+    pub title: String,
+    pub release: String,
+    pub namespace: Option<String>,
+    pub timeout: Option<Duration>,
+}
+
+/// `monitor-addons.toml`: one `[[phase]]` table per component
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AddonFile {
+    #[serde(default)]
+    phase: Vec<AddonDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AddonDefinition {
+    name: String,
+    /// "log" (default) or "helm"
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    /// Defaults to the name
+    title: Option<String>,
+    log_file: Option<String>,
+    start_marker: Option<String>,
+    success_marker: Option<String>,
+    summary_marker: Option<String>,
+    #[serde(default)]
+    error_patterns: Vec<String>,
+    /// Helm release name, defaults to the phase name
+    release: Option<String>,
+    namespace: Option<String>,
+    /// Defaults to `monitoring::ADDON_TIMEOUT_SECS`
+    timeout_secs: Option<u64>,
+    /// Bool terraform output; the phase only runs when it is true
+    enabled_output: Option<String>,
+}
+
+impl AddonDefinition {
+    fn into_check(self, name: &str) -> Result<AddonCheck, String> {
+        let title = self.title.unwrap_or_else(|| name.to_string());
+        let timeout = Some(Duration::from_secs(self.timeout_secs.unwrap_or(monitoring::ADDON_TIMEOUT_SECS)));
+        match self.kind.as_deref().unwrap_or("log") {
+            "log" => {
+                if self.release.is_some() || self.namespace.is_some() {
+                    return Err(format!("phase '{}' sets release or namespace without type = \"helm\"", name));
+                }
+                let required = |field: &str, value: Option<String>| {
+                    value
+                        .filter(|value| !value.is_empty())
+                        .ok_or_else(|| format!("phase '{}' needs a {}", name, field))
+                };
+                Ok(AddonCheck::Log(LogPhase {
+                    title,
+                    log_file: required("log_file", self.log_file)?,
+                    start_marker: required("start_marker", self.start_marker)?,
+                    success_marker: required("success_marker", self.success_marker)?,
+                    summary_marker: self.summary_marker.filter(|marker| !marker.is_empty()),
+                    error_patterns: self.error_patterns,
+                    timeout,
+                }))
+            }
+            "helm" => {
+                let log_fields = [&self.log_file, &self.start_marker, &self.success_marker, &self.summary_marker];
+                if log_fields.iter().any(|field| field.is_some()) || !self.error_patterns.is_empty() {
+                    return Err(format!("phase '{}' of type helm has log file settings", name));
+                }
+                Ok(AddonCheck::Helm(HelmPhase {
+                    title,
+                    release: self.release.unwrap_or_else(|| name.to_string()),
+                    namespace: self.namespace,
+                    timeout,
+                }))
+            }
+            other => Err(format!("phase '{}' has unknown type '{}', expected 'log' or 'helm'", name, other)),
+        }
+    }
+}
+
+/// Add-on phases of an add-on file, in file order. Names must be unique and must not
+/// shadow a built-in phase.
+pub fn parse_addons(content: &str) -> Result<Vec<AddonPhase>, String> {
+    let file: AddonFile = toml::from_str(content).map_err(|e| e.to_string())?;
+    let mut addons: Vec<AddonPhase> = Vec::new();
+    for definition in file.phase {
+        let name = definition.name.clone();
+        if name.trim().is_empty() {
+            return Err("a phase has an empty name".to_string());
+        }
+        if name.parse::<MonitorPhase>().is_ok() {
+            return Err(format!("phase '{}' is built in", name));
+        }
+        if addons.iter().any(|addon| addon.name == name) {
+            return Err(format!("phase '{}' is defined twice", name));
+        }
+        let enabled_output = definition.enabled_output.clone().filter(|output| !output.is_empty());
+        let check = definition.into_check(&name)?;
+        addons.push(AddonPhase { name, check, enabled_output });
+    }
+    Ok(addons)
+}
+
+/// Logs the monitor reads, with the built-in phases overridable in im-deploy.toml
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonitorLogs {
+    pub server_log: String,
+    pub phases: BTreeMap<MonitorPhase, LogPhase>,
+}
+
+impl Default for MonitorLogs {
+    fn default() -> Self {
+        Self {
+            server_log: monitoring::SERVER_LOG.to_string(),
+            phases: MonitorPhase::ALL
+                .into_iter()
+                .filter_map(|phase| LogPhase::default_for(phase).map(|log_phase| (phase, log_phase)))
+                .collect(),
+        }
+    }
+}
+
+impl MonitorLogs {
+    /// How `phase` is followed; log-driven phases always have an entry
+    pub fn phase(&self, phase: MonitorPhase) -> Option<&LogPhase> {
+        self.phases.get(&phase)
+    }
+}
+
+/// Which phases a monitor run checks
+#[derive(Debug, Clone, Default)]
+pub struct MonitorOptions {
+    /// Continue an interrupted run: skip completed phases and keep its timing
+    pub resume: bool,
+    pub skip: Vec<MonitorPhase>,
+    /// Add-on phases to leave out, by name
+    pub skip_addons: Vec<String>,
+    /// Run only these phases; empty runs all
+    pub only: Vec<MonitorPhase>,
+    /// Add-on phases to run only, by name, next to `only`
+    pub only_addons: Vec<String>,
+    /// Seconds between checks; defaults to `monitoring::CHECK_INTERVAL_SECS`
+    pub interval_secs: Option<u64>,
+    /// Keep showing node and pod health after all phases complete
+    pub watch: bool,
+}
+
+impl MonitorOptions {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.unwrap_or(monitoring::CHECK_INTERVAL_SECS))
+    }
+
+    pub fn selects(&self, phase: MonitorPhase) -> bool {
+        (self.runs_all() || self.only.contains(&phase)) && !self.skip.contains(&phase)
+    }
+
+    /// Leave out the phases named in `names`; names other than the built-in phases are
+    /// taken as add-ons, checked once the add-on file is loaded
+    pub fn skip_named(&mut self, names: Vec<String>) {
+        for name in names {
+            match name.parse::<MonitorPhase>() {
+                Ok(phase) => self.skip.push(phase),
+                Err(_) => self.skip_addons.push(name),
+            }
+        }
+    }
+
+    /// Run only the phases named in `names`, built-in or add-on like `skip_named`
+    pub fn only_named(&mut self, names: Vec<String>) {
+        for name in names {
+            match name.parse::<MonitorPhase>() {
+                Ok(phase) => self.only.push(phase),
+                Err(_) => self.only_addons.push(name),
+            }
+        }
+    }
+
+    /// Add-on phases run unless only other phases are asked for or they are skipped by name
+    pub fn selects_addon(&self, name: &str) -> bool {
+        (self.runs_all() || self.only_addons.iter().any(|only| only == name))
+            && !self.skip_addons.iter().any(|skipped| skipped == name)
+    }
+
+    fn runs_all(&self) -> bool {
+        self.only.is_empty() && self.only_addons.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phase_names_roundtrip() {
+        for phase in MonitorPhase::ALL {
+            assert_eq!(phase.name().parse::<MonitorPhase>().unwrap(), phase);
+        }
+        assert_eq!("GPU".parse::<MonitorPhase>().unwrap(), MonitorPhase::Gpu);
+
+        let err = "grafana".parse::<MonitorPhase>().unwrap_err();
+        assert!(err.contains("argocd-serve"));
+    }
+
+    #[test]
+    fn test_options_select_phases() {
+        let all = MonitorOptions::default();
+        assert!(MonitorPhase::ALL.iter().all(|p| all.selects(*p)));
+
+        let skip_gpu = MonitorOptions {
+            skip: vec![MonitorPhase::Gpu],
+            ..Default::default()
+        };
+        assert!(!skip_gpu.selects(MonitorPhase::Gpu));
+        assert!(skip_gpu.selects(MonitorPhase::Argocd));
+
+        let only_argocd = MonitorOptions {
+            only: vec![MonitorPhase::Argocd],
+            ..Default::default()
+        };
+        assert!(only_argocd.selects(MonitorPhase::Argocd));
+        assert!(!only_argocd.selects(MonitorPhase::Nodes));
+        assert!(!only_argocd.selects_addon("longhorn"));
+
+        let mut skip_named = MonitorOptions::default();
+        skip_named.skip_named(vec!["gpu".to_string(), "longhorn".to_string()]);
+        assert_eq!(skip_named.skip, [MonitorPhase::Gpu]);
+        assert!(!skip_named.selects_addon("longhorn"));
+        assert!(skip_named.selects_addon("velero"));
+
+        let mut only_named = MonitorOptions::default();
+        only_named.only_named(vec!["nodes".to_string(), "longhorn".to_string()]);
+        assert_eq!(only_named.only, [MonitorPhase::Nodes]);
+        assert!(only_named.selects(MonitorPhase::Nodes));
+        assert!(!only_named.selects(MonitorPhase::Gpu));
+        assert!(only_named.selects_addon("longhorn"));
+        assert!(!only_named.selects_addon("velero"));
+
+        let mut only_addon = MonitorOptions::default();
+        only_addon.only_named(vec!["longhorn".to_string()]);
+        assert!(!only_addon.selects(MonitorPhase::Nodes));
+        assert!(only_addon.selects_addon("longhorn"));
+    }
+
+    #[test]
+    fn test_default_log_phases() {
+        let logs = MonitorLogs::default();
+        assert_eq!(logs.server_log, "/var/log/k3s-server.log");
+        assert_eq!(logs.phases.keys().copied().collect::<Vec<_>>(), [MonitorPhase::Gpu, MonitorPhase::Argocd, MonitorPhase::ArgocdServe]);
+        assert_eq!(logs.phase(MonitorPhase::Gpu).unwrap().log_name(), "gpu-operator-install.log");
+        assert!(logs.phase(MonitorPhase::ArgocdServe).unwrap().summary_marker.is_some());
+        assert!(logs.phase(MonitorPhase::Nodes).is_none());
+    }
+
+    #[test]
+    fn test_parse_addons() {
+        let addons = parse_addons(
+            r#"
+[[phase]]
+name = "longhorn"
+title = "Longhorn installation"
+log_file = "/var/log/longhorn-install.log"
+start_marker = "Installing Longhorn..."
+success_marker = "Longhorn installation complete!"
+error_patterns = ["helm: release failed"]
+timeout_secs = 900
+enabled_output = "longhorn_enabled"
+
+[[phase]]
+name = "velero"
+log_file = "/var/log/velero-install.log"
+start_marker = "Installing Velero..."
+success_marker = "Velero ready"
+"#,
+        )
+        .unwrap();
+        assert_eq!(addons.len(), 2);
+        assert_eq!(addons[0].title(), "Longhorn installation");
+        let AddonCheck::Log(longhorn) = &addons[0].check else {
+            panic!("expected a log phase: {:?}", addons[0]);
+        };
+        assert_eq!(longhorn.error_patterns, ["helm: release failed"]);
+        assert_eq!(longhorn.timeout, Some(Duration::from_secs(900)));
+        assert_eq!(addons[1].title(), "velero");
+        let AddonCheck::Log(velero) = &addons[1].check else {
+            panic!("expected a log phase: {:?}", addons[1]);
+        };
+        assert_eq!(velero.timeout, Some(Duration::from_secs(monitoring::ADDON_TIMEOUT_SECS)));
+        assert!(parse_addons("").unwrap().is_empty());
+
+        // Only an output that is true enables the phase
+        let outputs = serde_json::json!({"longhorn_enabled": {"value": false}});
+        assert!(!addons[0].enabled(&outputs));
+        assert!(addons[0].enabled(&serde_json::json!({"longhorn_enabled": {"value": true}})));
+        assert!(!addons[0].enabled(&serde_json::json!({})));
+        assert!(addons[1].enabled(&outputs));
+
+        let builtin = "[[phase]]\nname = \"argocd\"\nlog_file = \"/x.log\"\nstart_marker = \"a\"\nsuccess_marker = \"b\"\n";
+        assert!(parse_addons(builtin).unwrap_err().contains("built in"));
+        let twice = builtin.replace("argocd", "velero").repeat(2);
+        assert!(parse_addons(&twice).unwrap_err().contains("defined twice"));
+        assert!(parse_addons(&builtin.replace("argocd", "velero").replace("\"a\"", "\"\"")).unwrap_err().contains("start_marker"));
+        assert!(parse_addons("[[phase]]\nname = \"x\"\n").unwrap_err().contains("log_file"));
+    }
+
+    #[test]
+    fn test_parse_helm_addons() {
+        let addons = parse_addons(
+            "[[phase]]\nname = \"cert-manager\"\ntype = \"helm\"\nnamespace = \"cert-manager\"\ntimeout_secs = 600\n\n\
+             [[phase]]\nname = \"storage\"\ntype = \"helm\"\nrelease = \"longhorn\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            addons[0].check,
+            AddonCheck::Helm(HelmPhase {
+                title: "cert-manager".to_string(),
+                release: "cert-manager".to_string(),
+                namespace: Some("cert-manager".to_string()),
+                timeout: Some(Duration::from_secs(600)),
+            })
+        );
+        assert!(matches!(&addons[1].check, AddonCheck::Helm(helm) if helm.release == "longhorn"));
+
+        let mixed = "[[phase]]\nname = \"x\"\ntype = \"helm\"\nlog_file = \"/x.log\"\n";
+        assert!(parse_addons(mixed).unwrap_err().contains("log file settings"));
+        assert!(parse_addons("[[phase]]\nname = \"x\"\ntype = \"job\"\n").unwrap_err().contains("unknown type"));
+    }
+
+    #[test]
+    fn test_options_interval() {
+        assert_eq!(
+            MonitorOptions::default().interval(),
+            Duration::from_secs(monitoring::CHECK_INTERVAL_SECS)
+        );
+        let fast = MonitorOptions {
+            interval_secs: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(fast.interval(), Duration::from_secs(3));
+    }
+}
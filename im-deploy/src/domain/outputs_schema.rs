@@ -0,0 +1,184 @@
+use crate::domain::cluster::CLUSTER_OUTPUT_SUFFIX;
+use crate::errors::TerraformError;
+use serde_json::Value;
+
+/// Version of the outputs below, declared by the root module's `outputs_schema_version`
+/// output. Bump both with every change older im-deploy releases can't read.
+pub const OUTPUTS_SCHEMA_VERSION: u64 = 1;
+pub const VERSION_OUTPUT: &str = "outputs_schema_version";
+
+/// Shape of a value im-deploy reads; null always stands for "not set"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    Bool,
+    Text,
+    TextList,
+    /// e.g. `ssh_users = { server = "ubuntu", agent = "rocky" }`
+    TextMap,
+    /// e.g. `tailscale_hostnames = { openstack_servers = [...] }`
+    TextListMap,
+}
+
+impl Shape {
+    fn check(self, value: &Value) -> std::result::Result<(), String> {
+        let is_text_list = |value: &Value| value.as_array().is_some_and(|items| items.iter().all(Value::is_string));
+        let valid = match self {
+            Shape::Bool => value.is_boolean(),
+            Shape::Text => value.is_string(),
+            Shape::TextList => is_text_list(value),
+            Shape::TextMap => value.as_object().is_some_and(|map| map.values().all(|v| v.is_string() || v.is_null())),
+            Shape::TextListMap => value.as_object().is_some_and(|map| map.values().all(|v| is_text_list(v) || v.is_null())),
+        };
+        if value.is_null() || valid {
+            return Ok(());
+        }
+        Err(format!("expected {}, found {}", self.describe(), value))
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Shape::Bool => "a bool",
+            Shape::Text => "a string",
+            Shape::TextList => "a list of strings",
+            Shape::TextMap => "a map of strings",
+            Shape::TextListMap => "a map of string lists",
+        }
+    }
+}
+
+/// Root outputs im-deploy reads besides the per-cloud ones
+const OUTPUTS: [(&str, Shape); 9] = [
+    ("tailscale_enabled", Shape::Bool),
+    ("tailscale_hostnames", Shape::TextListMap),
+    ("all_bastion_ips", Shape::TextList),
+    ("all_server_ips", Shape::TextList),
+    ("all_agent_ips", Shape::TextList),
+    ("primary_api_endpoint", Shape::Text),
+    ("kubeconfig_source", Shape::Text),
+    ("enable_nvidia_gpu_operator", Shape::Bool),
+    ("enable_argocd", Shape::Bool),
+];
+
+/// Fields of each `<cloud>_cluster` output
+const CLUSTER_FIELDS: [(&str, Shape); 14] = [
+    ("display_name", Shape::Text),
+    ("cluster_name", Shape::Text),
+    ("bastion_ip", Shape::Text),
+    ("loadbalancer_ip", Shape::Text),
+    ("network_id", Shape::Text),
+    ("server_ips", Shape::TextList),
+    ("agent_ips", Shape::TextList),
+    ("server_ipv6s", Shape::TextList),
+    ("agent_ipv6s", Shape::TextList),
+    ("server_public_ips", Shape::TextList),
+    ("agent_public_ips", Shape::TextList),
+    ("ssh_users", Shape::TextMap),
+    ("tailscale_hostnames", Shape::TextListMap),
+    ("tailscale_subnet_routes", Shape::TextList),
+];
+
+/// Fields a cloud's output can't do without
+const REQUIRED_CLUSTER_FIELDS: [&str; 2] = ["server_ips", "agent_ips"];
+
+/// Schema version the outputs declare; None for outputs written before versioning
+pub fn schema_version(outputs: &Value) -> Option<u64> {
+    outputs.get(VERSION_OUTPUT)?.get("value")?.as_u64()
+}
+
+/// Check `terraform output -json` against the schema this release reads. Outputs without a
+/// version pass, so a cluster deployed before versioning keeps working until the next apply.
+pub fn validate(outputs: &Value) -> std::result::Result<(), TerraformError> {
+    let Some(map) = outputs.as_object() else {
+        return Err(invalid("outputs", "expected an object of outputs".to_string()));
+    };
+    let value = |name: &str| map.get(name).and_then(|output| output.get("value")).filter(|v| !v.is_null());
+
+    if let Some(version) = value(VERSION_OUTPUT) {
+        match version.as_u64() {
+            Some(OUTPUTS_SCHEMA_VERSION) => {}
+            Some(found) => {
+                return Err(TerraformError::OutputsVersionMismatch {
+                    expected: OUTPUTS_SCHEMA_VERSION,
+                    found: format!("v{}", found),
+                });
+            }
+            None => return Err(invalid(VERSION_OUTPUT, format!("expected a number, found {}", version))),
+        }
+    }
+
+    for (name, shape) in OUTPUTS {
+        if let Some(output) = value(name) {
+            shape.check(output).map_err(|reason| invalid(name, reason))?;
+        }
+    }
+
+    for name in map.keys().filter(|key| key.len() > CLUSTER_OUTPUT_SUFFIX.len() && key.ends_with(CLUSTER_OUTPUT_SUFFIX)) {
+        let Some(cluster) = value(name) else {
+            continue;
+        };
+        if !cluster.is_object() {
+            return Err(invalid(name, format!("expected an object, found {}", cluster)));
+        }
+        for (field, shape) in CLUSTER_FIELDS {
+            if let Some(field_value) = cluster.get(field) {
+                shape
+                    .check(field_value)
+                    .map_err(|reason| invalid(&format!("{}.{}", name, field), reason))?;
+            }
+        }
+        if let Some(field) = REQUIRED_CLUSTER_FIELDS.iter().find(|field| cluster.get(**field).is_none_or(Value::is_null)) {
+            return Err(invalid(name, format!("{} is missing", field)));
+        }
+    }
+    Ok(())
+}
+
+fn invalid(output: &str, reason: String) -> TerraformError {
+    TerraformError::InvalidOutput {
+        output: output.to_string(),
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn outputs(version: Value, cluster: Value) -> Value {
+        json!({
+            "outputs_schema_version": {"value": version},
+            "openstack_cluster": {"value": cluster},
+            "tailscale_enabled": {"value": false},
+            "kubeconfig_source": {"value": null}
+        })
+    }
+
+    #[test]
+    fn test_outputs_match_schema() {
+        let cluster = json!({"server_ips": ["10.0.1.10"], "agent_ips": [], "bastion_ip": null, "ssh_users": {"server": "ubuntu"}});
+        assert!(validate(&outputs(json!(1), cluster.clone())).is_ok());
+        assert_eq!(schema_version(&outputs(json!(1), cluster.clone())), Some(1));
+        // Nothing deployed, or deployed before outputs were versioned
+        assert!(validate(&json!({})).is_ok());
+        assert!(validate(&outputs(Value::Null, cluster)).is_ok());
+    }
+
+    #[test]
+    fn test_outputs_schema_errors_name_the_output() {
+        let cluster = json!({"server_ips": ["10.0.1.10"], "agent_ips": []});
+        let err = validate(&outputs(json!(2), cluster.clone())).unwrap_err();
+        assert!(err.to_string().starts_with("Terraform module outputs v1 expected, found v2"), "{}", err);
+
+        let err = validate(&outputs(json!(1), json!({"server_ips": "10.0.1.10", "agent_ips": []}))).unwrap_err();
+        assert!(err.to_string().contains("openstack_cluster.server_ips"), "{}", err);
+        assert!(err.to_string().contains("expected a list of strings, found \"10.0.1.10\""), "{}", err);
+
+        let err = validate(&outputs(json!(1), json!({"server_ips": []}))).unwrap_err();
+        assert!(err.to_string().contains("agent_ips is missing"), "{}", err);
+
+        let mut bad_flag = outputs(json!(1), cluster);
+        bad_flag["tailscale_enabled"]["value"] = json!("yes");
+        assert!(validate(&bad_flag).unwrap_err().to_string().contains("tailscale_enabled"));
+    }
+}
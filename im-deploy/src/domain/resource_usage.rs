@@ -0,0 +1,185 @@
+use std::cmp::Reverse;
+
+/// One line of `kubectl top nodes`. Nodes metrics-server has no sample for yet show
+/// `<unknown>` and have no values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeUsage {
+    pub name: String,
+    pub cpu_millicores: Option<u64>,
+    pub cpu_percent: Option<u64>,
+    pub memory_bytes: Option<u64>,
+    pub memory_percent: Option<u64>,
+}
+
+/// One line of `kubectl top pods -A`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PodUsage {
+    pub namespace: String,
+    pub name: String,
+    pub cpu_millicores: u64,
+    pub memory_bytes: u64,
+}
+
+/// Column the `top` tables are sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    #[default]
+    Cpu,
+    Memory,
+    Name,
+}
+
+impl SortKey {
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Cpu => "CPU",
+            SortKey::Memory => "memory",
+            SortKey::Name => "name",
+        }
+    }
+}
+
+/// Parse `kubectl top nodes --no-headers`: name, CPU, CPU%, memory, memory%
+pub fn parse_top_nodes(output: &str) -> Vec<NodeUsage> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let (name, values) = columns.split_first()?;
+            let value = |index: usize| values.get(index).copied().unwrap_or_default();
+            Some(NodeUsage {
+                name: name.to_string(),
+                cpu_millicores: parse_cpu(value(0)),
+                cpu_percent: parse_percent(value(1)),
+                memory_bytes: parse_memory(value(2)),
+                memory_percent: parse_percent(value(3)),
+            })
+        })
+        .collect()
+}
+
+/// Parse `kubectl top pods -A --no-headers`: namespace, name, CPU, memory
+pub fn parse_top_pods(output: &str) -> Vec<PodUsage> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let [namespace, name, cpu, memory, ..] = columns.as_slice() else {
+                return None;
+            };
+            Some(PodUsage {
+                namespace: namespace.to_string(),
+                name: name.to_string(),
+                cpu_millicores: parse_cpu(cpu)?,
+                memory_bytes: parse_memory(memory)?,
+            })
+        })
+        .collect()
+}
+
+/// Millicores of a Kubernetes CPU quantity such as `250m`, `2` or `1500000n`
+pub fn parse_cpu(quantity: &str) -> Option<u64> {
+    if let Some(nanocores) = quantity.strip_suffix('n') {
+        return nanocores.parse::<u64>().ok().map(|n| n / 1_000_000);
+    }
+    if let Some(millicores) = quantity.strip_suffix('m') {
+        return millicores.parse().ok();
+    }
+    quantity.parse::<u64>().ok().map(|cores| cores * 1000)
+}
+
+/// Bytes of a Kubernetes memory quantity such as `1200Mi`, `2Gi` or `512000Ki`
+pub fn parse_memory(quantity: &str) -> Option<u64> {
+    const UNITS: [(&str, u64); 6] = [
+        ("Ki", 1 << 10),
+        ("Mi", 1 << 20),
+        ("Gi", 1 << 30),
+        ("k", 1_000),
+        ("M", 1_000_000),
+        ("G", 1_000_000_000),
+    ];
+    for (suffix, factor) in UNITS {
+        if let Some(number) = quantity.strip_suffix(suffix) {
+            return number.parse::<u64>().ok().map(|n| n * factor);
+        }
+    }
+    quantity.parse().ok()
+}
+
+fn parse_percent(text: &str) -> Option<u64> {
+    text.strip_suffix('%')?.parse().ok()
+}
+
+/// Memory in the largest binary unit that keeps it above 1, e.g. `1.2Gi`
+pub fn format_memory(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{}B", bytes),
+        1024..1_048_576 => format!("{}Ki", bytes >> 10),
+        1_048_576..1_073_741_824 => format!("{}Mi", bytes >> 20),
+        _ => format!("{:.1}Gi", bytes as f64 / (1u64 << 30) as f64),
+    }
+}
+
+/// Highest usage first; nodes without metrics last
+pub fn sort_nodes(nodes: &mut [NodeUsage], key: SortKey) {
+    match key {
+        SortKey::Cpu => nodes.sort_by(|a, b| b.cpu_millicores.cmp(&a.cpu_millicores).then_with(|| a.name.cmp(&b.name))),
+        SortKey::Memory => nodes.sort_by(|a, b| b.memory_bytes.cmp(&a.memory_bytes).then_with(|| a.name.cmp(&b.name))),
+        SortKey::Name => nodes.sort_by(|a, b| a.name.cmp(&b.name)),
+    }
+}
+
+pub fn sort_pods(pods: &mut [PodUsage], key: SortKey) {
+    match key {
+        SortKey::Cpu => pods.sort_by_key(|p| Reverse(p.cpu_millicores)),
+        SortKey::Memory => pods.sort_by_key(|p| Reverse(p.memory_bytes)),
+        SortKey::Name => pods.sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_top_output() {
+        let mut nodes = parse_top_nodes(
+            "k3s-server-0   250m   12%   1200Mi   30%\n\
+             k3s-agent-0    1500m  37%   6Gi      75%\n\
+             k3s-agent-1    <unknown>  <unknown>  <unknown>  <unknown>\n",
+        );
+        assert_eq!(
+            nodes[0],
+            NodeUsage {
+                name: "k3s-server-0".to_string(),
+                cpu_millicores: Some(250),
+                cpu_percent: Some(12),
+                memory_bytes: Some(1200 << 20),
+                memory_percent: Some(30),
+            }
+        );
+        assert_eq!(nodes[2].cpu_millicores, None);
+
+        sort_nodes(&mut nodes, SortKey::Memory);
+        let order: Vec<&str> = nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(order, ["k3s-agent-0", "k3s-server-0", "k3s-agent-1"]);
+
+        let mut pods = parse_top_pods(
+            "kube-system  coredns-abc   3m    20Mi\n\
+             immich       server-xyz    120m  512Mi\n",
+        );
+        sort_pods(&mut pods, SortKey::Cpu);
+        assert_eq!(pods[0].name, "server-xyz");
+        assert_eq!(pods[0].memory_bytes, 512 << 20);
+    }
+
+    #[test]
+    fn test_quantities() {
+        assert_eq!(parse_cpu("2"), Some(2000));
+        assert_eq!(parse_cpu("1500000n"), Some(1));
+        assert_eq!(parse_memory("512000Ki"), Some(512_000 * 1024));
+        assert_eq!(parse_memory("<unknown>"), None);
+        assert_eq!(format_memory(1200 << 20), "1.2Gi");
+        assert_eq!(format_memory(20 << 20), "20Mi");
+    }
+}
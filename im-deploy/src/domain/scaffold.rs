@@ -0,0 +1,325 @@
+use crate::domain::cluster::CLUSTER_OUTPUT_SUFFIX;
+use std::path::PathBuf;
+
+/// A file `scaffold provider` writes, relative to the terraform directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScaffoldFile {
+    pub path: PathBuf,
+    pub content: String,
+}
+
+/// Cloud names become terraform identifiers and output keys like `<cloud>_cluster` and
+/// `<cloud>_servers`, so they are limited to lowercase letters and digits
+pub fn validate_cloud_name(cloud: &str) -> std::result::Result<(), String> {
+    if !cloud.starts_with(|c: char| c.is_ascii_lowercase())
+        || !cloud.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+    {
+        return Err(format!(
+            "'{}' must start with a lowercase letter and contain only lowercase letters and digits",
+            cloud
+        ));
+    }
+    if cloud == "openstack" {
+        return Err("openstack already has a module".to_string());
+    }
+    Ok(())
+}
+
+/// Directory of the cloud's module, e.g. `modules/hetzner-k3s`
+pub fn module_dir(cloud: &str) -> PathBuf {
+    PathBuf::from("modules").join(format!("{}-k3s", cloud))
+}
+
+/// The module skeleton and the root file wiring it to the `<cloud>_cluster` output
+/// `cloud_providers_from_outputs` reads
+pub fn provider_files(cloud: &str) -> Vec<ScaffoldFile> {
+    let module = module_dir(cloud);
+    [
+        (module.join("variables.tf"), MODULE_VARIABLES),
+        (module.join("main.tf"), MODULE_MAIN),
+        (module.join("outputs.tf"), MODULE_OUTPUTS),
+        (module.join("versions.tf"), MODULE_VERSIONS),
+        (PathBuf::from(format!("{}.tf", cloud)), ROOT_FILE),
+    ]
+    .into_iter()
+    .map(|(path, template)| ScaffoldFile {
+        path,
+        content: fill(template, cloud),
+    })
+    .collect()
+}
+
+/// terraform.tfvars.example section for the cloud. Settings the example already has, such as
+/// a placeholder `enable_<cloud> = false`, are left out.
+pub fn tfvars_entries(cloud: &str, existing: &str) -> String {
+    // The setting of a line, also when commented out
+    let key = |line: &str| line.trim_start_matches('#').split_whitespace().next().map(str::to_string);
+    let present = |line: &str| existing.lines().any(|existing| key(existing) == key(line));
+    fill(TFVARS, cloud)
+        .lines()
+        .filter(|line| !line.contains(" = ") || !present(line))
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+fn fill(template: &str, cloud: &str) -> String {
+    template
+        .replace("{cloud}", cloud)
+        .replace("{output_suffix}", CLUSTER_OUTPUT_SUFFIX)
+}
+
+const MODULE_VARIABLES: &str = r#"# {cloud} K3s Cluster Module - Input Variables
+###############################################################################
+# Required Variables
+###############################################################################
+variable "cluster_name" {
+  description = "Name of the K3s cluster; nodes are named <cluster_name>-server-<i> and <cluster_name>-agent-<i>"
+  type        = string
+}
+variable "k3s_token" {
+  description = "K3s cluster token for node authentication"
+  type        = string
+  sensitive   = true
+}
+variable "ssh_public_key_path" {
+  description = "Path to SSH public key file"
+  type        = string
+}
+###############################################################################
+# Optional Configuration with Defaults
+###############################################################################
+variable "server_count" {
+  description = "Number of control plane servers"
+  type        = number
+  default     = 3
+}
+variable "agent_count" {
+  description = "Number of worker nodes"
+  type        = number
+  default     = 3
+}
+variable "server_ssh_user" {
+  description = "Login user of the server image"
+  type        = string
+  default     = "ubuntu"
+}
+variable "agent_ssh_user" {
+  description = "Login user of the agent image"
+  type        = string
+  default     = "ubuntu"
+}
+variable "enable_tailscale" {
+  description = "Join the nodes to the tailnet"
+  type        = bool
+  default     = false
+}
+"#;
+
+const MODULE_MAIN: &str = r#"# {cloud} K3s Cluster Module
+# TODO: create the network, the bastion and one instance per node. Name instances
+# local.server_names / local.agent_names so im-deploy matches them to Kubernetes nodes.
+locals {
+  server_names = [for i in range(var.server_count) : "${var.cluster_name}-server-${i}"]
+  agent_names  = [for i in range(var.agent_count) : "${var.cluster_name}-agent-${i}"]
+}
+"#;
+
+const MODULE_OUTPUTS: &str = r#"# Module Outputs
+# im-deploy reads these through the root {cloud}{output_suffix} output
+output "cluster_name" {
+  description = "Name of the K3s cluster"
+  value       = var.cluster_name
+}
+output "bastion_ip" {
+  description = "Public IP address of the bastion host, null to connect directly or via Tailscale"
+  value       = null # TODO
+}
+output "loadbalancer_ip" {
+  description = "Public IP address of the API load balancer"
+  value       = null # TODO
+}
+output "server_ips" {
+  description = "Private IP addresses of server nodes, in node order"
+  value       = [] # TODO
+}
+output "server_ipv6s" {
+  description = "IPv6 addresses of server nodes (empty for nodes without one)"
+  value       = [] # TODO
+}
+output "agent_ips" {
+  description = "Private IP addresses of agent nodes, in node order"
+  value       = [] # TODO
+}
+output "agent_ipv6s" {
+  description = "IPv6 addresses of agent nodes (empty for nodes without one)"
+  value       = [] # TODO
+}
+output "tailscale_server_hostnames" {
+  description = "Tailscale MagicDNS hostnames of server nodes"
+  value       = var.enable_tailscale ? local.server_names : []
+}
+output "tailscale_agent_hostnames" {
+  description = "Tailscale MagicDNS hostnames of agent nodes"
+  value       = var.enable_tailscale ? local.agent_names : []
+}
+"#;
+
+const MODULE_VERSIONS: &str = r#"# {cloud} K3s Cluster Module - Provider Versions
+terraform {
+  required_version = ">= 1.0"
+  required_providers {
+    # TODO: the {cloud} provider
+  }
+}
+"#;
+
+const ROOT_FILE: &str = r#"###############################################################################
+# {cloud} K3s Cluster
+###############################################################################
+variable "enable_{cloud}" {
+  description = "Deploy the {cloud} cluster"
+  type        = bool
+  default     = false
+}
+variable "{cloud}_server_count" {
+  description = "Number of K3s servers in {cloud}"
+  type        = number
+  default     = 3
+}
+variable "{cloud}_agent_count" {
+  description = "Number of K3s agents in {cloud}"
+  type        = number
+  default     = 3
+}
+variable "{cloud}_server_ssh_user" {
+  description = "Login user of the server image"
+  type        = string
+  default     = "ubuntu"
+}
+variable "{cloud}_agent_ssh_user" {
+  description = "Login user of the agent image"
+  type        = string
+  default     = "ubuntu"
+}
+
+module "{cloud}_k3s" {
+  source = "./modules/{cloud}-k3s"
+  count  = var.enable_{cloud} ? 1 : 0
+
+  cluster_name        = "${local.cluster_name}-{cloud}"
+  k3s_token           = var.k3s_token
+  ssh_public_key_path = var.ssh_key_path
+  server_count        = var.{cloud}_server_count
+  agent_count         = var.{cloud}_agent_count
+  server_ssh_user     = var.{cloud}_server_ssh_user
+  agent_ssh_user      = var.{cloud}_agent_ssh_user
+  enable_tailscale    = var.enable_tailscale
+}
+
+# Read by im-deploy; the keys match the openstack{output_suffix} output
+output "{cloud}{output_suffix}" {
+  description = "{cloud} K3s cluster information"
+  value = var.enable_{cloud} ? {
+    display_name    = "{cloud}"
+    cluster_name    = module.{cloud}_k3s[0].cluster_name
+    bastion_ip      = module.{cloud}_k3s[0].bastion_ip
+    loadbalancer_ip = module.{cloud}_k3s[0].loadbalancer_ip
+    server_ips      = module.{cloud}_k3s[0].server_ips
+    agent_ips       = module.{cloud}_k3s[0].agent_ips
+    server_ipv6s    = module.{cloud}_k3s[0].server_ipv6s
+    agent_ipv6s     = module.{cloud}_k3s[0].agent_ipv6s
+    ssh_users = {
+      server = var.{cloud}_server_ssh_user
+      agent  = var.{cloud}_agent_ssh_user
+    }
+    tailscale_hostnames = {
+      server = module.{cloud}_k3s[0].tailscale_server_hostnames
+      agent  = module.{cloud}_k3s[0].tailscale_agent_hostnames
+    }
+  } : null
+}
+"#;
+
+const TFVARS: &str = r#"
+###############################################################################
+# {cloud} (scaffolded, see modules/{cloud}-k3s)
+###############################################################################
+enable_{cloud} = false
+# {cloud}_server_count    = 3
+# {cloud}_agent_count     = 3
+# {cloud}_server_ssh_user = "ubuntu"
+# {cloud}_agent_ssh_user  = "ubuntu"
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::cluster::cloud_providers_from_outputs;
+    use serde_json::json;
+
+    #[test]
+    fn test_cloud_name_validation() {
+        assert!(validate_cloud_name("hetzner").is_ok());
+        assert!(validate_cloud_name("ovh2").is_ok());
+        assert!(validate_cloud_name("openstack").is_err());
+        assert!(validate_cloud_name("my_cloud").is_err());
+        assert!(validate_cloud_name("2cloud").is_err());
+        assert!(validate_cloud_name("").is_err());
+    }
+
+    #[test]
+    fn test_scaffolded_output_is_extracted() {
+        let files = provider_files("hetzner");
+        let paths: Vec<String> = files.iter().map(|f| f.path.display().to_string()).collect();
+        assert_eq!(
+            paths,
+            [
+                "modules/hetzner-k3s/variables.tf",
+                "modules/hetzner-k3s/main.tf",
+                "modules/hetzner-k3s/outputs.tf",
+                "modules/hetzner-k3s/versions.tf",
+                "hetzner.tf",
+            ]
+        );
+        assert!(files.iter().all(|f| !f.content.contains("{cloud}")));
+        let root = &files[4].content;
+        assert!(root.contains("output \"hetzner_cluster\" {"));
+
+        // The keys of the root output, as terraform would report them
+        let cluster = json!({
+            "display_name": "hetzner",
+            "cluster_name": "demo-hetzner",
+            "bastion_ip": null,
+            "loadbalancer_ip": null,
+            "server_ips": ["10.1.0.10"],
+            "agent_ips": ["10.1.0.20"],
+            "server_ipv6s": [""],
+            "agent_ipv6s": [""],
+            "ssh_users": {"server": "ubuntu", "agent": "root"},
+            "tailscale_hostnames": {"server": ["demo-hetzner-server-0"], "agent": ["demo-hetzner-agent-0"]}
+        });
+        for key in cluster.as_object().unwrap().keys() {
+            assert!(root.contains(&format!("    {} ", key)), "{} missing from the scaffold", key);
+        }
+        let outputs = json!({
+            "hetzner_cluster": {"value": cluster},
+            "tailscale_enabled": {"value": true}
+        });
+        let providers = cloud_providers_from_outputs(&outputs);
+        assert_eq!(providers[0].name, "hetzner");
+        let agent = &providers[0].servers[1];
+        assert_eq!(agent.name, "k3s-hetzner-agent-0");
+        assert_eq!(agent.cloud_provider, "hetzner");
+        assert_eq!(agent.login_user(), "root");
+        assert_eq!(agent.tailscale_hostname.as_deref(), Some("demo-hetzner-agent-0"));
+        assert!(agent.matches_node_name("demo-hetzner-agent-0"));
+    }
+
+    #[test]
+    fn test_tfvars_entries_skip_existing_settings() {
+        let entries = tfvars_entries("aws", "enable_openstack = true\nenable_aws       = false  # Future support\n");
+        assert!(!entries.contains("enable_aws"));
+        assert!(entries.contains("# aws_server_count    = 3\n"));
+        assert!(tfvars_entries("hetzner", "").contains("\nenable_hetzner = false\n"));
+    }
+}
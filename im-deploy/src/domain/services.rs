@@ -1,5 +1,6 @@
 use crate::domain::connection::ConnectionStrategy;
 use crate::errors::Result;
+use crate::runner::CommandRunner;
 use std::fmt;
 /// Information about a deployed service
 #[derive(Debug, Clone)]
@@ -59,15 +60,17 @@ impl fmt::Display for ServiceInfo {
 }
 /// Helper to execute kubectl commands via SSH
 pub fn execute_kubectl_command(
+    runner: &dyn CommandRunner,
     strategy: &ConnectionStrategy,
     command: &str,
 ) -> Result<String> {
     let full_command = format!("sudo kubectl {}", command);
-    let output = strategy.execute_command(&full_command)?;
+    let output = strategy.execute_command(runner, &full_command)?;
     Ok(String::from_utf8_lossy(&output.stdout).to_string())
 }
 /// Get secret value from kubernetes
 pub fn get_k8s_secret(
+    runner: &dyn CommandRunner,
     strategy: &ConnectionStrategy,
     secret_name: &str,
     namespace: &str,
@@ -77,6 +80,6 @@ pub fn get_k8s_secret(
         r#"get secret {} -n {} -o jsonpath="{{.data.{}}}" 2>/dev/null | base64 -d"#,
         secret_name, namespace, key
     );
-    let output = execute_kubectl_command(strategy, &command)?;
+    let output = execute_kubectl_command(runner, strategy, &command)?;
     Ok(output.trim().to_string())
 }
@@ -0,0 +1,177 @@
+use crate::constants::ssh;
+use crate::domain::cluster::CloudProvider;
+use crate::domain::connection::ConnectionStrategy;
+use std::path::{Path, PathBuf};
+
+/// One `Host` block of the exported OpenSSH config
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshHost {
+    pub alias: String,
+    pub hostname: String,
+    pub user: String,
+    /// Alias of the bastion's entry when the host is only reachable through it
+    pub proxy_jump: Option<String>,
+    pub identity_file: Option<PathBuf>,
+}
+
+/// File the host entries are written to, included from ~/.ssh/config via `Include config.d/*`
+pub fn config_path(home: &Path, cluster_name: &str) -> PathBuf {
+    home.join(ssh::CONFIG_DIR).join(format!("im-deploy-{}", cluster_name))
+}
+
+/// Host entries for every server, connecting the same way as `im-deploy ssh`. Aliases
+/// are the server names; with several providers they are prefixed with the provider.
+pub fn hosts(cluster_name: &str, providers: &[CloudProvider], identity_file: Option<&Path>) -> Vec<SshHost> {
+    let mut hosts = Vec::new();
+    let prefixed = providers.len() > 1;
+
+    for provider in providers {
+        let prefix = if prefixed {
+            format!("{}-", provider.name.to_lowercase())
+        } else {
+            String::new()
+        };
+
+        // Hops go through the bastion's own entry, so its user and identity file apply
+        let bastion_alias = format!("{}{}-bastion", prefix, cluster_name);
+        if let Some(bastion_ip) = &provider.bastion_ip {
+            hosts.push(SshHost {
+                alias: bastion_alias.clone(),
+                hostname: bastion_ip.clone(),
+                user: ssh::SSH_USER.to_string(),
+                proxy_jump: None,
+                identity_file: identity_file.map(Path::to_path_buf),
+            });
+        }
+
+        for server in &provider.servers {
+            let Ok(strategy) = ConnectionStrategy::from_server(server, provider.bastion_ip.as_deref()) else {
+                continue;
+            };
+            let (hostname, proxy_jump) = match strategy {
+                ConnectionStrategy::Tailscale { hostname: host, .. }
+                | ConnectionStrategy::TailscaleSsh { hostname: host, .. }
+                | ConnectionStrategy::Direct { host, .. } => (host, None),
+                ConnectionStrategy::Bastion { target_ip, .. } => (target_ip, Some(bastion_alias.clone())),
+            };
+            hosts.push(SshHost {
+                alias: format!("{}{}", prefix, server.name),
+                hostname,
+                user: server.login_user().to_string(),
+                proxy_jump,
+                identity_file: identity_file.map(Path::to_path_buf),
+            });
+        }
+    }
+
+    hosts
+}
+
+pub fn render(cluster_name: &str, hosts: &[SshHost]) -> String {
+    let mut config = format!(
+        "# Generated by im-deploy for cluster '{}'; removed by 'im-deploy destroy'\n",
+        cluster_name
+    );
+    for host in hosts {
+        config.push_str(&format!("\nHost {}\n", host.alias));
+        config.push_str(&format!("    HostName {}\n", host.hostname));
+        config.push_str(&format!("    User {}\n", host.user));
+        if let Some(proxy_jump) = &host.proxy_jump {
+            config.push_str(&format!("    ProxyJump {}\n", proxy_jump));
+        }
+        if let Some(identity_file) = &host.identity_file {
+            config.push_str(&format!("    IdentityFile \"{}\"\n", identity_file.display()));
+        }
+        // Servers are recreated with the same addresses on every deploy
+        config.push_str("    StrictHostKeyChecking no\n");
+        config.push_str("    UserKnownHostsFile /dev/null\n");
+    }
+    config
+}
+
+/// Whether ~/.ssh/config already includes the config.d directory
+pub fn includes_config_dir(ssh_config: &str) -> bool {
+    ssh_config.lines().any(|line| {
+        let mut words = line.split_whitespace();
+        words.next().is_some_and(|keyword| keyword.eq_ignore_ascii_case("include"))
+            && words.any(|pattern| pattern.contains("config.d/"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::cluster::ServerInfo;
+
+    fn server(name: &str, ip: &str, tailscale_hostname: Option<&str>) -> ServerInfo {
+        ServerInfo {
+            name: name.to_string(),
+            ip: ip.to_string(),
+            addresses: Vec::new(),
+            cloud_provider: "OpenStack".to_string(),
+            tailscale_hostname: tailscale_hostname.map(str::to_string),
+            tailscale_ip: None,
+            ssh_user: None,
+            public_ip: None,
+            tailscale_ssh: false,
+            via_subnet_router: false,
+        }
+    }
+
+    #[test]
+    fn test_hosts_via_bastion() {
+        let providers = vec![CloudProvider {
+            name: "OpenStack".to_string(),
+            bastion_ip: Some("203.0.113.5".to_string()),
+            tailscale_enabled: false,
+            servers: vec![server("k3s-server-0", "10.0.0.10", None), server("k3s-agent-1", "10.0.0.21", None)],
+            subnet_routes: Vec::new(),
+        }];
+        let hosts = hosts("demo", &providers, Some(Path::new("/home/me/.ssh/id_ed25519")));
+
+        assert_eq!(hosts.len(), 3);
+        assert_eq!(hosts[0].alias, "demo-bastion");
+        assert_eq!(hosts[2].alias, "k3s-agent-1");
+        assert_eq!(hosts[2].hostname, "10.0.0.21");
+        assert_eq!(hosts[2].proxy_jump.as_deref(), Some("demo-bastion"));
+
+        let rendered = render("demo", &hosts);
+        assert!(rendered.contains("Host demo-bastion\n    HostName 203.0.113.5\n    User ubuntu\n"));
+        assert!(rendered.contains("Host k3s-agent-1\n    HostName 10.0.0.21\n    User ubuntu\n    ProxyJump demo-bastion\n"));
+        assert!(rendered.contains("IdentityFile \"/home/me/.ssh/id_ed25519\""));
+    }
+
+    #[test]
+    fn test_hosts_via_tailscale_prefixed_per_provider() {
+        let providers = vec![
+            CloudProvider {
+                name: "OpenStack".to_string(),
+                bastion_ip: None,
+                tailscale_enabled: true,
+                servers: vec![server("k3s-server-0", "10.0.0.10", Some("demo-server-0"))],
+                subnet_routes: Vec::new(),
+            },
+            CloudProvider {
+                name: "Other".to_string(),
+                bastion_ip: None,
+                tailscale_enabled: false,
+                // Neither Tailscale nor a bastion: no way to connect
+                servers: vec![server("k3s-server-0", "10.1.0.10", None)],
+                subnet_routes: Vec::new(),
+            },
+        ];
+        let hosts = hosts("demo", &providers, None);
+
+        assert_eq!(hosts.len(), 1);
+        assert_eq!(hosts[0].alias, "openstack-k3s-server-0");
+        assert_eq!(hosts[0].hostname, "demo-server-0");
+        assert!(hosts[0].proxy_jump.is_none());
+    }
+
+    #[test]
+    fn test_includes_config_dir() {
+        assert!(includes_config_dir("Include config.d/*\n\nHost *\n"));
+        assert!(includes_config_dir("  include ~/.ssh/config.d/im-deploy-*"));
+        assert!(!includes_config_dir("Host *\n    IdentityFile ~/.ssh/id_ed25519\n"));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::domain::connection::shell_quote;
+use std::path::{Path, PathBuf};
+
+/// Base64 key material of an OpenSSH public key line, without type and comment.
+/// authorized_keys entries are matched on it since clouds may rewrite the comment.
+pub fn key_blob(public_key: &str) -> Option<&str> {
+    public_key.split_whitespace().nth(1)
+}
+
+/// Remote command adding `public_key` to the login user's authorized_keys unless present
+pub fn authorize_command(public_key: &str) -> Option<String> {
+    let blob = key_blob(public_key)?;
+    Some(format!(
+        "mkdir -p ~/.ssh && chmod 700 ~/.ssh && touch ~/.ssh/authorized_keys && \
+         (grep -qF {} ~/.ssh/authorized_keys || echo {} >> ~/.ssh/authorized_keys)",
+        shell_quote(blob),
+        shell_quote(public_key.trim())
+    ))
+}
+
+/// Remote command removing every authorized_keys entry for `public_key`
+pub fn revoke_command(public_key: &str) -> Option<String> {
+    let blob = key_blob(public_key)?;
+    Some(format!(
+        "(grep -vF {} ~/.ssh/authorized_keys || true) > ~/.ssh/authorized_keys.new && \
+         chmod 600 ~/.ssh/authorized_keys.new && mv ~/.ssh/authorized_keys.new ~/.ssh/authorized_keys",
+        shell_quote(blob)
+    ))
+}
+
+/// Where the replacement of `identity` is generated, e.g. `~/.ssh/id_ed25519-20250102T030405Z`
+pub fn rotated_identity(identity: &Path, stamp: &str) -> PathBuf {
+    let mut name = identity.file_name().unwrap_or_default().to_os_string();
+    name.push(format!("-{}", stamp));
+    identity.with_file_name(name)
+}
+
+/// Public key file ssh-keygen writes next to a private key
+pub fn public_key_path(identity: &Path) -> PathBuf {
+    let mut path = identity.as_os_str().to_os_string();
+    path.push(".pub");
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIFq0 it's me\n";
+
+    #[test]
+    fn test_authorized_keys_commands_match_on_key_material() {
+        assert_eq!(key_blob(KEY), Some("AAAAC3NzaC1lZDI1NTE5AAAAIFq0"));
+        assert_eq!(authorize_command("garbage"), None);
+
+        let authorize = authorize_command(KEY).unwrap();
+        assert!(authorize.contains("grep -qF 'AAAAC3NzaC1lZDI1NTE5AAAAIFq0' ~/.ssh/authorized_keys"));
+        assert!(authorize.contains(r"echo 'ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIFq0 it'\''s me' >>"));
+
+        let revoke = revoke_command(KEY).unwrap();
+        assert!(revoke.starts_with("(grep -vF 'AAAAC3NzaC1lZDI1NTE5AAAAIFq0' ~/.ssh/authorized_keys || true)"));
+    }
+
+    #[test]
+    fn test_key_paths() {
+        let rotated = rotated_identity(Path::new("/home/me/.ssh/id_ed25519"), "20250102T030405Z");
+        assert_eq!(rotated, PathBuf::from("/home/me/.ssh/id_ed25519-20250102T030405Z"));
+        assert_eq!(public_key_path(&rotated), PathBuf::from("/home/me/.ssh/id_ed25519-20250102T030405Z.pub"));
+    }
+}
@@ -0,0 +1,173 @@
+use crate::domain::cluster::ServerInfo;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Markdown job summary of a deploy or destroy, shown on the GitHub Actions run page
+#[derive(Debug, Clone, Default)]
+pub struct StepSummary {
+    /// e.g. "Deploy demo"
+    pub title: String,
+    pub succeeded: bool,
+    /// Phase name and how long it took, in order
+    pub phases: Vec<(String, Duration)>,
+    pub nodes: Vec<ServerInfo>,
+    /// Label and URL or address
+    pub endpoints: Vec<(String, String)>,
+    pub warnings: Vec<String>,
+    /// Steps left out on purpose, with why
+    pub skipped: Vec<String>,
+}
+
+impl StepSummary {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let (icon, outcome) = if self.succeeded { ("✅", "succeeded") } else { ("❌", "failed") };
+        let mut markdown = format!("## {} {} {}\n\n", icon, self.title, outcome);
+
+        if !self.phases.is_empty() {
+            markdown.push_str("| Phase | Duration |\n|---|---:|\n");
+            for (phase, duration) in &self.phases {
+                markdown.push_str(&format!("| {} | {} |\n", cell(phase), format_duration(*duration)));
+            }
+            let total: Duration = self.phases.iter().map(|(_, duration)| *duration).sum();
+            markdown.push_str(&format!("| **Total** | **{}** |\n\n", format_duration(total)));
+        }
+
+        if !self.nodes.is_empty() {
+            markdown.push_str("| Node | IP | Tailscale |\n|---|---|---|\n");
+            for node in &self.nodes {
+                markdown.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    cell(&node.name),
+                    cell(&node.ip),
+                    node.tailscale_hostname.as_deref().map(cell).unwrap_or_default()
+                ));
+            }
+            markdown.push('\n');
+        }
+
+        if !self.endpoints.is_empty() {
+            markdown.push_str("| Endpoint | Address |\n|---|---|\n");
+            for (label, address) in &self.endpoints {
+                markdown.push_str(&format!("| {} | `{}` |\n", cell(label), cell(address)));
+            }
+            markdown.push('\n');
+        }
+
+        if !self.skipped.is_empty() {
+            markdown.push_str("### Skipped steps\n\n");
+            for step in &self.skipped {
+                markdown.push_str(&format!("- {}\n", step));
+            }
+            markdown.push('\n');
+        }
+
+        if !self.warnings.is_empty() {
+            markdown.push_str("### Warnings\n\n");
+            for warning in &self.warnings {
+                markdown.push_str(&format!("- {}\n", warning.replace('\n', " ")));
+            }
+            markdown.push('\n');
+        }
+
+        markdown
+    }
+}
+
+/// Add to the summary file; several steps of a job write to the same file
+pub fn append(path: &Path, markdown: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(markdown.as_bytes())
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{}m {:02}s", duration.as_secs() / 60, duration.as_secs() % 60)
+}
+
+/// Text safe inside a table cell
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_step_summary() {
+        let summary = StepSummary {
+            title: "Deploy demo".to_string(),
+            succeeded: true,
+            phases: vec![
+                ("Terraform apply".to_string(), Duration::from_secs(252)),
+                ("Nodes".to_string(), Duration::from_secs(95)),
+            ],
+            nodes: vec![ServerInfo {
+                name: "k3s-server-0".to_string(),
+                ip: "10.0.1.10".to_string(),
+                addresses: Vec::new(),
+                cloud_provider: "openstack".to_string(),
+                tailscale_hostname: Some("demo-server-0".to_string()),
+                tailscale_ip: None,
+                ssh_user: None,
+                public_ip: None,
+                tailscale_ssh: false,
+                via_subnet_router: false,
+            }],
+            endpoints: vec![("Kubernetes API".to_string(), "https://203.0.113.5:6443".to_string())],
+            warnings: vec!["Could not update DNS record k8s: a|b".to_string()],
+            skipped: Vec::new(),
+        };
+
+        assert_eq!(
+            summary.render(),
+            "## ✅ Deploy demo succeeded\n\n\
+             | Phase | Duration |\n|---|---:|\n\
+             | Terraform apply | 4m 12s |\n\
+             | Nodes | 1m 35s |\n\
+             | **Total** | **5m 47s** |\n\n\
+             | Node | IP | Tailscale |\n|---|---|---|\n\
+             | k3s-server-0 | 10.0.1.10 | demo-server-0 |\n\n\
+             | Endpoint | Address |\n|---|---|\n\
+             | Kubernetes API | `https://203.0.113.5:6443` |\n\n\
+             ### Warnings\n\n\
+             - Could not update DNS record k8s: a|b\n\n"
+        );
+    }
+
+    #[test]
+    fn test_failed_summary_without_details() {
+        let summary = StepSummary::new("Destroy demo");
+        assert_eq!(summary.render(), "## ❌ Destroy demo failed\n\n");
+    }
+
+    #[test]
+    fn test_summary_lists_skipped_steps() {
+        let summary = StepSummary {
+            succeeded: true,
+            skipped: vec!["Tailscale cleanup (--skip-tailscale)".to_string()],
+            ..StepSummary::new("Destroy demo")
+        };
+        assert_eq!(
+            summary.render(),
+            "## ✅ Destroy demo succeeded\n\n### Skipped steps\n\n- Tailscale cleanup (--skip-tailscale)\n\n"
+        );
+    }
+
+    #[test]
+    fn test_append_keeps_earlier_steps() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("summary.md");
+        append(&path, "first\n").unwrap();
+        append(&path, "second\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+    }
+}
@@ -0,0 +1,212 @@
+use crate::constants::ssh;
+use serde_json::{json, Map, Value};
+
+/// Tag of one cloud's nodes. Each cloud module names its cluster `<cluster>-<cloud>` and tags
+/// its nodes with that name (`tailscale.tf`).
+pub fn node_tag(cluster_name: &str, cloud: &str) -> String {
+    format!("tag:{}-{}", cluster_name, cloud)
+}
+
+/// Tags the terraform auth keys put on the nodes of the given clouds. Keys can only carry tags
+/// their creator owns, so a missing tagOwners entry fails every node's `tailscale up`.
+pub fn cluster_tags(cluster_name: &str, clouds: &[String]) -> Vec<String> {
+    let mut tags: Vec<String> = ["k3s", "server", "agent"].iter().map(|tag| format!("tag:{}", tag)).collect();
+    for cloud in clouds {
+        for tag in [format!("tag:{}", cloud), node_tag(cluster_name, cloud)] {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+    }
+    tags
+}
+
+/// Policy stanza for the cluster: `owner` owns the node tags, reaches and ssh-es into the
+/// nodes, and the nodes reach each other. Subnet routes the cluster advertises are approved
+/// without a visit to the admin console.
+pub fn suggest(cluster_name: &str, clouds: &[String], owner: &str, routes: &[String]) -> Value {
+    let node_tags: Vec<String> = clouds.iter().map(|cloud| node_tag(cluster_name, cloud)).collect();
+    let node_ports: Vec<String> = node_tags.iter().map(|tag| format!("{}:*", tag)).collect();
+    let tag_owners: Map<String, Value> = cluster_tags(cluster_name, clouds)
+        .into_iter()
+        .map(|tag| (tag, json!([owner])))
+        .collect();
+    let mut stanza = json!({
+        "tagOwners": tag_owners,
+        "acls": [
+            {"action": "accept", "src": [owner], "dst": node_ports},
+            {"action": "accept", "src": node_tags, "dst": node_ports},
+        ],
+        "ssh": [
+            {"action": "accept", "src": [owner], "dst": node_tags, "users": [ssh::SSH_USER, "root"]},
+        ],
+    });
+    if !routes.is_empty() {
+        let approvers: Map<String, Value> = routes.iter().map(|route| (route.clone(), json!(node_tags))).collect();
+        stanza["autoApprovers"] = json!({ "routes": approvers });
+    }
+    stanza
+}
+
+/// Whether a HuJSON policy file has comments, which a merge through the JSON API would drop
+pub fn has_comments(policy: &str) -> bool {
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = policy.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == '/' && matches!(chars.peek(), Some('/' | '*')) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Add what `stanza` has and `policy` lacks, keeping everything else. Returns one line per
+/// change; none means the policy already covers the cluster.
+pub fn merge(policy: &mut Value, stanza: &Value) -> Vec<String> {
+    let mut changes = Vec::new();
+    if !policy.is_object() {
+        *policy = json!({});
+    }
+    let Value::Object(sections) = policy else {
+        return changes;
+    };
+
+    let tag_owners = sections.entry("tagOwners").or_insert_with(|| json!({}));
+    merge_lists(tag_owners, &stanza["tagOwners"], "tagOwners", &mut changes);
+
+    for section in ["acls", "ssh"] {
+        let Some(rules) = stanza[section].as_array() else {
+            continue;
+        };
+        let existing = sections.entry(section).or_insert_with(|| json!([]));
+        if !existing.is_array() {
+            *existing = json!([]);
+        }
+        let Value::Array(existing) = existing else {
+            continue;
+        };
+        for rule in rules {
+            if !existing.contains(rule) {
+                existing.push(rule.clone());
+                changes.push(format!("{}: add {}", section, rule));
+            }
+        }
+    }
+
+    if let Some(routes) = stanza.pointer("/autoApprovers/routes") {
+        let approvers = sections.entry("autoApprovers").or_insert_with(|| json!({}));
+        if !approvers.is_object() {
+            *approvers = json!({});
+        }
+        let existing = approvers
+            .as_object_mut()
+            .map(|approvers| approvers.entry("routes").or_insert_with(|| json!({})));
+        if let Some(existing) = existing {
+            merge_lists(existing, routes, "autoApprovers.routes", &mut changes);
+        }
+    }
+    changes
+}
+
+/// Merge maps of key to list, e.g. tag to owners, adding missing keys and list entries
+fn merge_lists(target: &mut Value, from: &Value, section: &str, changes: &mut Vec<String>) {
+    let Some(from) = from.as_object() else {
+        return;
+    };
+    if !target.is_object() {
+        *target = json!({});
+    }
+    let Value::Object(target) = target else {
+        return;
+    };
+    for (key, values) in from {
+        let entry = target.entry(key.clone()).or_insert_with(|| json!([]));
+        let Value::Array(existing) = entry else {
+            continue;
+        };
+        for value in values.as_array().into_iter().flatten() {
+            if !existing.contains(value) {
+                existing.push(value.clone());
+                changes.push(format!("{} {}: add {}", section, key, value));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn openstack() -> Vec<String> {
+        vec!["openstack".to_string()]
+    }
+
+    #[test]
+    fn test_cluster_tags_match_the_node_tags() {
+        // tailscale.tf tags servers k3s, openstack, server and the module's cluster name
+        let tags = cluster_tags("demo", &openstack());
+        for tag in ["tag:k3s", "tag:openstack", "tag:server", "tag:agent", "tag:demo-openstack"] {
+            assert!(tags.contains(&tag.to_string()), "missing {}", tag);
+        }
+        assert!(!tags.contains(&"tag:demo".to_string()));
+
+        let tags = cluster_tags("demo", &["openstack".to_string(), "hetzner".to_string()]);
+        assert_eq!(tags.len(), 7);
+        assert!(tags.contains(&"tag:demo-hetzner".to_string()));
+    }
+
+    #[test]
+    fn test_suggest_owns_the_node_tags() {
+        let stanza = suggest("demo", &openstack(), "autogroup:admin", &[]);
+
+        let owners = stanza["tagOwners"].as_object().unwrap();
+        assert_eq!(owners.len(), 5);
+        assert_eq!(owners["tag:demo-openstack"], json!(["autogroup:admin"]));
+        assert_eq!(stanza["acls"][0]["dst"], json!(["tag:demo-openstack:*"]));
+        assert_eq!(stanza["ssh"][0]["dst"], json!(["tag:demo-openstack"]));
+        assert_eq!(stanza["ssh"][0]["users"], json!(["ubuntu", "root"]));
+        assert!(stanza.get("autoApprovers").is_none());
+
+        let routed = suggest("demo", &openstack(), "autogroup:admin", &["10.0.0.0/24".to_string()]);
+        assert_eq!(routed["autoApprovers"]["routes"]["10.0.0.0/24"], json!(["tag:demo-openstack"]));
+    }
+
+    #[test]
+    fn test_merge_keeps_the_existing_policy() {
+        let mut policy = json!({
+            "tagOwners": {"tag:k3s": ["alice@example.com"], "tag:demo-openstack": ["autogroup:admin"]},
+            "acls": [{"action": "accept", "src": ["*"], "dst": ["*:*"]}],
+            "groups": {"group:ops": ["alice@example.com"]},
+        });
+        let stanza = suggest("demo", &openstack(), "autogroup:admin", &["10.0.0.0/24".to_string()]);
+
+        let changes = merge(&mut policy, &stanza);
+        assert_eq!(policy["tagOwners"]["tag:k3s"], json!(["alice@example.com", "autogroup:admin"]));
+        assert_eq!(policy["tagOwners"]["tag:demo-openstack"], json!(["autogroup:admin"]));
+        assert_eq!(policy["acls"].as_array().unwrap().len(), 3);
+        assert_eq!(policy["groups"]["group:ops"], json!(["alice@example.com"]));
+        assert_eq!(policy["autoApprovers"]["routes"]["10.0.0.0/24"], json!(["tag:demo-openstack"]));
+        // Four tag owners, two acls, one ssh rule and the route
+        assert_eq!(changes.len(), 8);
+
+        assert!(merge(&mut policy, &stanza).is_empty());
+    }
+
+    #[test]
+    fn test_has_comments() {
+        assert!(has_comments("{\n  // Admins own the tags\n  \"tagOwners\": {}\n}"));
+        assert!(has_comments("{ /* block */ }"));
+        assert!(!has_comments(r#"{"hosts": {"web": "https://example.com/path"}}"#));
+        assert!(!has_comments(r#"{"note": "a \" // quoted"}"#));
+    }
+}
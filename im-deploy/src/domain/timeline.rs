@@ -0,0 +1,245 @@
+use crate::constants::monitoring;
+use crate::domain::monitor::MonitorPhase;
+use crate::history::{HistoryEntry, MonitorProgress, PhaseTiming};
+use serde::{Deserialize, Serialize};
+
+/// What a timeline row stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MilestoneKind {
+    Apply,
+    Phase,
+    /// The instant a node turned Ready
+    NodeReady,
+    /// The monitored server was unreachable over SSH
+    Interruption,
+}
+
+/// One row of a deployment timeline, in seconds after the deploy started
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Milestone {
+    pub label: String,
+    pub kind: MilestoneKind,
+    pub start_secs: u64,
+    pub end_secs: u64,
+}
+
+impl Milestone {
+    fn span(label: String, kind: MilestoneKind, start_secs: u64, end_secs: u64) -> Self {
+        Self { label, kind, start_secs, end_secs }
+    }
+}
+
+/// Timeline of a deploy and the monitor run that followed it. Without a deploy shortly
+/// before the monitor, the timeline starts with the monitor.
+pub fn build(deploy: Option<&HistoryEntry>, progress: &MonitorProgress) -> Vec<Milestone> {
+    let deploy = deploy.filter(|entry| {
+        let applied = entry.started_at + entry.duration_secs;
+        entry.started_at <= progress.started_at && progress.started_at <= applied + monitoring::TIMELINE_APPLY_GAP_SECS
+    });
+    let origin = deploy.map_or(progress.started_at, |entry| entry.started_at);
+    let offset = progress.started_at - origin;
+
+    let mut milestones = Vec::new();
+    if let Some(entry) = deploy {
+        milestones.push(Milestone::span("terraform apply".to_string(), MilestoneKind::Apply, 0, entry.duration_secs));
+    }
+    // Built-in phases in their order, then add-on phases as they finished
+    let builtin: Vec<&str> = MonitorPhase::ALL.iter().map(|phase| phase.name()).collect();
+    let mut addons: Vec<(&String, &PhaseTiming)> =
+        progress.completed.iter().filter(|(name, _)| !builtin.contains(&name.as_str())).collect();
+    addons.sort_by_key(|(name, timing)| (timing.finished_after_secs, *name));
+    let phases = builtin
+        .iter()
+        .filter_map(|name| progress.completed.get_key_value(*name))
+        .chain(addons);
+    for (name, timing) in phases {
+        let end = offset + timing.finished_after_secs;
+        milestones.push(Milestone::span(
+            name.clone(),
+            MilestoneKind::Phase,
+            end.saturating_sub(timing.duration_secs),
+            end,
+        ));
+    }
+    let mut nodes: Vec<(&String, &u64)> = progress.node_ready_at.iter().collect();
+    nodes.sort_by_key(|(name, ready_at)| (**ready_at, *name));
+    for (name, ready_at) in nodes {
+        let at = ready_at.saturating_sub(origin);
+        milestones.push(Milestone::span(format!("{} Ready", name), MilestoneKind::NodeReady, at, at));
+    }
+    for interruption in &progress.interruptions {
+        let start = offset + interruption.started_after_secs;
+        milestones.push(Milestone::span(
+            format!("{} unreachable", interruption.server),
+            MilestoneKind::Interruption,
+            start,
+            start + interruption.duration_secs,
+        ));
+    }
+    milestones
+}
+
+/// Gantt chart of a timeline with bars scaled to `width` columns
+pub fn render(milestones: &[Milestone], width: usize) -> String {
+    let total = milestones.iter().map(|m| m.end_secs).max().unwrap_or(0).max(1);
+    let label_width = milestones.iter().map(|m| m.label.chars().count()).max().unwrap_or(0);
+    let column = |secs: u64| (secs as usize * width / total as usize).min(width.saturating_sub(1));
+
+    let mut out = String::new();
+    for milestone in milestones {
+        let start = column(milestone.start_secs);
+        let (bar, note) = match milestone.kind {
+            MilestoneKind::NodeReady => ("◆".to_string(), format!("at {}", format_secs(milestone.end_secs))),
+            kind => {
+                let fill = if kind == MilestoneKind::Interruption { "░" } else { "█" };
+                let length = (column(milestone.end_secs) - start).max(1);
+                (fill.repeat(length), format_secs(milestone.end_secs - milestone.start_secs))
+            }
+        };
+        let padding = width.saturating_sub(start + bar.chars().count());
+        out.push_str(&format!(
+            "{:label_width$} │{}{}{}│ {}\n",
+            milestone.label,
+            " ".repeat(start),
+            bar,
+            " ".repeat(padding),
+            note
+        ));
+    }
+    out.push_str(&format!(
+        "{:label_width$} 0{:>width$}\n",
+        "",
+        format_secs(total),
+        width = width + 1
+    ));
+    out
+}
+
+/// A milestone reached at a different time than in another deploy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shift {
+    pub label: String,
+    pub previous_secs: u64,
+    pub current_secs: u64,
+}
+
+impl Shift {
+    /// Seconds later than before; negative when the milestone was reached earlier
+    pub fn delta_secs(&self) -> i64 {
+        self.current_secs as i64 - self.previous_secs as i64
+    }
+}
+
+/// Milestones of both timelines whose end moved by at least `threshold_secs`, in the order
+/// of the current timeline
+pub fn compare(current: &[Milestone], previous: &[Milestone], threshold_secs: u64) -> Vec<Shift> {
+    current
+        .iter()
+        .filter_map(|milestone| {
+            let before = previous.iter().find(|m| m.kind == milestone.kind && m.label == milestone.label)?;
+            Some(Shift {
+                label: milestone.label.clone(),
+                previous_secs: before.end_secs,
+                current_secs: milestone.end_secs,
+            })
+        })
+        .filter(|shift| shift.delta_secs().unsigned_abs() >= threshold_secs)
+        .collect()
+}
+
+/// e.g. "4m 05s", or "42s" below a minute
+pub fn format_secs(secs: u64) -> String {
+    if secs < 60 {
+        format!("{}s", secs)
+    } else {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{MonitorInterruption, Operation};
+    use std::collections::BTreeMap;
+
+    fn deploy(started_at: u64, duration_secs: u64) -> HistoryEntry {
+        HistoryEntry {
+            operation: Operation::Deploy,
+            cluster_name: "demo".to_string(),
+            started_at,
+            duration_secs,
+            success: true,
+            terraform_version: None,
+            slowest_resources: Vec::new(),
+            expires_at: None,
+            timeline: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_timeline_offsets_monitor_by_apply() {
+        let progress = MonitorProgress {
+            started_at: 1300,
+            completed: BTreeMap::from([
+                ("cloud-init".to_string(), PhaseTiming { finished_after_secs: 120, duration_secs: 120 }),
+                ("nodes".to_string(), PhaseTiming { finished_after_secs: 200, duration_secs: 80 }),
+                ("longhorn".to_string(), PhaseTiming { finished_after_secs: 190, duration_secs: 30 }),
+            ]),
+            interruptions: vec![MonitorInterruption {
+                server: "k3s-server-0".to_string(),
+                started_after_secs: 30,
+                duration_secs: 40,
+                switched_to: None,
+            }],
+            node_ready_at: BTreeMap::from([
+                ("k3s-server-1".to_string(), 1480),
+                ("k3s-server-0".to_string(), 1450),
+            ]),
+        };
+
+        let timeline = build(Some(&deploy(1000, 290)), &progress);
+        let rows: Vec<(&str, u64, u64)> = timeline.iter().map(|m| (m.label.as_str(), m.start_secs, m.end_secs)).collect();
+        assert_eq!(
+            rows,
+            [
+                ("terraform apply", 0, 290),
+                ("cloud-init", 300, 420),
+                ("nodes", 420, 500),
+                ("longhorn", 460, 490),
+                ("k3s-server-0 Ready", 450, 450),
+                ("k3s-server-1 Ready", 480, 480),
+                ("k3s-server-0 unreachable", 330, 370),
+            ]
+        );
+
+        // A deploy after the monitor started, or long before it, is not the one it watched
+        assert_eq!(build(Some(&deploy(2000, 290)), &progress)[0].label, "cloud-init");
+        assert_eq!(build(Some(&deploy(100, 290)), &progress)[0].start_secs, 0);
+
+        let chart = render(&timeline, 50);
+        assert_eq!(chart.lines().count(), timeline.len() + 1);
+        assert!(chart.lines().next().unwrap().starts_with("terraform apply          │█████████████████████████████"));
+        assert!(chart.contains("◆"));
+        assert!(chart.lines().last().unwrap().ends_with("8m 20s"));
+    }
+
+    #[test]
+    fn test_compare_reports_moved_milestones() {
+        let row = |label: &str, end_secs| Milestone::span(label.to_string(), MilestoneKind::Phase, 0, end_secs);
+        let previous = [row("cloud-init", 400), row("nodes", 500)];
+        let current = [row("cloud-init", 420), row("nodes", 700), row("gpu", 900)];
+
+        let shifts = compare(&current, &previous, 60);
+        assert_eq!(
+            shifts,
+            [Shift {
+                label: "nodes".to_string(),
+                previous_secs: 500,
+                current_secs: 700
+            }]
+        );
+        assert_eq!(shifts[0].delta_secs(), 200);
+        assert_eq!(compare(&previous, &current, 60)[0].delta_secs(), -200);
+    }
+}
@@ -0,0 +1,51 @@
+use crate::constants::kubernetes;
+use crate::domain::connection::shell_quote;
+
+/// Prints 64 random hex characters, run on the control node
+pub const GENERATE_COMMAND: &str = "head -c 32 /dev/urandom | od -An -tx1 | tr -d ' \\n'";
+
+pub fn is_valid_token(token: &str) -> bool {
+    token.len() == 64 && token.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Switch the cluster to `new` on a server; k3s then only accepts the new token from joining nodes
+pub fn rotate_command(old: &str, new: &str) -> String {
+    format!("sudo k3s token rotate --token {} --new-token {}", shell_quote(old), shell_quote(new))
+}
+
+/// Store `token` in the node's k3s environment file and restart k3s with it. Prints the node's
+/// time once the service is up again.
+pub fn restart_command(is_server: bool, token: &str) -> String {
+    let (env_file, service) = if is_server {
+        (kubernetes::SERVER_ENV_FILE, "k3s")
+    } else {
+        (kubernetes::AGENT_ENV_FILE, "k3s-agent")
+    };
+    format!(
+        "sudo sed -i '/^K3S_TOKEN=/d' {env} && echo {line} | sudo tee -a {env} >/dev/null && \
+         sudo systemctl restart {service} && date +%s",
+        env = env_file,
+        line = shell_quote(&format!("K3S_TOKEN='{}'", token)),
+        service = service
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_rotation_commands() {
+        assert!(is_valid_token(&"ab12".repeat(16)));
+        assert!(!is_valid_token("ab12"));
+        assert!(!is_valid_token(&"zz12".repeat(16)));
+
+        assert_eq!(rotate_command("old", "new"), "sudo k3s token rotate --token 'old' --new-token 'new'");
+
+        let agent = restart_command(false, "new");
+        assert!(agent.starts_with("sudo sed -i '/^K3S_TOKEN=/d' /etc/systemd/system/k3s-agent.service.env"));
+        assert!(agent.contains(r"echo 'K3S_TOKEN='\''new'\''' | sudo tee -a"));
+        assert!(agent.ends_with("sudo systemctl restart k3s-agent && date +%s"));
+        assert!(restart_command(true, "new").contains("systemctl restart k3s &&"));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::constants::exit_code;
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -15,12 +16,31 @@ pub enum ImDeployError {
     #[error("SSH error: {0}")]
     Ssh(#[from] SshError),
 
+    #[error("DNS error: {0}")]
+    Dns(#[from] DnsError),
+
     #[error("Configuration error: {0}")]
     Config(#[from] ConfigError),
 
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    #[error("{action} requires an interactive terminal; {hint}")]
+    NotInteractive { action: String, hint: String },
+
+    #[error("{hook} hook failed{}", code.map(|c| format!(" (exit code: {})", c)).unwrap_or_default())]
+    HookFailed { hook: String, code: Option<i32> },
+
+    /// The user declined a confirmation
+    #[error("{0} cancelled")]
+    Cancelled(String),
+
+    #[error("Cleanup incomplete: {0}")]
+    CleanupIncomplete(String),
+
+    #[error("{0}")]
+    MonitorTimeout(String),
+
     #[error("{0}")]
     Other(#[from] anyhow::Error),
 }
@@ -44,6 +64,34 @@ pub enum TerraformError {
 
     #[error("Failed to extract {resource} from terraform outputs")]
     ResourceNotFound { resource: String },
+
+    #[error("Terraform outputs list no k3s server to run kubectl on; set the kubeconfig_source output to a host holding the cluster kubeconfig")]
+    NoControlNode,
+
+    #[error("Failed to determine terraform version: {0}")]
+    VersionDetectionFailed(String),
+
+    #[error("Terraform command timed out after {minutes} minutes: {command}")]
+    Timeout { command: String, minutes: u64 },
+
+    #[error("Terraform version {found} does not satisfy required version \"{required}\"")]
+    IncompatibleVersion { found: String, required: String },
+
+    #[error("Terraform module outputs v{expected} expected, found {found}; apply the terraform code matching this im-deploy release")]
+    OutputsVersionMismatch { expected: u64, found: String },
+
+    #[error("Terraform output {output} is invalid: {reason}")]
+    InvalidOutput { output: String, reason: String },
+
+    #[error("Terraform output {output} is missing, {needed_for} needs it; {hint}")]
+    MissingOutput {
+        output: String,
+        needed_for: String,
+        hint: String,
+    },
+
+    #[error("Terraform reports no outputs, {needed_for} needs a deployed cluster; run 'im-deploy deploy' first")]
+    NotDeployed { needed_for: String },
 }
 
 #[derive(Error, Debug)]
@@ -87,6 +135,27 @@ pub enum TailscaleError {
 
     #[error("Failed to parse Tailscale response: {0}")]
     ParseError(String),
+
+    #[error("Tailscale API key rejected for tailnet {tailnet} (HTTP {status}); check the key and tailnet name")]
+    Unauthorized { status: u16, tailnet: String },
+
+    #[error("Tailscale API key cannot delete devices: needs the devices:core scope, has {scopes}")]
+    MissingDeleteScope { scopes: String },
+
+    #[error("Tailscale API key expired at {0}")]
+    KeyExpired(String),
+
+    #[error("{} Tailscale device(s) still present after cleanup: {}; remove them in the admin console", .0.len(), .0.join(", "))]
+    DevicesLeft(Vec<String>),
+}
+
+#[derive(Error, Debug)]
+pub enum DnsError {
+    #[error("DNS API request failed: {0}")]
+    ApiError(String),
+
+    #[error("nsupdate failed: {0}")]
+    UpdateFailed(String),
 }
 
 #[derive(Error, Debug)]
@@ -97,11 +166,14 @@ pub enum SshError {
     #[error("SSH command execution failed: {command}")]
     CommandFailed { command: String },
 
-    #[error("Neither Tailscale nor bastion host available for connection")]
+    #[error("Neither Tailscale, bastion host nor public IP available for connection")]
     NoConnectionMethod,
 
     #[error("Tailscale hostname not found for server {0}")]
     TailscaleHostnameNotFound(String),
+
+    #[error("{host} not reachable over SSH after {attempts} attempts: {reason}")]
+    Unreachable { host: String, attempts: u32, reason: String },
 }
 
 #[derive(Error, Debug)]
@@ -119,6 +191,24 @@ pub enum ConfigError {
     InvalidValue { field: String, reason: String },
 }
 
+impl ImDeployError {
+    /// Exit code reporting this error to scripts
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ImDeployError::Config(_)
+            | ImDeployError::NotInteractive { .. }
+            | ImDeployError::Terraform(TerraformError::DirectoryNotFound(_) | TerraformError::BinaryNotFound) => {
+                exit_code::CONFIG
+            }
+            ImDeployError::Terraform(_) => exit_code::TERRAFORM,
+            ImDeployError::CleanupIncomplete(_) => exit_code::CLEANUP_INCOMPLETE,
+            ImDeployError::MonitorTimeout(_) => exit_code::MONITOR_TIMEOUT,
+            ImDeployError::Cancelled(_) => exit_code::CANCELLED,
+            _ => exit_code::FAILURE,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, ImDeployError>;
 
 #[cfg(test)]
@@ -152,6 +242,19 @@ mod tests {
             resource: "load balancer IP".to_string(),
         };
         assert!(err.to_string().contains("load balancer IP"));
+
+        let err = TerraformError::Timeout {
+            command: "tofu destroy --auto-approve".to_string(),
+            minutes: 30,
+        };
+        assert!(err.to_string().contains("timed out after 30 minutes"));
+
+        let err = TerraformError::IncompatibleVersion {
+            found: "1.4.0".to_string(),
+            required: ">= 1.5".to_string(),
+        };
+        assert!(err.to_string().contains("1.4.0"));
+        assert!(err.to_string().contains(">= 1.5"));
     }
 
     #[test]
@@ -165,6 +268,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_not_interactive_error_mentions_flag() {
+        let err = ImDeployError::NotInteractive {
+            action: "Server selection".to_string(),
+            hint: "pass --server <name>".to_string(),
+        };
+        assert!(err.to_string().contains("Server selection requires an interactive terminal"));
+        assert!(err.to_string().contains("--server <name>"));
+    }
+
+    #[test]
+    fn test_exit_codes_distinguish_failure_kinds() {
+        let codes = [
+            ImDeployError::from(ConfigError::TerraformDirNotFound).exit_code(),
+            ImDeployError::from(TerraformError::CommandFailed {
+                command: "terraform apply".to_string(),
+                code: Some(1),
+            })
+            .exit_code(),
+            ImDeployError::CleanupIncomplete("1 resource(s) left".to_string()).exit_code(),
+            ImDeployError::MonitorTimeout("Nodes not Ready".to_string()).exit_code(),
+            ImDeployError::Cancelled("Destroy".to_string()).exit_code(),
+            ImDeployError::from(anyhow::anyhow!("generic error")).exit_code(),
+        ];
+        assert_eq!(codes, [2, 3, 4, 5, 6, 1]);
+        assert_eq!(ImDeployError::Cancelled("Destroy".to_string()).to_string(), "Destroy cancelled");
+    }
+
     #[test]
     fn test_error_conversion_from_anyhow() {
         let anyhow_err = anyhow::anyhow!("generic error");
@@ -185,7 +316,7 @@ mod tests {
         let err = SshError::NoConnectionMethod;
         assert!(err
             .to_string()
-            .contains("Neither Tailscale nor bastion host"));
+            .contains("Neither Tailscale, bastion host nor public IP"));
 
         let err = SshError::TailscaleHostnameNotFound("k3s-server-0".to_string());
         assert!(err.to_string().contains("Tailscale hostname not found"));
@@ -0,0 +1,621 @@
+use crate::constants::files;
+use crate::domain::cluster::ClusterInfo;
+use crate::domain::timeline::Milestone;
+use crate::errors::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Deploy,
+    Destroy,
+}
+
+/// A single deploy or destroy run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub operation: Operation,
+    pub cluster_name: String,
+    /// Unix timestamp (seconds) when the run started
+    pub started_at: u64,
+    pub duration_secs: u64,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terraform_version: Option<String>,
+    /// Resources that took longest during apply, slowest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub slowest_resources: Vec<ResourceTiming>,
+    /// Unix timestamp (seconds) after which `reap` destroys the cluster, from `deploy --ttl`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+    /// Apply, monitor phases and node Ready times of a deploy, filled in by the monitor
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub timeline: Vec<Milestone>,
+}
+
+/// Time terraform spent creating or changing one resource
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceTiming {
+    pub address: String,
+    pub secs: u64,
+}
+
+/// Kubernetes node statuses as last seen by the monitor
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NodeStatusSnapshot {
+    /// Unix timestamp (seconds) of the observation
+    pub updated_at: u64,
+    /// Node name -> status, e.g. "Ready" or "NotReady"
+    pub nodes: BTreeMap<String, String>,
+}
+
+/// Phases a monitor run has completed, so an interrupted run can be resumed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MonitorProgress {
+    /// Unix timestamp (seconds) when monitoring started
+    pub started_at: u64,
+    /// Phase name -> when it completed
+    pub completed: BTreeMap<String, PhaseTiming>,
+    /// Times the polled server dropped off SSH, in order
+    #[serde(default)]
+    pub interruptions: Vec<MonitorInterruption>,
+    /// Node name -> unix timestamp (seconds) it turned Ready
+    #[serde(default)]
+    pub node_ready_at: BTreeMap<String, u64>,
+}
+
+/// A stretch of a monitor run in which the server it polls could not be reached over SSH
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonitorInterruption {
+    pub server: String,
+    /// Seconds after the monitor started
+    pub started_after_secs: u64,
+    pub duration_secs: u64,
+    /// Server the monitor continued on when it gave up waiting for `server`
+    pub switched_to: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseTiming {
+    /// Seconds after the monitor started
+    pub finished_after_secs: u64,
+    /// Seconds the phase itself took
+    pub duration_secs: u64,
+}
+
+/// Cluster metadata as of the last time terraform outputs could be read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedCluster {
+    /// Unix timestamp (seconds) of the terraform output it was taken from
+    pub updated_at: u64,
+    pub cluster: ClusterInfo,
+}
+
+/// Deployment history kept in `<terraform_dir>/.im-deploy/history.json`,
+/// plus the last known node statuses, monitor progress and cluster metadata next to it
+pub struct HistoryStore {
+    path: PathBuf,
+    node_status_path: PathBuf,
+    monitor_progress_path: PathBuf,
+    cluster_cache_path: PathBuf,
+}
+
+impl HistoryStore {
+    pub fn new(terraform_dir: &Path) -> Self {
+        let data_dir = terraform_dir.join(files::DATA_DIR);
+        Self {
+            path: data_dir.join(files::HISTORY_FILE),
+            node_status_path: data_dir.join(files::NODE_STATUS_FILE),
+            monitor_progress_path: data_dir.join(files::MONITOR_PROGRESS_FILE),
+            cluster_cache_path: data_dir.join(files::CLUSTER_CACHE_FILE),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn load(&self) -> Result<Vec<HistoryEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.path)?;
+        let entries = serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", self.path.display(), e))?;
+        Ok(entries)
+    }
+
+    pub fn append(&self, entry: HistoryEntry) -> Result<()> {
+        let mut entries = self.load()?;
+        entries.push(entry);
+        self.save(&entries)
+    }
+
+    /// Attach a timeline to the most recent successful deploy. Returns the timeline of the
+    /// successful deploy before it, to compare against, or None without a deploy to attach to.
+    pub fn record_timeline(&self, timeline: Vec<Milestone>) -> Result<Option<Vec<Milestone>>> {
+        let mut entries = self.load()?;
+        let mut deploys = entries
+            .iter_mut()
+            .rev()
+            .filter(|e| e.operation == Operation::Deploy && e.success);
+        let Some(latest) = deploys.next() else {
+            return Ok(None);
+        };
+        latest.timeline = timeline;
+        let previous = deploys.next().map(|e| e.timeline.clone()).unwrap_or_default();
+        self.save(&entries)?;
+        Ok(Some(previous))
+    }
+
+    fn save(&self, entries: &[HistoryEntry]) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let content = serde_json::to_string_pretty(entries)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize history: {}", e))?;
+        fs::write(&self.path, content)?;
+        Ok(())
+    }
+
+    pub fn save_node_statuses(&self, nodes: BTreeMap<String, String>) -> Result<()> {
+        if let Some(parent) = self.node_status_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let snapshot = NodeStatusSnapshot {
+            updated_at: unix_now(),
+            nodes,
+        };
+        let content = serde_json::to_string_pretty(&snapshot)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize node statuses: {}", e))?;
+        fs::write(&self.node_status_path, content)?;
+        Ok(())
+    }
+
+    pub fn load_node_statuses(&self) -> Result<Option<NodeStatusSnapshot>> {
+        if !self.node_status_path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&self.node_status_path)?;
+        let snapshot = serde_json::from_str(&content).map_err(|e| {
+            anyhow::anyhow!("Failed to parse {}: {}", self.node_status_path.display(), e)
+        })?;
+        Ok(Some(snapshot))
+    }
+
+    /// Remember `cluster` for offline use; outputs without a deployed cluster keep the last one
+    pub fn save_cluster(&self, cluster: &ClusterInfo) -> Result<()> {
+        if !cluster.deployed {
+            return Ok(());
+        }
+        if let Some(parent) = self.cluster_cache_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let cached = CachedCluster {
+            updated_at: unix_now(),
+            cluster: cluster.clone(),
+        };
+        let content = serde_json::to_string_pretty(&cached)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize cluster metadata: {}", e))?;
+        fs::write(&self.cluster_cache_path, content)?;
+        Ok(())
+    }
+
+    /// Forget the cached cluster once it is destroyed
+    pub fn clear_cluster(&self) -> Result<()> {
+        if self.cluster_cache_path.exists() {
+            fs::remove_file(&self.cluster_cache_path)?;
+        }
+        Ok(())
+    }
+
+    pub fn load_cluster(&self) -> Result<Option<CachedCluster>> {
+        if !self.cluster_cache_path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&self.cluster_cache_path)?;
+        let cached = serde_json::from_str(&content).map_err(|e| {
+            anyhow::anyhow!("Failed to parse {}: {}", self.cluster_cache_path.display(), e)
+        })?;
+        Ok(Some(cached))
+    }
+
+    pub fn save_monitor_progress(&self, progress: &MonitorProgress) -> Result<()> {
+        if let Some(parent) = self.monitor_progress_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let content = serde_json::to_string_pretty(progress)
+            .map_err(|e| anyhow::anyhow!("Failed to serialize monitor progress: {}", e))?;
+        fs::write(&self.monitor_progress_path, content)?;
+        Ok(())
+    }
+
+    /// Progress of the last monitor run, unless a deploy has replaced the cluster since
+    pub fn load_monitor_progress(&self) -> Result<Option<MonitorProgress>> {
+        if !self.monitor_progress_path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&self.monitor_progress_path)?;
+        let progress: MonitorProgress = serde_json::from_str(&content).map_err(|e| {
+            anyhow::anyhow!("Failed to parse {}: {}", self.monitor_progress_path.display(), e)
+        })?;
+
+        if self.deployed_since(progress.started_at + 1)? {
+            return Ok(None);
+        }
+        Ok(Some(progress))
+    }
+
+    /// Whether a successful deploy started at or after `timestamp`, e.g. to detect
+    /// that a locally cached kubeconfig predates the current cluster
+    pub fn deployed_since(&self, timestamp: u64) -> Result<bool> {
+        Ok(self
+            .last_successful(Operation::Deploy)?
+            .is_some_and(|e| e.started_at >= timestamp))
+    }
+
+    /// When the deployed cluster expires: the TTL of the last successful run, if that was a
+    /// deploy. A later deploy without --ttl keeps the cluster for good.
+    pub fn expiry(&self) -> Result<Option<u64>> {
+        Ok(self
+            .load()?
+            .into_iter()
+            .rev()
+            .find(|e| e.success)
+            .filter(|e| e.operation == Operation::Deploy)
+            .and_then(|e| e.expires_at))
+    }
+
+    /// Most recent successful run of the given operation
+    pub fn last_successful(&self, operation: Operation) -> Result<Option<HistoryEntry>> {
+        Ok(self
+            .load()?
+            .into_iter()
+            .rev()
+            .find(|e| e.operation == operation && e.success))
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Human readable age of a timestamp relative to `now`, e.g. "3h 12m ago"
+pub fn format_age(timestamp: u64, now: u64) -> String {
+    let secs = now.saturating_sub(timestamp);
+    match secs {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86400 => format!("{}h {}m ago", secs / 3600, (secs % 3600) / 60),
+        _ => format!("{}d {}h ago", secs / 86400, (secs % 86400) / 3600),
+    }
+}
+
+/// Human readable time until a timestamp, e.g. "in 3h 12m"
+pub fn format_until(timestamp: u64, now: u64) -> String {
+    let secs = timestamp.saturating_sub(now);
+    match secs {
+        0..60 => "now".to_string(),
+        60..3600 => format!("in {}m", secs / 60),
+        3600..86400 => format!("in {}h {}m", secs / 3600, (secs % 3600) / 60),
+        _ => format!("in {}d {}h", secs / 86400, (secs % 86400) / 3600),
+    }
+}
+
+/// Parse a lifetime such as `8h`, `30m`, `2d` or `1h30m`
+pub fn parse_ttl(text: &str) -> std::result::Result<Duration, String> {
+    let invalid = || format!("invalid duration '{}', expected e.g. 8h, 30m, 2d or 1h30m", text);
+    let mut secs = 0u64;
+    let mut number = String::new();
+    for c in text.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            'w' => 7 * 86400,
+            _ => return Err(invalid()),
+        };
+        let value: u64 = number.parse().map_err(|_| invalid())?;
+        secs = secs.saturating_add(value.saturating_mul(unit));
+        number.clear();
+    }
+    if !number.is_empty() || secs == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// Unix timestamp of an RFC 3339 time such as `2025-01-02T03:04:05Z` or with a UTC offset.
+/// Fractional seconds are ignored.
+pub fn parse_rfc3339(time: &str) -> Option<u64> {
+    let (date, rest) = time.split_once(['T', 't', ' '])?;
+    let mut date_parts = date.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (date_parts.next()??, date_parts.next()??, date_parts.next()??);
+
+    let offset_start = rest.find(['Z', 'z', '+', '-']).unwrap_or(rest.len());
+    let (clock, offset) = rest.split_at(offset_start);
+    let mut clock_parts = clock.split('.').next()?.splitn(3, ':').map(|p| p.parse::<i64>().ok());
+    let (hour, minute, second) = (clock_parts.next()??, clock_parts.next()??, clock_parts.next()??);
+    let offset_secs = match offset.split_at_checked(1) {
+        Some((sign @ ("+" | "-"), value)) => {
+            let (h, m) = value.split_once(':').unwrap_or((value.get(..2)?, value.get(2..)?));
+            let secs = h.parse::<i64>().ok()? * 3600 + m.parse::<i64>().ok()? * 60;
+            if sign == "+" { secs } else { -secs }
+        }
+        _ => 0,
+    };
+
+    // Days since the epoch for a proleptic Gregorian date (Howard Hinnant's algorithm)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second - offset_secs;
+    u64::try_from(secs).ok()
+}
+
+/// RFC 3339 UTC time of a Unix timestamp, e.g. `2025-01-02T03:04:05Z`
+pub fn format_rfc3339(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let secs = timestamp % 86400;
+
+    // Inverse of the date calculation in parse_rfc3339
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::timeline::MilestoneKind;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_rfc3339() {
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_rfc3339("2024-02-29T12:30:15Z"), Some(1709209815));
+        assert_eq!(parse_rfc3339("2025-10-17T08:00:00.123456+02:00"), Some(1760680800));
+        assert_eq!(parse_rfc3339("not a time"), None);
+    }
+
+    #[test]
+    fn test_format_rfc3339() {
+        assert_eq!(format_rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_rfc3339(1709209815), "2024-02-29T12:30:15Z");
+        for timestamp in [951782400, 1760680800, 4102444799] {
+            assert_eq!(parse_rfc3339(&format_rfc3339(timestamp)), Some(timestamp));
+        }
+    }
+
+    fn entry(operation: Operation, started_at: u64, success: bool) -> HistoryEntry {
+        HistoryEntry {
+            operation,
+            cluster_name: "test".to_string(),
+            started_at,
+            duration_secs: 60,
+            success,
+            terraform_version: Some("1.11.4".to_string()),
+            slowest_resources: Vec::new(),
+            expires_at: None,
+            timeline: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_history_missing_file_is_empty() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = HistoryStore::new(temp_dir.path());
+        assert!(store.load().unwrap().is_empty());
+        assert!(store.last_successful(Operation::Deploy).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_history_append_and_last_successful() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = HistoryStore::new(temp_dir.path());
+
+        store.append(entry(Operation::Deploy, 100, true)).unwrap();
+        store.append(entry(Operation::Destroy, 200, true)).unwrap();
+        store.append(entry(Operation::Deploy, 300, false)).unwrap();
+
+        assert_eq!(store.load().unwrap().len(), 3);
+        assert!(store.path().starts_with(temp_dir.path().join(".im-deploy")));
+
+        let last = store.last_successful(Operation::Deploy).unwrap().unwrap();
+        assert_eq!(last.started_at, 100);
+        assert_eq!(last.terraform_version.as_deref(), Some("1.11.4"));
+    }
+
+    #[test]
+    fn test_history_slowest_resources_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = HistoryStore::new(temp_dir.path());
+
+        // Entries written before resource timings were recorded still load
+        fs::create_dir_all(store.path().parent().unwrap()).unwrap();
+        fs::write(
+            store.path(),
+            r#"[{"operation":"deploy","cluster_name":"test","started_at":1,"duration_secs":60,"success":true}]"#,
+        )
+        .unwrap();
+
+        let mut timed = entry(Operation::Deploy, 100, true);
+        timed.slowest_resources = vec![ResourceTiming {
+            address: "module.k3s.openstack_lb_loadbalancer_v2.lb".to_string(),
+            secs: 312,
+        }];
+        store.append(timed).unwrap();
+
+        let entries = store.load().unwrap();
+        assert!(entries[0].slowest_resources.is_empty());
+        assert_eq!(entries[1].slowest_resources[0].secs, 312);
+    }
+
+    #[test]
+    fn test_parse_ttl() {
+        assert_eq!(parse_ttl("8h"), Ok(Duration::from_secs(8 * 3600)));
+        assert_eq!(parse_ttl("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_ttl("2d"), Ok(Duration::from_secs(2 * 86400)));
+        assert!(parse_ttl("8").is_err());
+        assert!(parse_ttl("0h").is_err());
+        assert!(parse_ttl("h").is_err());
+        assert!(parse_ttl("8 hours").is_err());
+        assert_eq!(format_until(100 + 3 * 3600 + 720, 100), "in 3h 12m");
+    }
+
+    #[test]
+    fn test_expiry_follows_last_successful_run() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = HistoryStore::new(temp_dir.path());
+        assert_eq!(store.expiry().unwrap(), None);
+
+        let mut ephemeral = entry(Operation::Deploy, 100, true);
+        ephemeral.expires_at = Some(1000);
+        store.append(ephemeral).unwrap();
+        store.append(entry(Operation::Deploy, 200, false)).unwrap();
+        assert_eq!(store.expiry().unwrap(), Some(1000));
+
+        store.append(entry(Operation::Destroy, 300, true)).unwrap();
+        assert_eq!(store.expiry().unwrap(), None);
+    }
+
+    #[test]
+    fn test_deployed_since() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = HistoryStore::new(temp_dir.path());
+        assert!(!store.deployed_since(0).unwrap());
+
+        store.append(entry(Operation::Deploy, 500, true)).unwrap();
+        store.append(entry(Operation::Deploy, 900, false)).unwrap();
+
+        assert!(store.deployed_since(400).unwrap());
+        assert!(store.deployed_since(500).unwrap());
+        // Failed deploys don't replace the cluster's credentials
+        assert!(!store.deployed_since(600).unwrap());
+    }
+
+    #[test]
+    fn test_node_status_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = HistoryStore::new(temp_dir.path());
+        assert!(store.load_node_statuses().unwrap().is_none());
+
+        let nodes = BTreeMap::from([("prod-server-0".to_string(), "Ready".to_string())]);
+        store.save_node_statuses(nodes).unwrap();
+
+        let snapshot = store.load_node_statuses().unwrap().unwrap();
+        assert_eq!(snapshot.nodes["prod-server-0"], "Ready");
+        assert!(snapshot.updated_at > 0);
+    }
+
+    #[test]
+    fn test_monitor_progress_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = HistoryStore::new(temp_dir.path());
+        assert!(store.load_monitor_progress().unwrap().is_none());
+
+        store.append(entry(Operation::Deploy, 1000, true)).unwrap();
+        let progress = MonitorProgress {
+            started_at: 1200,
+            completed: BTreeMap::from([(
+                "nodes".to_string(),
+                PhaseTiming {
+                    finished_after_secs: 240,
+                    duration_secs: 240,
+                },
+            )]),
+            interruptions: vec![MonitorInterruption {
+                server: "k3s-server-0".to_string(),
+                started_after_secs: 60,
+                duration_secs: 45,
+                switched_to: None,
+            }],
+            node_ready_at: BTreeMap::from([("k3s-server-0".to_string(), 1400)]),
+        };
+        store.save_monitor_progress(&progress).unwrap();
+
+        let loaded = store.load_monitor_progress().unwrap().unwrap();
+        assert_eq!(loaded.completed["nodes"].finished_after_secs, 240);
+        assert_eq!(loaded.interruptions, progress.interruptions);
+        assert_eq!(loaded.node_ready_at, progress.node_ready_at);
+
+        // A newer deploy makes the saved progress stale
+        store.append(entry(Operation::Deploy, 1500, true)).unwrap();
+        assert!(store.load_monitor_progress().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_record_timeline_on_last_successful_deploy() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = HistoryStore::new(temp_dir.path());
+        let timeline = |end_secs| {
+            vec![Milestone {
+                label: "nodes".to_string(),
+                kind: MilestoneKind::Phase,
+                start_secs: 0,
+                end_secs,
+            }]
+        };
+        assert_eq!(store.record_timeline(timeline(100)).unwrap(), None);
+
+        store.append(entry(Operation::Deploy, 100, true)).unwrap();
+        assert_eq!(store.record_timeline(timeline(300)).unwrap(), Some(Vec::new()));
+        store.append(entry(Operation::Deploy, 200, true)).unwrap();
+        store.append(entry(Operation::Deploy, 300, false)).unwrap();
+
+        assert_eq!(store.record_timeline(timeline(400)).unwrap(), Some(timeline(300)));
+        let entries = store.load().unwrap();
+        assert_eq!(entries[1].timeline, timeline(400));
+        assert!(entries[2].timeline.is_empty());
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(1000, 1030), "just now");
+        assert_eq!(format_age(1000, 1000 + 5 * 60), "5m ago");
+        assert_eq!(format_age(0, 2 * 3600 + 15 * 60), "2h 15m ago");
+        assert_eq!(format_age(0, 3 * 86400 + 4 * 3600), "3d 4h ago");
+        // Clock skew should not underflow
+        assert_eq!(format_age(2000, 1000), "just now");
+    }
+}
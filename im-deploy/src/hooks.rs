@@ -0,0 +1,154 @@
+use crate::constants::env_vars;
+use crate::errors::{ImDeployError, Result};
+use serde::Deserialize;
+use crate::terraform::spawn_output_reader;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tracing::debug;
+
+/// Shell commands run around deploy and destroy, from the `[hooks]` table of im-deploy.toml
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hooks {
+    pub pre_deploy: Option<String>,
+    pub post_deploy: Option<String>,
+    pub pre_destroy: Option<String>,
+    pub post_destroy: Option<String>,
+    /// Before `reap` destroys an expired cluster, e.g. to notify its owners
+    pub pre_reap: Option<String>,
+    /// Directory of the config file, so hooks can use relative script paths
+    #[serde(skip)]
+    pub working_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookPoint {
+    PreDeploy,
+    PostDeploy,
+    PreDestroy,
+    PostDestroy,
+    PreReap,
+}
+
+impl HookPoint {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HookPoint::PreDeploy => "pre_deploy",
+            HookPoint::PostDeploy => "post_deploy",
+            HookPoint::PreDestroy => "pre_destroy",
+            HookPoint::PostDestroy => "post_destroy",
+            HookPoint::PreReap => "pre_reap",
+        }
+    }
+}
+
+/// Values passed to hooks as environment variables
+#[derive(Debug, Clone, Default)]
+pub struct HookContext {
+    pub cluster_name: String,
+    pub terraform_dir: PathBuf,
+    /// API load balancer floating IP, once terraform has created it
+    pub lb_ip: Option<String>,
+    /// Kubeconfig fetched by `im-deploy kubectl`, when present
+    pub kubeconfig: Option<PathBuf>,
+    /// Unix timestamp the cluster expired at, for `pre_reap`
+    pub expires_at: Option<u64>,
+}
+
+impl HookContext {
+    pub fn env(&self, point: HookPoint) -> Vec<(&'static str, String)> {
+        let mut env = vec![
+            (env_vars::HOOK, point.name().to_string()),
+            (env_vars::CLUSTER_NAME, self.cluster_name.clone()),
+            (env_vars::TERRAFORM_DIR, self.terraform_dir.display().to_string()),
+        ];
+        if let Some(lb_ip) = &self.lb_ip {
+            env.push((env_vars::LB_IP, lb_ip.clone()));
+        }
+        if let Some(kubeconfig) = &self.kubeconfig {
+            env.push((env_vars::KUBECONFIG, kubeconfig.display().to_string()));
+        }
+        if let Some(expires_at) = self.expires_at {
+            env.push((env_vars::EXPIRES_AT, expires_at.to_string()));
+        }
+        env
+    }
+}
+
+impl Hooks {
+    pub fn command(&self, point: HookPoint) -> Option<&str> {
+        match point {
+            HookPoint::PreDeploy => self.pre_deploy.as_deref(),
+            HookPoint::PostDeploy => self.post_deploy.as_deref(),
+            HookPoint::PreDestroy => self.pre_destroy.as_deref(),
+            HookPoint::PostDestroy => self.post_destroy.as_deref(),
+            HookPoint::PreReap => self.pre_reap.as_deref(),
+        }
+        .filter(|command| !command.trim().is_empty())
+    }
+
+    /// Run the hook for `point` through `sh -c`, if one is configured. Its output is printed
+    /// line by line with secrets redacted.
+    pub fn run(&self, point: HookPoint, context: &HookContext) -> Result<()> {
+        let Some(command) = self.command(point) else {
+            return Ok(());
+        };
+
+        debug!("Running {} hook: {}", point.name(), command);
+        let mut process = Command::new("sh");
+        process.arg("-c").arg(command).envs(context.env(point));
+        if let Some(dir) = self.working_dir.as_deref().filter(|dir| Path::new(dir).is_dir()) {
+            process.current_dir(dir);
+        }
+
+        let mut child = process.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+        for line in spawn_output_reader(&mut child) {
+            println!("  {}", line);
+        }
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(ImDeployError::HookFailed {
+                hook: point.name().to_string(),
+                code: status.code(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hook_receives_context() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let hooks = Hooks {
+            post_deploy: Some("echo \"$IM_DEPLOY_HOOK $IM_DEPLOY_CLUSTER_NAME $IM_DEPLOY_LB_IP\" > hook.out".to_string()),
+            working_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let context = HookContext {
+            cluster_name: "demo".to_string(),
+            lb_ip: Some("192.0.2.10".to_string()),
+            ..Default::default()
+        };
+
+        hooks.run(HookPoint::PostDeploy, &context).unwrap();
+        let written = std::fs::read_to_string(dir.path().join("hook.out")).unwrap();
+        assert_eq!(written.trim(), "post_deploy demo 192.0.2.10");
+
+        // Unconfigured hooks are a no-op
+        hooks.run(HookPoint::PreDestroy, &context).unwrap();
+    }
+
+    #[test]
+    fn test_failing_hook() {
+        let hooks = Hooks {
+            pre_destroy: Some("exit 3".to_string()),
+            ..Default::default()
+        };
+        let err = hooks.run(HookPoint::PreDestroy, &HookContext::default()).unwrap_err();
+        assert!(matches!(err, ImDeployError::HookFailed { code: Some(3), .. }));
+    }
+}
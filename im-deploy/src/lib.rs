@@ -2,12 +2,21 @@
 // Re-export modules from main for testing
 
 // Declare modules here so they can be used by integration tests
+// First, so its `println!` is in scope for the others
+#[macro_use]
+pub mod output;
 pub mod config;
 pub mod constants;
 pub mod domain;
 pub mod errors;
+pub mod history;
+pub mod hooks;
+pub mod openstack;
+pub mod progress_events;
+pub mod prompt;
+pub mod runner;
+pub mod terraform;
 
 // These are internal and don't need to be public
-pub(crate) mod openstack;
 pub(crate) mod tailscale;
 
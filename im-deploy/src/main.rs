@@ -1,23 +1,39 @@
+// First, so its `println!` is in scope for the others
+#[macro_use]
+pub mod output;
 pub mod config;
 mod commands;
 pub mod constants;
+mod dns;
 pub mod domain;
 pub mod errors;
+pub mod history;
+pub mod hooks;
 mod openstack;
+pub mod progress_events;
+pub mod prompt;
+pub mod runner;
 mod tailscale;
+pub mod terraform;
 mod tui;
 
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 use crossterm::{
     event::{self, Event, KeyCode, KeyEventKind},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use errors::Result;
+use domain::monitor::MonitorOptions;
+use errors::{ImDeployError, Result};
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
 };
 use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use terraform::InitOptions;
 use tracing::{error, info};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
@@ -29,6 +45,11 @@ struct Cli {
     #[arg(short = 'y', long = "yes", global = true)]
     yes: bool,
 
+    /// Start Tailscale, run 'tailscale up' and switch to the configured account without asking
+    /// (runs sudo on Linux); --yes alone never changes the local Tailscale client
+    #[arg(long = "manage-tailscale", global = true)]
+    manage_tailscale: bool,
+
     /// Dry run mode - show what would be done without making changes
     #[arg(long = "dry-run", global = true)]
     dry_run: bool,
@@ -37,29 +58,392 @@ struct Cli {
     #[arg(short = 'd', long = "debug", global = true)]
     debug: bool,
 
+    /// Terraform directory to use instead of auto-detecting ./terraform or ../terraform
+    #[arg(long = "terraform-dir", global = true, value_name = "DIR")]
+    terraform_dir: Option<PathBuf>,
+
+    /// Terraform/OpenTofu binary name or path to use instead of searching PATH
+    #[arg(long = "terraform-bin", global = true, value_name = "BIN")]
+    terraform_bin: Option<String>,
+
+    /// Print terraform progress as newline-delimited JSON events instead of text (for CI)
+    #[arg(long = "json", global = true)]
+    json: bool,
+
+    /// Print only a final summary line; the exit code tells what failed (for CI)
+    #[arg(short = 'q', long = "quiet", global = true)]
+    quiet: bool,
+
+    /// Stream deploy and monitor progress as newline-delimited JSON to a file or Unix socket
+    #[arg(long = "events-file", global = true, value_name = "PATH")]
+    events_file: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
+    /// Initialize the terraform directory, sharing downloaded providers through the plugin cache
+    Init {
+        /// Upgrade providers and modules to the newest allowed versions
+        #[arg(long)]
+        upgrade: bool,
+
+        /// Ignore the saved backend configuration
+        #[arg(long, conflicts_with = "migrate_state")]
+        reconfigure: bool,
+
+        /// Copy existing state into a changed backend
+        #[arg(long)]
+        migrate_state: bool,
+    },
     /// Deploy the K3s cluster using Terraform/OpenTofu
-    Deploy,
+    Deploy {
+        /// Let the cluster expire after this long (e.g. 8h, 2d); 'reap' then destroys it
+        #[arg(long, value_name = "DURATION", value_parser = history::parse_ttl)]
+        ttl: Option<Duration>,
+    },
     /// Destroy the K3s cluster
-    Destroy,
+    Destroy {
+        /// Minutes to wait for terraform destroy before interrupting it (default from im-deploy.toml or 30)
+        #[arg(long, value_name = "MINUTES")]
+        timeout: Option<u64>,
+
+        /// On timeout, re-run orphan cleanup, remove stuck resources from state and retry without asking
+        #[arg(long)]
+        force: bool,
+
+        /// Re-run orphan cleanup and terraform destroy up to N more times if destroy fails
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        retries: u32,
+
+        /// Keep network, floating IPs, security groups and the load balancer for a fast redeploy
+        #[arg(long)]
+        keep_network: bool,
+
+        /// Keep the Longhorn volumes for a fast redeploy
+        #[arg(long)]
+        keep_volumes: bool,
+
+        /// Delete all DOWN or unattached floating IPs in the project, not only the cluster's
+        #[arg(long)]
+        all_orphans: bool,
+
+        /// Query the live APIs and print the ordered deletion plan without deleting anything
+        #[arg(long)]
+        what_if: bool,
+
+        /// Detach security groups that are still in use from unbound ports, then retry deleting them
+        #[arg(long)]
+        detach_orphaned_ports: bool,
+
+        /// Leave the cluster's Tailscale devices alone, e.g. while Tailscale is down
+        #[arg(long)]
+        skip_tailscale: bool,
+
+        /// Skip deleting dynamic OpenStack load balancers, floating IPs and ports before and after terraform destroy
+        #[arg(long)]
+        skip_openstack_cleanup: bool,
+
+        /// Also destroy the resources matching preserve_on_destroy, by default the Longhorn backup container
+        #[arg(long)]
+        no_preserve_backups: bool,
+    },
+    /// Remove orphaned OpenStack resources of the cluster outside of destroy
+    Cleanup {
+        /// Also delete the cluster's routers, subnets and networks, e.g. after the terraform state was lost
+        #[arg(long)]
+        deep: bool,
+
+        /// Delete all DOWN or unattached floating IPs in the project, not only the cluster's
+        #[arg(long)]
+        all_orphans: bool,
+    },
+    /// Destroy the cluster if the TTL of its deploy has passed (for cron and scheduled CI)
+    Reap {
+        /// Minutes to wait for terraform destroy before interrupting it (default from im-deploy.toml or 30)
+        #[arg(long, value_name = "MINUTES")]
+        timeout: Option<u64>,
+
+        /// Re-run orphan cleanup and terraform destroy up to N more times if destroy fails
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        retries: u32,
+    },
+    /// Show the OpenStack console log of a server, e.g. when it is unreachable over SSH
+    Console {
+        /// Server to show (e.g. k3s-server-0 or server-0)
+        node: String,
+
+        /// Fetch only the last N lines
+        #[arg(long, value_name = "N")]
+        lines: Option<u32>,
+    },
+    /// Snapshot node root disks as a restore point and list the cluster's snapshots
+    Snapshot {
+        /// Server to snapshot (e.g. k3s-server-0 or server-0)
+        #[arg(long, value_name = "NAME", conflicts_with = "all")]
+        node: Option<String>,
+
+        /// Snapshot every OpenStack node of the cluster
+        #[arg(long)]
+        all: bool,
+
+        /// Delete all but the newest --keep snapshots of each node
+        #[arg(long)]
+        prune: bool,
+
+        /// Snapshots of each node to keep when pruning
+        #[arg(long, value_name = "N", default_value_t = constants::openstack::SNAPSHOT_KEEP)]
+        keep: usize,
+    },
+    /// Back up the k3s datastore (etcd snapshot or sqlite copy) from the first server
+    EtcdBackup {
+        /// Only list the existing backups
+        #[arg(long, conflicts_with_all = ["output", "swift"])]
+        list: bool,
+
+        /// File to write (default: <terraform dir>/.im-deploy/backups/<cluster>-datastore-<timestamp>.<ext>)
+        #[arg(long, short = 'o', value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Also upload the backup to the Longhorn backup container in Swift
+        #[arg(long)]
+        swift: bool,
+
+        /// Delete all but the newest --keep backups, locally and on the server
+        #[arg(long)]
+        prune: bool,
+
+        /// Backups to keep when pruning
+        #[arg(long, value_name = "N", default_value_t = constants::kubernetes::DATASTORE_BACKUP_KEEP)]
+        keep: usize,
+    },
+    /// Restore a freshly deployed cluster from an 'etcd-backup' file and wait until all nodes rejoin
+    Restore {
+        /// Backup file written by 'im-deploy etcd-backup'
+        #[arg(long, value_name = "FILE")]
+        snapshot: PathBuf,
+    },
+    /// Rebuild a node from one of its snapshots and wait until it rejoins the cluster
+    RestoreNode {
+        /// Server to rebuild (e.g. k3s-agent-0 or agent-0)
+        node: String,
+
+        /// Image ID of the snapshot, as listed by 'im-deploy snapshot'
+        #[arg(long, value_name = "ID")]
+        snapshot: String,
+    },
+    /// Replace the cluster's SSH key: authorize a new key on all nodes, update terraform, revoke the old one
+    RotateSshKey,
+    /// Replace the k3s join token on all servers and agents, restarting them one at a time
+    RotateToken,
+    /// Renew the k3s certificates on all servers and agents, restarting them one at a time
+    RotateCerts,
     /// SSH into a cluster server
-    Ssh,
+    Ssh {
+        /// Server to connect to (e.g. k3s-server-0) instead of choosing interactively
+        #[arg(long, value_name = "NAME")]
+        server: Option<String>,
+
+        /// Cloud provider to use when the cluster spans several
+        #[arg(long, value_name = "NAME")]
+        provider: Option<String>,
+    },
+    /// Open a SOCKS proxy into the cluster network through the bastion or a Tailscale node
+    Tunnel {
+        /// Local port of the proxy
+        #[arg(long, value_name = "PORT")]
+        port: Option<u16>,
+
+        /// Cloud provider to use when the cluster spans several
+        #[arg(long, value_name = "NAME")]
+        provider: Option<String>,
+    },
     /// Copy kubeconfig from the cluster to local directory
-    CopyKubeconfig,
+    CopyKubeconfig {
+        /// Disable TLS verification in the kubeconfig, e.g. when the API certificate lacks the LB address
+        #[arg(long)]
+        insecure_skip_tls_verify: bool,
+    },
     /// Monitor cluster formation and readiness
-    Monitor,
+    Monitor {
+        /// Continue an interrupted run, skipping phases it already completed
+        #[arg(long)]
+        resume: bool,
+
+        /// Phase to leave out (cloud-init, nodes, api, control-plane, workloads, gpu, argocd,
+        /// argocd-serve, or an add-on from monitor-addons.toml); repeatable
+        #[arg(long, value_name = "PHASE")]
+        skip_phase: Vec<String>,
+
+        /// Check only this phase, built-in or add-on; repeatable
+        #[arg(long, value_name = "PHASE", conflicts_with = "skip_phase")]
+        only_phase: Vec<String>,
+
+        /// Seconds between checks
+        #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+        interval: Option<u64>,
+
+        /// Keep showing node and pod health once all phases have completed
+        #[arg(long)]
+        watch: bool,
+    },
+    /// Show deployment state and control-plane health
+    Status,
+    /// Show Kubernetes events, warnings first, to debug a deploy without setting up kubectl
+    Events {
+        /// Only events of this namespace
+        #[arg(long, short = 'n', value_name = "NAMESPACE")]
+        namespace: Option<String>,
+
+        /// Keep refreshing and show new events as they happen
+        #[arg(long, short = 'f')]
+        follow: bool,
+    },
+    /// Show CPU and memory usage of nodes and pods, refreshing until closed
+    Top {
+        /// Seconds between refreshes
+        #[arg(long, value_name = "SECS", default_value_t = constants::monitoring::TOP_REFRESH_SECS, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+    },
     /// Display service URLs and credentials
     Info,
+    /// Audit OpenStack and Tailscale resources against terraform state
+    Inventory,
+    /// Import untracked OpenStack resources at the terraform addresses they most likely belong to
+    Import,
+    /// Run kubectl against the cluster, fetching the kubeconfig when needed
+    ///
+    /// Example: im-deploy kubectl -- get pods -A
+    Kubectl {
+        /// Open a root shell on a node through a privileged debug pod
+        #[arg(long, value_name = "NODE")]
+        node_shell: Option<String>,
+
+        /// Fetch a fresh kubeconfig even if the cached one looks current
+        #[arg(long)]
+        refresh: bool,
+
+        /// Arguments passed to kubectl
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Deploy and check applications hosted on the cluster
+    App {
+        #[command(subcommand)]
+        command: AppCommands,
+    },
+    /// GPU verification
+    Gpu {
+        #[command(subcommand)]
+        command: GpuCommands,
+    },
+    /// Export cluster access settings for other tools
+    Export {
+        #[command(subcommand)]
+        command: ExportCommands,
+    },
+    /// Look up OpenStack flavors and images for terraform.tfvars
+    Openstack {
+        #[command(subcommand)]
+        command: OpenstackCommands,
+    },
+    /// Generate terraform skeletons
+    Scaffold {
+        #[command(subcommand)]
+        command: ScaffoldCommands,
+    },
+    /// Check the tailnet setup the cluster relies on
+    Tailscale {
+        #[command(subcommand)]
+        command: TailscaleCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum AppCommands {
+    /// Create or sync the application's ArgoCD Application and wait until it is ready
+    Deploy {
+        /// Application name (e.g. immich)
+        name: String,
+    },
+    /// Show ArgoCD sync/health and component readiness
+    Status {
+        /// Application name (e.g. immich)
+        name: String,
+    },
+    /// Dump the application's database with pg_dump to a local file
+    Backup {
+        /// Application name (e.g. immich)
+        name: String,
+
+        /// Dump file to write (default: <terraform dir>/.im-deploy/backups/<app>-<db>-<timestamp>.dump)
+        #[arg(long, short = 'o', value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Also upload the dump to the Longhorn backup container in Swift
+        #[arg(long)]
+        swift: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum GpuCommands {
+    /// Run nvidia-smi on GPU nodes and check the NVIDIA device plugin
+    Check {
+        /// Also run a CUDA vector-add test pod
+        #[arg(long)]
+        cuda_test: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExportCommands {
+    /// Write ~/.ssh/config.d/im-deploy-<cluster> so plain `ssh <server>` works
+    SshConfig,
+}
+
+#[derive(Subcommand)]
+enum OpenstackCommands {
+    /// List flavors with their vCPUs, RAM and disk
+    Flavors {
+        /// Fail if a flavor set in terraform.tfvars does not exist
+        #[arg(long)]
+        check: bool,
+    },
+    /// List active images with their IDs
+    Images,
+}
+
+#[derive(Subcommand)]
+enum ScaffoldCommands {
+    /// Module for a new cloud, wired to the outputs im-deploy reads
+    Provider {
+        /// Cloud name in lowercase letters and digits (e.g. hetzner)
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum TailscaleCommands {
+    /// Print the policy file stanza (tagOwners, acls, ssh rules) the cluster tag needs
+    AclSuggest {
+        /// Who owns the node tags and may reach the nodes (user, group, tag or autogroup)
+        #[arg(long, default_value = "autogroup:admin")]
+        owner: String,
+
+        /// Merge the stanza into the tailnet policy file through the API
+        #[arg(long)]
+        apply: bool,
+    },
 }
 
 struct MainMenuSelector {
-    commands: Vec<(&'static str, &'static str)>,
+    /// (name, description, requires a deployed cluster)
+    commands: Vec<(&'static str, &'static str, bool)>,
     state: ListState,
+    status: Option<commands::ClusterStatus>,
 }
 
 impl MainMenuSelector {
@@ -68,14 +452,18 @@ impl MainMenuSelector {
         state.select(Some(0));
         Self {
             commands: vec![
-                ("Deploy", "Deploy the K3s cluster using Terraform/OpenTofu"),
-                ("Destroy", "Destroy the K3s cluster"),
-                ("SSH", "SSH into a cluster server"),
-                ("Copy Kubeconfig", "Copy kubeconfig from the cluster to local directory"),
-                ("Monitor", "Monitor cluster formation and readiness"),
-                ("Info", "Display service URLs and credentials"),
+                ("Deploy", "Deploy the K3s cluster using Terraform/OpenTofu", false),
+                ("Destroy", "Destroy the K3s cluster", true),
+                ("SSH", "SSH into a cluster server", true),
+                ("Copy Kubeconfig", "Copy kubeconfig from the cluster to local directory", true),
+                ("Monitor", "Monitor cluster formation and readiness", true),
+                ("Info", "Display service URLs and credentials", true),
+                ("Inventory", "Audit OpenStack and Tailscale resources against terraform state", false),
+                ("GPU Check", "Run nvidia-smi on GPU nodes and check the NVIDIA device plugin", true),
+                ("Immich Status", "Show Immich sync, health and component readiness", true),
             ],
             state,
+            status: None,
         }
     }
 
@@ -101,20 +489,103 @@ impl MainMenuSelector {
         self.state.select(Some(i));
     }
 
+    /// Commands that need a cluster are disabled once the status is known to be undeployed.
+    /// While the status is loading or unknown, everything stays enabled.
+    fn is_enabled(&self, index: usize) -> bool {
+        let requires_cluster = self.commands[index].2;
+        let not_deployed = self
+            .status
+            .as_ref()
+            .is_some_and(|s| s.state == commands::DeploymentState::NotDeployed);
+        !(requires_cluster && not_deployed)
+    }
+
     fn get_selected(&self) -> Option<Commands> {
-        self.state.selected().map(|i| match i {
-            0 => Commands::Deploy,
-            1 => Commands::Destroy,
-            2 => Commands::Ssh,
-            3 => Commands::CopyKubeconfig,
-            4 => Commands::Monitor,
+        self.state.selected().filter(|&i| self.is_enabled(i)).map(|i| match i {
+            0 => Commands::Deploy { ttl: None },
+            1 => Commands::Destroy {
+                timeout: None,
+                force: false,
+                retries: 0,
+                keep_network: false,
+                keep_volumes: false,
+                all_orphans: false,
+                what_if: false,
+                detach_orphaned_ports: false,
+                skip_tailscale: false,
+                skip_openstack_cleanup: false,
+                no_preserve_backups: false,
+            },
+            2 => Commands::Ssh {
+                server: None,
+                provider: None,
+            },
+            3 => Commands::CopyKubeconfig {
+                insecure_skip_tls_verify: false,
+            },
+            4 => Commands::Monitor {
+                resume: false,
+                skip_phase: Vec::new(),
+                only_phase: Vec::new(),
+                interval: None,
+                watch: false,
+            },
             5 => Commands::Info,
-            _ => Commands::Deploy,
+            6 => Commands::Inventory,
+            7 => Commands::Gpu {
+                command: GpuCommands::Check { cuda_test: false },
+            },
+            8 => Commands::App {
+                command: AppCommands::Status {
+                    name: "immich".to_string(),
+                },
+            },
+            _ => Commands::Deploy { ttl: None },
         })
     }
+
+    fn status_lines(&self) -> Vec<Line<'static>> {
+        let Some(status) = &self.status else {
+            return vec![Line::from(Span::styled(
+                "Loading cluster state...",
+                Style::default().fg(Color::Yellow),
+            ))];
+        };
+
+        let (state_text, state_color) = match &status.state {
+            commands::DeploymentState::Deployed => ("Deployed".to_string(), Color::Green),
+            commands::DeploymentState::NotDeployed => ("Not deployed".to_string(), Color::DarkGray),
+            commands::DeploymentState::Unknown(e) => (format!("Unknown ({})", e), Color::Red),
+            commands::DeploymentState::Cached { updated_at, .. } => (
+                format!("Deployed as of {} (cached)", history::format_age(*updated_at, history::unix_now())),
+                Color::Yellow,
+            ),
+        };
+
+        let last_deploy = status
+            .last_deploy
+            .as_ref()
+            .map(|e| history::format_age(e.started_at, history::unix_now()))
+            .unwrap_or_else(|| "never".to_string());
+
+        let label = |text: &'static str| Span::styled(text, Style::default().fg(Color::Gray));
+        vec![
+            Line::from(vec![label("Cluster:     "), Span::raw(status.cluster_name.clone())]),
+            Line::from(vec![label("State:       "), Span::styled(state_text, Style::default().fg(state_color))]),
+            Line::from(vec![label("Nodes:       "), Span::raw(status.node_count.to_string())]),
+            Line::from(vec![label("Last deploy: "), Span::raw(last_deploy)]),
+        ]
+    }
 }
 
-fn run_main_menu() -> Result<Option<Commands>> {
+fn run_main_menu(config: &config::Config) -> Result<Option<Commands>> {
+    // Terraform outputs can take a few seconds, so load them in the background
+    let (status_tx, status_rx) = mpsc::channel();
+    let status_config = config.clone();
+    thread::spawn(move || {
+        let _ = status_tx.send(commands::load_cluster_status(&status_config));
+    });
+
     enable_raw_mode()?;
     crossterm::execute!(io::stdout(), EnterAlternateScreen)?;
 
@@ -122,16 +593,30 @@ fn run_main_menu() -> Result<Option<Commands>> {
     let mut selector = MainMenuSelector::new();
 
     let result = loop {
+        if let Ok(status) = status_rx.try_recv() {
+            selector.status = Some(status);
+        }
+
         terminal.draw(|frame| {
             let area = frame.area();
+            let [main_area, help_area] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(area);
+            let [menu_area, status_area] =
+                Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(main_area);
 
             let items: Vec<ListItem> = selector
                 .commands
                 .iter()
-                .map(|(name, desc)| {
+                .enumerate()
+                .map(|(i, (name, desc, _))| {
+                    let (name_style, desc_style) = if selector.is_enabled(i) {
+                        (Style::default().fg(Color::Cyan).bold(), Style::default().fg(Color::Gray))
+                    } else {
+                        (Style::default().fg(Color::DarkGray), Style::default().fg(Color::DarkGray))
+                    };
                     ListItem::new(vec![
-                        Line::from(Span::styled(*name, Style::default().fg(Color::Cyan).bold())),
-                        Line::from(Span::styled(format!("  {}", desc), Style::default().fg(Color::Gray))),
+                        Line::from(Span::styled(*name, name_style)),
+                        Line::from(Span::styled(format!("  {}", desc), desc_style)),
                     ])
                 })
                 .collect();
@@ -145,25 +630,37 @@ fn run_main_menu() -> Result<Option<Commands>> {
                 .highlight_style(Style::default().bg(Color::DarkGray))
                 .highlight_symbol("> ");
 
-            frame.render_stateful_widget(list, area, &mut selector.state);
+            frame.render_stateful_widget(list, menu_area, &mut selector.state);
+
+            let status_paragraph = Paragraph::new(selector.status_lines())
+                .wrap(Wrap { trim: true })
+                .block(Block::default().title("Cluster Status").borders(Borders::ALL));
+            frame.render_widget(status_paragraph, status_area);
 
             let help_text = "\nPress ↑/↓ to navigate, Enter to select, Q to quit";
             let help_paragraph = Paragraph::new(help_text)
                 .block(Block::default().borders(Borders::NONE));
-
-            let help_area = Rect::new(area.x, area.bottom().saturating_sub(2), area.width, 2);
             frame.render_widget(help_paragraph, help_area);
         })?;
 
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press {
-                match key.code {
-                    KeyCode::Char('q') | KeyCode::Char('Q') => break None,
-                    KeyCode::Down | KeyCode::Char('j') => selector.next(),
-                    KeyCode::Up | KeyCode::Char('k') => selector.previous(),
-                    KeyCode::Enter => break selector.get_selected(),
-                    _ => {}
+        // Poll so the status pane refreshes as soon as the background load finishes
+        if !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
+
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Char('Q') => break None,
+                KeyCode::Down | KeyCode::Char('j') => selector.next(),
+                KeyCode::Up | KeyCode::Char('k') => selector.previous(),
+                KeyCode::Enter => {
+                    if let Some(cmd) = selector.get_selected() {
+                        break Some(cmd);
+                    }
                 }
+                _ => {}
             }
         }
     };
@@ -174,27 +671,76 @@ fn run_main_menu() -> Result<Option<Commands>> {
     Ok(result)
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+fn main() {
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let command_name = matches.subcommand_name().unwrap_or("im-deploy").to_string();
+    let quiet = cli.quiet;
+    output::set_quiet(quiet);
 
     // Initialize tracing with environment filter
-    // Use RUST_LOG env var to control log level, or default based on --debug flag
-    let default_level = if cli.debug { "debug" } else { "warn" };
+    // Use RUST_LOG env var to control log level, or default based on --debug and --quiet
+    let default_level = match (cli.debug, quiet) {
+        (true, _) => "debug",
+        (false, true) => "off",
+        (false, false) => "warn",
+    };
     tracing_subscriber::registry()
         .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_level)))
-        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_subscriber::fmt::layer().with_writer(|| output::Redacting(io::stdout())))
         .init();
 
+    let result = run(cli);
+
+    match &result {
+        Ok(()) if quiet => match output::warnings().len() {
+            0 => std::println!("{}: ok", command_name),
+            count => std::println!("{}: ok with {} warning(s)", command_name, count),
+        },
+        Ok(()) => {}
+        Err(e) => {
+            error!("Command failed: {}", e);
+            if quiet {
+                eprintln!("{}: failed (exit {}): {}", command_name, e.exit_code(), e);
+            } else if let ImDeployError::Cancelled(_) = e {
+                println!("{}.", e);
+            } else {
+                // Printed here instead of returned, the default report would bypass redaction
+                eprintln!("Error: {:?}", e);
+            }
+            std::process::exit(e.exit_code());
+        }
+    }
+}
 
+fn run(cli: Cli) -> Result<()> {
     if cli.dry_run {
         info!("🌵 DRY RUN MODE - No actual changes will be made");
     }
 
+    // Load configuration before showing the menu so invalid flags fail at startup
+    let overrides = config::ConfigOverrides {
+        terraform_dir: cli.terraform_dir,
+        terraform_bin: cli.terraform_bin,
+        json_output: cli.json,
+        events_file: cli.events_file,
+        auto_confirm: cli.yes,
+        manage_tailscale: cli.manage_tailscale,
+    };
+    let config = config::load_config_with_overrides(cli.dry_run, &overrides)?;
+    for secret in config.secrets() {
+        output::register_secret(secret);
+    }
+
     let command = match cli.command {
         Some(cmd) => cmd,
         None => {
             // No command provided, show interactive menu
-            match run_main_menu()? {
+            tui::ensure_interactive(
+                "The main menu",
+                "pass a subcommand such as 'deploy', 'destroy' or 'info' (see --help)",
+            )?;
+            match run_main_menu(&config)? {
                 Some(cmd) => cmd,
                 None => {
                     info!("Exiting");
@@ -204,22 +750,135 @@ fn main() -> Result<()> {
         }
     };
 
-    // Load configuration
-    let config = config::load_config(cli.dry_run)?;
-
-    let result = match command {
-        Commands::Deploy => commands::cmd_deploy(&config, cli.yes),
-        Commands::Destroy => commands::cmd_destroy(&config, cli.yes),
-        Commands::Ssh => commands::cmd_ssh(&config),
-        Commands::CopyKubeconfig => commands::cmd_copy_kubeconfig(&config),
-        Commands::Monitor => commands::cmd_monitor(&config),
+    match command {
+        Commands::Init {
+            upgrade,
+            reconfigure,
+            migrate_state,
+        } => commands::cmd_init(
+            &config,
+            &InitOptions {
+                upgrade,
+                reconfigure,
+                migrate_state,
+            },
+        ),
+        Commands::Deploy { ttl } => commands::cmd_deploy(&config, cli.yes, ttl),
+        Commands::Destroy {
+            timeout,
+            force,
+            retries,
+            keep_network,
+            keep_volumes,
+            all_orphans,
+            what_if,
+            detach_orphaned_ports,
+            skip_tailscale,
+            skip_openstack_cleanup,
+            no_preserve_backups,
+        } => {
+            let options = commands::DestroyOptions {
+                timeout: Duration::from_secs(60 * timeout.unwrap_or(config.destroy_timeout_mins)),
+                force,
+                retries,
+                keep_network,
+                keep_volumes,
+                all_orphans,
+                what_if,
+                detach_orphaned_ports,
+                skip_tailscale,
+                skip_openstack_cleanup,
+                preserve_backups: !no_preserve_backups,
+            };
+            commands::cmd_destroy(&config, cli.yes, &options)
+        }
+        Commands::Cleanup { deep, all_orphans } => commands::cmd_cleanup(&config, cli.yes, deep, all_orphans),
+        Commands::Reap { timeout, retries } => {
+            let options = commands::DestroyOptions {
+                timeout: Duration::from_secs(60 * timeout.unwrap_or(config.destroy_timeout_mins)),
+                force: false,
+                retries,
+                keep_network: false,
+                keep_volumes: false,
+                all_orphans: false,
+                what_if: false,
+                detach_orphaned_ports: false,
+                skip_tailscale: false,
+                skip_openstack_cleanup: false,
+                preserve_backups: true,
+            };
+            commands::cmd_reap(&config, &options)
+        }
+        Commands::Console { node, lines } => commands::cmd_console(&config, &node, lines),
+        Commands::Snapshot { node, all, prune, keep } => {
+            commands::cmd_snapshot(&config, node.as_deref(), all, prune, keep, cli.yes)
+        }
+        Commands::EtcdBackup {
+            list,
+            output,
+            swift,
+            prune,
+            keep,
+        } => commands::cmd_etcd_backup(&config, list, output, swift, prune, keep, cli.yes),
+        Commands::Restore { snapshot } => commands::cmd_restore(&config, &snapshot, cli.yes),
+        Commands::RestoreNode { node, snapshot } => commands::cmd_restore_node(&config, &node, &snapshot, cli.yes),
+        Commands::RotateSshKey => commands::cmd_rotate_ssh_key(&config, cli.yes),
+        Commands::RotateToken => commands::cmd_rotate_token(&config, cli.yes),
+        Commands::RotateCerts => commands::cmd_rotate_certs(&config, cli.yes),
+        Commands::Ssh { server, provider } => commands::cmd_ssh(&config, server.as_deref(), provider.as_deref()),
+        Commands::Tunnel { port, provider } => commands::cmd_tunnel(&config, provider.as_deref(), port),
+        Commands::CopyKubeconfig {
+            insecure_skip_tls_verify,
+        } => commands::cmd_copy_kubeconfig(&config, insecure_skip_tls_verify),
+        Commands::Monitor {
+            resume,
+            skip_phase,
+            only_phase,
+            interval,
+            watch,
+        } => {
+            let mut options = MonitorOptions {
+                resume,
+                interval_secs: interval,
+                watch,
+                ..Default::default()
+            };
+            options.skip_named(skip_phase);
+            options.only_named(only_phase);
+            commands::cmd_monitor(&config, &options)
+        }
+        Commands::Status => commands::cmd_status(&config),
+        Commands::Events { namespace, follow } => commands::cmd_events(&config, namespace.as_deref(), follow),
+        Commands::Top { interval } => commands::cmd_top(&config, Duration::from_secs(interval)),
         Commands::Info => commands::cmd_info(&config),
-    };
-
-    if let Err(ref e) = result {
-        error!("Command failed: {}", e);
+        Commands::Inventory => commands::cmd_inventory(&config),
+        Commands::Import => commands::cmd_import(&config, cli.yes),
+        Commands::Kubectl {
+            node_shell,
+            refresh,
+            args,
+        } => commands::cmd_kubectl(&config, &args, node_shell.as_deref(), refresh),
+        Commands::App { command } => match command {
+            AppCommands::Deploy { name } => commands::cmd_app_deploy(&config, &name),
+            AppCommands::Status { name } => commands::cmd_app_status(&config, &name),
+            AppCommands::Backup { name, output, swift } => commands::cmd_app_backup(&config, &name, output, swift),
+        },
+        Commands::Gpu {
+            command: GpuCommands::Check { cuda_test },
+        } => commands::cmd_gpu_check(&config, cuda_test),
+        Commands::Export {
+            command: ExportCommands::SshConfig,
+        } => commands::cmd_export_ssh_config(&config),
+        Commands::Openstack { command } => match command {
+            OpenstackCommands::Flavors { check } => commands::cmd_openstack_flavors(&config, check),
+            OpenstackCommands::Images => commands::cmd_openstack_images(&config),
+        },
+        Commands::Scaffold {
+            command: ScaffoldCommands::Provider { name },
+        } => commands::cmd_scaffold_provider(&config, &name),
+        Commands::Tailscale {
+            command: TailscaleCommands::AclSuggest { owner, apply },
+        } => commands::cmd_tailscale_acl_suggest(&config, &owner, apply),
     }
-
-    result
 }
 
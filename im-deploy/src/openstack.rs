@@ -1,7 +1,18 @@
+use crate::config::OpenStackConfig;
+use crate::constants::openstack as os_constants;
+use crate::domain::inventory::{Inventory, InventoryItem, ResourceKind};
+use crate::errors::OpenStackError;
+use crate::history;
+use crate::output;
 use anyhow::{Context, Result};
-use reqwest::blocking::Client;
+use reqwest::blocking::{Client, Response};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
+use std::thread;
+use std::time::{Duration, Instant};
 
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
@@ -31,6 +42,99 @@ struct Endpoint {
     url: String,
     interface: String,
     region: Option<String>,
+    region_id: Option<String>,
+}
+
+#[allow(dead_code)]
+impl Endpoint {
+    fn region(&self) -> Option<&str> {
+        self.region_id.as_deref().or(self.region.as_deref())
+    }
+}
+
+/// Base URLs of the services im-deploy talks to
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ServiceEndpoints {
+    pub neutron: String,
+    /// Only if the cloud offers load balancers; without it there are none to clean up
+    pub octavia: Option<String>,
+    pub nova: String,
+    /// Cinder needs the project ID, which only comes with the token
+    pub cinder: Option<String>,
+    pub glance: Option<String>,
+    /// Only if the cloud offers object storage
+    pub swift: Option<String>,
+}
+
+/// Append `version` unless the URL already ends in a version segment such as v2 or v2.0
+fn versioned(url: &str, version: &str) -> String {
+    let url = url.trim_end_matches('/');
+    let last = url.rsplit('/').next().unwrap_or_default();
+    let has_version = last.strip_prefix('v').is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()));
+    if has_version {
+        url.to_string()
+    } else {
+        format!("{}/{}", url, version)
+    }
+}
+
+fn catalog_url(catalog: &[CatalogEntry], service_types: &[&str], region: &str, interface: &str) -> Option<String> {
+    catalog
+        .iter()
+        .filter(|entry| service_types.contains(&entry.service_type.as_str()))
+        .flat_map(|entry| &entry.endpoints)
+        .find(|e| e.interface == interface && e.region() == Some(region))
+        .map(|e| e.url.trim_end_matches('/').to_string())
+}
+
+/// Pick endpoints from the token's catalog for `region` and `interface`. Without a catalog
+/// (unscoped token) they are derived from the Keystone URL with the default ports.
+fn resolve_endpoints(
+    catalog: &[CatalogEntry],
+    auth_url: &str,
+    project_id: Option<&str>,
+    region: &str,
+    interface: &str,
+) -> Result<ServiceEndpoints> {
+    if catalog.is_empty() {
+        return Ok(ServiceEndpoints {
+            neutron: auth_url.replace(":5000/v3", ":9696/v2.0"),
+            octavia: Some(auth_url.replace(":5000/v3", ":9876/v2.0")),
+            nova: auth_url.replace(":5000/v3", ":8774/v2.1"),
+            cinder: project_id.map(|id| format!("{}/{}", auth_url.replace(":5000/v3", ":8776/v3"), id)),
+            glance: Some(auth_url.replace(":5000/v3", ":9292/v2")),
+            swift: None,
+        });
+    }
+
+    let mut regions: Vec<&str> = catalog.iter().flat_map(|e| &e.endpoints).filter_map(|e| e.region()).collect();
+    regions.sort_unstable();
+    regions.dedup();
+    if !regions.contains(&region) {
+        return Err(anyhow::anyhow!(
+            "Region '{}' has no endpoints in the service catalog (available: {}); set openstack_region or {}",
+            region,
+            regions.join(", "),
+            crate::constants::env_vars::OS_REGION_NAME
+        ));
+    }
+
+    let required = |service_type: &str| {
+        catalog_url(catalog, &[service_type], region, interface).with_context(|| {
+            format!(
+                "No {} endpoint with interface '{}' in region '{}' of the service catalog",
+                service_type, interface, region
+            )
+        })
+    };
+    Ok(ServiceEndpoints {
+        neutron: versioned(&required("network")?, "v2.0"),
+        octavia: catalog_url(catalog, &["load-balancer"], region, interface).map(|url| versioned(&url, "v2.0")),
+        nova: required("compute")?,
+        cinder: catalog_url(catalog, &["block-storage", "volumev3"], region, interface),
+        glance: catalog_url(catalog, &["image"], region, interface).map(|url| versioned(&url, "v2")),
+        swift: catalog_url(catalog, &["object-store"], region, interface),
+    })
 }
 
 #[allow(dead_code)]
@@ -99,6 +203,106 @@ struct FloatingIP {
     floating_ip_address: String,
     status: String,
     port_id: Option<String>,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Network of the associated port (fip-port-details extension)
+    #[serde(default)]
+    port_details: Option<FloatingIPPortDetails>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct FloatingIPPortDetails {
+    network_id: Option<String>,
+}
+
+#[allow(dead_code)]
+impl FloatingIP {
+    fn is_orphaned(&self) -> bool {
+        self.status.eq_ignore_ascii_case("down") || self.port_id.is_none()
+    }
+
+    /// Untagged floating IPs belong to the cluster if the description or a tag names it,
+    /// e.g. the cloud controller's "Floating IP for Kubernetes external service ... from
+    /// cluster <name>", or the associated port is on the cluster network
+    fn belongs_to(&self, cluster: &ClusterScope) -> bool {
+        cluster.owns(&self.tags, || {
+            cluster.is_named_in(&self.description)
+                || self.tags.iter().any(|tag| tag == cluster.name)
+                || cluster.is_on_network(self.port_details.as_ref().and_then(|d| d.network_id.as_deref()))
+        })
+    }
+}
+
+/// The cluster whose leftovers cleanup may delete. Resources terraform tagged `cluster=<name>`
+/// are matched on the tag alone; untagged ones, created by the cloud controller or by an
+/// older deployment, by name, description or network.
+#[derive(Debug, Clone, Copy)]
+pub struct ClusterScope<'a> {
+    pub name: &'a str,
+    pub network_id: Option<&'a str>,
+}
+
+#[allow(dead_code)]
+impl ClusterScope<'_> {
+    /// Whether the resource belongs to the cluster, by `cluster=` tag if it has one, else by `untagged`
+    fn owns(&self, tags: &[String], untagged: impl FnOnce() -> bool) -> bool {
+        let mut clusters = tags
+            .iter()
+            .filter_map(|tag| tag.strip_prefix(os_constants::CLUSTER_TAG_KEY)?.strip_prefix('='))
+            .peekable();
+        if clusters.peek().is_none() {
+            return untagged();
+        }
+        clusters.any(|name| name == self.name)
+    }
+
+    fn is_on_network(&self, network_id: Option<&str>) -> bool {
+        self.network_id.is_some() && self.network_id == network_id
+    }
+
+    /// Whether the cluster name appears as a word in a description
+    fn is_named_in(&self, text: &str) -> bool {
+        text.split(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+            .any(|word| word == self.name)
+    }
+
+    /// Whether a load balancer component is named after the cluster: `<cluster>-api-listener`
+    /// from terraform, or `pool_0_kube_service_<cluster>_<namespace>_<service>` from the cloud
+    /// controller manager
+    fn names_component(&self, name: &str) -> bool {
+        name.strip_prefix(self.name).is_some_and(|rest| rest.starts_with('-'))
+            || name.split('_').any(|part| self.is_named_in(part))
+    }
+
+    /// Terraform names network resources `<cluster>-network`, `<cluster>-router`, ...
+    fn is_named(&self, name: &str, suffix: &str) -> bool {
+        name.strip_prefix(self.name)
+            .and_then(|rest| rest.strip_prefix('-'))
+            .is_some_and(|rest| rest == suffix)
+    }
+}
+
+/// Which orphaned floating IPs cleanup may delete
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy)]
+pub enum FloatingIpScope {
+    /// Only those of the cluster, safe in a shared project
+    Cluster,
+    /// Every floating IP in the project that is down or unattached
+    AllOrphans,
+}
+
+#[allow(dead_code)]
+impl FloatingIpScope {
+    fn includes(&self, fip: &FloatingIP, cluster: &ClusterScope) -> bool {
+        match self {
+            FloatingIpScope::Cluster => fip.belongs_to(cluster),
+            FloatingIpScope::AllOrphans => true,
+        }
+    }
 }
 
 #[allow(dead_code)]
@@ -114,6 +318,31 @@ struct Port {
     name: String,
     device_owner: String,
     network_id: String,
+    #[serde(default)]
+    device_id: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    security_groups: Vec<String>,
+}
+
+#[allow(dead_code)]
+impl Port {
+    /// Not bound to an instance, router, DHCP agent or load balancer
+    fn is_unbound(&self) -> bool {
+        self.device_id.is_empty()
+    }
+
+    fn describe(&self) -> String {
+        let name = if self.name.is_empty() { &self.id } else { &self.name };
+        match (self.device_owner.as_str(), self.device_id.as_str()) {
+            (_, "") => format!("port {} (unbound)", name),
+            (owner, device) if owner.starts_with("compute:") => format!("port {} of instance {}", name, device),
+            (owner, device) => format!("port {} of {} {}", name, owner, device),
+        }
+    }
 }
 
 #[allow(dead_code)]
@@ -129,6 +358,10 @@ struct LoadBalancer {
     name: String,
     vip_network_id: String,
     provisioning_status: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    updated_at: Option<String>,
 }
 
 #[allow(dead_code)]
@@ -137,6 +370,80 @@ struct LoadBalancersResponse {
     loadbalancers: Vec<LoadBalancer>,
 }
 
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct IdRef {
+    id: String,
+}
+
+/// Listener, pool or health monitor of an Octavia load balancer
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct LbComponent {
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    provisioning_status: String,
+    /// Parents of listeners and pools
+    #[serde(default)]
+    loadbalancers: Vec<IdRef>,
+    /// Parents of health monitors
+    #[serde(default)]
+    pools: Vec<IdRef>,
+    /// Members of pools
+    #[serde(default)]
+    members: Vec<IdRef>,
+}
+
+#[allow(dead_code)]
+impl LbComponent {
+    fn label(&self, kind: &str) -> String {
+        let name = if self.name.is_empty() { &self.id } else { &self.name };
+        format!("{} {}", kind, name)
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct ListenersResponse {
+    listeners: Vec<LbComponent>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct PoolsResponse {
+    pools: Vec<LbComponent>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct HealthMonitorsResponse {
+    healthmonitors: Vec<LbComponent>,
+}
+
+/// Whether a load balancer component was left behind: its load balancers are in ERROR, or gone.
+/// Components of a gone load balancer can only be attributed to the cluster by name, e.g.
+/// `listener_0_kube_service_<cluster>_...` or `<cluster>-api-listener`.
+fn is_orphaned_component(
+    parents: &[IdRef],
+    name: &str,
+    lbs: &HashMap<&str, &LoadBalancer>,
+    cluster: &ClusterScope,
+) -> bool {
+    let named = cluster.names_component(name);
+    if parents.is_empty() {
+        return named;
+    }
+    parents.iter().all(|parent| match lbs.get(parent.id.as_str()) {
+        Some(lb) => {
+            lb.provisioning_status == "ERROR"
+                && cluster.owns(&lb.tags, || cluster.is_on_network(Some(&lb.vip_network_id)))
+        }
+        None => named,
+    })
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct Volume {
@@ -152,12 +459,227 @@ struct VolumesResponse {
     volumes: Vec<Volume>,
 }
 
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct Server {
+    id: String,
+    name: String,
+    status: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ServerResponse {
+    server: Server,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct ServersResponse {
+    servers: Vec<Server>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConsoleOutput {
+    output: String,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Deserialize)]
+pub struct Flavor {
+    pub id: String,
+    pub name: String,
+    pub vcpus: u32,
+    /// MiB
+    pub ram: u64,
+    /// GiB
+    pub disk: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct FlavorsResponse {
+    flavors: Vec<Flavor>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Deserialize)]
+pub struct Image {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Bytes; unset while the image is queued
+    #[serde(default)]
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImagesResponse {
+    images: Vec<Image>,
+    /// Path of the next page, e.g. "/v2/images?marker=<id>"
+    next: Option<String>,
+}
+
+/// Image Nova created from a node with `createImage`. For boot-from-volume nodes the image only
+/// references Cinder snapshots of the volumes.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Snapshot {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub status: String,
+    /// RFC 3339
+    pub created_at: String,
+    #[serde(default, rename = "im_deploy_cluster")]
+    pub cluster: Option<String>,
+    /// Instance name of the node
+    #[serde(default, rename = "im_deploy_node")]
+    pub node: Option<String>,
+    /// JSON list of block device mappings, set for volume-backed snapshots
+    #[serde(default)]
+    block_device_mapping: Option<String>,
+}
+
+impl Snapshot {
+    /// Cinder snapshots the image stands for; deleting the image leaves them behind
+    pub fn volume_snapshot_ids(&self) -> Vec<String> {
+        let Some(mappings) = self
+            .block_device_mapping
+            .as_deref()
+            .and_then(|bdm| serde_json::from_str::<Vec<serde_json::Value>>(bdm).ok())
+        else {
+            return Vec::new();
+        };
+        mappings
+            .iter()
+            .filter_map(|mapping| mapping["snapshot_id"].as_str())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SnapshotsResponse {
+    images: Vec<Snapshot>,
+    next: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateImageResponse {
+    image_id: String,
+}
+
+/// Snapshots beyond the newest `keep` of each node, oldest first
+pub fn snapshots_to_prune(snapshots: &[Snapshot], keep: usize) -> Vec<&Snapshot> {
+    let mut by_node: BTreeMap<&str, Vec<&Snapshot>> = BTreeMap::new();
+    for snapshot in snapshots {
+        by_node.entry(snapshot.node.as_deref().unwrap_or_default()).or_default().push(snapshot);
+    }
+    let mut prune: Vec<&Snapshot> = by_node
+        .into_values()
+        .flat_map(|mut node_snapshots| {
+            // RFC 3339 times of one cloud sort chronologically
+            node_snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+            node_snapshots.into_iter().skip(keep)
+        })
+        .collect();
+    prune.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    prune
+}
+
+#[derive(Debug, Deserialize)]
+struct QuotaDetailsResponse {
+    quota: QuotaDetails,
+}
+
+#[derive(Debug, Deserialize)]
+struct QuotaDetails {
+    floatingip: QuotaUsage,
+}
+
+#[derive(Debug, Deserialize)]
+struct QuotaUsage {
+    /// -1 for unlimited
+    limit: i64,
+    used: i64,
+    #[serde(default)]
+    reserved: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct IpAvailabilityResponse {
+    network_ip_availability: IpAvailability,
+}
+
+#[derive(Debug, Deserialize)]
+struct IpAvailability {
+    total_ips: u64,
+    used_ips: u64,
+}
+
+/// Room for new floating IPs in the project quota and the external network. Either side is
+/// unknown when the API is unavailable or, for the pool, restricted to admins.
+#[allow(dead_code)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FloatingIpCapacity {
+    pub quota_limit: Option<i64>,
+    pub quota_used: Option<i64>,
+    pub pool_free: Option<u64>,
+    /// Floating IPs the cluster already holds, which terraform does not allocate again
+    pub cluster_owned: u32,
+}
+
+#[allow(dead_code)]
+impl FloatingIpCapacity {
+    fn quota_free(&self) -> Option<i64> {
+        match (self.quota_limit?, self.quota_used?) {
+            (limit, _) if limit < 0 => None,
+            (limit, used) => Some((limit - used).max(0)),
+        }
+    }
+
+    /// Why `needed` more floating IPs cannot be allocated, if they cannot
+    pub fn shortfall(&self, needed: u32) -> Option<String> {
+        let missing = needed.saturating_sub(self.cluster_owned) as i64;
+        if missing == 0 {
+            return None;
+        }
+        if let Some(free) = self.quota_free()
+            && free < missing
+        {
+            return Some(format!(
+                "the project quota allows {} more floating IP(s) ({} of {} used), {} needed",
+                free,
+                self.quota_used.unwrap_or_default(),
+                self.quota_limit.unwrap_or_default(),
+                missing
+            ));
+        }
+        if let Some(free) = self.pool_free
+            && (free as i64) < missing
+        {
+            return Some(format!("the external network has {} free address(es), {} needed", free, missing));
+        }
+        None
+    }
+}
+
+/// Configured flavors, as (tfvars variable, flavor), that match no flavor by name or ID
+#[allow(dead_code)]
+pub fn unknown_flavors<'a>(configured: &'a BTreeMap<String, String>, flavors: &[Flavor]) -> Vec<(&'a str, &'a str)> {
+    configured
+        .iter()
+        .filter(|(_, wanted)| !flavors.iter().any(|f| &&f.name == wanted || &&f.id == wanted))
+        .map(|(var, wanted)| (var.as_str(), wanted.as_str()))
+        .collect()
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 struct SecurityGroup {
     id: String,
     name: String,
     description: String,
+    #[serde(default)]
+    tags: Vec<String>,
 }
 
 #[allow(dead_code)]
@@ -166,28 +688,277 @@ struct SecurityGroupsResponse {
     security_groups: Vec<SecurityGroup>,
 }
 
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct Network {
+    id: String,
+    name: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[allow(dead_code)]
+impl Network {
+    fn belongs_to(&self, cluster: &ClusterScope) -> bool {
+        cluster.owns(&self.tags, || cluster.is_named(&self.name, "network"))
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct NetworksResponse {
+    networks: Vec<Network>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct Subnet {
+    id: String,
+    name: String,
+    network_id: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct SubnetsResponse {
+    subnets: Vec<Subnet>,
+}
+
+/// Routers carry the same fields as networks that cleanup needs
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct RoutersResponse {
+    routers: Vec<Network>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct SecurityGroupRule {
+    id: String,
+    security_group_id: String,
+    #[serde(default)]
+    direction: String,
+    #[serde(default)]
+    protocol: Option<String>,
+    #[serde(default)]
+    port_range_min: Option<u16>,
+    #[serde(default)]
+    port_range_max: Option<u16>,
+}
+
+#[allow(dead_code)]
+impl SecurityGroupRule {
+    /// e.g. "ingress tcp 6443" or "egress any"
+    fn summary(&self) -> String {
+        let protocol = self.protocol.as_deref().unwrap_or("any");
+        match (self.port_range_min, self.port_range_max) {
+            (Some(min), Some(max)) if min != max => format!("{} {} {}-{}", self.direction, protocol, min, max),
+            (Some(port), _) => format!("{} {} {}", self.direction, protocol, port),
+            _ => format!("{} {}", self.direction, protocol),
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct SecurityGroupRulesResponse {
+    security_group_rules: Vec<SecurityGroupRule>,
+}
+
+/// Shown each batch of resources a cleanup pass is about to delete, e.g. "load balancers",
+/// and returns the IDs to go ahead with
+pub type DeletionReview = Box<dyn Fn(&str, &[InventoryItem]) -> Result<Vec<String>>>;
+
+/// Row of a deletion review
+fn candidate(kind: ResourceKind, id: &str, name: &str, status: &str) -> InventoryItem {
+    InventoryItem {
+        kind,
+        id: id.to_string(),
+        name: name.to_string(),
+        status: status.to_string(),
+        orphan_candidate: true,
+    }
+}
+
+/// Remove entries owned by another project from the lists of a response; returns how many.
+/// Entries without an owner, e.g. shared images, are kept.
+fn drop_foreign_entries(body: &mut serde_json::Value, project_id: &str) -> usize {
+    let Some(object) = body.as_object_mut() else {
+        return 0;
+    };
+    let mut dropped = 0;
+    for list in object.values_mut().filter_map(|value| value.as_array_mut()) {
+        let before = list.len();
+        list.retain(|entry| {
+            let owner = entry
+                .get("project_id")
+                .or_else(|| entry.get("tenant_id"))
+                .and_then(|owner| owner.as_str());
+            owner.is_none_or(|owner| owner.is_empty() || owner == project_id)
+        });
+        dropped += before - list.len();
+    }
+    dropped
+}
+
+/// Human-readable message of an OpenStack API fault body.
+/// Neutron wraps it in NeutronError, Octavia uses faultstring, Keystone error and Nova a
+/// single key naming the fault, e.g. itemNotFound.
+fn fault_message(body: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(body).ok()?;
+    let object = value.as_object()?;
+    let message = if let Some(fault) = object.get("NeutronError").or_else(|| object.get("error")) {
+        fault.get("message")
+    } else if let Some(fault) = object.get("faultstring") {
+        Some(fault)
+    } else if object.len() == 1 {
+        object.values().next().and_then(|fault| fault.get("message"))
+    } else {
+        object.get("message")
+    };
+    message
+        .and_then(|message| message.as_str())
+        .map(|message| message.trim().to_string())
+        .filter(|message| !message.is_empty())
+}
+
+/// Describe a failed OpenStack response: status, fault message and the request ID that
+/// support needs to find the call in the service logs.
+fn api_fault(response: Response) -> String {
+    let status = response.status();
+    let request_id = ["x-openstack-request-id", "x-compute-request-id"]
+        .iter()
+        .find_map(|header| response.headers().get(*header))
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let body = response.text().unwrap_or_default();
+    let mut message = status.to_string();
+    if let Some(fault) = fault_message(&body) {
+        message = format!("{}: {}", message, fault);
+    } else if !body.trim().is_empty() {
+        message = format!("{}: {}", message, body.trim());
+    }
+    if let Some(request_id) = request_id {
+        message = format!("{} (request ID {})", message, request_id);
+    }
+    message
+}
+
+fn list_failed(resource: &str, response: Response) -> OpenStackError {
+    OpenStackError::ListFailed {
+        resource: resource.to_string(),
+        message: api_fault(response),
+    }
+}
+
+fn delete_failed(resource: &str, id: &str, response: Response) -> OpenStackError {
+    OpenStackError::DeleteFailed {
+        resource: resource.to_string(),
+        id: id.to_string(),
+        message: api_fault(response),
+    }
+}
+
+/// Provisioning status reported for a load balancer that no longer exists
+const LB_DELETED: &str = "DELETED";
+
+fn lb_deletion_finished(status: &str) -> bool {
+    status == LB_DELETED || status == "ERROR"
+}
+
+/// Octavia rejects changes, including deletion, while a load balancer is PENDING_*
+fn lb_settled(status: &str) -> bool {
+    !status.starts_with("PENDING_")
+}
+
+/// What a load balancer needs before it can be deleted
+#[derive(Debug, PartialEq, Eq)]
+enum LbRecovery {
+    None,
+    /// ERROR: rebuild it with an Octavia failover so the cascade delete can succeed
+    Failover,
+    /// PENDING_*: wait until Octavia finishes the running operation
+    Settle,
+}
+
+fn lb_recovery(status: &str) -> LbRecovery {
+    match status {
+        "ERROR" => LbRecovery::Failover,
+        status if !lb_settled(status) => LbRecovery::Settle,
+        _ => LbRecovery::None,
+    }
+}
+
+/// Request cascade deletion of a load balancer; a 404 means it is already gone
+fn request_lb_deletion(
+    client: &Client,
+    auth_token: &str,
+    octavia_endpoint: &str,
+    lb_id: &str,
+) -> std::result::Result<(), String> {
+    let url = format!("{}/lbaas/loadbalancers/{}?cascade=true", octavia_endpoint, lb_id);
+    match client.delete(&url).header("X-Auth-Token", auth_token).send() {
+        Ok(resp) if resp.status().is_success() || resp.status().as_u16() == 404 => Ok(()),
+        Ok(resp) => Err(api_fault(resp)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Progress of the load balancer deletions as (name, provisioning status) rows
+fn lb_progress_table(rows: &[(&str, &str)], elapsed: Duration) -> String {
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0).max("LOAD BALANCER".len());
+    let mut table = format!("    [{:>4}s] {:<width$}  STATUS\n", elapsed.as_secs(), "LOAD BALANCER");
+    for (name, status) in rows {
+        table.push_str(&format!("            {:<width$}  {}\n", name, status));
+    }
+    table
+}
+
+fn http_client_builder() -> reqwest::blocking::ClientBuilder {
+    Client::builder().timeout(Duration::from_secs(30))
+}
+
 pub struct OpenStackClient {
     client: Client,
     auth_token: String,
     neutron_endpoint: String,
-    octavia_endpoint: String
+    octavia_endpoint: Option<String>,
+    nova_endpoint: String,
+    glance_endpoint: Option<String>,
+    project_name: String,
+    /// Scope of the token; listed resources of other projects are ignored
+    project_id: Option<String>,
+    cinder_endpoint: Option<String>,
+    swift_endpoint: Option<String>,
+    /// Asked before every batch deletion; without one, cleanup deletes everything it finds
+    review: Option<DeletionReview>,
+    /// Remove a security group from unbound ports using it when deleting it fails with 409
+    detach_orphaned_ports: bool,
+    /// Only record what cleanup would delete in `plan`
+    what_if: bool,
+    plan: RefCell<Vec<InventoryItem>>,
+    /// Resources cleanup tried and failed to delete
+    failed_deletions: Cell<usize>,
 }
 
 #[allow(dead_code)]
 impl OpenStackClient {
 
-    pub fn new(
-        auth_url: &str,
-        username: &str,
-        password: &str,
-        project_name: &str,
-        cacert_file: Option<&str>,
-        insecure: bool,
-    ) -> Result<Self> {
+    pub fn new(config: &OpenStackConfig) -> Result<Self> {
         println!("Authenticating with OpenStack...");
 
-        let mut client_builder = Client::builder()
-            .timeout(std::time::Duration::from_secs(30));
+        let auth_url = config.auth_url.as_str();
+        let username = config.username.as_str();
+        let password = config.password.as_str();
+        let project_name = config.project_name.as_str();
+        let cacert_file = config.cacert_file.as_deref();
+        let insecure = config.insecure;
+
+        let mut client_builder = http_client_builder();
 
         // Handle certificate validation
         if insecure {
@@ -210,7 +981,7 @@ impl OpenStackClient {
                         user: User {
                             name: username.to_string(),
                             domain: Domain {
-                                name: "Default".to_string(),
+                                name: config.user_domain.clone(),
                             },
                             password: password.to_string(),
                         },
@@ -220,7 +991,7 @@ impl OpenStackClient {
                     project: Project {
                         name: project_name.to_string(),
                         domain: Domain {
-                            name: "Default".to_string(),
+                            name: config.project_domain.clone(),
                         },
                     },
                 },
@@ -235,13 +1006,7 @@ impl OpenStackClient {
             .context("Failed to authenticate with OpenStack")?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().unwrap_or_default();
-            return Err(anyhow::anyhow!(
-                "OpenStack authentication failed ({}): {}",
-                status,
-                body
-            ));
+            return Err(OpenStackError::AuthFailed(api_fault(response)).into());
         }
 
         let auth_token = response
@@ -252,69 +1017,648 @@ impl OpenStackClient {
             .context("Invalid X-Subject-Token header")?
             .to_string();
 
-        let _token_data: TokenResponse = response
+        let token_data: TokenResponse = response
             .json()
             .context("Failed to parse authentication response")?;
 
-        let neutron_endpoint = auth_url.replace(":5000/v3", ":9696/v2.0");
-        let octavia_endpoint = auth_url.replace(":5000/v3", ":9876/v2.0");
+        let project_id = token_data.token.project.map(|p| p.id);
+        let endpoints = resolve_endpoints(
+            &token_data.token.catalog,
+            auth_url,
+            project_id.as_deref(),
+            &config.region,
+            &config.interface,
+        )?;
 
         println!("  -> Authenticated successfully\n");
 
-        Ok(Self {
+        Ok(Self::from_parts(client, auth_token, endpoints, project_name, project_id))
+    }
+
+    /// Client for an already issued token and known endpoints, without talking to Keystone,
+    /// e.g. to run against a mock server
+    pub fn with_endpoints(endpoints: ServiceEndpoints, auth_token: &str, project_id: Option<&str>) -> Result<Self> {
+        let client = http_client_builder().build()?;
+        let project_name = project_id.unwrap_or_default();
+        Ok(Self::from_parts(client, auth_token.to_string(), endpoints, project_name, project_id.map(str::to_string)))
+    }
+
+    /// Client for an already issued token, resolving the endpoints from the body of the
+    /// Keystone token response like `new` does
+    pub fn from_catalog(token_body: &str, auth_token: &str, auth_url: &str, region: &str, interface: &str) -> Result<Self> {
+        let token_data: TokenResponse =
+            serde_json::from_str(token_body).context("Failed to parse authentication response")?;
+        let project_id = token_data.token.project.map(|p| p.id);
+        let endpoints = resolve_endpoints(&token_data.token.catalog, auth_url, project_id.as_deref(), region, interface)?;
+        Self::with_endpoints(endpoints, auth_token, project_id.as_deref())
+    }
+
+    fn from_parts(
+        client: Client,
+        auth_token: String,
+        endpoints: ServiceEndpoints,
+        project_name: &str,
+        project_id: Option<String>,
+    ) -> Self {
+        Self {
             client,
             auth_token,
-            neutron_endpoint,
-            octavia_endpoint,
-        })
+            neutron_endpoint: endpoints.neutron,
+            octavia_endpoint: endpoints.octavia,
+            nova_endpoint: endpoints.nova,
+            glance_endpoint: endpoints.glance,
+            project_name: project_name.to_string(),
+            project_id,
+            cinder_endpoint: endpoints.cinder,
+            swift_endpoint: endpoints.swift,
+            review: None,
+            detach_orphaned_ports: false,
+            what_if: false,
+            plan: RefCell::new(Vec::new()),
+            failed_deletions: Cell::new(0),
+        }
+    }
+
+    /// Resources cleanup failed to delete so far; any means it only partly succeeded
+    pub fn failed_deletions(&self) -> usize {
+        self.failed_deletions.get()
+    }
+
+    /// Project name and ID, for confirmations
+    pub fn project_label(&self) -> String {
+        match self.project_id {
+            Some(ref id) => format!("{} ({})", self.project_name, id),
+            None => self.project_name.clone(),
+        }
+    }
+
+    pub fn with_deletion_review(mut self, review: DeletionReview) -> Self {
+        self.review = Some(review);
+        self
+    }
+
+    pub fn with_orphaned_port_detach(mut self) -> Self {
+        self.detach_orphaned_ports = true;
+        self
+    }
+
+    /// Let the user deselect resources before a batch deletion
+    fn review<'a, T>(&self, what: &str, resources: Vec<&'a T>, item: impl Fn(&T) -> InventoryItem) -> Result<Vec<&'a T>> {
+        let items: Vec<InventoryItem> = resources.iter().map(|r| item(r)).collect();
+        if self.what_if {
+            self.plan.borrow_mut().extend(items);
+            return Ok(Vec::new());
+        }
+        let Some(ref review) = self.review else {
+            return Ok(resources);
+        };
+
+        let approved: HashSet<String> = review(what, &items)?.into_iter().collect();
+        let total = resources.len();
+        let kept: Vec<&T> = resources
+            .into_iter()
+            .zip(&items)
+            .filter(|(_, item)| approved.contains(&item.id))
+            .map(|(resource, _)| resource)
+            .collect();
+
+        if kept.len() < total {
+            println!("  Skipping {} deselected {}", total - kept.len(), what);
+        }
+        Ok(kept)
+    }
+
+    /// Upload a local file to a Swift container
+    pub fn upload_object(&self, container: &str, object: &str, path: &std::path::Path) -> Result<()> {
+        let swift = self
+            .swift_endpoint
+            .as_ref()
+            .context("No object-store endpoint in the OpenStack service catalog")?;
+
+        let file = fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let url = format!("{}/{}/{}", swift, container, object);
+        let response = self
+            .client
+            .put(&url)
+            .header("X-Auth-Token", &self.auth_token)
+            // Dumps can be large; the client default is tuned for API calls
+            .timeout(std::time::Duration::from_secs(30 * 60))
+            .body(reqwest::blocking::Body::from(file))
+            .send()
+            .with_context(|| format!("Failed to upload {} to Swift", object))?;
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Swift upload of {} failed: {}", object, api_fault(response)));
+        }
+
+        Ok(())
+    }
+
+    /// Floating IP quota usage of the project and free addresses on the external network `pool`.
+    /// Fails only when the pool does not exist.
+    pub fn floating_ip_capacity(&self, pool: &str, cluster: &ClusterScope) -> Result<FloatingIpCapacity> {
+        let mut capacity = FloatingIpCapacity::default();
+
+        let url = format!("{}/networks?name={}&router:external=true", self.neutron_endpoint, pool);
+        let networks = self
+            .list::<NetworksResponse>(&url, "external networks")?
+            .context("Could not list external networks")?;
+        let network = networks
+            .networks
+            .first()
+            .with_context(|| format!("Floating IP pool '{}' is not an external network of this cloud", pool))?;
+
+        if let Some(ref project_id) = self.project_id {
+            let url = format!("{}/quotas/{}/details", self.neutron_endpoint, project_id);
+            if let Some(details) = self.list::<QuotaDetailsResponse>(&url, "network quotas")? {
+                let usage = details.quota.floatingip;
+                capacity.quota_limit = Some(usage.limit);
+                capacity.quota_used = Some(usage.used + usage.reserved);
+            }
+        }
+
+        // Restricted to admins by the default Neutron policy, so failures are expected
+        let url = format!("{}/network-ip-availabilities/{}", self.neutron_endpoint, network.id);
+        if let Ok(response) = self.client.get(&url).header("X-Auth-Token", &self.auth_token).send()
+            && response.status().is_success()
+            && let Ok(availability) = response.json::<IpAvailabilityResponse>()
+        {
+            let availability = availability.network_ip_availability;
+            capacity.pool_free = Some(availability.total_ips.saturating_sub(availability.used_ips));
+        }
+
+        let url = format!("{}/floatingips", self.neutron_endpoint);
+        if let Some(fips) = self.list::<FloatingIPsResponse>(&url, "floating IPs")? {
+            capacity.cluster_owned = fips.floatingips.iter().filter(|fip| fip.belongs_to(cluster)).count() as u32;
+        }
+        Ok(capacity)
+    }
+
+    /// Flavors visible to the project, smallest first
+    pub fn flavors(&self) -> Result<Vec<Flavor>> {
+        let url = format!("{}/flavors/detail", self.nova_endpoint);
+        let mut flavors = self
+            .list::<FlavorsResponse>(&url, "flavors")?
+            .context("Could not list flavors")?
+            .flavors;
+        flavors.sort_by(|a, b| (a.vcpus, a.ram, a.disk, &a.name).cmp(&(b.vcpus, b.ram, b.disk, &b.name)));
+        Ok(flavors)
+    }
+
+    /// Active images visible to the project, by name
+    pub fn images(&self) -> Result<Vec<Image>> {
+        let glance = self
+            .glance_endpoint
+            .as_ref()
+            .context("No image endpoint in the OpenStack service catalog")?;
+        let mut images = Vec::new();
+        let mut url = format!("{}/images?status=active", glance);
+        loop {
+            let page = self
+                .list::<ImagesResponse>(&url, "images")?
+                .context("Could not list images")?;
+            images.extend(page.images);
+            match page.next {
+                // An absolute path such as /v2/images?marker=<id>
+                Some(next) => url = reqwest::Url::parse(glance)?.join(&next)?.to_string(),
+                None => break,
+            }
+        }
+        images.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(images)
+    }
+
+    /// ID of the server with this exact name
+    fn server_id(&self, server_name: &str) -> Result<String> {
+        let url = format!("{}/servers/detail", self.nova_endpoint);
+        let servers = self
+            .list::<ServersResponse>(&url, "servers")?
+            .context("Could not list servers")?;
+        servers
+            .servers
+            .into_iter()
+            .find(|s| s.name == server_name)
+            .map(|s| s.id)
+            .with_context(|| format!("No server named {}", server_name))
+    }
+
+    /// Nova console log of the server with this exact name, the last `lines` lines or all of it
+    pub fn console_output(&self, server_name: &str, lines: Option<u32>) -> Result<String> {
+        let server_id = self.server_id(server_name)?;
+
+        let action = match lines {
+            Some(length) => serde_json::json!({"os-getConsoleOutput": {"length": length}}),
+            None => serde_json::json!({"os-getConsoleOutput": {}}),
+        };
+        let url = format!("{}/servers/{}/action", self.nova_endpoint, server_id);
+        let response = self
+            .client
+            .post(&url)
+            .header("X-Auth-Token", &self.auth_token)
+            .json(&action)
+            .send()
+            .context("Failed to request console output")?;
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Console output of {} unavailable: {}", server_name, api_fault(response)));
+        }
+
+        let console: ConsoleOutput = response.json().context("Failed to parse console output")?;
+        Ok(console.output)
+    }
+
+    /// Snapshot the root disk of a server with Nova `createImage`, labeled with the cluster and
+    /// node. Nova snapshots volume-backed servers through Cinder. Returns the image ID.
+    pub fn create_snapshot(&self, cluster_name: &str, server_name: &str, snapshot_name: &str) -> Result<String> {
+        let server_id = self.server_id(server_name)?;
+        let action = serde_json::json!({"createImage": {
+            "name": snapshot_name,
+            "metadata": {"im_deploy_cluster": cluster_name, "im_deploy_node": server_name},
+        }});
+        let url = format!("{}/servers/{}/action", self.nova_endpoint, server_id);
+        let response = self
+            .client
+            .post(&url)
+            .header("X-Auth-Token", &self.auth_token)
+            .json(&action)
+            .send()
+            .with_context(|| format!("Failed to request a snapshot of {}", server_name))?;
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Snapshot of {} failed: {}", server_name, api_fault(response)));
+        }
+
+        // Before compute API 2.45 the image is only named in the Location header
+        if let Some(id) = response
+            .headers()
+            .get("Location")
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| location.rsplit('/').next())
+            .filter(|id| !id.is_empty())
+        {
+            return Ok(id.to_string());
+        }
+        let created: CreateImageResponse = response.json().context("Failed to parse createImage response")?;
+        Ok(created.image_id)
+    }
+
+    /// Rebuild a server from an image, e.g. a snapshot of it, keeping its ID, name and ports.
+    /// Returns once Nova reports the server ACTIVE again.
+    pub fn rebuild_server(&self, server_name: &str, image_id: &str, timeout: Duration) -> Result<()> {
+        let server_id = self.server_id(server_name)?;
+        let url = format!("{}/servers/{}", self.nova_endpoint, server_id);
+        let response = self
+            .client
+            .post(format!("{}/action", url))
+            .header("X-Auth-Token", &self.auth_token)
+            .json(&serde_json::json!({"rebuild": {"imageRef": image_id}}))
+            .send()
+            .with_context(|| format!("Failed to request a rebuild of {}", server_name))?;
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Rebuild of {} failed: {}", server_name, api_fault(response)));
+        }
+
+        // Nova reports REBUILD from the moment it accepts the request, so ACTIVE means done
+        let start = Instant::now();
+        loop {
+            thread::sleep(Duration::from_secs(os_constants::LOADBALANCER_POLL_INTERVAL_SECS));
+            let server = self
+                .client
+                .get(&url)
+                .header("X-Auth-Token", &self.auth_token)
+                .send()
+                .ok()
+                .filter(|response| response.status().is_success())
+                .and_then(|response| response.json::<ServerResponse>().ok())
+                .map(|response| response.server);
+            match server.as_ref().map(|s| s.status.as_str()) {
+                Some("ERROR") => return Err(anyhow::anyhow!("{} went into ERROR during the rebuild", server_name)),
+                Some("ACTIVE") => return Ok(()),
+                _ => {}
+            }
+            if start.elapsed() > timeout {
+                return Err(anyhow::anyhow!(
+                    "{} not ACTIVE {}s after the rebuild started",
+                    server_name,
+                    timeout.as_secs()
+                ));
+            }
+        }
+    }
+
+    /// A snapshot im-deploy took, by image ID
+    pub fn snapshot(&self, image_id: &str) -> Result<Snapshot> {
+        let glance = self
+            .glance_endpoint
+            .as_ref()
+            .context("No image endpoint in the OpenStack service catalog")?;
+        let url = format!("{}/images/{}", glance, image_id);
+        let response = self
+            .client
+            .get(&url)
+            .header("X-Auth-Token", &self.auth_token)
+            .send()
+            .with_context(|| format!("Failed to look up snapshot {}", image_id))?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Snapshot {} unavailable: {}", image_id, api_fault(response)));
+        }
+        response.json().context("Failed to parse image response")
+    }
+
+    /// Snapshots im-deploy took of the cluster's nodes, oldest first
+    pub fn snapshots(&self, cluster_name: &str) -> Result<Vec<Snapshot>> {
+        let glance = self
+            .glance_endpoint
+            .as_ref()
+            .context("No image endpoint in the OpenStack service catalog")?;
+        let mut snapshots = Vec::new();
+        let mut url = format!("{}/images?im_deploy_cluster={}", glance, cluster_name);
+        loop {
+            let page = self
+                .list::<SnapshotsResponse>(&url, "snapshots")?
+                .context("Could not list snapshots")?;
+            // Clouds that do not filter on custom properties return every image
+            snapshots.extend(page.images.into_iter().filter(|s| s.cluster.as_deref() == Some(cluster_name)));
+            match page.next {
+                Some(next) => url = reqwest::Url::parse(glance)?.join(&next)?.to_string(),
+                None => break,
+            }
+        }
+        snapshots.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        Ok(snapshots)
+    }
+
+    /// Delete a snapshot image and the Cinder snapshots it references
+    pub fn delete_snapshot(&self, snapshot: &Snapshot) -> Result<()> {
+        let glance = self
+            .glance_endpoint
+            .as_ref()
+            .context("No image endpoint in the OpenStack service catalog")?;
+        let url = format!("{}/images/{}", glance, snapshot.id);
+        let response = self
+            .client
+            .delete(&url)
+            .header("X-Auth-Token", &self.auth_token)
+            .send()
+            .with_context(|| format!("Failed to delete snapshot {}", snapshot.id))?;
+        if !response.status().is_success() && response.status().as_u16() != 404 {
+            return Err(delete_failed("snapshot", &snapshot.id, response).into());
+        }
+
+        for volume_snapshot in snapshot.volume_snapshot_ids() {
+            let cinder = self
+                .cinder_endpoint
+                .as_ref()
+                .context("Token has no project scope, cannot delete volume snapshots")?;
+            let url = format!("{}/snapshots/{}", cinder, volume_snapshot);
+            let response = self
+                .client
+                .delete(&url)
+                .header("X-Auth-Token", &self.auth_token)
+                .send()
+                .with_context(|| format!("Failed to delete volume snapshot {}", volume_snapshot))?;
+            if !response.status().is_success() && response.status().as_u16() != 404 {
+                return Err(delete_failed("volume snapshot", &volume_snapshot, response).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// GET a list endpoint; failures are reported and yield `None` so one broken
+    /// service does not hide the others
+    fn list<T: DeserializeOwned>(&self, url: &str, what: &str) -> Result<Option<T>> {
+        let response = self
+            .client
+            .get(url)
+            .header("X-Auth-Token", &self.auth_token)
+            .send()
+            .with_context(|| format!("Failed to list {}", what))?;
+
+        if !response.status().is_success() {
+            output::warning(&list_failed(what, response).to_string());
+            return Ok(None);
+        }
+
+        Ok(Some(self.parse_own(response, what)?))
+    }
+
+    /// Parse a list response without the entries of other projects, which admin tokens see too
+    fn parse_own<T: DeserializeOwned>(&self, response: Response, what: &str) -> Result<T> {
+        let mut body: serde_json::Value = response
+            .json()
+            .with_context(|| format!("Failed to parse {} response", what))?;
+        if let Some(ref project_id) = self.project_id {
+            let foreign = drop_foreign_entries(&mut body, project_id);
+            if foreign > 0 {
+                output::warning(&format!("Ignoring {} {} of other projects", foreign, what));
+            }
+        }
+        serde_json::from_value(body).with_context(|| format!("Failed to parse {} response", what))
+    }
+
+    /// Read-only listing of the cluster's servers, volumes, load balancers, floating IPs,
+    /// ports and security groups. Ports and floating IPs need the cluster network.
+    pub fn inventory(&self, cluster_name: &str, network_id: Option<&str>) -> Result<Inventory> {
+        let prefix = format!("{}-", cluster_name);
+        let mut items = Vec::new();
+        let mut unchecked = Vec::new();
+        let mut push = |kind, id: &str, name: &str, status: &str, orphan_candidate| {
+            items.push(InventoryItem {
+                kind,
+                id: id.to_string(),
+                name: name.to_string(),
+                status: status.to_string(),
+                orphan_candidate,
+            })
+        };
+
+        println!("Listing servers...");
+        let url = format!("{}/servers/detail", self.nova_endpoint);
+        if let Some(response) = self.list::<ServersResponse>(&url, "servers")? {
+            for server in response.servers.iter().filter(|s| s.name.starts_with(&prefix)) {
+                push(ResourceKind::Server, &server.id, &server.name, &server.status, true);
+            }
+        } else {
+            unchecked.push(ResourceKind::Server);
+        }
+
+        println!("Listing volumes...");
+        if let Some(ref cinder) = self.cinder_endpoint {
+            let url = format!("{}/volumes/detail", cinder);
+            if let Some(response) = self.list::<VolumesResponse>(&url, "volumes")? {
+                for volume in response.volumes.iter().filter(|v| v.name.starts_with(&prefix)) {
+                    push(ResourceKind::Volume, &volume.id, &volume.name, &volume.status, volume.status == "available");
+                }
+            } else {
+                unchecked.push(ResourceKind::Volume);
+            }
+        } else {
+            output::warning("Token has no project scope, skipping volumes");
+            unchecked.push(ResourceKind::Volume);
+        }
+
+        println!("Listing load balancers...");
+        let response = match &self.octavia_endpoint {
+            Some(octavia) => self.list::<LoadBalancersResponse>(&format!("{}/lbaas/loadbalancers", octavia), "load balancers")?,
+            None => {
+                println!("  No load-balancer endpoint in the service catalog, skipping");
+                None
+            }
+        };
+        if let Some(response) = response {
+            for lb in response
+                .loadbalancers
+                .iter()
+                .filter(|lb| Some(lb.vip_network_id.as_str()) == network_id || lb.name.starts_with(&prefix))
+            {
+                push(
+                    ResourceKind::LoadBalancer,
+                    &lb.id,
+                    &lb.name,
+                    &lb.provisioning_status,
+                    lb.provisioning_status == "ERROR",
+                );
+            }
+        } else {
+            unchecked.push(ResourceKind::LoadBalancer);
+        }
+
+        if let Some(net_id) = network_id {
+            println!("Listing ports...");
+            let url = format!("{}/ports?network_id={}", self.neutron_endpoint, net_id);
+            let mut cluster_ports = HashSet::new();
+            if let Some(response) = self.list::<PortsResponse>(&url, "ports")? {
+                for port in response.ports.iter().filter(|p| !p.device_owner.starts_with("network:dhcp")) {
+                    cluster_ports.insert(port.id.clone());
+                    let name = if port.name.is_empty() { &port.device_owner } else { &port.name };
+                    let unbound = port.device_id.is_empty() && port.device_owner.is_empty();
+                    push(ResourceKind::Port, &port.id, name, &port.status, unbound);
+                }
+            } else {
+                unchecked.push(ResourceKind::Port);
+            }
+
+            // Unassociated floating IPs are project-wide; only those tied to the cluster are
+            // listed, the ones the post-destroy cleanup removes without --all-orphans
+            println!("Listing floating IPs...");
+            let url = format!("{}/floatingips", self.neutron_endpoint);
+            let cluster = ClusterScope {
+                name: cluster_name,
+                network_id: Some(net_id),
+            };
+            if let Some(response) = self.list::<FloatingIPsResponse>(&url, "floating IPs")? {
+                for fip in &response.floatingips {
+                    let on_cluster = fip.port_id.as_ref().is_some_and(|p| cluster_ports.contains(p));
+                    if on_cluster || (fip.port_id.is_none() && FloatingIpScope::Cluster.includes(fip, &cluster)) {
+                        push(ResourceKind::FloatingIp, &fip.id, &fip.floating_ip_address, &fip.status, fip.port_id.is_none());
+                    }
+                }
+            } else {
+                unchecked.push(ResourceKind::FloatingIp);
+            }
+        } else {
+            output::warning("network_id unknown, skipping ports and floating IPs");
+            unchecked.extend([ResourceKind::Port, ResourceKind::FloatingIp]);
+        }
+
+        println!("Listing security groups...");
+        let url = format!("{}/security-groups", self.neutron_endpoint);
+        if let Some(response) = self.list::<SecurityGroupsResponse>(&url, "security groups")? {
+            for sg in response
+                .security_groups
+                .iter()
+                .filter(|sg| sg.name.starts_with(&prefix) || sg.name.starts_with("lb-sg-"))
+            {
+                push(ResourceKind::SecurityGroup, &sg.id, &sg.name, "-", sg.name.starts_with(&prefix));
+            }
+        } else {
+            unchecked.push(ResourceKind::SecurityGroup);
+        }
+
+        Ok(Inventory { items, unchecked })
+    }
+
+    /// Run the list calls and filters of the destroy cleanup without deleting anything, returning
+    /// the resources it would delete in order. `fip_scope` is None when the post-destroy cleanup
+    /// is skipped (`--keep-network`).
+    pub fn deletion_plan(mut self, cluster: &ClusterScope, fip_scope: Option<FloatingIpScope>) -> Result<Vec<InventoryItem>> {
+        self.what_if = true;
+
+        if let Some(network_id) = cluster.network_id {
+            self.cleanup_loadbalancers(network_id, cluster)?;
+        }
+        self.cleanup_lb_components(cluster)?;
+        if let Some(network_id) = cluster.network_id {
+            self.cleanup_octavia_ports(network_id, cluster)?;
+        }
+        if let Some(fip_scope) = fip_scope {
+            self.cleanup_floating_ips(cluster, fip_scope)?;
+            self.cleanup_loadbalancer_ports(cluster)?;
+            self.cleanup_security_groups(cluster)?;
+        }
+
+        Ok(self.plan.into_inner())
     }
 
-    pub fn cleanup_before_destroy(&self, network_id: &str, _cluster_name: &str) -> Result<()> {
+    pub fn cleanup_before_destroy(&self, network_id: &str, cluster_name: &str) -> Result<()> {
         println!("\n=== Pre-Destroy Cleanup ===");
         println!("Removing dynamic resources to prevent terraform destroy from blocking...\n");
 
-        self.cleanup_loadbalancers(network_id)?;
+        let cluster = ClusterScope {
+            name: cluster_name,
+            network_id: Some(network_id),
+        };
+        self.cleanup_loadbalancers(network_id, &cluster)?;
+        self.cleanup_lb_components(&cluster)?;
 
         // Manually delete Octavia ports after LB deletion
         // Cascade delete should handle this, but sometimes ports linger
-        self.cleanup_octavia_ports(network_id)?;
+        self.cleanup_octavia_ports(network_id, &cluster)?;
 
         println!("\n=== Pre-destroy cleanup complete ===");
         println!("Terraform destroy can now proceed safely.\n");
         Ok(())
     }
 
-    pub fn cleanup_after_destroy(&self, cluster_name: &str) -> Result<()> {
+    pub fn cleanup_after_destroy(&self, cluster: &ClusterScope, fip_scope: FloatingIpScope) -> Result<()> {
         println!("\n=== Post-Destroy Cleanup ===");
         println!("Cleaning up remaining orphaned resources...\n");
 
-        self.cleanup_floating_ips()?;
-        self.cleanup_loadbalancer_ports()?;
+        self.cleanup_floating_ips(cluster, fip_scope)?;
+        self.cleanup_loadbalancer_ports(cluster)?;
 
         // Security groups must be deleted last, after all resources using them are gone
-        self.cleanup_security_groups(cluster_name)?;
+        self.cleanup_security_groups(cluster)?;
 
         Ok(())
     }
 
-    pub fn cleanup_orphaned_resources(&self, network_id: Option<&str>) -> Result<()> {
+    pub fn cleanup_orphaned_resources(&self, cluster: &ClusterScope, fip_scope: FloatingIpScope) -> Result<()> {
         println!("\n=== Cleanup Orphaned Resources ===\n");
 
-        self.cleanup_floating_ips()?;
-        self.cleanup_loadbalancer_ports()?;
+        self.cleanup_floating_ips(cluster, fip_scope)?;
+        self.cleanup_loadbalancer_ports(cluster)?;
 
-        if let Some(net_id) = network_id {
-            self.cleanup_loadbalancers(net_id)?;
-            self.cleanup_network_ports(net_id)?;
+        if let Some(net_id) = cluster.network_id {
+            self.cleanup_loadbalancers(net_id, cluster)?;
+        }
+        self.cleanup_lb_components(cluster)?;
+        if let Some(net_id) = cluster.network_id {
+            self.cleanup_network_ports(net_id, cluster)?;
         }
 
         Ok(())
     }
 
-    fn cleanup_loadbalancers(&self, network_id: &str) -> Result<()> {
+    fn cleanup_loadbalancers(&self, network_id: &str, cluster: &ClusterScope) -> Result<()> {
         println!("Checking for dynamically created load balancers...");
+        let Some(octavia_endpoint) = &self.octavia_endpoint else {
+            println!("  No load-balancer endpoint in the service catalog, skipping");
+            return Ok(());
+        };
 
-        let url = format!("{}/lbaas/loadbalancers", self.octavia_endpoint);
+        let url = format!("{}/lbaas/loadbalancers", octavia_endpoint);
         let response = self
             .client
             .get(&url)
@@ -323,15 +1667,11 @@ impl OpenStackClient {
             .context("Failed to list load balancers")?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().unwrap_or_default();
-            eprintln!("WARNING: Failed to list load balancers ({}): {}", status, body);
+            output::warning(&list_failed("load balancers", response).to_string());
             return Ok(());
         }
 
-        let lbs_response: LoadBalancersResponse = response
-            .json()
-            .context("Failed to parse load balancers response")?;
+        let lbs_response: LoadBalancersResponse = self.parse_own(response, "load balancers")?;
 
         // Filter load balancers by network_id AND exclude terraform-managed ones
         // K8s creates LBs with names like: kube_service_<namespace>_<service>_<uuid>
@@ -340,124 +1680,346 @@ impl OpenStackClient {
         let network_lbs: Vec<&LoadBalancer> = lbs_response
             .loadbalancers
             .iter()
-            .filter(|lb| {
-                // Must be on the cluster network
-                lb.vip_network_id == network_id
-                // Must be a k8s-created LB (starts with "kube" or "kube_service")
-                && (lb.name.starts_with("kube_service_") || lb.name.starts_with("kube-"))
-                // Explicitly exclude terraform-managed LB (ends with "-lb")
-                && !lb.name.ends_with("-lb")
-            })
+            .filter(|lb| {
+                // Must be on the cluster network
+                lb.vip_network_id == network_id
+                // Must be a k8s-created LB (starts with "kube" or "kube_service")
+                && (lb.name.starts_with("kube_service_") || lb.name.starts_with("kube-"))
+                // Explicitly exclude terraform-managed LB (ends with "-lb")
+                && !lb.name.ends_with("-lb")
+                // Never touch LBs tagged for another cluster sharing the network
+                && cluster.owns(&lb.tags, || true)
+            })
+            .collect();
+
+        if network_lbs.is_empty() {
+            println!("  -> No dynamically created load balancers found on network {}", network_id);
+            println!("     (Terraform-managed load balancers are preserved)");
+            return Ok(());
+        }
+
+        println!("  Found {} dynamically created load balancer(s) to delete:", network_lbs.len());
+        for lb in &network_lbs {
+            println!("    - {} ({}) [status: {}]", lb.name, lb.id, lb.provisioning_status);
+        }
+        let network_lbs = self.review("load balancers", network_lbs, |lb| {
+            candidate(ResourceKind::LoadBalancer, &lb.id, &lb.name, &lb.provisioning_status)
+        })?;
+        if network_lbs.is_empty() {
+            return Ok(());
+        }
+
+        let network_lbs = self.recover_loadbalancers(network_lbs);
+        if network_lbs.is_empty() {
+            return Ok(());
+        }
+
+        // Cascade delete handles the LB children (listeners, pools, members, monitors). Octavia
+        // deletes asynchronously, so all deletions are requested at once and polled together.
+        let (client, auth_token) = (&self.client, &self.auth_token);
+        let requests: Vec<(&LoadBalancer, std::result::Result<(), String>)> = thread::scope(|scope| {
+            let handles: Vec<_> = network_lbs
+                .into_iter()
+                .map(|lb| scope.spawn(move || (lb, request_lb_deletion(client, auth_token, octavia_endpoint, &lb.id))))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+                .collect()
+        });
+
+        let mut failed_count = 0;
+        let mut deleting = Vec::new();
+        for (lb, result) in requests {
+            match result {
+                Ok(()) => {
+                    println!("    Deleting load balancer: {} (cascade)", lb.name);
+                    deleting.push(lb);
+                }
+                Err(e) => {
+                    eprintln!("    ERROR: Failed to delete {}: {}", lb.name, e);
+                    failed_count += 1;
+                }
+            }
+        }
+
+        let mut deleted_count = 0;
+        let mut pending_count = 0;
+        let statuses = self.wait_for_lb_deletions(&deleting);
+        for (lb, status) in deleting.iter().zip(&statuses) {
+            match status.as_str() {
+                LB_DELETED => deleted_count += 1,
+                "ERROR" => {
+                    eprintln!("    ERROR: Load balancer {} went into ERROR while deleting", lb.name);
+                    eprintln!("           Operator intervention required: ask your cloud admin to delete {}", lb.id);
+                    failed_count += 1;
+                }
+                // Still deleting is no failure, Octavia finishes it on its own
+                _ => {
+                    output::warning(&format!(
+                        "Load balancer {} deletion timed out (may still be deleting); wait a few minutes and retry destroy",
+                        lb.name
+                    ));
+                    pending_count += 1;
+                }
+            }
+        }
+
+        println!(
+            "  Load balancers: {} deleted, {} still deleting, {} failed",
+            deleted_count, pending_count, failed_count
+        );
+
+        self.failed_deletions.set(self.failed_deletions.get() + failed_count);
+
+        if failed_count + pending_count > 0 {
+            output::warning(
+                "Some load balancers are not deleted yet; terraform destroy may still block. Wait a few minutes and retry destroy, or delete them from the OpenStack dashboard.",
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Delete listeners, pools, members and health monitors whose load balancer is gone or in
+    /// ERROR, e.g. after a failed cascade delete; they block deleting the network
+    fn cleanup_lb_components(&self, cluster: &ClusterScope) -> Result<()> {
+        println!("\nChecking for orphaned load balancer listeners, pools and health monitors...");
+
+        let Some(endpoint) = &self.octavia_endpoint else {
+            return Ok(());
+        };
+        let url = format!("{}/lbaas/loadbalancers", endpoint);
+        let Some(lbs) = self.list::<LoadBalancersResponse>(&url, "load balancers")? else {
+            return Ok(());
+        };
+        let url = format!("{}/lbaas/listeners", endpoint);
+        let Some(listeners) = self.list::<ListenersResponse>(&url, "listeners")? else {
+            return Ok(());
+        };
+        let url = format!("{}/lbaas/pools", endpoint);
+        let Some(pools) = self.list::<PoolsResponse>(&url, "pools")? else {
+            return Ok(());
+        };
+        let url = format!("{}/lbaas/healthmonitors", endpoint);
+        let Some(monitors) = self.list::<HealthMonitorsResponse>(&url, "health monitors")? else {
+            return Ok(());
+        };
+
+        let lbs: HashMap<&str, &LoadBalancer> = lbs.loadbalancers.iter().map(|lb| (lb.id.as_str(), lb)).collect();
+        let orphaned_pools: Vec<&LbComponent> = pools
+            .pools
+            .iter()
+            .filter(|pool| is_orphaned_component(&pool.loadbalancers, &pool.name, &lbs, cluster))
             .collect();
+        let orphaned_pool_ids: HashSet<&str> = orphaned_pools.iter().map(|pool| pool.id.as_str()).collect();
+        let pool_ids: HashSet<&str> = pools.pools.iter().map(|pool| pool.id.as_str()).collect();
+
+        // Dependents first: monitors and members, then pools, then listeners
+        let mut components: Vec<(InventoryItem, String)> = Vec::new();
+        for monitor in &monitors.healthmonitors {
+            let named = cluster.names_component(&monitor.name);
+            let orphaned = if monitor.pools.is_empty() {
+                named
+            } else {
+                monitor.pools.iter().all(|pool| {
+                    orphaned_pool_ids.contains(pool.id.as_str()) || (!pool_ids.contains(pool.id.as_str()) && named)
+                })
+            };
+            if orphaned {
+                components.push((
+                    candidate(ResourceKind::LbComponent, &monitor.id, &monitor.label("health monitor"), &monitor.provisioning_status),
+                    format!("{}/lbaas/healthmonitors/{}", endpoint, monitor.id),
+                ));
+            }
+        }
+        for pool in &orphaned_pools {
+            for member in &pool.members {
+                components.push((
+                    candidate(ResourceKind::LbComponent, &member.id, &format!("member of {}", pool.label("pool")), "-"),
+                    format!("{}/lbaas/pools/{}/members/{}", endpoint, pool.id, member.id),
+                ));
+            }
+        }
+        for pool in &orphaned_pools {
+            components.push((
+                candidate(ResourceKind::LbComponent, &pool.id, &pool.label("pool"), &pool.provisioning_status),
+                format!("{}/lbaas/pools/{}", endpoint, pool.id),
+            ));
+        }
+        for listener in &listeners.listeners {
+            if is_orphaned_component(&listener.loadbalancers, &listener.name, &lbs, cluster) {
+                components.push((
+                    candidate(ResourceKind::LbComponent, &listener.id, &listener.label("listener"), &listener.provisioning_status),
+                    format!("{}/lbaas/listeners/{}", endpoint, listener.id),
+                ));
+            }
+        }
 
-        if network_lbs.is_empty() {
-            println!("  -> No dynamically created load balancers found on network {}", network_id);
-            println!("     (Terraform-managed load balancers are preserved)");
+        if components.is_empty() {
+            println!("  -> No orphaned load balancer components found");
             return Ok(());
         }
 
-        println!("  Found {} dynamically created load balancer(s) to delete:", network_lbs.len());
-        for lb in &network_lbs {
-            println!("    - {} ({}) [status: {}]", lb.name, lb.id, lb.provisioning_status);
+        println!("  Found {} orphaned load balancer component(s):", components.len());
+        for (item, _) in &components {
+            println!("    - {} ({})", item.name, item.id);
+        }
+        let components = self.review("load balancer components", components.iter().collect(), |(item, _)| item.clone())?;
+        if components.is_empty() {
+            return Ok(());
         }
 
         let mut deleted_count = 0;
         let mut failed_count = 0;
 
-        for lb in network_lbs {
-            println!("    Deleting load balancer: {} ...", lb.name);
-
-            // Always use cascade delete to handle LB children (listeners, pools, members, monitors)
-            let delete_url = format!("{}/lbaas/loadbalancers/{}?cascade=true", self.octavia_endpoint, lb.id);
+        for (item, delete_url) in components {
             match self
                 .client
-                .delete(&delete_url)
+                .delete(delete_url)
                 .header("X-Auth-Token", &self.auth_token)
                 .send()
             {
                 Ok(resp) if resp.status().is_success() || resp.status().as_u16() == 404 => {
-                    // Wait for LB to be deleted (Octavia async deletion)
-                    if self.wait_for_lb_deletion(&lb.id, 120).is_ok() {
-                        println!("    -> Deleted load balancer: {} (cascade)", lb.name);
-                        deleted_count += 1;
-                    } else {
-                        eprintln!("    WARNING: Load balancer {} deletion timed out (may still be deleting)", lb.name);
-                        eprintln!("             Wait a few minutes and retry destroy");
-                        failed_count += 1;
-                    }
+                    println!("    -> Deleted {}", item.name);
+                    deleted_count += 1;
                 }
                 Ok(resp) => {
-                    let status = resp.status();
-                    let body = resp.text().unwrap_or_default();
-                    eprintln!("    ERROR: Failed to delete {}: {} - {}", lb.name, status, body);
+                    eprintln!("    ERROR: {}", delete_failed("load balancer component", &item.name, resp));
                     failed_count += 1;
                 }
                 Err(e) => {
-                    eprintln!("    ERROR: Failed to delete {}: {}", lb.name, e);
+                    eprintln!("    ERROR: Failed to delete {}: {}", item.name, e);
                     failed_count += 1;
                 }
             }
         }
 
-        println!("  Load balancers: {} deleted, {} failed", deleted_count, failed_count);
+        println!("  Load balancer components: {} deleted, {} failed", deleted_count, failed_count);
 
-        if failed_count > 0 {
-            println!("  WARNING: Some load balancers could not be deleted.");
-            println!("           Terraform destroy may still block. You may need to:");
-            println!("           1. Wait a few minutes and retry destroy");
-            println!("           2. Manually delete LBs from OpenStack dashboard");
+        self.failed_deletions.set(self.failed_deletions.get() + failed_count);
+        Ok(())
+    }
+
+    /// Bring load balancers that would block a cascade delete into a deletable state: trigger an
+    /// Octavia failover for those in ERROR and wait for PENDING_* ones to settle. Returns the load
+    /// balancers to delete; those still pending need an operator and are reported and skipped.
+    fn recover_loadbalancers<'a>(&self, lbs: Vec<&'a LoadBalancer>) -> Vec<&'a LoadBalancer> {
+        let Some(octavia_endpoint) = &self.octavia_endpoint else {
+            return lbs;
+        };
+        let mut waiting = Vec::new();
+        for lb in &lbs {
+            match lb_recovery(&lb.provisioning_status) {
+                LbRecovery::None => {}
+                LbRecovery::Failover => {
+                    println!("    Load balancer {} is in ERROR, triggering failover...", lb.name);
+                    let url = format!("{}/lbaas/loadbalancers/{}/failover", octavia_endpoint, lb.id);
+                    match self.client.put(&url).header("X-Auth-Token", &self.auth_token).send() {
+                        Ok(resp) if resp.status().is_success() => waiting.push(*lb),
+                        Ok(resp) => output::warning(&format!(
+                            "Failover of {} rejected: {}; trying to delete it in ERROR state",
+                            lb.name,
+                            api_fault(resp)
+                        )),
+                        Err(e) => output::warning(&format!("Failover of {} failed: {}", lb.name, e)),
+                    }
+                }
+                LbRecovery::Settle => waiting.push(*lb),
+            }
+        }
+        if waiting.is_empty() {
+            return lbs;
         }
 
-        Ok(())
+        println!("    Waiting for {} load balancer(s) to leave PENDING_* states...", waiting.len());
+        let timeout = Duration::from_secs(os_constants::LOADBALANCER_FAILOVER_TIMEOUT_SECS);
+        let statuses = self.wait_for_lbs(&waiting, "PENDING_UPDATE", timeout, lb_settled);
+        let stuck: HashSet<&str> = waiting
+            .iter()
+            .zip(&statuses)
+            .filter(|(_, status)| !lb_settled(status))
+            .map(|(lb, status)| {
+                let since = lb
+                    .updated_at
+                    .as_deref()
+                    .and_then(history::parse_rfc3339)
+                    .map(|updated| format!(" for {}", history::format_age(updated, history::unix_now())))
+                    .unwrap_or_default();
+                eprintln!("    ERROR: Load balancer {} is stuck in {}{}", lb.name, status, since);
+                eprintln!("           Octavia rejects deleting it until the operation finishes. Operator intervention");
+                eprintln!("           required: ask your cloud admin to reset or fail over load balancer {}", lb.id);
+                lb.id.as_str()
+            })
+            .collect();
+
+        lbs.into_iter().filter(|lb| !stuck.contains(lb.id.as_str())).collect()
     }
 
-    fn wait_for_lb_deletion(&self, lb_id: &str, timeout_secs: u64) -> Result<()> {
-        use std::thread;
-        use std::time::{Duration, Instant};
+    /// Poll load balancers until all are gone or in ERROR, printing a status table whenever a
+    /// status changes. Returns the last status of each, `DELETED` once it is gone.
+    fn wait_for_lb_deletions(&self, lbs: &[&LoadBalancer]) -> Vec<String> {
+        let timeout = Duration::from_secs(os_constants::LOADBALANCER_DELETION_TIMEOUT_SECS);
+        self.wait_for_lbs(lbs, "PENDING_DELETE", timeout, lb_deletion_finished)
+    }
 
+    /// Poll load balancers until `finished` holds for each status or `timeout` elapses, printing
+    /// a status table whenever a status changes. Returns the last status of each.
+    fn wait_for_lbs(
+        &self,
+        lbs: &[&LoadBalancer],
+        initial_status: &str,
+        timeout: Duration,
+        finished: fn(&str) -> bool,
+    ) -> Vec<String> {
         let start = Instant::now();
-        let timeout = Duration::from_secs(timeout_secs);
+        let mut statuses = vec![initial_status.to_string(); lbs.len()];
+        let mut printed: Option<Vec<String>> = None;
 
         loop {
-            if start.elapsed() > timeout {
-                return Err(anyhow::anyhow!("Timeout waiting for LB deletion"));
+            for (lb, status) in lbs.iter().zip(statuses.iter_mut()) {
+                if !finished(status)
+                    && let Some(current) = self.lb_status(&lb.id)
+                {
+                    *status = current;
+                }
             }
 
-            let check_url = format!("{}/lbaas/loadbalancers/{}", self.octavia_endpoint, lb_id);
-            match self
-                .client
-                .get(&check_url)
-                .header("X-Auth-Token", &self.auth_token)
-                .send()
-            {
-                Ok(resp) if resp.status().as_u16() == 404 => {
-                    // LB is deleted
-                    return Ok(());
-                }
-                Ok(resp) if resp.status().is_success() => {
-                    // LB still exists, check status
-                    if let Ok(lb_check) = resp.json::<serde_json::Value>() {
-                        if let Some(status) = lb_check.get("loadbalancer")
-                            .and_then(|lb| lb.get("provisioning_status"))
-                            .and_then(|s| s.as_str())
-                        {
-                            if status == "DELETED" || status == "ERROR" {
-                                return Ok(());
-                            }
-                        }
-                    }
-                    // Still deleting, wait and retry
-                    thread::sleep(Duration::from_secs(5));
-                }
-                _ => {
-                    // Error checking status, assume it might be deleted
-                    thread::sleep(Duration::from_secs(5));
-                }
+            if printed.as_ref() != Some(&statuses) {
+                let rows: Vec<(&str, &str)> = lbs
+                    .iter()
+                    .zip(&statuses)
+                    .map(|(lb, status)| (lb.name.as_str(), status.as_str()))
+                    .collect();
+                print!("{}", lb_progress_table(&rows, start.elapsed()));
+                printed = Some(statuses.clone());
             }
+
+            if statuses.iter().all(|s| finished(s)) || start.elapsed() >= timeout {
+                return statuses;
+            }
+            thread::sleep(Duration::from_secs(os_constants::LOADBALANCER_POLL_INTERVAL_SECS));
+        }
+    }
+
+    /// Provisioning status of a load balancer, `DELETED` once it is gone, None if it could not be read
+    fn lb_status(&self, lb_id: &str) -> Option<String> {
+        let url = format!("{}/lbaas/loadbalancers/{}", self.octavia_endpoint.as_ref()?, lb_id);
+        let response = self.client.get(&url).header("X-Auth-Token", &self.auth_token).send().ok()?;
+        if response.status().as_u16() == 404 {
+            return Some(LB_DELETED.to_string());
+        }
+        if !response.status().is_success() {
+            return None;
         }
+        let body: serde_json::Value = response.json().ok()?;
+        body.get("loadbalancer")?
+            .get("provisioning_status")?
+            .as_str()
+            .map(str::to_string)
     }
 
-    fn cleanup_floating_ips(&self) -> Result<()> {
+    fn cleanup_floating_ips(&self, cluster: &ClusterScope, scope: FloatingIpScope) -> Result<()> {
         println!("\nChecking for orphaned floating IPs...");
 
         let url = format!("{}/floatingips", self.neutron_endpoint);
@@ -469,23 +2031,25 @@ impl OpenStackClient {
             .context("Failed to list floating IPs")?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().unwrap_or_default();
-            eprintln!("  WARNING: Failed to list floating IPs ({}): {}", status, body);
+            output::warning(&list_failed("floating IPs", response).to_string());
             return Ok(());
         }
 
-        let fips_response: FloatingIPsResponse = response
-            .json()
-            .context("Failed to parse floating IPs response")?;
+        let fips_response: FloatingIPsResponse = self.parse_own(response, "floating IPs")?;
 
         // Find orphaned floating IPs (status DOWN or not associated with a port)
-        let orphaned_fips: Vec<&FloatingIP> = fips_response
+        let (orphaned_fips, foreign_fips): (Vec<&FloatingIP>, Vec<&FloatingIP>) = fips_response
             .floatingips
             .iter()
-            .filter(|fip| fip.status.to_lowercase() == "down" || fip.port_id.is_none())
-            .collect();
-
+            .filter(|fip| fip.is_orphaned())
+            .partition(|fip| scope.includes(fip, cluster));
+
+        if !foreign_fips.is_empty() {
+            println!(
+                "  Keeping {} orphaned floating IP(s) not tied to the cluster (use --all-orphans to delete them)",
+                foreign_fips.len()
+            );
+        }
         if orphaned_fips.is_empty() {
             println!("  -> No orphaned floating IPs found");
             return Ok(());
@@ -495,6 +2059,12 @@ impl OpenStackClient {
         for fip in &orphaned_fips {
             println!("    - {} ({})", fip.floating_ip_address, fip.id);
         }
+        let orphaned_fips = self.review("floating IPs", orphaned_fips, |fip| {
+            candidate(ResourceKind::FloatingIp, &fip.id, &fip.floating_ip_address, &fip.status)
+        })?;
+        if orphaned_fips.is_empty() {
+            return Ok(());
+        }
 
         let mut deleted_count = 0;
         let mut failed_count = 0;
@@ -512,9 +2082,7 @@ impl OpenStackClient {
                     deleted_count += 1;
                 }
                 Ok(resp) => {
-                    let status = resp.status();
-                    let body = resp.text().unwrap_or_default();
-                    eprintln!("    ERROR: Failed to delete {}: {} - {}", fip.floating_ip_address, status, body);
+                    eprintln!("    ERROR: {}", delete_failed("floating IP", &fip.floating_ip_address, resp));
                     failed_count += 1;
                 }
                 Err(e) => {
@@ -525,10 +2093,12 @@ impl OpenStackClient {
         }
 
         println!("  Floating IPs: {} deleted, {} failed", deleted_count, failed_count);
+
+        self.failed_deletions.set(self.failed_deletions.get() + failed_count);
         Ok(())
     }
 
-    fn cleanup_loadbalancer_ports(&self) -> Result<()> {
+    fn cleanup_loadbalancer_ports(&self, cluster: &ClusterScope) -> Result<()> {
         println!("\nChecking for orphaned load balancer ports...");
 
         let url = format!("{}/ports", self.neutron_endpoint);
@@ -540,23 +2110,22 @@ impl OpenStackClient {
             .context("Failed to list ports")?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().unwrap_or_default();
-            eprintln!("  WARNING: Failed to list ports ({}): {}", status, body);
+            output::warning(&list_failed("ports", response).to_string());
             return Ok(());
         }
 
-        let ports_response: PortsResponse = response
-            .json()
-            .context("Failed to parse ports response")?;
+        let ports_response: PortsResponse = self.parse_own(response, "ports")?;
 
-        // Find Octavia load balancer ports
-        let lb_ports: Vec<&Port> = ports_response
+        // Find Octavia load balancer ports of the cluster; ports of other projects' LBs are listed too
+        let (lb_ports, foreign_ports): (Vec<&Port>, Vec<&Port>) = ports_response
             .ports
             .iter()
             .filter(|p| p.device_owner.starts_with("Octavia") || p.device_owner.starts_with("octavia"))
-            .collect();
+            .partition(|p| cluster.owns(&p.tags, || cluster.is_on_network(Some(&p.network_id))));
 
+        if !foreign_ports.is_empty() {
+            println!("  Keeping {} load balancer port(s) not tied to the cluster", foreign_ports.len());
+        }
         if lb_ports.is_empty() {
             println!("  -> No orphaned load balancer ports found");
             return Ok(());
@@ -566,6 +2135,12 @@ impl OpenStackClient {
         for port in &lb_ports {
             println!("    - {} ({})", port.name, port.id);
         }
+        let lb_ports = self.review("load balancer ports", lb_ports, |port| {
+            candidate(ResourceKind::Port, &port.id, &port.name, &port.status)
+        })?;
+        if lb_ports.is_empty() {
+            return Ok(());
+        }
 
         let mut deleted_count = 0;
         let mut failed_count = 0;
@@ -583,9 +2158,7 @@ impl OpenStackClient {
                     deleted_count += 1;
                 }
                 Ok(resp) => {
-                    let status = resp.status();
-                    let body = resp.text().unwrap_or_default();
-                    eprintln!("    ERROR: Failed to delete {}: {} - {}", port.name, status, body);
+                    eprintln!("    ERROR: {}", delete_failed("port", &port.name, resp));
                     failed_count += 1;
                 }
                 Err(e) => {
@@ -596,10 +2169,12 @@ impl OpenStackClient {
         }
 
         println!("  Load balancer ports: {} deleted, {} failed", deleted_count, failed_count);
+
+        self.failed_deletions.set(self.failed_deletions.get() + failed_count);
         Ok(())
     }
 
-    fn cleanup_network_ports(&self, network_id: &str) -> Result<()> {
+    fn cleanup_network_ports(&self, network_id: &str, cluster: &ClusterScope) -> Result<()> {
         println!("\nChecking for orphaned network ports on {}...", network_id);
 
         let url = format!("{}/ports?network_id={}", self.neutron_endpoint, network_id);
@@ -611,15 +2186,11 @@ impl OpenStackClient {
             .context("Failed to list network ports")?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().unwrap_or_default();
-            eprintln!("  WARNING: Failed to list network ports ({}): {}", status, body);
+            output::warning(&list_failed("network ports", response).to_string());
             return Ok(());
         }
 
-        let ports_response: PortsResponse = response
-            .json()
-            .context("Failed to parse network ports response")?;
+        let ports_response: PortsResponse = self.parse_own(response, "network ports")?;
 
         // Find orphaned ports (not owned by compute, router, or DHCP)
         let orphaned_ports: Vec<&Port> = ports_response
@@ -629,6 +2200,7 @@ impl OpenStackClient {
                 !p.device_owner.starts_with("compute:")
                     && !p.device_owner.starts_with("network:router_")
                     && !p.device_owner.starts_with("network:dhcp")
+                    && cluster.owns(&p.tags, || true)
             })
             .collect();
 
@@ -641,6 +2213,12 @@ impl OpenStackClient {
         for port in &orphaned_ports {
             println!("    - {} ({}) [{}]", port.name, port.id, port.device_owner);
         }
+        let orphaned_ports = self.review("network ports", orphaned_ports, |port| {
+            candidate(ResourceKind::Port, &port.id, &port.name, &port.status)
+        })?;
+        if orphaned_ports.is_empty() {
+            return Ok(());
+        }
 
         let mut deleted_count = 0;
         let mut failed_count = 0;
@@ -658,9 +2236,7 @@ impl OpenStackClient {
                     deleted_count += 1;
                 }
                 Ok(resp) => {
-                    let status = resp.status();
-                    let body = resp.text().unwrap_or_default();
-                    eprintln!("    ERROR: Failed to delete {}: {} - {}", port.name, status, body);
+                    eprintln!("    ERROR: {}", delete_failed("port", &port.name, resp));
                     failed_count += 1;
                 }
                 Err(e) => {
@@ -671,20 +2247,25 @@ impl OpenStackClient {
         }
 
         println!("  Network ports: {} deleted, {} failed", deleted_count, failed_count);
+
+        self.failed_deletions.set(self.failed_deletions.get() + failed_count);
         Ok(())
     }
 
-    fn cleanup_octavia_ports(&self, network_id: &str) -> Result<()> {
-        use std::thread;
-        use std::time::Duration;
-
+    fn cleanup_octavia_ports(&self, network_id: &str, cluster: &ClusterScope) -> Result<()> {
         println!("\nCleaning up Octavia load balancer ports...");
+        // Without Octavia there are no load balancer ports to linger
+        let Some(octavia_endpoint) = &self.octavia_endpoint else {
+            return Ok(());
+        };
 
         // Give Octavia a moment to start port cleanup after LB deletion
-        thread::sleep(Duration::from_secs(5));
+        if !self.what_if {
+            thread::sleep(Duration::from_secs(5));
+        }
 
         // First, get the list of all load balancers to identify terraform-managed ones
-        let lb_url = format!("{}/lbaas/loadbalancers", self.octavia_endpoint);
+        let lb_url = format!("{}/lbaas/loadbalancers", octavia_endpoint);
         let lb_response = self
             .client
             .get(&lb_url)
@@ -693,13 +2274,13 @@ impl OpenStackClient {
             .context("Failed to list load balancers")?;
 
         let mut terraform_lb_ids = std::collections::HashSet::new();
-        if lb_response.status().is_success() {
-            if let Ok(lbs_response) = lb_response.json::<LoadBalancersResponse>() {
-                // Identify terraform-managed LBs (ones that end with "-lb")
-                for lb in lbs_response.loadbalancers.iter() {
-                    if lb.vip_network_id == network_id && lb.name.ends_with("-lb") {
-                        terraform_lb_ids.insert(lb.id.clone());
-                    }
+        if lb_response.status().is_success()
+            && let Ok(lbs_response) = self.parse_own::<LoadBalancersResponse>(lb_response, "load balancers")
+        {
+            // Identify terraform-managed LBs (ones that end with "-lb")
+            for lb in lbs_response.loadbalancers.iter() {
+                if lb.vip_network_id == network_id && lb.name.ends_with("-lb") {
+                    terraform_lb_ids.insert(lb.id.clone());
                 }
             }
         }
@@ -713,15 +2294,11 @@ impl OpenStackClient {
             .context("Failed to list network ports")?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().unwrap_or_default();
-            eprintln!("  WARNING: Failed to list network ports ({}): {}", status, body);
+            output::warning(&list_failed("network ports", response).to_string());
             return Ok(());
         }
 
-        let ports_response: PortsResponse = response
-            .json()
-            .context("Failed to parse network ports response")?;
+        let ports_response: PortsResponse = self.parse_own(response, "network ports")?;
 
         // Find Octavia ports on this network, excluding terraform-managed ones
         // Port names are typically: octavia-lb-{loadbalancer_id}
@@ -730,7 +2307,7 @@ impl OpenStackClient {
             .iter()
             .filter(|p| {
                 let is_octavia = p.device_owner.starts_with("Octavia") || p.device_owner.starts_with("octavia");
-                if !is_octavia {
+                if !is_octavia || !cluster.owns(&p.tags, || true) {
                     return false;
                 }
 
@@ -756,6 +2333,12 @@ impl OpenStackClient {
         for port in &octavia_ports {
             println!("    - {} ({})", port.name, port.id);
         }
+        let octavia_ports = self.review("Octavia ports", octavia_ports, |port| {
+            candidate(ResourceKind::Port, &port.id, &port.name, &port.status)
+        })?;
+        if octavia_ports.is_empty() {
+            return Ok(());
+        }
 
         let mut deleted_count = 0;
         let mut failed_count = 0;
@@ -773,9 +2356,7 @@ impl OpenStackClient {
                     deleted_count += 1;
                 }
                 Ok(resp) => {
-                    let status = resp.status();
-                    let body = resp.text().unwrap_or_default();
-                    eprintln!("    ERROR: Failed to delete {}: {} - {}", port.name, status, body);
+                    eprintln!("    ERROR: {}", delete_failed("port", &port.name, resp));
                     failed_count += 1;
                 }
                 Err(e) => {
@@ -787,15 +2368,18 @@ impl OpenStackClient {
 
         println!("  Octavia ports: {} deleted, {} failed", deleted_count, failed_count);
 
+        self.failed_deletions.set(self.failed_deletions.get() + failed_count);
+
         if failed_count > 0 {
-            eprintln!("  WARNING: Some ports could not be deleted. Terraform destroy may still block.");
-            eprintln!("           Wait a moment and retry, or check OpenStack dashboard.");
+            output::warning(
+                "Some Octavia ports could not be deleted; terraform destroy may still block. Wait a moment and retry, or check the OpenStack dashboard.",
+            );
         }
 
         Ok(())
     }
 
-    fn cleanup_security_groups(&self, cluster_name: &str) -> Result<()> {
+    pub fn cleanup_security_groups(&self, cluster: &ClusterScope) -> Result<()> {
         println!("\nChecking for orphaned security groups...");
 
         let url = format!("{}/security-groups", self.neutron_endpoint);
@@ -807,33 +2391,29 @@ impl OpenStackClient {
             .context("Failed to list security groups")?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().unwrap_or_default();
-            eprintln!("  WARNING: Failed to list security groups ({}): {}", status, body);
+            output::warning(&list_failed("security groups", response).to_string());
             return Ok(());
         }
 
-        let sgs_response: SecurityGroupsResponse = response
-            .json()
-            .context("Failed to parse security groups response")?;
+        let sgs_response: SecurityGroupsResponse = self.parse_own(response, "security groups")?;
 
         // Find security groups to delete
         let orphaned_sgs: Vec<&SecurityGroup> = sgs_response
             .security_groups
             .iter()
             .filter(|sg| {
-                // Match K8s load balancer security groups
-                if sg.name.starts_with("lb-sg-") {
-                    return true;
-                }
-
-                // Also catch any terraform-managed groups that weren't properly deleted
-                if sg.name == format!("{}-server", cluster_name)
-                    || sg.name == format!("{}-agent", cluster_name) {
-                    return true;
-                }
+                cluster.owns(&sg.tags, || {
+                    // K8s load balancer security groups, described as
+                    // "Security Group for <service> Service LoadBalancer in cluster <name>"
+                    if sg.name.starts_with("lb-sg-") {
+                        return cluster.is_named_in(&sg.description);
+                    }
 
-                false
+                    // Also catch any terraform-managed groups that weren't properly deleted
+                    ["server", "agent", "bastion"]
+                        .iter()
+                        .any(|role| sg.name == format!("{}-{}", cluster.name, role))
+                })
             })
             .collect();
 
@@ -846,8 +2426,15 @@ impl OpenStackClient {
         for sg in &orphaned_sgs {
             println!("    - {} ({})", sg.name, sg.id);
         }
+        let orphaned_sgs = self.review("security groups", orphaned_sgs, |sg| {
+            candidate(ResourceKind::SecurityGroup, &sg.id, &sg.name, "-")
+        })?;
+        if orphaned_sgs.is_empty() {
+            return Ok(());
+        }
 
         let mut deleted_count = 0;
+        let mut in_use_count = 0;
         let mut failed_count = 0;
 
         for sg in orphaned_sgs {
@@ -865,15 +2452,21 @@ impl OpenStackClient {
                 }
                 Ok(resp) => {
                     let status = resp.status();
-                    let body = resp.text().unwrap_or_default();
+                    let err = delete_failed("security group", &sg.name, resp);
 
-                    // Security groups might still be in use - this is expected sometimes
+                    // Security groups might still be in use - this is expected sometimes, and
+                    // OpenStack releases them once their ports are gone
                     if status.as_u16() == 409 {
-                        eprintln!("    WARNING: Security group {} still in use (will be cleaned up by OpenStack eventually)", sg.name);
+                        println!("    Security group {} still in use", sg.name);
+                        if self.resolve_security_group_in_use(sg) {
+                            deleted_count += 1;
+                        } else {
+                            in_use_count += 1;
+                        }
                     } else {
-                        eprintln!("    ERROR: Failed to delete {}: {} - {}", sg.name, status, body);
+                        eprintln!("    ERROR: {}", err);
+                        failed_count += 1;
                     }
-                    failed_count += 1;
                 }
                 Err(e) => {
                     eprintln!("    ERROR: Failed to delete {}: {}", sg.name, e);
@@ -882,12 +2475,539 @@ impl OpenStackClient {
             }
         }
 
-        println!("  Security groups: {} deleted, {} failed/skipped", deleted_count, failed_count);
+        println!(
+            "  Security groups: {} deleted, {} still in use, {} failed",
+            deleted_count, in_use_count, failed_count
+        );
 
-        if failed_count > 0 {
-            println!("  Note: Some security groups may still be in use and will be cleaned up automatically by OpenStack");
+        self.failed_deletions.set(self.failed_deletions.get() + failed_count);
+
+        if in_use_count > 0 {
+            println!("  Note: Security groups still in use are cleaned up automatically by OpenStack once released");
+        }
+
+        Ok(())
+    }
+
+    /// ID of the cluster network, for when terraform outputs are unavailable
+    pub fn find_cluster_network(&self, cluster_name: &str) -> Result<Option<String>> {
+        let cluster = ClusterScope {
+            name: cluster_name,
+            network_id: None,
+        };
+        let url = format!("{}/networks", self.neutron_endpoint);
+        let networks = self.list::<NetworksResponse>(&url, "networks")?;
+        Ok(networks.and_then(|n| n.networks.into_iter().find(|n| n.belongs_to(&cluster)).map(|n| n.id)))
+    }
+
+    /// Remove the cluster's routers, subnets and networks, e.g. after the terraform state was
+    /// lost. Router interfaces are detached first; ports still on a network are reported.
+    pub fn cleanup_networks(&self, cluster: &ClusterScope) -> Result<()> {
+        println!("\nChecking for orphaned routers, subnets and networks...");
+
+        let endpoint = &self.neutron_endpoint;
+        let (Some(routers), Some(subnets), Some(networks)) = (
+            self.list::<RoutersResponse>(&format!("{}/routers", endpoint), "routers")?,
+            self.list::<SubnetsResponse>(&format!("{}/subnets", endpoint), "subnets")?,
+            self.list::<NetworksResponse>(&format!("{}/networks", endpoint), "networks")?,
+        ) else {
+            return Ok(());
+        };
+
+        let networks: Vec<&Network> = networks.networks.iter().filter(|n| n.belongs_to(cluster)).collect();
+        let network_ids: HashSet<&str> = networks.iter().map(|n| n.id.as_str()).collect();
+
+        // Deletion order: routers (after detaching their interfaces), subnets, networks
+        let mut resources: Vec<(&str, InventoryItem)> = Vec::new();
+        for router in routers.routers.iter().filter(|r| cluster.owns(&r.tags, || cluster.is_named(&r.name, "router"))) {
+            let name = format!("router {}", router.name);
+            resources.push(("routers", candidate(ResourceKind::Network, &router.id, &name, &router.status)));
+        }
+        for subnet in subnets
+            .subnets
+            .iter()
+            .filter(|s| network_ids.contains(s.network_id.as_str()) || cluster.owns(&s.tags, || cluster.is_named(&s.name, "subnet")))
+        {
+            let name = format!("subnet {}", subnet.name);
+            resources.push(("subnets", candidate(ResourceKind::Network, &subnet.id, &name, "-")));
+        }
+        for network in networks {
+            let name = format!("network {}", network.name);
+            resources.push(("networks", candidate(ResourceKind::Network, &network.id, &name, &network.status)));
+        }
+
+        if resources.is_empty() {
+            println!("  -> No orphaned routers, subnets or networks found");
+            return Ok(());
+        }
+
+        println!("  Found {} orphaned router(s), subnet(s) and network(s):", resources.len());
+        for (_, item) in &resources {
+            println!("    - {} ({})", item.name, item.id);
+        }
+        let resources = self.review("networks", resources.iter().collect(), |(_, item)| item.clone())?;
+        if resources.is_empty() {
+            return Ok(());
+        }
+
+        let mut deleted_count = 0;
+        let mut failed_count = 0;
+
+        for (collection, item) in resources {
+            if *collection == "routers" {
+                self.detach_router_interfaces(item);
+            }
+
+            println!("    Deleting {} ...", item.name);
+            let delete_url = format!("{}/{}/{}", endpoint, collection, item.id);
+            match self
+                .client
+                .delete(&delete_url)
+                .header("X-Auth-Token", &self.auth_token)
+                .send()
+            {
+                Ok(resp) if resp.status().is_success() || resp.status().as_u16() == 404 => {
+                    println!("    -> Deleted {}", item.name);
+                    deleted_count += 1;
+                }
+                Ok(resp) => {
+                    let status = resp.status();
+                    eprintln!("    ERROR: {}", delete_failed(collection.trim_end_matches('s'), &item.name, resp));
+                    if status.as_u16() == 409 && *collection == "networks" {
+                        self.report_ports_on_network(&item.id);
+                    }
+                    failed_count += 1;
+                }
+                Err(e) => {
+                    eprintln!("    ERROR: Failed to delete {}: {}", item.name, e);
+                    failed_count += 1;
+                }
+            }
         }
 
+        println!("  Networks: {} deleted, {} failed", deleted_count, failed_count);
+
+        self.failed_deletions.set(self.failed_deletions.get() + failed_count);
         Ok(())
     }
+
+    /// A router cannot be deleted while subnets are attached to it
+    fn detach_router_interfaces(&self, router: &InventoryItem) {
+        let url = format!("{}/ports?device_id={}", self.neutron_endpoint, router.id);
+        let ports = match self.list::<PortsResponse>(&url, "router ports") {
+            Ok(Some(response)) => response.ports,
+            Ok(None) => return,
+            Err(e) => {
+                output::warning(&format!("Could not list interfaces of {}: {}", router.name, e));
+                return;
+            }
+        };
+
+        for port in ports.iter().filter(|p| p.device_owner.starts_with("network:router_interface")) {
+            let url = format!("{}/routers/{}/remove_router_interface", self.neutron_endpoint, router.id);
+            match self
+                .client
+                .put(&url)
+                .header("X-Auth-Token", &self.auth_token)
+                .json(&serde_json::json!({"port_id": port.id}))
+                .send()
+            {
+                Ok(resp) if resp.status().is_success() || resp.status().as_u16() == 404 => {
+                    println!("    -> Detached interface {} from {}", port.id, router.name);
+                }
+                Ok(resp) => output::warning(&format!("Failed to detach interface {} from {}: {}", port.id, router.name, api_fault(resp))),
+                Err(e) => output::warning(&format!("Failed to detach interface {} from {}: {}", port.id, router.name, e)),
+            }
+        }
+    }
+
+    /// Explain a 409 on network deletion; DHCP ports are removed by Neutron itself
+    fn report_ports_on_network(&self, network_id: &str) {
+        let url = format!("{}/ports?network_id={}", self.neutron_endpoint, network_id);
+        if let Ok(Some(response)) = self.list::<PortsResponse>(&url, "ports on the network") {
+            for port in response.ports.iter().filter(|p| !p.device_owner.starts_with("network:dhcp")) {
+                println!("             Still in use by {}", port.describe());
+            }
+        }
+    }
+
+    /// Print what keeps a security group from being deleted: ports using it and rules of other
+    /// groups referring to it. With `detach_orphaned_ports`, remove it from unbound ports and
+    /// retry the delete when nothing else uses it. Returns whether it was deleted.
+    fn resolve_security_group_in_use(&self, sg: &SecurityGroup) -> bool {
+        let url = format!("{}/ports?security_groups={}", self.neutron_endpoint, sg.id);
+        let ports = match self.list::<PortsResponse>(&url, "ports using the security group") {
+            Ok(Some(response)) => response.ports,
+            Ok(None) => return false,
+            Err(e) => {
+                println!("             Could not look up ports using it: {}", e);
+                return false;
+            }
+        };
+        let url = format!("{}/security-group-rules?remote_group_id={}", self.neutron_endpoint, sg.id);
+        let rules: Vec<SecurityGroupRule> = match self.list::<SecurityGroupRulesResponse>(&url, "security group rules") {
+            Ok(Some(response)) => response
+                .security_group_rules
+                .into_iter()
+                .filter(|rule| rule.security_group_id != sg.id)
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        if ports.is_empty() && rules.is_empty() {
+            println!("             No ports or rules reference it; OpenStack should release it shortly");
+            return false;
+        }
+        for port in &ports {
+            println!("             Used by {}", port.describe());
+        }
+        for rule in &rules {
+            println!(
+                "             Referenced by rule {} ({}) of security group {}",
+                rule.id,
+                rule.summary(),
+                rule.security_group_id
+            );
+        }
+
+        let only_unbound_ports = rules.is_empty() && ports.iter().all(Port::is_unbound);
+        if !only_unbound_ports {
+            return false;
+        }
+        if !self.detach_orphaned_ports {
+            eprintln!("             Only unbound ports use it; rerun with --detach-orphaned-ports to detach it");
+            return false;
+        }
+
+        for port in &ports {
+            let remaining: Vec<&String> = port.security_groups.iter().filter(|id| **id != sg.id).collect();
+            let url = format!("{}/ports/{}", self.neutron_endpoint, port.id);
+            match self
+                .client
+                .put(&url)
+                .header("X-Auth-Token", &self.auth_token)
+                .json(&serde_json::json!({"port": {"security_groups": remaining}}))
+                .send()
+            {
+                Ok(resp) if resp.status().is_success() => println!("    -> Detached {} from {}", sg.name, port.describe()),
+                Ok(resp) => {
+                    eprintln!("    ERROR: Failed to detach {} from {}: {}", sg.name, port.describe(), api_fault(resp));
+                    return false;
+                }
+                Err(e) => {
+                    eprintln!("    ERROR: Failed to detach {} from {}: {}", sg.name, port.describe(), e);
+                    return false;
+                }
+            }
+        }
+
+        let url = format!("{}/security-groups/{}", self.neutron_endpoint, sg.id);
+        match self.client.delete(&url).header("X-Auth-Token", &self.auth_token).send() {
+            Ok(resp) if resp.status().is_success() || resp.status().as_u16() == 404 => {
+                println!("    -> Deleted security group: {}", sg.name);
+                true
+            }
+            Ok(resp) => {
+                eprintln!("    ERROR: {}", delete_failed("security group", &sg.name, resp));
+                false
+            }
+            Err(e) => {
+                eprintln!("    ERROR: Failed to delete {} after detaching: {}", sg.name, e);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fip(description: &str, tags: &[&str], network_id: Option<&str>) -> FloatingIP {
+        FloatingIP {
+            id: "fip".to_string(),
+            floating_ip_address: "203.0.113.7".to_string(),
+            status: "DOWN".to_string(),
+            port_id: None,
+            description: description.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            port_details: network_id.map(|id| FloatingIPPortDetails { network_id: Some(id.to_string()) }),
+        }
+    }
+
+    fn cluster<'a>(name: &'a str, network_id: Option<&'a str>) -> ClusterScope<'a> {
+        ClusterScope { name, network_id }
+    }
+
+    #[test]
+    fn test_floating_ip_belongs_to_cluster() {
+        let ccm = fip(
+            "Floating IP for Kubernetes external service default/traefik from cluster demo",
+            &[],
+            None,
+        );
+        assert!(ccm.belongs_to(&cluster("demo", None)));
+        assert!(!ccm.belongs_to(&cluster("dem", None)));
+        assert!(fip("", &["demo"], None).belongs_to(&cluster("demo", None)));
+        assert!(fip("", &[], Some("net-1")).belongs_to(&cluster("demo", Some("net-1"))));
+        assert!(!fip("other workload", &["demo-2"], Some("net-2")).belongs_to(&cluster("demo", Some("net-1"))));
+    }
+
+    #[test]
+    fn test_cluster_tag_overrides_heuristics() {
+        let demo = cluster("demo", Some("net-1"));
+        assert!(fip("", &["cluster=demo"], None).belongs_to(&demo));
+        // Tagged for another cluster on the same network
+        assert!(!fip("from cluster demo", &["cluster=demo-2"], Some("net-1")).belongs_to(&demo));
+        assert!(demo.owns(&["team=lab".to_string(), "cluster=demo".to_string()], || false));
+        assert!(!demo.owns(&["cluster=other".to_string()], || true));
+        assert!(demo.owns(&["team=lab".to_string()], || true));
+    }
+
+    #[test]
+    fn test_lb_progress_table() {
+        let table = lb_progress_table(
+            &[("kube_service_demo_default_traefik", "PENDING_DELETE"), ("kube_service_demo_x", LB_DELETED)],
+            Duration::from_secs(15),
+        );
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "    [  15s] LOAD BALANCER                      STATUS");
+        assert_eq!(lines[2], "            kube_service_demo_x                DELETED");
+        assert!(lb_deletion_finished("ERROR"));
+        assert!(!lb_deletion_finished("PENDING_DELETE"));
+    }
+
+    #[test]
+    fn test_orphaned_lb_components() {
+        let lb = |id: &str, status: &str, network: &str| LoadBalancer {
+            id: id.to_string(),
+            name: format!("kube_service_demo_{}", id),
+            vip_network_id: network.to_string(),
+            provisioning_status: status.to_string(),
+            tags: Vec::new(),
+            updated_at: None,
+        };
+        let (active, error, foreign) = (lb("lb-1", "ACTIVE", "net-1"), lb("lb-2", "ERROR", "net-1"), lb("lb-3", "ERROR", "net-2"));
+        let lbs: HashMap<&str, &LoadBalancer> = [("lb-1", &active), ("lb-2", &error), ("lb-3", &foreign)].into();
+        let demo = cluster("demo", Some("net-1"));
+        let parent = |id: &str| vec![IdRef { id: id.to_string() }];
+
+        assert!(!is_orphaned_component(&parent("lb-1"), "demo-api-listener", &lbs, &demo));
+        assert!(is_orphaned_component(&parent("lb-2"), "listener_0", &lbs, &demo));
+        // ERROR load balancer of another cluster
+        assert!(!is_orphaned_component(&parent("lb-3"), "listener_0", &lbs, &demo));
+        // Load balancer gone: only components named after the cluster
+        assert!(is_orphaned_component(&parent("lb-gone"), "pool_0_kube_service_demo_web", &lbs, &demo));
+        assert!(!is_orphaned_component(&parent("lb-gone"), "pool_0_kube_service_prod_web", &lbs, &demo));
+        assert!(is_orphaned_component(&parent("lb-gone"), "demo-api-listener", &lbs, &demo));
+        // Names merely containing the cluster's are another cluster's
+        assert!(!is_orphaned_component(&parent("lb-gone"), "pool_0_kube_service_demo2_web", &lbs, &demo));
+        assert!(!is_orphaned_component(&parent("lb-gone"), "predemo-api-listener", &lbs, &demo));
+        assert!(!is_orphaned_component(&[], "listener_0_kube_service_demodb_web", &lbs, &demo));
+    }
+
+    #[test]
+    fn test_lb_recovery() {
+        assert_eq!(lb_recovery("ACTIVE"), LbRecovery::None);
+        assert_eq!(lb_recovery("ERROR"), LbRecovery::Failover);
+        assert_eq!(lb_recovery("PENDING_UPDATE"), LbRecovery::Settle);
+        assert!(lb_settled("ERROR"));
+        assert!(!lb_settled("PENDING_DELETE"));
+    }
+
+    #[test]
+    fn test_security_group_usage() {
+        let rule: SecurityGroupRule = serde_json::from_value(serde_json::json!({
+            "id": "r1", "security_group_id": "sg-2", "direction": "ingress",
+            "protocol": "tcp", "port_range_min": 30000, "port_range_max": 32767
+        }))
+        .unwrap();
+        assert_eq!(rule.summary(), "ingress tcp 30000-32767");
+        let rule: SecurityGroupRule =
+            serde_json::from_value(serde_json::json!({"id": "r2", "security_group_id": "sg-2", "direction": "egress"})).unwrap();
+        assert_eq!(rule.summary(), "egress any");
+
+        let port: Port = serde_json::from_value(serde_json::json!({
+            "id": "p1", "name": "", "network_id": "net", "device_id": "", "device_owner": "", "security_groups": ["sg-1"]
+        }))
+        .unwrap();
+        assert!(port.is_unbound());
+        assert_eq!(port.describe(), "port p1 (unbound)");
+        let port: Port = serde_json::from_value(serde_json::json!({
+            "id": "p2", "name": "demo-server-0", "network_id": "net", "device_id": "vm-1", "device_owner": "compute:nova"
+        }))
+        .unwrap();
+        assert_eq!(port.describe(), "port demo-server-0 of instance vm-1");
+    }
+
+    #[test]
+    fn test_cluster_network_names() {
+        let demo = cluster("demo", None);
+        assert!(demo.is_named("demo-network", "network"));
+        assert!(!demo.is_named("demo-prod-network", "network"));
+        assert!(!demo.is_named("demonetwork", "network"));
+
+        let network = |name: &str, tags: &[&str]| Network {
+            id: "net-1".to_string(),
+            name: name.to_string(),
+            status: "ACTIVE".to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        };
+        assert!(network("demo-network", &[]).belongs_to(&demo));
+        assert!(network("renamed", &["cluster=demo"]).belongs_to(&demo));
+        assert!(!network("demo-network", &["cluster=prod"]).belongs_to(&demo));
+    }
+
+    #[test]
+    fn test_floating_ip_shortfall() {
+        let capacity = FloatingIpCapacity {
+            quota_limit: Some(10),
+            quota_used: Some(9),
+            pool_free: None,
+            cluster_owned: 0,
+        };
+        assert_eq!(capacity.shortfall(1), None);
+        assert!(capacity.shortfall(2).unwrap().contains("quota allows 1 more"));
+        // Already holds its floating IPs from an earlier apply
+        assert_eq!(FloatingIpCapacity { cluster_owned: 2, ..capacity.clone() }.shortfall(2), None);
+
+        let unlimited = FloatingIpCapacity {
+            quota_limit: Some(-1),
+            quota_used: Some(40),
+            pool_free: Some(1),
+            cluster_owned: 0,
+        };
+        assert!(unlimited.shortfall(2).unwrap().contains("external network has 1 free"));
+        assert_eq!(FloatingIpCapacity::default().shortfall(2), None);
+    }
+
+    #[test]
+    fn test_resolve_endpoints_by_region() {
+        let catalog: Vec<CatalogEntry> = serde_json::from_value(serde_json::json!([
+            {"type": "network", "endpoints": [
+                {"url": "https://net.one:9696", "interface": "public", "region": "RegionOne", "region_id": "RegionOne"},
+                {"url": "https://net.two:9696/", "interface": "public", "region": "RegionTwo", "region_id": "RegionTwo"},
+                {"url": "http://net.two.internal:9696", "interface": "internal", "region_id": "RegionTwo"}
+            ]},
+            {"type": "load-balancer", "endpoints": [
+                {"url": "https://lb.two:9876", "interface": "public", "region_id": "RegionTwo"}
+            ]},
+            {"type": "compute", "endpoints": [
+                {"url": "https://nova.two:8774/v2.1", "interface": "public", "region_id": "RegionTwo"}
+            ]},
+            {"type": "image", "endpoints": [
+                {"url": "https://glance.two:9292", "interface": "public", "region_id": "RegionTwo"}
+            ]}
+        ]))
+        .unwrap();
+
+        let endpoints = resolve_endpoints(&catalog, "https://keystone:5000/v3", None, "RegionTwo", "public").unwrap();
+        assert_eq!(endpoints.neutron, "https://net.two:9696/v2.0");
+        assert_eq!(endpoints.octavia.as_deref(), Some("https://lb.two:9876/v2.0"));
+        assert_eq!(endpoints.nova, "https://nova.two:8774/v2.1");
+        assert_eq!(endpoints.glance.as_deref(), Some("https://glance.two:9292/v2"));
+        assert_eq!(endpoints.cinder, None);
+
+        let err = resolve_endpoints(&catalog, "https://keystone:5000/v3", None, "RegionThree", "public").unwrap_err();
+        assert!(err.to_string().contains("available: RegionOne, RegionTwo"));
+        let err = resolve_endpoints(&catalog, "https://keystone:5000/v3", None, "RegionTwo", "internal").unwrap_err();
+        assert!(err.to_string().contains("No compute endpoint with interface 'internal'"));
+
+        // Clouds without Octavia still resolve, their load balancer cleanup is skipped
+        let without_octavia: Vec<CatalogEntry> = catalog.into_iter().filter(|e| e.service_type != "load-balancer").collect();
+        let endpoints = resolve_endpoints(&without_octavia, "https://keystone:5000/v3", None, "RegionTwo", "public").unwrap();
+        assert_eq!(endpoints.octavia, None);
+    }
+
+    #[test]
+    fn test_drop_foreign_entries() {
+        let mut body = serde_json::json!({
+            "ports": [
+                {"id": "own", "project_id": "p1"},
+                {"id": "foreign", "project_id": "p2"},
+                {"id": "legacy", "tenant_id": "p2"},
+                {"id": "unowned"}
+            ],
+            "ports_links": [],
+            "next": "/v2.0/ports?marker=unowned"
+        });
+        assert_eq!(drop_foreign_entries(&mut body, "p1"), 2);
+        let ids: Vec<&str> = body["ports"].as_array().unwrap().iter().map(|p| p["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, ["own", "unowned"]);
+    }
+
+    #[test]
+    fn test_fault_message() {
+        let neutron = r#"{"NeutronError": {"type": "PortInUse", "message": "Unable to complete operation on port p1, it is in use.", "detail": ""}}"#;
+        assert_eq!(
+            fault_message(neutron).as_deref(),
+            Some("Unable to complete operation on port p1, it is in use.")
+        );
+        let octavia = r#"{"faultcode": "Client", "faultstring": "Invalid state PENDING_UPDATE of loadbalancer resource lb1", "debuginfo": null}"#;
+        assert_eq!(
+            fault_message(octavia).as_deref(),
+            Some("Invalid state PENDING_UPDATE of loadbalancer resource lb1")
+        );
+        let nova = r#"{"itemNotFound": {"code": 404, "message": "Instance demo could not be found."}}"#;
+        assert_eq!(fault_message(nova).as_deref(), Some("Instance demo could not be found."));
+        let keystone = r#"{"error": {"code": 401, "title": "Unauthorized", "message": "The request you have made requires authentication."}}"#;
+        assert_eq!(
+            fault_message(keystone).as_deref(),
+            Some("The request you have made requires authentication.")
+        );
+        assert_eq!(fault_message("<html>502 Bad Gateway</html>"), None);
+        assert_eq!(fault_message(r#"{"ports": []}"#), None);
+    }
+
+    #[test]
+    fn test_unknown_flavors() {
+        let flavors: Vec<Flavor> = serde_json::from_value(serde_json::json!([
+            {"id": "1", "name": "m1.small", "vcpus": 1, "ram": 2048, "disk": 20},
+            {"id": "abc", "name": "m1.medium", "vcpus": 2, "ram": 4096, "disk": 40}
+        ]))
+        .unwrap();
+        let configured = BTreeMap::from([
+            ("openstack_agent_flavor".to_string(), "m1.large".to_string()),
+            ("openstack_bastion_flavor".to_string(), "m1.small".to_string()),
+            ("openstack_server_flavor".to_string(), "abc".to_string()),
+        ]);
+        assert_eq!(unknown_flavors(&configured, &flavors), vec![("openstack_agent_flavor", "m1.large")]);
+    }
+
+    #[test]
+    fn test_floating_ip_scope() {
+        let foreign = fip("", &[], None);
+        assert!(foreign.is_orphaned());
+        assert!(FloatingIpScope::AllOrphans.includes(&foreign, &cluster("demo", None)));
+        assert!(!FloatingIpScope::Cluster.includes(&foreign, &cluster("demo", None)));
+    }
+
+    #[test]
+    fn test_snapshots_to_prune_per_node() {
+        let snapshots: Vec<Snapshot> = serde_json::from_value(serde_json::json!([
+            {"id": "s0-old", "status": "active", "created_at": "2025-01-01T00:00:00Z", "im_deploy_node": "demo-server-0"},
+            {"id": "a0", "status": "active", "created_at": "2025-01-02T00:00:00Z", "im_deploy_node": "demo-agent-0"},
+            {"id": "s0-mid", "status": "active", "created_at": "2025-01-03T00:00:00Z", "im_deploy_node": "demo-server-0"},
+            {"id": "s0-new", "status": "queued", "created_at": "2025-01-04T00:00:00Z", "im_deploy_node": "demo-server-0"}
+        ]))
+        .unwrap();
+
+        let ids = |keep| snapshots_to_prune(&snapshots, keep).iter().map(|s| s.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids(1), ["s0-old", "s0-mid"]);
+        assert_eq!(ids(2), ["s0-old"]);
+        assert!(ids(3).is_empty());
+    }
+
+    #[test]
+    fn test_volume_snapshot_ids() {
+        let snapshot: Snapshot = serde_json::from_value(serde_json::json!({
+            "id": "img", "status": "active", "created_at": "2025-01-01T00:00:00Z",
+            "block_device_mapping": "[{\"boot_index\": 0, \"snapshot_id\": \"vs-1\", \"source_type\": \"snapshot\"}]"
+        }))
+        .unwrap();
+        assert_eq!(snapshot.volume_snapshot_ids(), ["vs-1"]);
+
+        let image_backed = Snapshot { block_device_mapping: None, ..snapshot };
+        assert!(image_backed.volume_snapshot_ids().is_empty());
+    }
 }
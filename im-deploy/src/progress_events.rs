@@ -0,0 +1,190 @@
+use crate::history::unix_now;
+use crate::output;
+use serde::Serialize;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+/// Progress of a deploy or monitor run, streamed as newline-delimited JSON by `--events-file`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    PhaseStarted {
+        phase: String,
+    },
+    PhaseCompleted {
+        phase: String,
+        duration_secs: u64,
+    },
+    NodeReady {
+        node: String,
+        /// Unix timestamp (seconds) the node turned Ready
+        ready_at: u64,
+    },
+    /// A failure that ends the run, e.g. a failed install log or a timeout
+    ErrorDetected {
+        phase: Option<String>,
+        message: String,
+    },
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    /// Unix timestamp (seconds) the event was emitted
+    timestamp: u64,
+    #[serde(flatten)]
+    event: &'a ProgressEvent,
+}
+
+/// Destination of progress events: a file they are appended to, or a Unix socket a dashboard
+/// listens on. The default sink drops them.
+#[derive(Clone, Default)]
+pub struct EventSink {
+    stream: Option<Arc<Mutex<Stream>>>,
+}
+
+struct Stream {
+    writer: Box<dyn Write + Send>,
+    /// Started and not completed yet, so errors can be attributed to it
+    phase: Option<String>,
+}
+
+impl fmt::Debug for EventSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventSink").field("enabled", &self.stream.is_some()).finish()
+    }
+}
+
+impl EventSink {
+    /// Connect to the socket at `path`, or append to the file there, creating it if needed
+    pub fn open(path: &Path) -> io::Result<Self> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            if std::fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+                return Ok(Self::from_writer(std::os::unix::net::UnixStream::connect(path)?));
+            }
+        }
+        Ok(Self::from_writer(OpenOptions::new().create(true).append(true).open(path)?))
+    }
+
+    pub fn from_writer(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            stream: Some(Arc::new(Mutex::new(Stream {
+                writer: Box::new(writer),
+                phase: None,
+            }))),
+        }
+    }
+
+    /// Report a failure, attributed to the phase in progress
+    pub fn error(&self, message: &str) {
+        let phase = self.stream.as_ref().and_then(|stream| {
+            let stream = stream.lock().unwrap_or_else(|e| e.into_inner());
+            stream.phase.clone()
+        });
+        self.emit(ProgressEvent::ErrorDetected {
+            phase,
+            message: message.to_string(),
+        });
+    }
+
+    /// Write one event line. A reader that went away must not fail the deploy, so errors are
+    /// only logged.
+    pub fn emit(&self, event: ProgressEvent) {
+        let Some(stream) = &self.stream else {
+            return;
+        };
+        let record = Record {
+            timestamp: unix_now(),
+            event: &event,
+        };
+        let Ok(json) = serde_json::to_string(&record) else {
+            return;
+        };
+        let line = format!("{}\n", output::redact(&json));
+        let mut stream = stream.lock().unwrap_or_else(|e| e.into_inner());
+        match &event {
+            ProgressEvent::PhaseStarted { phase } => stream.phase = Some(phase.clone()),
+            // The API load balancer phase overlaps the nodes phase
+            ProgressEvent::PhaseCompleted { phase, .. } if stream.phase.as_ref() == Some(phase) => stream.phase = None,
+            _ => {}
+        }
+        let writer = &mut stream.writer;
+        if let Err(e) = writer.write_all(line.as_bytes()).and_then(|_| writer.flush()) {
+            debug!("Could not write progress event: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use tempfile::TempDir;
+
+    fn read_events(path: &Path) -> Vec<Value> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_events_appended_as_json_lines() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("events.jsonl");
+        std::fs::write(&path, "{\"event\":\"earlier\"}\n").unwrap();
+
+        let sink = EventSink::open(&path).unwrap();
+        sink.emit(ProgressEvent::PhaseStarted {
+            phase: "nodes".to_string(),
+        });
+        sink.clone().emit(ProgressEvent::NodeReady {
+            node: "k3s-server-0".to_string(),
+            ready_at: 1735787045,
+        });
+        EventSink::default().emit(ProgressEvent::PhaseStarted {
+            phase: "dropped".to_string(),
+        });
+
+        let events = read_events(&path);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[1]["event"], "phase_started");
+        assert_eq!(events[1]["phase"], "nodes");
+        assert!(events[1]["timestamp"].as_u64().unwrap() > 0);
+        assert_eq!(events[2]["event"], "node_ready");
+        assert_eq!(events[2]["ready_at"], 1735787045);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_events_sent_to_unix_socket() {
+        use std::io::{BufRead, BufReader};
+        use std::os::unix::net::UnixListener;
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("events.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let sink = EventSink::open(&path).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        sink.emit(ProgressEvent::PhaseStarted {
+            phase: "gpu".to_string(),
+        });
+        sink.error("GPU Operator installation failed");
+
+        let events: Vec<Value> = BufReader::new(stream)
+            .lines()
+            .take(2)
+            .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+            .collect();
+        assert_eq!(events[1]["event"], "error_detected");
+        assert_eq!(events[1]["phase"], "gpu");
+        assert_eq!(events[1]["message"], "GPU Operator installation failed");
+    }
+}
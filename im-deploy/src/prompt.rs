@@ -0,0 +1,123 @@
+use crate::errors::{ImDeployError, Result};
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use tracing::debug;
+
+#[cfg(test)]
+pub use scripted::ScriptedPrompter;
+
+/// Asks the user to confirm a step. Library code reports what needs a decision and the
+/// command layer asks through this, so `--yes` and tests can answer instead of a terminal.
+pub trait Prompter: fmt::Debug + Send + Sync {
+    /// Ask a yes/no `question`; an empty answer takes `default_yes`
+    fn confirm(&self, question: &str, default_yes: bool) -> Result<bool>;
+
+    /// Whether a person answers, for steps too risky to confirm automatically
+    fn is_interactive(&self) -> bool;
+}
+
+/// Asks on the terminal; fails without one, naming `--yes`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TerminalPrompter;
+
+impl Prompter for TerminalPrompter {
+    fn confirm(&self, question: &str, default_yes: bool) -> Result<bool> {
+        if !self.is_interactive() {
+            return Err(ImDeployError::NotInteractive {
+                action: format!("Prompt '{}'", question),
+                hint: "pass --yes to confirm automatically".to_string(),
+            });
+        }
+
+        let suffix = if default_yes { "(Y/n)" } else { "(y/N)" };
+        print!("{} {}: ", question, suffix);
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+
+        let trimmed = input.trim();
+        if trimmed.is_empty() {
+            return Ok(default_yes);
+        }
+        Ok(trimmed.eq_ignore_ascii_case("y"))
+    }
+
+    fn is_interactive(&self) -> bool {
+        io::stdin().is_terminal() && io::stdout().is_terminal()
+    }
+}
+
+/// Confirms every question, for `--yes`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AutoConfirm;
+
+impl Prompter for AutoConfirm {
+    fn confirm(&self, question: &str, _default_yes: bool) -> Result<bool> {
+        debug!("Confirmed automatically: {}", question);
+        Ok(true)
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod scripted {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    /// Test double answering questions in order from a script. Questions beyond the script fail
+    /// as if there were no terminal.
+    #[derive(Debug, Default)]
+    pub struct ScriptedPrompter {
+        answers: Mutex<VecDeque<bool>>,
+        questions: Mutex<Vec<String>>,
+    }
+
+    impl ScriptedPrompter {
+        pub fn new(answers: &[bool]) -> Self {
+            Self {
+                answers: Mutex::new(answers.iter().copied().collect()),
+                questions: Mutex::new(Vec::new()),
+            }
+        }
+
+        /// Questions asked so far, in order
+        pub fn questions(&self) -> Vec<String> {
+            self.questions.lock().unwrap().clone()
+        }
+    }
+
+    impl Prompter for ScriptedPrompter {
+        fn confirm(&self, question: &str, _default_yes: bool) -> Result<bool> {
+            self.questions.lock().unwrap().push(question.to_string());
+            self.answers.lock().unwrap().pop_front().ok_or_else(|| ImDeployError::NotInteractive {
+                action: format!("Prompt '{}'", question),
+                hint: "no scripted answer left".to_string(),
+            })
+        }
+
+        fn is_interactive(&self) -> bool {
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripted_prompter() {
+        let prompter = ScriptedPrompter::new(&[true, false]);
+
+        assert!(prompter.confirm("First?", false).unwrap());
+        assert!(!prompter.confirm("Second?", true).unwrap());
+        assert!(prompter.confirm("Third?", true).is_err());
+        assert_eq!(prompter.questions(), ["First?", "Second?", "Third?"]);
+        assert!(AutoConfirm.confirm("Anything?", false).unwrap());
+    }
+}
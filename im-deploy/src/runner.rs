@@ -0,0 +1,179 @@
+use std::fmt;
+use std::io;
+use std::process::{Child, Command, ExitStatus, Output};
+
+#[cfg(test)]
+pub use scripted::ScriptedRunner;
+
+/// Runs the external programs im-deploy drives: terraform, ssh, kubectl and tailscale. Commands are
+/// prepared by the caller; the runner decides how, or whether, they are executed.
+pub trait CommandRunner: fmt::Debug + Send + Sync {
+    /// Run to completion with captured stdout and stderr
+    fn output(&self, command: &mut Command) -> io::Result<Output>;
+
+    /// Run to completion with the stdio the command was set up with
+    fn status(&self, command: &mut Command) -> io::Result<ExitStatus>;
+
+    /// Start the command, e.g. to stream its output while it runs
+    fn spawn(&self, command: &mut Command) -> io::Result<Child>;
+}
+
+/// Executes commands on the local system
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn output(&self, command: &mut Command) -> io::Result<Output> {
+        command.output()
+    }
+
+    fn status(&self, command: &mut Command) -> io::Result<ExitStatus> {
+        command.status()
+    }
+
+    fn spawn(&self, command: &mut Command) -> io::Result<Child> {
+        command.spawn()
+    }
+}
+
+/// Command line as shown in logs and matched by `ScriptedRunner`, e.g. "terraform output -json"
+pub fn command_line(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|part| part.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod scripted {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::process::Stdio;
+    use std::sync::Mutex;
+
+    #[derive(Debug)]
+    struct Script {
+        pattern: String,
+        code: i32,
+        stdout: String,
+    }
+
+    /// Test double answering commands from a script instead of running them. The first entry
+    /// whose pattern occurs in the command line answers it; unscripted commands fail as if the
+    /// program did not exist.
+    #[derive(Debug, Default)]
+    pub struct ScriptedRunner {
+        scripts: Vec<Script>,
+        calls: Mutex<Vec<String>>,
+        envs: Mutex<Vec<BTreeMap<String, String>>>,
+    }
+
+    impl ScriptedRunner {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Answer commands containing `pattern` with `stdout` and exit code `code`
+        pub fn on(mut self, pattern: &str, code: i32, stdout: &str) -> Self {
+            self.scripts.push(Script {
+                pattern: pattern.to_string(),
+                code,
+                stdout: stdout.to_string(),
+            });
+            self
+        }
+
+        /// Command lines run so far, in order
+        pub fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+
+        /// Environment variables set on each command run so far, in the order of `calls`
+        pub fn envs(&self) -> Vec<BTreeMap<String, String>> {
+            self.envs.lock().unwrap().clone()
+        }
+
+        fn answer(&self, command: &Command) -> io::Result<&Script> {
+            let line = command_line(command);
+            self.calls.lock().unwrap().push(line.clone());
+            let env = command
+                .get_envs()
+                .filter_map(|(name, value)| Some((name.to_string_lossy().into_owned(), value?.to_string_lossy().into_owned())))
+                .collect();
+            self.envs.lock().unwrap().push(env);
+            self.scripts
+                .iter()
+                .find(|script| line.contains(&script.pattern))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no scripted response for `{}`", line)))
+        }
+    }
+
+    #[cfg(unix)]
+    fn exit_status(code: i32) -> ExitStatus {
+        std::os::unix::process::ExitStatusExt::from_raw(code << 8)
+    }
+
+    #[cfg(windows)]
+    fn exit_status(code: i32) -> ExitStatus {
+        std::os::windows::process::ExitStatusExt::from_raw(code as u32)
+    }
+
+    impl CommandRunner for ScriptedRunner {
+        fn output(&self, command: &mut Command) -> io::Result<Output> {
+            let script = self.answer(command)?;
+            Ok(Output {
+                status: exit_status(script.code),
+                stdout: script.stdout.clone().into_bytes(),
+                stderr: Vec::new(),
+            })
+        }
+
+        fn status(&self, command: &mut Command) -> io::Result<ExitStatus> {
+            let script = self.answer(command)?;
+            print!("{}", script.stdout);
+            Ok(exit_status(script.code))
+        }
+
+        /// Streamed output needs a real child process; one that prints the scripted output is
+        /// started in its place
+        fn spawn(&self, command: &mut Command) -> io::Result<Child> {
+            let script = self.answer(command)?;
+            Command::new("sh")
+                .args(["-c", "printf '%s' \"$1\"; exit \"$2\"", "sh"])
+                .arg(&script.stdout)
+                .arg(script.code.to_string())
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripted_runner() {
+        let runner = ScriptedRunner::new()
+            .on("output -json", 0, "{}")
+            .on("destroy", 1, "");
+
+        let output = runner.output(Command::new("terraform").args(["output", "-json"])).unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"{}");
+
+        let status = runner
+            .status(Command::new("terraform").args(["destroy", "-auto-approve"]).env("TF_VAR_cluster_name", "ci"))
+            .unwrap();
+        assert_eq!(status.code(), Some(1));
+
+        let err = runner.output(&mut Command::new("ssh")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert_eq!(runner.calls(), ["terraform output -json", "terraform destroy -auto-approve", "ssh"]);
+        assert_eq!(runner.envs()[1]["TF_VAR_cluster_name"], "ci");
+        assert!(runner.envs()[0].is_empty());
+    }
+}
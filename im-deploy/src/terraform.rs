@@ -0,0 +1,1055 @@
+use crate::constants::terraform as tf_constants;
+use crate::errors::{Result, TerraformError};
+use crate::output;
+use crate::runner::CommandRunner;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, ExitStatus};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Semantic version reported by `terraform --version` / `tofu --version`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TerraformVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl TerraformVersion {
+    /// Parse "1.11.4", "v1.6.0" or "1.7.0-beta1" (pre-release suffixes are ignored)
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().trim_start_matches('v');
+        let core = s.split(['-', '+']).next()?;
+        let mut parts = core.split('.');
+
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next().map(|p| p.parse()).transpose().ok()?.unwrap_or(0);
+        let patch = parts.next().map(|p| p.parse()).transpose().ok()?.unwrap_or(0);
+
+        Some(Self { major, minor, patch })
+    }
+
+    /// Extract the version from the first line of `--version` output,
+    /// e.g. "Terraform v1.11.4" or "OpenTofu v1.6.2"
+    pub fn from_version_output(output: &str) -> Option<Self> {
+        output
+            .lines()
+            .next()?
+            .split_whitespace()
+            .find(|word| word.starts_with('v'))
+            .and_then(Self::parse)
+    }
+}
+
+impl fmt::Display for TerraformVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Eq,
+    NotEq,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+    Pessimistic,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Requirement {
+    operator: Operator,
+    version: TerraformVersion,
+    /// Number of version segments written, needed for `~>` semantics
+    segments: usize,
+}
+
+impl Requirement {
+    fn matches(&self, v: &TerraformVersion) -> bool {
+        match self.operator {
+            Operator::Eq => *v == self.version,
+            Operator::NotEq => *v != self.version,
+            Operator::Greater => *v > self.version,
+            Operator::GreaterEq => *v >= self.version,
+            Operator::Less => *v < self.version,
+            Operator::LessEq => *v <= self.version,
+            Operator::Pessimistic => {
+                // ~> 1.2 allows >= 1.2, < 2.0; ~> 1.2.3 allows >= 1.2.3, < 1.3.0
+                let upper = if self.segments <= 2 {
+                    TerraformVersion { major: self.version.major + 1, minor: 0, patch: 0 }
+                } else {
+                    TerraformVersion { major: self.version.major, minor: self.version.minor + 1, patch: 0 }
+                };
+                *v >= self.version && *v < upper
+            }
+        }
+    }
+}
+
+/// A terraform-style version constraint such as ">= 1.5, < 2.0" or "~> 1.6"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionConstraint {
+    raw: String,
+    requirements: Vec<Requirement>,
+}
+
+impl VersionConstraint {
+    pub fn parse(raw: &str) -> std::result::Result<Self, String> {
+        let mut requirements = Vec::new();
+
+        for part in raw.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (operator, rest) = [
+                (">=", Operator::GreaterEq),
+                ("<=", Operator::LessEq),
+                ("!=", Operator::NotEq),
+                ("~>", Operator::Pessimistic),
+                (">", Operator::Greater),
+                ("<", Operator::Less),
+                ("=", Operator::Eq),
+            ]
+            .iter()
+            .find_map(|(prefix, op)| part.strip_prefix(prefix).map(|rest| (*op, rest)))
+            .unwrap_or((Operator::Eq, part));
+
+            let rest = rest.trim();
+            let version = TerraformVersion::parse(rest)
+                .ok_or_else(|| format!("invalid version '{}' in constraint '{}'", rest, raw))?;
+            let segments = rest.split(['-', '+']).next().unwrap_or(rest).split('.').count();
+
+            requirements.push(Requirement { operator, version, segments });
+        }
+
+        if requirements.is_empty() {
+            return Err(format!("empty version constraint '{}'", raw));
+        }
+
+        Ok(Self { raw: raw.trim().to_string(), requirements })
+    }
+
+    pub fn matches(&self, version: &TerraformVersion) -> bool {
+        self.requirements.iter().all(|r| r.matches(version))
+    }
+}
+
+impl fmt::Display for VersionConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+/// Run `<bin> --version` and parse the reported version
+pub fn detect_version(runner: &dyn CommandRunner, terraform_bin: &str) -> Result<TerraformVersion> {
+    debug!("Detecting {} version", terraform_bin);
+
+    let output = runner
+        .output(
+            Command::new(terraform_bin)
+                .arg("--version")
+                // Skip the HashiCorp checkpoint call for update notices
+                .env("CHECKPOINT_DISABLE", "1"),
+        )
+        .map_err(|e| TerraformError::VersionDetectionFailed(e.to_string()))?;
+
+    if !output.status.success() {
+        return Err(TerraformError::VersionDetectionFailed(format!(
+            "{} --version exited with code {:?}",
+            terraform_bin,
+            output.status.code()
+        ))
+        .into());
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    TerraformVersion::from_version_output(&stdout).ok_or_else(|| {
+        TerraformError::VersionDetectionFailed(format!(
+            "unrecognized version output: {}",
+            stdout.lines().next().unwrap_or_default()
+        ))
+        .into()
+    })
+}
+
+/// Collect every `required_version` constraint declared in the root module and the modules it
+/// uses: local ones by their `source` path, registry and git ones where `terraform init`
+/// downloaded them to .terraform/modules
+pub fn module_version_constraints(terraform_dir: &Path) -> Vec<VersionConstraint> {
+    let mut constraints = Vec::new();
+    let mut visited = HashSet::new();
+    collect_constraints(terraform_dir, &mut constraints, &mut visited);
+
+    let downloaded = terraform_dir.join(tf_constants::STATE_DIR).join("modules");
+    for entry in fs::read_dir(downloaded).into_iter().flatten().flatten() {
+        if entry.path().is_dir() {
+            collect_constraints(&entry.path(), &mut constraints, &mut visited);
+        }
+    }
+    constraints
+}
+
+/// Read the `.tf` files of the module in `dir`, then the local modules they call
+fn collect_constraints(dir: &Path, constraints: &mut Vec<VersionConstraint>, visited: &mut HashSet<PathBuf>) {
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    if !visited.insert(dir.clone()) {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return;
+    };
+
+    let mut modules = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() || path.extension().is_none_or(|ext| ext != "tf") {
+            continue;
+        }
+
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+
+        for line in content.lines() {
+            if let Some(source) = local_module_source(line) {
+                modules.push(dir.join(source));
+                continue;
+            }
+            let Some(value) = line.trim().strip_prefix("required_version") else {
+                continue;
+            };
+            let value = value.trim().trim_start_matches('=').trim().trim_matches('"');
+            match VersionConstraint::parse(value) {
+                Ok(constraint) if !constraints.contains(&constraint) => constraints.push(constraint),
+                Ok(_) => {}
+                Err(e) => debug!("Ignoring required_version in {:?}: {}", path, e),
+            }
+        }
+    }
+
+    for module in modules {
+        collect_constraints(&module, constraints, visited);
+    }
+}
+
+/// Path of a local module from a `source = "./modules/k3s"` line; provider sources and
+/// remote module addresses are not paths
+fn local_module_source(line: &str) -> Option<&str> {
+    let value = line.trim().strip_prefix("source")?.trim_start().strip_prefix('=')?;
+    let value = value.trim().trim_matches('"');
+    (value.starts_with("./") || value.starts_with("../")).then_some(value)
+}
+
+/// Detect the binary version, warn when the terraform modules require something newer,
+/// and fail when a pinned `terraform_required_version` is not satisfied.
+/// Returns None if the version could not be determined and nothing is pinned.
+pub fn check_version_compatibility(
+    runner: &dyn CommandRunner,
+    terraform_bin: &str,
+    terraform_dir: &Path,
+    required: Option<&VersionConstraint>,
+) -> Result<Option<TerraformVersion>> {
+    let version = match detect_version(runner, terraform_bin) {
+        Ok(version) => version,
+        Err(e) if required.is_none() => {
+            warn!("Could not determine {} version: {}", terraform_bin, e);
+            return Ok(None);
+        }
+        Err(e) => return Err(e),
+    };
+
+    debug!("Using {} {}", terraform_bin, version);
+
+    for constraint in module_version_constraints(terraform_dir) {
+        if !constraint.matches(&version) {
+            warn!(
+                "{} {} does not satisfy the terraform modules' required_version \"{}\"",
+                terraform_bin, version, constraint
+            );
+        }
+    }
+
+    if let Some(required) = required
+        && !required.matches(&version)
+    {
+        return Err(TerraformError::IncompatibleVersion {
+            found: version.to_string(),
+            required: required.to_string(),
+        }
+        .into());
+    }
+
+    Ok(Some(version))
+}
+
+/// Flags for `terraform init`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InitOptions {
+    /// Upgrade providers and modules within their version constraints
+    pub upgrade: bool,
+    /// Ignore the saved backend configuration
+    pub reconfigure: bool,
+    /// Copy existing state into a changed backend
+    pub migrate_state: bool,
+}
+
+impl InitOptions {
+    /// Arguments for `terraform init`, run non-interactively
+    pub fn args(&self) -> Vec<&'static str> {
+        let mut args = vec!["init", "-input=false"];
+        if self.upgrade {
+            args.push("-upgrade");
+        }
+        // terraform rejects both at once; reconfigure wins as the less destructive choice
+        if self.reconfigure {
+            args.push("-reconfigure");
+        } else if self.migrate_state {
+            args.push("-migrate-state");
+        }
+        args
+    }
+}
+
+/// Outcome of a terraform run whose output was captured
+#[derive(Debug)]
+pub struct TerraformRun {
+    pub status: ExitStatus,
+    pub lines: Vec<String>,
+    pub progress: ApplyProgress,
+    /// The run was interrupted because it exceeded its timeout
+    pub timed_out: bool,
+}
+
+/// Forward stdout and stderr of a spawned process line by line into a channel, with
+/// secrets redacted. The channel disconnects once both streams are closed.
+pub fn spawn_output_reader(child: &mut Child) -> Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        let tx = tx.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(|l| l.ok()) {
+                let _ = tx.send(output::redact(&line));
+            }
+        });
+    }
+    if let Some(stderr) = child.stderr.take() {
+        let tx = tx.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(|l| l.ok()) {
+                let _ = tx.send(output::redact(&line));
+            }
+        });
+    }
+    rx
+}
+
+/// Send SIGINT so terraform can stop gracefully and release the state lock
+pub fn interrupt_process(child: &mut Child) {
+    #[cfg(unix)]
+    {
+        let _ = Command::new("kill")
+            .args(["-INT", &child.id().to_string()])
+            .status();
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = child.kill();
+    }
+}
+
+/// Interrupts a process once its deadline passes, and kills it if it ignores the interrupt
+pub struct Watchdog {
+    deadline: Option<Instant>,
+    interrupted_at: Option<Instant>,
+}
+
+impl Watchdog {
+    pub fn new(timeout: Option<Duration>) -> Self {
+        Self {
+            deadline: timeout.map(|t| Instant::now() + t),
+            interrupted_at: None,
+        }
+    }
+
+    pub fn timed_out(&self) -> bool {
+        self.interrupted_at.is_some()
+    }
+
+    /// Call regularly while the process runs
+    pub fn check(&mut self, child: &mut Child) {
+        let Some(deadline) = self.deadline else {
+            return;
+        };
+
+        match self.interrupted_at {
+            None if Instant::now() >= deadline => {
+                warn!("Timeout reached, interrupting terraform");
+                interrupt_process(child);
+                self.interrupted_at = Some(Instant::now());
+            }
+            Some(at) if at.elapsed() >= Duration::from_secs(tf_constants::INTERRUPT_GRACE_SECS) => {
+                warn!("Terraform did not stop after interrupt, killing it");
+                let _ = child.kill();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Resource lifecycle event parsed from human readable apply/destroy output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceEvent {
+    Started(String),
+    Completed(String),
+    Failed(String),
+}
+
+const STARTED_SUFFIXES: [&str; 4] = [": Creating...", ": Destroying...", ": Modifying...", ": Reading..."];
+const COMPLETED_MARKERS: [&str; 4] = [
+    ": Creation complete",
+    ": Destruction complete",
+    ": Modifications complete",
+    ": Read complete",
+];
+
+/// Parse lines such as `module.k3s.openstack_compute_instance_v2.server[0]: Creating...`
+pub fn parse_resource_event(line: &str) -> Option<ResourceEvent> {
+    let line = line.trim_start_matches('│').trim();
+
+    // Error diagnostics name the failing resource on a `with <address>,` line
+    if let Some(address) = line.strip_prefix("with ").and_then(|rest| rest.strip_suffix(','))
+        && !address.contains(' ')
+    {
+        return Some(ResourceEvent::Failed(address.to_string()));
+    }
+
+    // Destroy output prefixes the address with the resource ID, e.g. `x: Destroying... [id=abc]`
+    for suffix in STARTED_SUFFIXES {
+        if let Some(pos) = line.find(suffix) {
+            return Some(ResourceEvent::Started(line[..pos].to_string()));
+        }
+    }
+
+    for marker in COMPLETED_MARKERS {
+        if let Some(pos) = line.find(marker) {
+            return Some(ResourceEvent::Completed(line[..pos].to_string()));
+        }
+    }
+
+    None
+}
+
+/// Resource type of a managed resource address, e.g. `openstack_networking_network_v2` for
+/// `module.openstack_k3s[0].openstack_networking_network_v2.network`. Data sources yield `None`.
+pub fn resource_type(address: &str) -> Option<&str> {
+    // Split on dots outside of index brackets, since for_each keys may contain dots
+    let mut segments = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in address.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            '.' if depth == 0 => {
+                segments.push(&address[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    segments.push(&address[start..]);
+
+    let mut rest = segments.as_slice();
+    while let [first, _, tail @ ..] = rest
+        && (*first == "module" || first.starts_with("module["))
+    {
+        rest = tail;
+    }
+
+    match rest {
+        [kind, _name] if *kind != "data" => Some(kind),
+        _ => None,
+    }
+}
+
+/// Whether a resource address matches a pattern from `preserve_on_destroy`, in which `*`
+/// stands for any text, e.g. `module.*.openstack_objectstorage_container_v1.longhorn_backup[*]`
+pub fn address_matches(pattern: &str, address: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = address.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Where a cloud resource ID shows up in terraform state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateRef {
+    pub address: String,
+    /// `id`, or `vip_port_id` for the port Octavia creates for a load balancer
+    pub attribute: &'static str,
+}
+
+/// Map cloud resource IDs to where state has them, from `terraform show -json` output.
+/// Load balancers also claim their VIP port, which Octavia creates outside of state.
+pub fn state_resource_ids(show: &serde_json::Value) -> BTreeMap<String, StateRef> {
+    let mut ids = BTreeMap::new();
+    if let Some(root) = show.get("values").and_then(|v| v.get("root_module")) {
+        collect_state_ids(root, &mut ids);
+    }
+    ids
+}
+
+fn collect_state_ids(module: &serde_json::Value, ids: &mut BTreeMap<String, StateRef>) {
+    for resource in module.get("resources").and_then(|r| r.as_array()).into_iter().flatten() {
+        if resource.get("mode").and_then(|m| m.as_str()) != Some("managed") {
+            continue;
+        }
+        let (Some(address), Some(values)) = (resource.get("address").and_then(|a| a.as_str()), resource.get("values"))
+        else {
+            continue;
+        };
+        for key in ["id", "vip_port_id"] {
+            if let Some(id) = values.get(key).and_then(|v| v.as_str())
+                && !id.is_empty()
+            {
+                ids.insert(
+                    id.to_string(),
+                    StateRef {
+                        address: address.to_string(),
+                        attribute: key,
+                    },
+                );
+            }
+        }
+    }
+
+    for child in module.get("child_modules").and_then(|c| c.as_array()).into_iter().flatten() {
+        collect_state_ids(child, ids);
+    }
+}
+
+/// Event from the machine-readable UI of `terraform apply -json` / `destroy -json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ApplyEvent {
+    Started {
+        address: String,
+        module: String,
+        action: String,
+    },
+    /// Periodic "Still creating..." heartbeat
+    Progress {
+        address: String,
+        elapsed_secs: u64,
+    },
+    Completed {
+        address: String,
+        module: String,
+        action: String,
+        elapsed_secs: u64,
+    },
+    Errored {
+        address: String,
+        module: String,
+        elapsed_secs: u64,
+    },
+    Diagnostic {
+        severity: String,
+        summary: String,
+        detail: String,
+        address: Option<String>,
+    },
+    ChangeSummary {
+        add: u64,
+        change: u64,
+        remove: u64,
+    },
+}
+
+/// One line of `-json` output: the human readable message and the event it carries, if any
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonLine {
+    pub message: String,
+    pub event: Option<ApplyEvent>,
+}
+
+#[derive(Deserialize)]
+struct RawJsonLine {
+    #[serde(rename = "@message", default)]
+    message: String,
+    #[serde(rename = "type", default)]
+    kind: String,
+    hook: Option<RawHook>,
+    diagnostic: Option<RawDiagnostic>,
+    changes: Option<RawChanges>,
+}
+
+#[derive(Deserialize)]
+struct RawHook {
+    resource: Option<RawResource>,
+    #[serde(default)]
+    action: String,
+    #[serde(default)]
+    elapsed_seconds: u64,
+}
+
+#[derive(Deserialize)]
+struct RawResource {
+    addr: String,
+    #[serde(default)]
+    module: String,
+}
+
+#[derive(Deserialize)]
+struct RawDiagnostic {
+    #[serde(default)]
+    severity: String,
+    #[serde(default)]
+    summary: String,
+    #[serde(default)]
+    detail: String,
+    address: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawChanges {
+    #[serde(default)]
+    add: u64,
+    #[serde(default)]
+    change: u64,
+    #[serde(default)]
+    remove: u64,
+}
+
+/// Parse a line of `-json` output. Returns `None` for plain text, e.g. from `terraform init`
+pub fn parse_json_line(line: &str) -> Option<JsonLine> {
+    if !line.starts_with('{') {
+        return None;
+    }
+    let raw: RawJsonLine = serde_json::from_str(line).ok()?;
+
+    let event = match (raw.kind.as_str(), raw.hook) {
+        ("apply_start", Some(RawHook { resource: Some(r), action, .. })) => Some(ApplyEvent::Started {
+            address: r.addr,
+            module: r.module,
+            action,
+        }),
+        ("apply_progress", Some(RawHook { resource: Some(r), elapsed_seconds, .. })) => Some(ApplyEvent::Progress {
+            address: r.addr,
+            elapsed_secs: elapsed_seconds,
+        }),
+        ("apply_complete", Some(RawHook { resource: Some(r), action, elapsed_seconds })) => Some(ApplyEvent::Completed {
+            address: r.addr,
+            module: r.module,
+            action,
+            elapsed_secs: elapsed_seconds,
+        }),
+        ("apply_errored", Some(RawHook { resource: Some(r), elapsed_seconds, .. })) => Some(ApplyEvent::Errored {
+            address: r.addr,
+            module: r.module,
+            elapsed_secs: elapsed_seconds,
+        }),
+        ("diagnostic", _) => raw.diagnostic.map(|d| ApplyEvent::Diagnostic {
+            severity: d.severity,
+            summary: d.summary,
+            detail: d.detail,
+            address: d.address,
+        }),
+        ("change_summary", _) => raw.changes.map(|c| ApplyEvent::ChangeSummary {
+            add: c.add,
+            change: c.change,
+            remove: c.remove,
+        }),
+        _ => None,
+    };
+
+    Some(JsonLine { message: raw.message, event })
+}
+
+/// Running tally of resources touched by an apply or destroy
+#[derive(Debug, Default)]
+pub struct ApplyProgress {
+    pub in_progress: Vec<String>,
+    pub completed: usize,
+    pub failed: usize,
+    /// Seconds spent on completed resources per module, "" being the root module.
+    /// Only available from `-json` output.
+    pub module_timings: BTreeMap<String, u64>,
+    /// Address and seconds of every completed resource, from `-json` output
+    pub resource_timings: Vec<(String, u64)>,
+}
+
+impl ApplyProgress {
+    /// Record a line of terraform output, either `-json` or human readable, and return
+    /// the text to show for it
+    pub fn record(&mut self, line: &str) -> Vec<String> {
+        let Some(parsed) = parse_json_line(line) else {
+            self.record_text(line);
+            return vec![line.to_string()];
+        };
+
+        let mut display = vec![parsed.message];
+        if let Some(event) = parsed.event {
+            if let ApplyEvent::Diagnostic { detail, .. } = &event {
+                display.extend(detail.lines().map(|l| format!("  {}", l)));
+            }
+            self.record_event(event);
+        }
+        display
+    }
+
+    pub fn record_event(&mut self, event: ApplyEvent) {
+        match event {
+            ApplyEvent::Started { address, .. } => self.start(address),
+            ApplyEvent::Completed {
+                address,
+                module,
+                elapsed_secs,
+                ..
+            } => {
+                self.complete(&address);
+                *self.module_timings.entry(module).or_default() += elapsed_secs;
+                self.resource_timings.push((address, elapsed_secs));
+            }
+            ApplyEvent::Errored { address, .. } => self.fail(&address),
+            _ => {}
+        }
+    }
+
+    fn record_text(&mut self, line: &str) {
+        match parse_resource_event(line) {
+            Some(ResourceEvent::Started(address)) => self.start(address),
+            Some(ResourceEvent::Completed(address)) => self.complete(&address),
+            Some(ResourceEvent::Failed(address)) => self.fail(&address),
+            None => {}
+        }
+    }
+
+    fn start(&mut self, address: String) {
+        if !self.in_progress.contains(&address) {
+            self.in_progress.push(address);
+        }
+    }
+
+    fn complete(&mut self, address: &str) {
+        self.in_progress.retain(|a| a != address);
+        self.completed += 1;
+    }
+
+    fn fail(&mut self, address: &str) {
+        self.in_progress.retain(|a| a != address);
+        self.failed += 1;
+    }
+
+    /// Modules by time spent, slowest first
+    pub fn slowest_modules(&self) -> Vec<(&str, u64)> {
+        let mut modules: Vec<(&str, u64)> = self.module_timings.iter().map(|(m, secs)| (m.as_str(), *secs)).collect();
+        modules.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        modules
+    }
+
+    /// The `limit` resources that took longest, slowest first
+    pub fn slowest_resources(&self, limit: usize) -> Vec<(&str, u64)> {
+        let mut resources: Vec<(&str, u64)> =
+            self.resource_timings.iter().map(|(a, secs)| (a.as_str(), *secs)).collect();
+        resources.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        resources.truncate(limit);
+        resources
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn v(major: u64, minor: u64, patch: u64) -> TerraformVersion {
+        TerraformVersion { major, minor, patch }
+    }
+
+    #[test]
+    fn test_parse_version_output() {
+        assert_eq!(
+            TerraformVersion::from_version_output("Terraform v1.11.4\non linux_amd64\n"),
+            Some(v(1, 11, 4))
+        );
+        assert_eq!(
+            TerraformVersion::from_version_output("OpenTofu v1.6.0-beta2\non linux_amd64"),
+            Some(v(1, 6, 0))
+        );
+        assert_eq!(TerraformVersion::from_version_output("garbage"), None);
+    }
+
+    #[test]
+    fn test_constraint_operators() {
+        let c = VersionConstraint::parse(">= 1.5, < 2.0").unwrap();
+        assert!(c.matches(&v(1, 5, 0)));
+        assert!(c.matches(&v(1, 11, 4)));
+        assert!(!c.matches(&v(1, 4, 9)));
+        assert!(!c.matches(&v(2, 0, 0)));
+
+        let c = VersionConstraint::parse("1.6.2").unwrap();
+        assert!(c.matches(&v(1, 6, 2)));
+        assert!(!c.matches(&v(1, 6, 3)));
+
+        assert!(VersionConstraint::parse("").is_err());
+        assert!(VersionConstraint::parse(">= banana").is_err());
+    }
+
+    #[test]
+    fn test_pessimistic_constraint() {
+        let minor = VersionConstraint::parse("~> 1.6").unwrap();
+        assert!(minor.matches(&v(1, 9, 0)));
+        assert!(!minor.matches(&v(2, 0, 0)));
+
+        let patch = VersionConstraint::parse("~> 1.6.1").unwrap();
+        assert!(patch.matches(&v(1, 6, 5)));
+        assert!(!patch.matches(&v(1, 7, 0)));
+        assert!(!patch.matches(&v(1, 6, 0)));
+    }
+
+    #[test]
+    fn test_module_version_constraints() {
+        let temp_dir = TempDir::new().unwrap();
+        let module_dir = temp_dir.path().join("modules").join("k3s");
+        fs::create_dir_all(&module_dir).unwrap();
+        fs::write(
+            temp_dir.path().join("main.tf"),
+            "terraform {\n  required_version = \">= 1.0\"\n}\n\nmodule \"k3s\" {\n  source = \"./modules/k3s\"\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            module_dir.join("versions.tf"),
+            "terraform {\n  required_version = \">= 1.5\"\n}\n",
+        )
+        .unwrap();
+
+        let constraints = module_version_constraints(temp_dir.path());
+        assert_eq!(constraints.len(), 2);
+        assert!(constraints.iter().any(|c| !c.matches(&v(1, 4, 0))));
+    }
+
+    #[test]
+    fn test_module_version_constraints_follow_module_sources() {
+        let temp_dir = TempDir::new().unwrap();
+        let write = |path: &str, content: &str| {
+            let path = temp_dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write(
+            "main.tf",
+            "module \"k3s\" {\n  source = \"./modules/k3s\"\n}\nmodule \"dns\" {\n  source  = \"example/dns/openstack\"\n}\n",
+        );
+        // Calls back into its caller, which must not loop
+        write("modules/k3s/main.tf", "terraform {\n  required_version = \">= 1.5\"\n}\nmodule \"up\" {\n  source = \"../..\"\n}\n");
+        write(".terraform/modules/dns/versions.tf", "terraform {\n  required_version = \">= 1.6\"\n}\n");
+        // Neither called nor downloaded
+        write("examples/legacy/main.tf", "terraform {\n  required_version = \"< 1.0\"\n}\n");
+        write(".git/old.tf", "terraform {\n  required_version = \"< 1.0\"\n}\n");
+
+        let constraints = module_version_constraints(temp_dir.path());
+        let constraints: Vec<String> = constraints.iter().map(ToString::to_string).collect();
+        assert_eq!(constraints.len(), 2, "{:?}", constraints);
+        assert!(constraints.contains(&">= 1.5".to_string()));
+        assert!(constraints.contains(&">= 1.6".to_string()));
+    }
+
+    #[test]
+    fn test_init_options_args() {
+        assert_eq!(InitOptions::default().args(), vec!["init", "-input=false"]);
+
+        let upgrade = InitOptions { upgrade: true, migrate_state: true, ..Default::default() };
+        assert_eq!(upgrade.args(), vec!["init", "-input=false", "-upgrade", "-migrate-state"]);
+
+        let both = InitOptions { reconfigure: true, migrate_state: true, ..Default::default() };
+        assert_eq!(both.args(), vec!["init", "-input=false", "-reconfigure"]);
+    }
+
+    #[test]
+    fn test_parse_resource_event() {
+        assert_eq!(
+            parse_resource_event("module.k3s.openstack_compute_instance_v2.server[0]: Creating..."),
+            Some(ResourceEvent::Started("module.k3s.openstack_compute_instance_v2.server[0]".to_string()))
+        );
+        assert_eq!(
+            parse_resource_event("openstack_networking_network_v2.net: Destroying... [id=4f1c]"),
+            Some(ResourceEvent::Started("openstack_networking_network_v2.net".to_string()))
+        );
+        assert_eq!(
+            parse_resource_event("openstack_networking_network_v2.net: Creation complete after 7s [id=4f1c]"),
+            Some(ResourceEvent::Completed("openstack_networking_network_v2.net".to_string()))
+        );
+        assert_eq!(
+            parse_resource_event("│   with openstack_lb_loadbalancer_v2.lb,"),
+            Some(ResourceEvent::Failed("openstack_lb_loadbalancer_v2.lb".to_string()))
+        );
+        assert_eq!(parse_resource_event("Plan: 3 to add, 0 to change, 0 to destroy."), None);
+    }
+
+    #[test]
+    fn test_resource_type() {
+        assert_eq!(
+            resource_type("module.openstack_k3s[0].openstack_networking_network_v2.network"),
+            Some("openstack_networking_network_v2")
+        );
+        assert_eq!(
+            resource_type("module.openstack_k3s[0].openstack_networking_floatingip_v2.fip_lb[0]"),
+            Some("openstack_networking_floatingip_v2")
+        );
+        assert_eq!(resource_type(r#"tailscale_tailnet_key.keys["a.b"]"#), Some("tailscale_tailnet_key"));
+        assert_eq!(resource_type("module.a.module.b.null_resource.x"), Some("null_resource"));
+        assert_eq!(resource_type("module.openstack_k3s[0].data.openstack_images_image_v2.ubuntu"), None);
+        assert_eq!(resource_type("garbage"), None);
+    }
+
+    #[test]
+    fn test_address_matches() {
+        let container = "module.openstack_k3s[0].openstack_objectstorage_container_v1.longhorn_backup[0]";
+        assert!(address_matches("module.*.openstack_objectstorage_container_v1.longhorn_backup[*]", container));
+        assert!(address_matches(container, container));
+        assert!(address_matches("*longhorn_backup*", container));
+        assert!(!address_matches("module.*.openstack_objectstorage_container_v1.longhorn_backup", container));
+        assert!(!address_matches("module.*.openstack_blockstorage_volume_v3.*", container));
+        // A `*` matches no text as well, but parts must not overlap
+        assert!(address_matches("a*b", "ab"));
+        assert!(!address_matches("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn test_state_resource_ids() {
+        let show = serde_json::json!({
+            "values": {
+                "root_module": {
+                    "child_modules": [{
+                        "resources": [
+                            {
+                                "address": "module.k3s.openstack_compute_instance_v2.k3s_server[0]",
+                                "mode": "managed",
+                                "values": {"id": "srv-1"}
+                            },
+                            {
+                                "address": "module.k3s.openstack_lb_loadbalancer_v2.k3s_lb[0]",
+                                "mode": "managed",
+                                "values": {"id": "lb-1", "vip_port_id": "port-vip"}
+                            },
+                            {
+                                "address": "module.k3s.data.openstack_images_image_v2.ubuntu",
+                                "mode": "data",
+                                "values": {"id": "img-1"}
+                            }
+                        ]
+                    }]
+                }
+            }
+        });
+
+        let ids = state_resource_ids(&show);
+        assert_eq!(ids.len(), 3);
+        assert_eq!(ids["srv-1"].address, "module.k3s.openstack_compute_instance_v2.k3s_server[0]");
+        assert_eq!(ids["port-vip"].address, "module.k3s.openstack_lb_loadbalancer_v2.k3s_lb[0]");
+        assert_eq!(ids["port-vip"].attribute, "vip_port_id");
+        assert!(!ids.contains_key("img-1"));
+        assert!(state_resource_ids(&serde_json::json!({"format_version": "1.0"})).is_empty());
+    }
+
+    #[test]
+    fn test_apply_progress() {
+        let mut progress = ApplyProgress::default();
+        progress.record("a.one: Creating...");
+        progress.record("a.two: Creating...");
+        progress.record("a.one: Still creating... [10s elapsed]");
+        progress.record("a.one: Creation complete after 12s [id=1]");
+
+        assert_eq!(progress.in_progress, vec!["a.two".to_string()]);
+        assert_eq!(progress.completed, 1);
+        assert_eq!(progress.failed, 0);
+    }
+
+    #[test]
+    fn test_parse_json_line() {
+        let start = r#"{"@level":"info","@message":"module.k3s.openstack_lb_loadbalancer_v2.lb: Creating...","type":"apply_start","hook":{"resource":{"addr":"module.k3s.openstack_lb_loadbalancer_v2.lb","module":"module.k3s","resource_type":"openstack_lb_loadbalancer_v2"},"action":"create"}}"#;
+        let parsed = parse_json_line(start).unwrap();
+        assert_eq!(parsed.message, "module.k3s.openstack_lb_loadbalancer_v2.lb: Creating...");
+        assert_eq!(
+            parsed.event,
+            Some(ApplyEvent::Started {
+                address: "module.k3s.openstack_lb_loadbalancer_v2.lb".to_string(),
+                module: "module.k3s".to_string(),
+                action: "create".to_string(),
+            })
+        );
+
+        let diagnostic = r#"{"@level":"error","@message":"Error: quota exceeded","type":"diagnostic","diagnostic":{"severity":"error","summary":"quota exceeded","detail":"Quota exceeded for instances","address":"module.k3s.openstack_compute_instance_v2.agent[2]"}}"#;
+        assert!(matches!(
+            parse_json_line(diagnostic).unwrap().event,
+            Some(ApplyEvent::Diagnostic { address: Some(_), .. })
+        ));
+
+        let summary = r#"{"@message":"Apply complete! Resources: 3 added, 0 changed, 0 destroyed.","type":"change_summary","changes":{"add":3,"change":0,"remove":0,"operation":"apply"}}"#;
+        assert_eq!(
+            parse_json_line(summary).unwrap().event,
+            Some(ApplyEvent::ChangeSummary { add: 3, change: 0, remove: 0 })
+        );
+
+        // Messages without a typed event still carry their text
+        let version = r#"{"@message":"Terraform 1.11.4","type":"version","terraform":"1.11.4"}"#;
+        assert_eq!(parse_json_line(version).unwrap().event, None);
+
+        assert!(parse_json_line("Initializing provider plugins...").is_none());
+    }
+
+    #[test]
+    fn test_apply_progress_from_json() {
+        let mut progress = ApplyProgress::default();
+        for line in [
+            r#"{"@message":"a.one: Creating...","type":"apply_start","hook":{"resource":{"addr":"a.one","module":""},"action":"create"}}"#,
+            r#"{"@message":"module.k3s.b.two: Creating...","type":"apply_start","hook":{"resource":{"addr":"module.k3s.b.two","module":"module.k3s"},"action":"create"}}"#,
+            r#"{"@message":"module.k3s.b.two: Still creating... [10s elapsed]","type":"apply_progress","hook":{"resource":{"addr":"module.k3s.b.two","module":"module.k3s"},"action":"create","elapsed_seconds":10}}"#,
+            r#"{"@message":"a.one: Creation complete after 4s","type":"apply_complete","hook":{"resource":{"addr":"a.one","module":""},"action":"create","elapsed_seconds":4}}"#,
+            r#"{"@message":"module.k3s.b.two: Creation complete after 95s","type":"apply_complete","hook":{"resource":{"addr":"module.k3s.b.two","module":"module.k3s"},"action":"create","elapsed_seconds":95}}"#,
+        ] {
+            progress.record(line);
+        }
+
+        assert!(progress.in_progress.is_empty());
+        assert_eq!(progress.completed, 2);
+        assert_eq!(progress.slowest_modules(), vec![("module.k3s", 95), ("", 4)]);
+        assert_eq!(progress.slowest_resources(1), vec![("module.k3s.b.two", 95)]);
+
+        let display = progress.record(
+            r#"{"@message":"Error: quota exceeded","type":"diagnostic","diagnostic":{"severity":"error","summary":"quota exceeded","detail":"line one\nline two"}}"#,
+        );
+        assert_eq!(display, vec!["Error: quota exceeded", "  line one", "  line two"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_watchdog_interrupts_after_timeout() {
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        let mut watchdog = Watchdog::new(Some(Duration::ZERO));
+        assert!(!watchdog.timed_out());
+
+        watchdog.check(&mut child);
+        assert!(watchdog.timed_out());
+        assert!(!child.wait().unwrap().success());
+    }
+}
@@ -1,59 +1,278 @@
+use crate::constants::ssh;
 use crate::domain::cluster::{CloudProvider, ServerInfo};
-use crate::errors::Result;
+use crate::domain::events::{EventFilter, KubeEvent};
+use crate::domain::inventory::InventoryItem;
+use crate::domain::log_analysis::{self, LogLevel};
+use crate::domain::resource_usage::{self, format_memory, NodeUsage, PodUsage, SortKey};
+use crate::errors::{ImDeployError, Result};
+use crate::history::{self, NodeStatusSnapshot};
+use crate::output;
 use crossterm::{
     event::{self, Event, KeyCode, KeyEventKind},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Row, Table, Wrap},
 };
-use std::io;
+use crate::terraform::{interrupt_process, spawn_output_reader, ApplyProgress, TerraformRun, Watchdog};
+use std::collections::BTreeSet;
+use std::io::{self, IsTerminal};
+use std::process::Child;
+use std::sync::mpsc::TryRecvError;
+use std::time::{Duration, Instant};
+
+/// Whether both stdin and stdout are attached to a terminal, so TUI widgets and prompts can be used
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+/// Fail with a hint about the flag to pass instead when not attached to a terminal
+pub fn ensure_interactive(action: &str, hint: &str) -> Result<()> {
+    if is_interactive() {
+        return Ok(());
+    }
+    Err(ImDeployError::NotInteractive {
+        action: action.to_string(),
+        hint: hint.to_string(),
+    })
+}
 
 pub struct ServerSelector {
     servers: Vec<ServerInfo>,
+    /// Indices into `servers` matching the search query, servers before agents
+    visible: Vec<usize>,
+    /// Position of the highlighted server within `visible`
+    selected: Option<usize>,
     state: ListState,
+    query: String,
+    searching: bool,
+    /// Whether space toggles servers into `checked`
+    multi_select: bool,
+    /// Indices into `servers` toggled for a batch operation
+    checked: BTreeSet<usize>,
+}
+
+fn role_group(server: &ServerInfo) -> &'static str {
+    if server.is_server() {
+        "Servers"
+    } else if server.is_agent() {
+        "Agents"
+    } else {
+        "Other"
+    }
 }
 
 impl ServerSelector {
-    fn new(servers: Vec<ServerInfo>) -> Self {
-        let mut state = ListState::default();
-        if !servers.is_empty() {
-            state.select(Some(0));
-        }
-        Self { servers, state }
+    fn new(mut servers: Vec<ServerInfo>, multi_select: bool) -> Self {
+        // Stable sort keeps the terraform ordering within each role
+        servers.sort_by_key(|s| (!s.is_server(), !s.is_agent()));
+
+        let mut selector = Self {
+            servers,
+            visible: Vec::new(),
+            selected: None,
+            state: ListState::default(),
+            query: String::new(),
+            searching: false,
+            multi_select,
+            checked: BTreeSet::new(),
+        };
+        selector.apply_filter();
+        selector
+    }
+
+    fn apply_filter(&mut self) {
+        let query = self.query.to_lowercase();
+        self.visible = self
+            .servers
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| {
+                query.is_empty()
+                    || s.name.to_lowercase().contains(&query)
+                    || s.ip.contains(&query)
+                    || s.addresses.iter().any(|address| address.contains(&query))
+                    || s
+                        .tailscale_hostname
+                        .as_ref()
+                        .is_some_and(|h| h.to_lowercase().contains(&query))
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        self.selected = if self.visible.is_empty() { None } else { Some(0) };
+        self.sync_list_state();
+    }
+
+    /// Map the selected server to its row in the rendered list, accounting for group headers
+    fn sync_list_state(&mut self) {
+        let row = self.selected.map(|pos| {
+            let mut headers = 0;
+            let mut last_group = None;
+            for &i in &self.visible[..=pos] {
+                let group = role_group(&self.servers[i]);
+                if last_group != Some(group) {
+                    headers += 1;
+                    last_group = Some(group);
+                }
+            }
+            pos + headers
+        });
+        self.state.select(row);
     }
 
     fn next(&mut self) {
-        if self.servers.is_empty() {
+        if self.visible.is_empty() {
             return;
         }
-        let i = match self.state.selected() {
-            Some(i) => (i + 1) % self.servers.len(),
+        let i = match self.selected {
+            Some(i) => (i + 1) % self.visible.len(),
             None => 0,
         };
-        self.state.select(Some(i));
+        self.selected = Some(i);
+        self.sync_list_state();
     }
 
     fn previous(&mut self) {
-        if self.servers.is_empty() {
+        if self.visible.is_empty() {
             return;
         }
-        let i = match self.state.selected() {
+        let i = match self.selected {
             Some(i) => {
                 if i == 0 {
-                    self.servers.len() - 1
+                    self.visible.len() - 1
                 } else {
                     i - 1
                 }
             }
             None => 0,
         };
-        self.state.select(Some(i));
+        self.selected = Some(i);
+        self.sync_list_state();
+    }
+
+    fn push_query_char(&mut self, c: char) {
+        self.query.push(c);
+        self.apply_filter();
+    }
+
+    fn pop_query_char(&mut self) {
+        self.query.pop();
+        self.apply_filter();
+    }
+
+    fn clear_query(&mut self) {
+        self.query.clear();
+        self.apply_filter();
     }
 
     fn get_selected(&self) -> Option<&ServerInfo> {
-        self.state.selected().map(|i| &self.servers[i])
+        self.selected.map(|pos| &self.servers[self.visible[pos]])
+    }
+
+    fn toggle_selected(&mut self) {
+        if let Some(pos) = self.selected {
+            let index = self.visible[pos];
+            if !self.checked.remove(&index) {
+                self.checked.insert(index);
+            }
+        }
+    }
+
+    /// Check every visible server, or uncheck them all if they are already checked
+    fn toggle_all_visible(&mut self) {
+        if self.visible.iter().all(|i| self.checked.contains(i)) {
+            for i in &self.visible {
+                self.checked.remove(i);
+            }
+        } else {
+            self.checked.extend(self.visible.iter().copied());
+        }
+    }
+
+    /// Checked servers in display order, falling back to the highlighted one
+    fn get_checked(&self) -> Vec<ServerInfo> {
+        if self.checked.is_empty() {
+            return self.get_selected().cloned().into_iter().collect();
+        }
+        self.checked.iter().map(|&i| self.servers[i].clone()).collect()
+    }
+
+    fn list_items(&self) -> Vec<ListItem<'static>> {
+        let mut items = Vec::new();
+        let mut last_group = None;
+
+        for &i in &self.visible {
+            let server = &self.servers[i];
+            let group = role_group(server);
+            if last_group != Some(group) {
+                items.push(ListItem::new(Line::from(Span::styled(
+                    group,
+                    Style::default().fg(Color::Cyan).bold(),
+                ))));
+                last_group = Some(group);
+            }
+            let marker = match (self.multi_select, self.checked.contains(&i)) {
+                (false, _) => "",
+                (true, true) => "[x] ",
+                (true, false) => "[ ] ",
+            };
+            items.push(ListItem::new(format!("  {}{} ({})", marker, server.name, server.ip)));
+        }
+
+        items
+    }
+
+    fn detail_lines(
+        &self,
+        bastion_ip: Option<&str>,
+        node_statuses: Option<&NodeStatusSnapshot>,
+    ) -> Vec<Line<'static>> {
+        let Some(server) = self.get_selected() else {
+            return vec![Line::from("No server matches the search")];
+        };
+
+        let connection = match (&server.tailscale_hostname, bastion_ip) {
+            (Some(hostname), _) => format!("Tailscale ({})", hostname),
+            (None, Some(bastion)) => format!("{}@{} -> {}@{}", ssh::SSH_USER, bastion, server.login_user(), server.ip),
+            (None, None) => "no connection method".to_string(),
+        };
+
+        let k8s_status = node_statuses
+            .and_then(|snapshot| {
+                snapshot
+                    .nodes
+                    .iter()
+                    .find(|(name, _)| server.matches_node_name(name))
+                    .map(|(_, status)| {
+                        format!(
+                            "{} (seen {})",
+                            status,
+                            history::format_age(snapshot.updated_at, history::unix_now())
+                        )
+                    })
+            })
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let addresses = if server.addresses.is_empty() {
+            server.ip.clone()
+        } else {
+            server.addresses.join(", ")
+        };
+
+        let label = |text: &'static str| Span::styled(text, Style::default().fg(Color::Gray));
+        vec![
+            Line::from(vec![label("Name:       "), Span::raw(server.name.clone())]),
+            Line::from(vec![label("IP:         "), Span::raw(addresses)]),
+            Line::from(vec![label("Provider:   "), Span::raw(server.cloud_provider.clone())]),
+            Line::from(vec![
+                label("Tailscale:  "),
+                Span::raw(server.tailscale_hostname.clone().unwrap_or_else(|| "-".to_string())),
+            ]),
+            Line::from(vec![label("Connection: "), Span::raw(connection)]),
+            Line::from(vec![label("K8s status: "), Span::raw(k8s_status)]),
+        ]
     }
 }
 
@@ -104,53 +323,114 @@ impl CloudProviderSelector {
     }
 }
 
-pub fn run_server_selector(servers: Vec<ServerInfo>) -> Result<Option<ServerInfo>> {
+pub fn run_server_selector(
+    servers: Vec<ServerInfo>,
+    bastion_ip: Option<&str>,
+    node_statuses: Option<&NodeStatusSnapshot>,
+) -> Result<Option<ServerInfo>> {
+    let selected = run_server_selector_inner(servers, bastion_ip, node_statuses, false)?;
+    Ok(selected.and_then(|servers| servers.into_iter().next()))
+}
+
+/// Like `run_server_selector`, but space toggles servers for batch operations.
+/// Returns the checked servers, or the highlighted one if none were checked;
+/// an empty Vec means the user quit.
+#[allow(dead_code)]
+pub fn run_server_multi_selector(
+    servers: Vec<ServerInfo>,
+    bastion_ip: Option<&str>,
+    node_statuses: Option<&NodeStatusSnapshot>,
+) -> Result<Vec<ServerInfo>> {
+    Ok(run_server_selector_inner(servers, bastion_ip, node_statuses, true)?.unwrap_or_default())
+}
+
+fn run_server_selector_inner(
+    servers: Vec<ServerInfo>,
+    bastion_ip: Option<&str>,
+    node_statuses: Option<&NodeStatusSnapshot>,
+    multi_select: bool,
+) -> Result<Option<Vec<ServerInfo>>> {
     enable_raw_mode()?;
     crossterm::execute!(io::stdout(), EnterAlternateScreen)?;
 
     let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
-    let mut selector = ServerSelector::new(servers);
+    let mut selector = ServerSelector::new(servers, multi_select);
 
     let result = loop {
         terminal.draw(|frame| {
             let area = frame.area();
+            let [main_area, help_area] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(area);
+            let [list_area, detail_area] =
+                Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(main_area);
 
-            let items: Vec<ListItem> = selector
-                .servers
-                .iter()
-                .map(|server| {
-                    ListItem::new(format!("{} ({})", server.name, server.ip))
-                })
-                .collect();
+            let mut title = if multi_select {
+                format!("Select Servers ({} selected)", selector.checked.len())
+            } else {
+                "Select Server to SSH".to_string()
+            };
+            if selector.searching || !selector.query.is_empty() {
+                title.push_str(&format!(" - /{}", selector.query));
+            }
 
-            let list = List::new(items)
-                .block(
-                    Block::default()
-                        .title("Select Server to SSH")
-                        .borders(Borders::ALL),
-                )
+            let list = List::new(selector.list_items())
+                .block(Block::default().title(title).borders(Borders::ALL))
                 .highlight_style(Style::default().fg(Color::Yellow))
                 .highlight_symbol("> ");
 
-            frame.render_stateful_widget(list, area, &mut selector.state);
+            frame.render_stateful_widget(list, list_area, &mut selector.state);
+
+            let details = Paragraph::new(selector.detail_lines(bastion_ip, node_statuses))
+                .wrap(Wrap { trim: true })
+                .block(Block::default().title("Details").borders(Borders::ALL));
+            frame.render_widget(details, detail_area);
 
-            let help_text = "\nPress ↑/↓ to navigate, Enter to connect, Q to quit";
+            let help_text = if selector.searching {
+                "\nType to filter, Enter to finish, Esc to clear"
+            } else if multi_select {
+                "\nPress ↑/↓ to navigate, Space to toggle, A to toggle all, / to search, Enter to confirm, Q to quit"
+            } else {
+                "\nPress ↑/↓ to navigate, / to search, Enter to connect, Q to quit"
+            };
             let help_paragraph = Paragraph::new(help_text)
                 .block(Block::default().borders(Borders::NONE));
-
-            let help_area = Rect::new(area.x, area.bottom().saturating_sub(2), area.width, 2);
             frame.render_widget(help_paragraph, help_area);
         })?;
 
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press {
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            if selector.searching {
                 match key.code {
-                    KeyCode::Char('q') | KeyCode::Char('Q') => break None,
+                    KeyCode::Esc => {
+                        selector.clear_query();
+                        selector.searching = false;
+                    }
+                    KeyCode::Enter => selector.searching = false,
+                    KeyCode::Backspace => selector.pop_query_char(),
                     KeyCode::Down => selector.next(),
                     KeyCode::Up => selector.previous(),
-                    KeyCode::Enter => break selector.get_selected().cloned(),
+                    KeyCode::Char(c) => selector.push_query_char(c),
                     _ => {}
                 }
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Char('Q') => break None,
+                KeyCode::Char('/') => selector.searching = true,
+                KeyCode::Esc => selector.clear_query(),
+                KeyCode::Down => selector.next(),
+                KeyCode::Up => selector.previous(),
+                KeyCode::Char(' ') if multi_select => selector.toggle_selected(),
+                KeyCode::Char('a') | KeyCode::Char('A') if multi_select => selector.toggle_all_visible(),
+                KeyCode::Enter => {
+                    let chosen = selector.get_checked();
+                    if !chosen.is_empty() {
+                        break Some(chosen);
+                    }
+                }
+                _ => {}
             }
         }
     };
@@ -204,15 +484,320 @@ pub fn run_cloud_provider_selector(providers: Vec<CloudProvider>) -> Result<Opti
             frame.render_widget(help_paragraph, help_area);
         })?;
 
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press {
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Char('Q') => break None,
+                KeyCode::Down => selector.next(),
+                KeyCode::Up => selector.previous(),
+                KeyCode::Enter => break selector.get_selected().cloned(),
+                _ => {}
+            }
+        }
+    };
+
+    disable_raw_mode()?;
+    crossterm::execute!(io::stdout(), LeaveAlternateScreen)?;
+
+    Ok(result)
+}
+
+/// Scrollable pager for long remote logs
+pub struct LogViewer {
+    lines: Vec<String>,
+    /// Index of the first visible line
+    offset: usize,
+    /// Number of lines visible in the last rendered frame
+    page_height: usize,
+    query: String,
+    searching: bool,
+    /// Line indices matching `query`
+    matches: Vec<usize>,
+}
+
+/// Lines tagged as errors or worse; "ERROR" inside a message doesn't count
+fn is_error_line(line: &str) -> bool {
+    log_analysis::parse_level(line).is_some_and(|level| level >= LogLevel::Error)
+}
+
+impl LogViewer {
+    fn new(content: &str) -> Self {
+        let lines: Vec<String> = output::sanitize(content, None).lines().map(|l| l.to_string()).collect();
+        // Open at the first error so the interesting part is on screen immediately
+        let offset = lines.iter().position(|l| is_error_line(l)).unwrap_or(0);
+        Self {
+            lines,
+            offset,
+            page_height: 1,
+            query: String::new(),
+            searching: false,
+            matches: Vec::new(),
+        }
+    }
+
+    fn max_offset(&self) -> usize {
+        self.lines.len().saturating_sub(self.page_height)
+    }
+
+    fn scroll_down(&mut self, amount: usize) {
+        self.offset = (self.offset + amount).min(self.max_offset());
+    }
+
+    fn scroll_up(&mut self, amount: usize) {
+        self.offset = self.offset.saturating_sub(amount);
+    }
+
+    fn top(&mut self) {
+        self.offset = 0;
+    }
+
+    fn bottom(&mut self) {
+        self.offset = self.max_offset();
+    }
+
+    fn update_matches(&mut self) {
+        self.matches = if self.query.is_empty() {
+            Vec::new()
+        } else {
+            let query = self.query.to_lowercase();
+            self.lines
+                .iter()
+                .enumerate()
+                .filter(|(_, l)| l.to_lowercase().contains(&query))
+                .map(|(i, _)| i)
+                .collect()
+        };
+    }
+
+    /// Jump to the next match after the current offset, wrapping around
+    fn next_match(&mut self) {
+        if let Some(&line) = self
+            .matches
+            .iter()
+            .find(|&&i| i > self.offset)
+            .or_else(|| self.matches.first())
+        {
+            self.offset = line.min(self.max_offset());
+        }
+    }
+
+    fn previous_match(&mut self) {
+        if let Some(&line) = self
+            .matches
+            .iter()
+            .rev()
+            .find(|&&i| i < self.offset)
+            .or_else(|| self.matches.last())
+        {
+            self.offset = line.min(self.max_offset());
+        }
+    }
+
+    fn visible_lines(&self) -> Vec<Line<'_>> {
+        self.lines
+            .iter()
+            .enumerate()
+            .skip(self.offset)
+            .take(self.page_height)
+            .map(|(i, line)| {
+                let style = if self.matches.binary_search(&i).is_ok() {
+                    Style::default().fg(Color::Black).bg(Color::Yellow)
+                } else if is_error_line(line) {
+                    Style::default().fg(Color::Red)
+                } else {
+                    Style::default()
+                };
+                Line::from(Span::styled(line.as_str(), style))
+            })
+            .collect()
+    }
+}
+
+/// Show a log in a full-screen pager until the user quits
+pub fn run_log_viewer(title: &str, content: &str) -> Result<()> {
+    enable_raw_mode()?;
+    crossterm::execute!(io::stdout(), EnterAlternateScreen)?;
+
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    let mut viewer = LogViewer::new(content);
+
+    loop {
+        terminal.draw(|frame| {
+            let area = frame.area();
+            let [log_area, help_area] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(area);
+
+            // Account for the block borders
+            viewer.page_height = log_area.height.saturating_sub(2).max(1) as usize;
+            viewer.offset = viewer.offset.min(viewer.max_offset());
+
+            let position = format!(
+                "{} - lines {}-{} of {}",
+                title,
+                (viewer.offset + 1).min(viewer.lines.len()),
+                (viewer.offset + viewer.page_height).min(viewer.lines.len()),
+                viewer.lines.len()
+            );
+
+            let log = Paragraph::new(viewer.visible_lines())
+                .block(Block::default().title(position).borders(Borders::ALL));
+            frame.render_widget(log, log_area);
+
+            let help_text = if viewer.searching {
+                format!("\n/{}", viewer.query)
+            } else if !viewer.query.is_empty() {
+                format!("\n{} matches for '{}' - n/N next/previous, / new search, Q to close", viewer.matches.len(), viewer.query)
+            } else {
+                "\n↑/↓ scroll, PgUp/PgDn page, g/G top/bottom, / search, Q to close".to_string()
+            };
+            let help_paragraph = Paragraph::new(help_text)
+                .block(Block::default().borders(Borders::NONE));
+            frame.render_widget(help_paragraph, help_area);
+        })?;
+
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            if viewer.searching {
                 match key.code {
-                    KeyCode::Char('q') | KeyCode::Char('Q') => break None,
-                    KeyCode::Down => selector.next(),
-                    KeyCode::Up => selector.previous(),
-                    KeyCode::Enter => break selector.get_selected().cloned(),
+                    KeyCode::Esc => {
+                        viewer.query.clear();
+                        viewer.update_matches();
+                        viewer.searching = false;
+                    }
+                    KeyCode::Enter => {
+                        viewer.searching = false;
+                        viewer.next_match();
+                    }
+                    KeyCode::Backspace => {
+                        viewer.query.pop();
+                        viewer.update_matches();
+                    }
+                    KeyCode::Char(c) => {
+                        viewer.query.push(c);
+                        viewer.update_matches();
+                    }
                     _ => {}
                 }
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => break,
+                KeyCode::Down | KeyCode::Char('j') => viewer.scroll_down(1),
+                KeyCode::Up | KeyCode::Char('k') => viewer.scroll_up(1),
+                KeyCode::PageDown | KeyCode::Char(' ') => viewer.scroll_down(viewer.page_height),
+                KeyCode::PageUp => viewer.scroll_up(viewer.page_height),
+                KeyCode::Char('g') | KeyCode::Home => viewer.top(),
+                KeyCode::Char('G') | KeyCode::End => viewer.bottom(),
+                KeyCode::Char('/') => {
+                    viewer.query.clear();
+                    viewer.searching = true;
+                }
+                KeyCode::Char('n') => viewer.next_match(),
+                KeyCode::Char('N') => viewer.previous_match(),
+                _ => {}
+            }
+        }
+    }
+
+    disable_raw_mode()?;
+    crossterm::execute!(io::stdout(), LeaveAlternateScreen)?;
+
+    Ok(())
+}
+
+/// Modal yes/no dialog state
+struct ConfirmDialog {
+    yes_selected: bool,
+}
+
+impl ConfirmDialog {
+    fn toggle(&mut self) {
+        self.yes_selected = !self.yes_selected;
+    }
+}
+
+/// Center a box of the given size (in percent of width, absolute height) inside `area`
+fn centered_rect(area: Rect, width_percent: u16, height: u16) -> Rect {
+    let [_, vertical, _] = Layout::vertical([
+        Constraint::Fill(1),
+        Constraint::Length(height.min(area.height)),
+        Constraint::Fill(1),
+    ])
+    .areas(area);
+    let [_, rect, _] = Layout::horizontal([
+        Constraint::Percentage((100 - width_percent) / 2),
+        Constraint::Percentage(width_percent),
+        Constraint::Percentage((100 - width_percent) / 2),
+    ])
+    .areas(vertical);
+    rect
+}
+
+/// Ask for confirmation in a modal dialog, listing what the action will do.
+/// Y/N answer directly, ←/→ or Tab switch the focused button, Esc/Q cancel.
+pub fn run_confirm_dialog(title: &str, warning: &str, consequences: &[String], default_yes: bool) -> Result<bool> {
+    enable_raw_mode()?;
+    crossterm::execute!(io::stdout(), EnterAlternateScreen)?;
+
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    let mut dialog = ConfirmDialog { yes_selected: default_yes };
+
+    let result = loop {
+        terminal.draw(|frame| {
+            let mut lines = vec![
+                Line::from(Span::styled(warning, Style::default().fg(Color::Yellow).bold())),
+                Line::from(""),
+            ];
+            if !consequences.is_empty() {
+                lines.push(Line::from("This will:"));
+                for consequence in consequences {
+                    lines.push(Line::from(format!("  • {}", consequence)));
+                }
+                lines.push(Line::from(""));
+            }
+
+            let button = |label: &'static str, focused: bool| {
+                if focused {
+                    Span::styled(label, Style::default().fg(Color::Black).bg(Color::Yellow).bold())
+                } else {
+                    Span::styled(label, Style::default().fg(Color::Gray))
+                }
+            };
+            lines.push(Line::from(vec![
+                button("  Yes  ", dialog.yes_selected),
+                Span::raw("    "),
+                button("  No  ", !dialog.yes_selected),
+            ]).alignment(Alignment::Center));
+
+            let height = lines.len() as u16 + 2;
+            let area = centered_rect(frame.area(), 70, height);
+
+            frame.render_widget(Clear, area);
+            let paragraph = Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::default()
+                        .title(title)
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Red)),
+                );
+            frame.render_widget(paragraph, area);
+        })?;
+
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => break true,
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Char('q') | KeyCode::Esc => break false,
+                KeyCode::Left | KeyCode::Right | KeyCode::Tab | KeyCode::Char('h') | KeyCode::Char('l') => {
+                    dialog.toggle()
+                }
+                KeyCode::Enter => break dialog.yes_selected,
+                _ => {}
             }
         }
     };
@@ -223,3 +808,814 @@ pub fn run_cloud_provider_selector(providers: Vec<CloudProvider>) -> Result<Opti
     Ok(result)
 }
 
+/// Review list of resources about to be deleted, all checked initially
+struct DeletionReviewList<'a> {
+    items: &'a [InventoryItem],
+    checked: BTreeSet<usize>,
+    state: ListState,
+    /// Enter was pressed; waiting for Y to delete the checked resources
+    confirming: bool,
+}
+
+impl<'a> DeletionReviewList<'a> {
+    fn new(items: &'a [InventoryItem]) -> Self {
+        let mut state = ListState::default();
+        if !items.is_empty() {
+            state.select(Some(0));
+        }
+        Self {
+            items,
+            checked: (0..items.len()).collect(),
+            state,
+            confirming: false,
+        }
+    }
+
+    fn next(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.state.select(Some((i + 1) % self.items.len()));
+        }
+    }
+
+    fn previous(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.state.select(Some(if i == 0 { self.items.len() - 1 } else { i - 1 }));
+        }
+    }
+
+    fn toggle_selected(&mut self) {
+        if let Some(i) = self.state.selected()
+            && !self.checked.remove(&i)
+        {
+            self.checked.insert(i);
+        }
+    }
+
+    fn toggle_all(&mut self) {
+        if self.checked.len() == self.items.len() {
+            self.checked.clear();
+        } else {
+            self.checked = (0..self.items.len()).collect();
+        }
+    }
+
+    fn checked_ids(&self) -> Vec<String> {
+        self.checked.iter().map(|&i| self.items[i].id.clone()).collect()
+    }
+
+    fn list_items(&self) -> Vec<ListItem<'static>> {
+        let name_width = self.items.iter().map(|item| item.name.len()).max().unwrap_or(0).min(48);
+        self.items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let marker = if self.checked.contains(&i) { "[x]" } else { "[ ]" };
+                ListItem::new(format!(
+                    "{} {:<16} {:<name_width$} {}  {}",
+                    marker,
+                    item.kind.label(),
+                    item.name,
+                    item.id,
+                    item.status,
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Review a batch of resources before they are deleted. Space deselects resources, Enter
+/// followed by Y deletes the checked ones, Q/Esc skips the whole batch. Returns the IDs to delete.
+pub fn run_deletion_review(what: &str, items: &[InventoryItem]) -> Result<Vec<String>> {
+    if items.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    enable_raw_mode()?;
+    crossterm::execute!(io::stdout(), EnterAlternateScreen)?;
+
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    let mut review = DeletionReviewList::new(items);
+
+    let result = loop {
+        terminal.draw(|frame| {
+            let [list_area, help_area] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(frame.area());
+
+            let title = format!(
+                "Delete {}? ({} of {} selected)",
+                what,
+                review.checked.len(),
+                review.items.len()
+            );
+            let list = List::new(review.list_items())
+                .block(
+                    Block::default()
+                        .title(title)
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Red)),
+                )
+                .highlight_style(Style::default().fg(Color::Yellow))
+                .highlight_symbol("> ");
+            frame.render_stateful_widget(list, list_area, &mut review.state);
+
+            let help = if review.confirming {
+                Paragraph::new(Line::from(Span::styled(
+                    format!(
+                        "\nPermanently delete {} {}? Press Y to confirm, any other key to go back",
+                        review.checked.len(),
+                        what
+                    ),
+                    Style::default().fg(Color::Red).bold(),
+                )))
+            } else {
+                Paragraph::new(
+                    "\nPress ↑/↓ to navigate, Space to toggle, A to toggle all, Enter to delete selected, Q to skip all",
+                )
+            };
+            frame.render_widget(help, help_area);
+        })?;
+
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            if review.confirming {
+                match key.code {
+                    KeyCode::Char('y') | KeyCode::Char('Y') => break review.checked_ids(),
+                    _ => review.confirming = false,
+                }
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => break Vec::new(),
+                KeyCode::Down => review.next(),
+                KeyCode::Up => review.previous(),
+                KeyCode::Char(' ') => review.toggle_selected(),
+                KeyCode::Char('a') | KeyCode::Char('A') => review.toggle_all(),
+                KeyCode::Enter if review.checked.is_empty() => break Vec::new(),
+                KeyCode::Enter => review.confirming = true,
+                _ => {}
+            }
+        }
+    };
+
+    disable_raw_mode()?;
+    crossterm::execute!(io::stdout(), LeaveAlternateScreen)?;
+
+    Ok(result)
+}
+
+/// Stream a running terraform process into a scrolling pane with a resource progress header.
+/// `c` (or Ctrl+C) interrupts terraform; pressing it again kills it. If `timeout` elapses,
+/// terraform is interrupted the same way and the run is marked as timed out.
+pub fn run_terraform_output_pane(title: &str, child: &mut Child, timeout: Option<Duration>) -> Result<TerraformRun> {
+    let rx = spawn_output_reader(child);
+    let mut watchdog = Watchdog::new(timeout);
+
+    enable_raw_mode()?;
+    crossterm::execute!(io::stdout(), EnterAlternateScreen)?;
+
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    let start = Instant::now();
+    let mut lines: Vec<String> = Vec::new();
+    let mut progress = ApplyProgress::default();
+    let mut interrupts = 0;
+    // Lines scrolled up from the bottom; 0 follows the output
+    let mut scroll_back: usize = 0;
+    let mut page_height: usize = 1;
+    let mut exit_status = None;
+    let mut output_closed = false;
+
+    let result = loop {
+        loop {
+            match rx.try_recv() {
+                Ok(line) => lines.extend(progress.record(&line)),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    output_closed = true;
+                    break;
+                }
+            }
+        }
+
+        if exit_status.is_none() {
+            exit_status = child.try_wait()?;
+        }
+        if exit_status.is_none() {
+            watchdog.check(child);
+        }
+
+        // Close automatically on success or timeout (the caller decides how to recover);
+        // keep other failures on screen until a key is pressed
+        if let Some(status) = exit_status
+            && output_closed
+            && (status.success() || watchdog.timed_out())
+        {
+            break status;
+        }
+
+        terminal.draw(|frame| {
+            let area = frame.area();
+            let [header_area, log_area, help_area] = Layout::vertical([
+                Constraint::Length(5),
+                Constraint::Min(0),
+                Constraint::Length(2),
+            ])
+            .areas(area);
+
+            let elapsed = start.elapsed().as_secs();
+            let mut header = vec![
+                Line::from(vec![
+                    Span::styled(
+                        format!("Elapsed: {}m {:02}s", elapsed / 60, elapsed % 60),
+                        Style::default().fg(Color::Gray),
+                    ),
+                    Span::raw("   "),
+                    Span::styled(format!("{} complete", progress.completed), Style::default().fg(Color::Green)),
+                    Span::raw("   "),
+                    Span::styled(format!("{} in progress", progress.in_progress.len()), Style::default().fg(Color::Yellow)),
+                    Span::raw("   "),
+                    Span::styled(format!("{} failed", progress.failed), Style::default().fg(Color::Red)),
+                ]),
+            ];
+            for address in progress.in_progress.iter().rev().take(2) {
+                header.push(Line::from(Span::styled(
+                    format!("  ... {}", address),
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+            let header_block = Paragraph::new(header)
+                .block(Block::default().title(title).borders(Borders::ALL));
+            frame.render_widget(header_block, header_area);
+
+            page_height = log_area.height.saturating_sub(2).max(1) as usize;
+            scroll_back = scroll_back.min(lines.len().saturating_sub(page_height));
+            let end = lines.len() - scroll_back;
+            let start_line = end.saturating_sub(page_height);
+            let visible: Vec<Line> = lines[start_line..end]
+                .iter()
+                .map(|l| {
+                    let style = if l.contains("Error") {
+                        Style::default().fg(Color::Red)
+                    } else if l.contains("complete") {
+                        Style::default().fg(Color::Green)
+                    } else {
+                        Style::default()
+                    };
+                    Line::from(Span::styled(l.as_str(), style))
+                })
+                .collect();
+            let output = Paragraph::new(visible)
+                .block(Block::default().title("Output").borders(Borders::ALL));
+            frame.render_widget(output, log_area);
+
+            let help_text = match (exit_status, interrupts) {
+                (Some(_), _) => "\nTerraform failed - press any key to continue",
+                (None, _) if watchdog.timed_out() => "\nTimeout reached - waiting for terraform to stop...",
+                (None, 0) => "\nPgUp/PgDn scroll, End follow output, C to cancel",
+                (None, _) => "\nCancelling... press C again to kill terraform",
+            };
+            frame.render_widget(Paragraph::new(help_text), help_area);
+        })?;
+
+        if !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
+
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            if let Some(status) = exit_status {
+                break status;
+            }
+
+            match key.code {
+                // Raw mode delivers Ctrl+C as a key event instead of a signal
+                KeyCode::Char('c') | KeyCode::Char('C') => {
+                    if interrupts == 0 {
+                        interrupt_process(child);
+                    } else {
+                        let _ = child.kill();
+                    }
+                    interrupts += 1;
+                }
+                KeyCode::PageUp => scroll_back += page_height,
+                KeyCode::PageDown => scroll_back = scroll_back.saturating_sub(page_height),
+                KeyCode::Up => scroll_back += 1,
+                KeyCode::Down => scroll_back = scroll_back.saturating_sub(1),
+                KeyCode::End => scroll_back = 0,
+                _ => {}
+            }
+        }
+    };
+
+    disable_raw_mode()?;
+    crossterm::execute!(io::stdout(), LeaveAlternateScreen)?;
+
+    Ok(TerraformRun {
+        status: result,
+        lines,
+        progress,
+        timed_out: watchdog.timed_out(),
+    })
+}
+
+/// State of the `top` view: the last sample and how it is shown
+struct TopView {
+    nodes: Vec<NodeUsage>,
+    pods: Vec<PodUsage>,
+    sort: SortKey,
+    show_pods: bool,
+    /// Why the last refresh failed; the previous sample stays on screen
+    error: Option<String>,
+}
+
+impl TopView {
+    fn update(&mut self, sample: Result<(Vec<NodeUsage>, Vec<PodUsage>)>) {
+        match sample {
+            Ok((nodes, pods)) => {
+                self.nodes = nodes;
+                self.pods = pods;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+        self.sort_by(self.sort);
+    }
+
+    fn sort_by(&mut self, key: SortKey) {
+        self.sort = key;
+        resource_usage::sort_nodes(&mut self.nodes, key);
+        resource_usage::sort_pods(&mut self.pods, key);
+    }
+}
+
+/// Bar of `width` cells filled to `percent`
+fn usage_bar(percent: u64, width: usize) -> String {
+    let filled = (percent.min(100) as usize * width).div_ceil(100);
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+fn usage_color(percent: u64) -> Color {
+    match percent {
+        0..70 => Color::Green,
+        70..90 => Color::Yellow,
+        _ => Color::Red,
+    }
+}
+
+/// Usage cell with bar, e.g. `██████░░░░ 61% 2450m`
+fn usage_cell(percent: Option<u64>, amount: Option<String>) -> Line<'static> {
+    match (percent, amount) {
+        (Some(percent), Some(amount)) => Line::from(vec![
+            Span::styled(usage_bar(percent, 20), Style::default().fg(usage_color(percent))),
+            Span::raw(format!(" {:>3}% {}", percent, amount)),
+        ]),
+        _ => Line::from(Span::styled("no metrics yet", Style::default().fg(Color::DarkGray))),
+    }
+}
+
+/// Show node and pod resource usage, refreshed with `fetch` every `interval` until the user quits
+pub fn run_top_view(
+    title: &str,
+    interval: Duration,
+    mut fetch: impl FnMut() -> Result<(Vec<NodeUsage>, Vec<PodUsage>)>,
+) -> Result<()> {
+    let mut view = TopView {
+        nodes: Vec::new(),
+        pods: Vec::new(),
+        sort: SortKey::default(),
+        show_pods: false,
+        error: None,
+    };
+    view.update(fetch());
+    let mut refreshed = Instant::now();
+
+    enable_raw_mode()?;
+    crossterm::execute!(io::stdout(), EnterAlternateScreen)?;
+
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+
+    loop {
+        if refreshed.elapsed() >= interval {
+            view.update(fetch());
+            refreshed = Instant::now();
+        }
+
+        terminal.draw(|frame| {
+            let [table_area, help_area] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(frame.area());
+
+            let header_style = Style::default().fg(Color::Gray).bold();
+            let table = if view.show_pods {
+                let rows = view.pods.iter().map(|pod| {
+                    Row::new(vec![
+                        pod.namespace.clone(),
+                        pod.name.clone(),
+                        format!("{}m", pod.cpu_millicores),
+                        format_memory(pod.memory_bytes),
+                    ])
+                });
+                Table::new(rows, [Constraint::Length(20), Constraint::Min(30), Constraint::Length(10), Constraint::Length(10)])
+                    .header(Row::new(vec!["NAMESPACE", "POD", "CPU", "MEMORY"]).style(header_style))
+            } else {
+                let rows = view.nodes.iter().map(|node| {
+                    Row::new(vec![
+                        Line::from(node.name.clone()),
+                        usage_cell(node.cpu_percent, node.cpu_millicores.map(|m| format!("{}m", m))),
+                        usage_cell(node.memory_percent, node.memory_bytes.map(format_memory)),
+                    ])
+                });
+                Table::new(rows, [Constraint::Length(24), Constraint::Length(36), Constraint::Length(36)])
+                    .header(Row::new(vec!["NODE", "CPU", "MEMORY"]).style(header_style))
+            };
+            let what = if view.show_pods { "pods" } else { "nodes" };
+            let block = Block::default()
+                .title(format!("{} - {} by {} (every {}s)", title, what, view.sort.label(), interval.as_secs()))
+                .borders(Borders::ALL);
+            frame.render_widget(table.block(block), table_area);
+
+            let help = match &view.error {
+                Some(error) => Line::from(Span::styled(format!("\n{}", error), Style::default().fg(Color::Red))),
+                None => Line::from("\nc/m/n sort by CPU/memory/name, P nodes/pods, R refresh, Q to close"),
+            };
+            frame.render_widget(Paragraph::new(help).wrap(Wrap { trim: true }), help_area);
+        })?;
+
+        if !event::poll(Duration::from_millis(250))? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => break,
+                KeyCode::Char('c') => view.sort_by(SortKey::Cpu),
+                KeyCode::Char('m') => view.sort_by(SortKey::Memory),
+                KeyCode::Char('n') => view.sort_by(SortKey::Name),
+                KeyCode::Char('p') | KeyCode::Char('P') | KeyCode::Tab => view.show_pods = !view.show_pods,
+                KeyCode::Char('r') | KeyCode::Char('R') => {
+                    view.update(fetch());
+                    refreshed = Instant::now();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    disable_raw_mode()?;
+    crossterm::execute!(io::stdout(), LeaveAlternateScreen)?;
+
+    Ok(())
+}
+
+/// State of the events view
+struct EventsView {
+    events: Vec<KubeEvent>,
+    filter: EventFilter,
+    editing_reason: bool,
+    state: ListState,
+    error: Option<String>,
+}
+
+impl EventsView {
+    fn visible(&self) -> Vec<&KubeEvent> {
+        self.events.iter().filter(|event| self.filter.matches(event)).collect()
+    }
+
+    fn update(&mut self, events: Result<Vec<KubeEvent>>) {
+        match events {
+            Ok(events) => {
+                self.events = events;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+        self.clamp_selection();
+    }
+
+    fn clamp_selection(&mut self) {
+        let count = self.visible().len();
+        let selected = match count {
+            0 => None,
+            _ => Some(self.state.selected().unwrap_or(0).min(count - 1)),
+        };
+        self.state.select(selected);
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let count = self.visible().len();
+        if count > 0 {
+            let selected = self.state.selected().unwrap_or(0).saturating_add_signed(delta);
+            self.state.select(Some(selected.min(count - 1)));
+        }
+    }
+}
+
+fn event_item(event: &KubeEvent, now: u64) -> ListItem<'static> {
+    let (marker, color) = if event.is_warning() { ("W", Color::Red) } else { ("N", Color::Gray) };
+    let age = event.last_seen.map(|t| history::format_age(t, now)).unwrap_or_default();
+    let count = if event.count > 1 { format!(" (x{})", event.count) } else { String::new() };
+    ListItem::new(vec![
+        Line::from(vec![
+            Span::styled(format!("{} ", marker), Style::default().fg(color).bold()),
+            Span::styled(format!("{:<12} ", age), Style::default().fg(Color::DarkGray)),
+            Span::styled(format!("{}{} ", event.reason, count), Style::default().fg(color)),
+            Span::raw(format!("{}/{}", event.namespace, event.object)),
+        ]),
+        Line::from(Span::styled(format!("  {}", event.message), Style::default().fg(Color::Gray))),
+    ])
+}
+
+/// Browse events from `fetch`, warnings first. With `refresh` the list is fetched again at
+/// that interval until the user quits.
+pub fn run_events_view(
+    title: &str,
+    refresh: Option<Duration>,
+    mut fetch: impl FnMut() -> Result<Vec<KubeEvent>>,
+) -> Result<()> {
+    let mut view = EventsView {
+        events: Vec::new(),
+        filter: EventFilter::default(),
+        editing_reason: false,
+        state: ListState::default(),
+        error: None,
+    };
+    view.update(fetch());
+    let mut refreshed = Instant::now();
+
+    enable_raw_mode()?;
+    crossterm::execute!(io::stdout(), EnterAlternateScreen)?;
+
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+
+    loop {
+        if let Some(interval) = refresh
+            && refreshed.elapsed() >= interval
+        {
+            view.update(fetch());
+            refreshed = Instant::now();
+        }
+
+        terminal.draw(|frame| {
+            let [list_area, help_area] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(frame.area());
+
+            let now = history::unix_now();
+            let visible = view.visible();
+            let items: Vec<ListItem> = visible.iter().map(|event| event_item(event, now)).collect();
+            let mut heading = format!("{} - {} of {} events, {}", title, visible.len(), view.events.len(), view.filter.event_type.label());
+            if !view.filter.reason.is_empty() {
+                heading.push_str(&format!(", reason ~ '{}'", view.filter.reason));
+            }
+            let list = List::new(items)
+                .block(Block::default().title(heading).borders(Borders::ALL))
+                .highlight_style(Style::default().bg(Color::DarkGray));
+            frame.render_stateful_widget(list, list_area, &mut view.state);
+
+            let help = if view.editing_reason {
+                Line::from(format!("\nReason: {}", view.filter.reason))
+            } else if let Some(error) = &view.error {
+                Line::from(Span::styled(format!("\n{}", error), Style::default().fg(Color::Red)))
+            } else {
+                Line::from("\n↑/↓ scroll, T cycle type, / filter by reason, Q to close")
+            };
+            frame.render_widget(Paragraph::new(help), help_area);
+        })?;
+
+        if !event::poll(Duration::from_millis(250))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        if view.editing_reason {
+            match key.code {
+                KeyCode::Esc => {
+                    view.filter.reason.clear();
+                    view.editing_reason = false;
+                }
+                KeyCode::Enter => view.editing_reason = false,
+                KeyCode::Backspace => {
+                    view.filter.reason.pop();
+                }
+                KeyCode::Char(c) => view.filter.reason.push(c),
+                _ => {}
+            }
+            view.clamp_selection();
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => break,
+            KeyCode::Down | KeyCode::Char('j') => view.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => view.move_selection(-1),
+            KeyCode::PageDown => view.move_selection(10),
+            KeyCode::PageUp => view.move_selection(-10),
+            KeyCode::Char('t') | KeyCode::Char('T') => {
+                view.filter.event_type = view.filter.event_type.next();
+                view.clamp_selection();
+            }
+            KeyCode::Char('/') => {
+                view.filter.reason.clear();
+                view.editing_reason = true;
+            }
+            _ => {}
+        }
+    }
+
+    disable_raw_mode()?;
+    crossterm::execute!(io::stdout(), LeaveAlternateScreen)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(name: &str, ip: &str) -> ServerInfo {
+        ServerInfo {
+            name: name.to_string(),
+            ip: ip.to_string(),
+            addresses: Vec::new(),
+            cloud_provider: "openstack".to_string(),
+            tailscale_hostname: None,
+            tailscale_ip: None,
+            ssh_user: None,
+            public_ip: None,
+            tailscale_ssh: false,
+            via_subnet_router: false,
+        }
+    }
+
+    #[test]
+    fn test_server_selector_groups_servers_first() {
+        let servers = vec![
+            server("k3s-agent-0", "10.0.0.10"),
+            server("k3s-server-0", "10.0.0.1"),
+            server("k3s-agent-1", "10.0.0.11"),
+        ];
+        let mut selector = ServerSelector::new(servers, false);
+
+        // Header "Servers" is row 0, so the first server sits on row 1
+        assert_eq!(selector.get_selected().unwrap().name, "k3s-server-0");
+        assert_eq!(selector.state.selected(), Some(1));
+
+        // Moving into the agents skips the "Agents" header row
+        selector.next();
+        assert_eq!(selector.get_selected().unwrap().name, "k3s-agent-0");
+        assert_eq!(selector.state.selected(), Some(3));
+        assert_eq!(selector.list_items().len(), 5);
+    }
+
+    #[test]
+    fn test_server_selector_search() {
+        let servers = vec![
+            server("k3s-server-0", "10.0.0.1"),
+            server("k3s-agent-0", "10.0.0.10"),
+            server("k3s-agent-1", "10.0.0.11"),
+        ];
+        let mut selector = ServerSelector::new(servers, false);
+
+        for c in "agent-1".chars() {
+            selector.push_query_char(c);
+        }
+        assert_eq!(selector.visible.len(), 1);
+        assert_eq!(selector.get_selected().unwrap().name, "k3s-agent-1");
+
+        selector.push_query_char('x');
+        assert!(selector.get_selected().is_none());
+
+        selector.clear_query();
+        assert_eq!(selector.visible.len(), 3);
+    }
+
+    #[test]
+    fn test_server_selector_multi_select() {
+        let servers = vec![
+            server("k3s-server-0", "10.0.0.1"),
+            server("k3s-agent-0", "10.0.0.10"),
+            server("k3s-agent-1", "10.0.0.11"),
+        ];
+        let mut selector = ServerSelector::new(servers, true);
+
+        // Nothing checked falls back to the highlighted server
+        assert_eq!(selector.get_checked().len(), 1);
+
+        selector.next();
+        selector.toggle_selected();
+        selector.next();
+        selector.toggle_selected();
+        let names: Vec<_> = selector.get_checked().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["k3s-agent-0", "k3s-agent-1"]);
+
+        // Toggle all only affects the filtered servers
+        selector.push_query_char('s');
+        selector.push_query_char('e');
+        selector.push_query_char('r');
+        selector.toggle_all_visible();
+        assert_eq!(selector.get_checked().len(), 3);
+        selector.toggle_all_visible();
+        assert_eq!(selector.get_checked().len(), 2);
+    }
+
+    #[test]
+    fn test_deletion_review_deselect() {
+        use crate::domain::inventory::ResourceKind;
+
+        let items: Vec<InventoryItem> = ["lb-1", "lb-2", "lb-3"]
+            .iter()
+            .map(|id| InventoryItem {
+                kind: ResourceKind::LoadBalancer,
+                id: id.to_string(),
+                name: format!("kube_service_demo_{}", id),
+                status: "ACTIVE".to_string(),
+                orphan_candidate: true,
+            })
+            .collect();
+        let mut review = DeletionReviewList::new(&items);
+
+        // Everything is checked until deselected
+        assert_eq!(review.checked_ids(), vec!["lb-1", "lb-2", "lb-3"]);
+        review.next();
+        review.toggle_selected();
+        assert_eq!(review.checked_ids(), vec!["lb-1", "lb-3"]);
+
+        review.toggle_all();
+        assert_eq!(review.checked_ids().len(), 3);
+        review.toggle_all();
+        assert!(review.checked_ids().is_empty());
+
+        review.previous();
+        review.previous();
+        assert_eq!(review.state.selected(), Some(2));
+    }
+
+    #[test]
+    fn test_log_viewer_opens_at_first_error_and_searches() {
+        let log = (0..100)
+            .map(|i| match i {
+                // Mentions ERROR without being tagged as one
+                20 => "Checking for ERROR conditions".to_string(),
+                40 => "ERROR: k3s failed to start".to_string(),
+                70 => "retrying etcd join".to_string(),
+                _ => format!("line {}", i),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut viewer = LogViewer::new(&log);
+        viewer.page_height = 20;
+        assert_eq!(viewer.offset, 40);
+
+        viewer.bottom();
+        assert_eq!(viewer.offset, 80);
+        viewer.scroll_down(10);
+        assert_eq!(viewer.offset, 80);
+        viewer.top();
+        assert_eq!(viewer.offset, 0);
+
+        viewer.query = "etcd".to_string();
+        viewer.update_matches();
+        viewer.next_match();
+        assert_eq!(viewer.offset, 70);
+        viewer.previous_match();
+        assert_eq!(viewer.offset, 70);
+    }
+
+    #[test]
+    fn test_top_view_keeps_last_sample_on_error() {
+        let node = |name: &str, cpu: u64| NodeUsage {
+            name: name.to_string(),
+            cpu_millicores: Some(cpu),
+            cpu_percent: Some(cpu / 40),
+            memory_bytes: Some(1 << 30),
+            memory_percent: Some(25),
+        };
+        let mut view = TopView {
+            nodes: Vec::new(),
+            pods: Vec::new(),
+            sort: SortKey::Cpu,
+            show_pods: false,
+            error: None,
+        };
+
+        view.update(Ok((vec![node("k3s-agent-0", 200), node("k3s-server-0", 1800)], Vec::new())));
+        assert_eq!(view.nodes[0].name, "k3s-server-0");
+        view.sort_by(SortKey::Name);
+        assert_eq!(view.nodes[0].name, "k3s-agent-0");
+        view.sort_by(SortKey::Cpu);
+
+        view.update(Err(ImDeployError::Cancelled("refresh".to_string())));
+        assert_eq!(view.nodes.len(), 2);
+        assert!(view.error.is_some());
+
+        assert_eq!(usage_bar(45, 10), "█████░░░░░");
+        assert_eq!(usage_bar(120, 4), "████");
+    }
+}
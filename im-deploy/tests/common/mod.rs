@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 use im_deploy::domain::cluster::{CloudProvider, ServerInfo};
 use std::fs;
 use std::path::PathBuf;
@@ -8,12 +10,18 @@ pub fn create_test_server(name: &str, ip: &str, is_server: bool) -> ServerInfo {
     ServerInfo {
         name: name.to_string(),
         ip: ip.to_string(),
+        addresses: Vec::new(),
         cloud_provider: "openstack".to_string(),
         tailscale_hostname: if is_server {
             Some(format!("{}.tailnet.ts.net", name))
         } else {
             None
         },
+        tailscale_ip: None,
+        ssh_user: None,
+        public_ip: None,
+        tailscale_ssh: false,
+        via_subnet_router: false,
     }
 }
 
@@ -29,6 +37,7 @@ pub fn create_test_provider(
         bastion_ip: bastion_ip.map(|s| s.to_string()),
         tailscale_enabled,
         servers,
+        subnet_routes: Vec::new(),
     }
 }
 
@@ -64,7 +73,7 @@ pub fn load_fixture(filename: &str) -> String {
         .join("tests")
         .join("fixtures")
         .join(filename);
-    fs::read_to_string(fixture_path).expect(&format!("Failed to load fixture: {}", filename))
+    fs::read_to_string(fixture_path).unwrap_or_else(|_| panic!("Failed to load fixture: {}", filename))
 }
 
 /// Assert that an error matches a specific variant (helper macro would be better but this works)
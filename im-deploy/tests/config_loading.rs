@@ -2,6 +2,9 @@ mod common;
 
 use common::{create_temp_terraform_dir, load_fixture};
 use im_deploy::config;
+use im_deploy::domain::cluster::IpFamily;
+use im_deploy::domain::monitor::MonitorPhase;
+use im_deploy::hooks::HookPoint;
 use std::env;
 
 #[test]
@@ -52,6 +55,7 @@ fn test_load_config_with_minimal_tfvars() {
     assert!(result.is_ok());
     let cfg = result.unwrap();
     assert_eq!(cfg.cluster_name, "minimal-cluster");
+    assert_eq!(cfg.destroy_timeout_mins, 30);
     assert!(cfg.tailscale.is_none()); // Tailscale not enabled
     assert!(cfg.openstack.is_some());
     
@@ -110,6 +114,64 @@ tailscale_tailnet = "myorg.tailscale.ts.net"
     drop(temp_dir);
 }
 
+#[test]
+#[serial_test::serial]
+fn test_load_config_tailscale_ssh() {
+    let tfvars = r#"
+cluster_name = "ts-ssh"
+user_name = "user"
+user_password = "pass"
+tenant_name = "project"
+enable_tailscale = true
+tailscale_api_key = "tskey-test"
+tailscale_tailnet = "myorg.tailscale.ts.net"
+"#;
+    let (temp_dir, _) = create_temp_terraform_dir(tfvars);
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+
+    let default = config::load_config(false);
+    std::fs::write(temp_dir.path().join("im-deploy.toml"), "tailscale_ssh = true\n").unwrap();
+    let with_tailscale_ssh = config::load_config(false);
+
+    env::set_current_dir(original_dir).unwrap();
+
+    assert!(!default.unwrap().tailscale.unwrap().ssh);
+    assert!(with_tailscale_ssh.unwrap().tailscale.unwrap().ssh);
+
+    drop(temp_dir);
+}
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_tailscale_disable_key_expiry() {
+    let tfvars = r#"
+cluster_name = "ts-expiry"
+user_name = "user"
+user_password = "pass"
+tenant_name = "project"
+enable_tailscale = true
+tailscale_api_key = "tskey-test"
+tailscale_tailnet = "myorg.tailscale.ts.net"
+"#;
+    let (temp_dir, _) = create_temp_terraform_dir(tfvars);
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+
+    let default = config::load_config(false);
+    std::fs::write(temp_dir.path().join("im-deploy.toml"), "tailscale_disable_key_expiry = false\n").unwrap();
+    let kept = config::load_config(false);
+
+    env::set_current_dir(original_dir).unwrap();
+
+    assert!(default.unwrap().tailscale.unwrap().disable_key_expiry);
+    assert!(!kept.unwrap().tailscale.unwrap().disable_key_expiry);
+
+    drop(temp_dir);
+}
+
 #[test]
 #[serial_test::serial]
 fn test_load_config_with_openstack_defaults() {
@@ -136,11 +198,69 @@ tenant_name = "admin-project"
     // Should use default values
     assert!(os.auth_url.contains("private-cloud.informatik.hs-fulda.de"));
     assert_eq!(os.region, "RegionOne");
-    assert_eq!(os.insecure, true);
+    assert_eq!(os.user_domain, "Default");
+    assert_eq!(os.project_domain, "Default");
+    assert!(os.insecure);
     
     drop(temp_dir);
 }
 
+#[test]
+#[serial_test::serial]
+fn test_load_config_keystone_domains() {
+    let tfvars = r#"
+user_name = "jdoe"
+user_password = "secret"
+tenant_name = "research"
+openstack_user_domain = "ldap"
+openstack_project_domain = "faculty"
+"#;
+    let (temp_dir, _) = create_temp_terraform_dir(tfvars);
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+
+    // SAFETY: tests touching the environment are serialized
+    unsafe {
+        env::set_var("OS_PROJECT_DOMAIN_NAME", "from-env");
+    }
+    let result = config::load_config(false);
+    unsafe {
+        env::remove_var("OS_PROJECT_DOMAIN_NAME");
+    }
+    env::set_current_dir(original_dir).unwrap();
+
+    let os = result.unwrap().openstack.unwrap();
+    assert_eq!(os.user_domain, "ldap");
+    assert_eq!(os.project_domain, "from-env");
+
+    drop(temp_dir);
+}
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_node_floating_ips() {
+    let tfvars = r#"
+user_name = "admin"
+user_password = "secret"
+tenant_name = "admin-project"
+enable_bastion = false
+enable_node_floating_ips = true
+openstack_agent_count = 2
+"#;
+    let (temp_dir, _) = create_temp_terraform_dir(tfvars);
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+    let result = config::load_config(false);
+    env::set_current_dir(original_dir).unwrap();
+
+    // The load balancer, three servers by default and two agents
+    assert_eq!(result.unwrap().openstack.unwrap().floating_ips_needed, 6);
+
+    drop(temp_dir);
+}
+
 #[test]
 #[serial_test::serial]
 fn test_load_config_dry_run_mode() {
@@ -199,3 +319,512 @@ fn test_detect_terraform_dir_not_in_project() {
     assert!(err_msg.contains("Terraform directory not found"));
 }
 
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_env_overrides_tfvars() {
+    let tfvars = load_fixture("terraform.tfvars");
+    let (temp_dir, _) = create_temp_terraform_dir(&tfvars);
+
+    let fake_bin = temp_dir.path().join("tofu");
+    std::fs::write(&fake_bin, "").unwrap();
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+
+    // SAFETY: tests touching the environment are serialized
+    unsafe {
+        env::set_var("OS_PASSWORD", "from-env");
+        env::set_var("OS_REGION_NAME", "RegionTwo");
+        env::set_var("TS_API_KEY", "tskey-from-env");
+        env::set_var("IM_DEPLOY_TERRAFORM_BIN", &fake_bin);
+    }
+
+    let result = config::load_config(false);
+
+    unsafe {
+        env::remove_var("OS_PASSWORD");
+        env::remove_var("OS_REGION_NAME");
+        env::remove_var("TS_API_KEY");
+        env::remove_var("IM_DEPLOY_TERRAFORM_BIN");
+    }
+    env::set_current_dir(original_dir).unwrap();
+
+    let cfg = result.unwrap();
+    assert_eq!(cfg.terraform_bin, fake_bin.display().to_string());
+    assert_eq!(cfg.terraform_env["TF_VAR_user_password"], "from-env");
+    assert_eq!(cfg.terraform_env["TF_VAR_openstack_region"], "RegionTwo");
+    assert!(!cfg.terraform_env.contains_key("TF_VAR_user_name"));
+
+    let os = cfg.openstack.unwrap();
+    assert_eq!(os.password, "from-env");
+    assert_eq!(os.region, "RegionTwo");
+    // Values without an override still come from tfvars
+    assert_eq!(os.username, "test-user");
+
+    assert_eq!(cfg.tailscale.unwrap().api_key, "tskey-from-env");
+
+    drop(temp_dir);
+}
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_env_provides_missing_credentials() {
+    let tfvars = r#"
+cluster_name = "ci-cluster"
+enable_tailscale = true
+tailscale_tailnet = "ci.github.ts.net"
+"#;
+    let (temp_dir, _) = create_temp_terraform_dir(tfvars);
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+
+    // SAFETY: tests touching the environment are serialized
+    unsafe {
+        env::set_var("OS_USERNAME", "ci-user");
+        env::set_var("OS_PASSWORD", "ci-pass");
+        env::set_var("OS_PROJECT_NAME", "ci-project");
+        env::set_var("TS_API_KEY", "tskey-ci");
+    }
+
+    let result = config::load_config(false);
+
+    unsafe {
+        env::remove_var("OS_USERNAME");
+        env::remove_var("OS_PASSWORD");
+        env::remove_var("OS_PROJECT_NAME");
+        env::remove_var("TS_API_KEY");
+    }
+    env::set_current_dir(original_dir).unwrap();
+
+    let cfg = result.unwrap();
+    let os = cfg.openstack.unwrap();
+    assert_eq!(os.username, "ci-user");
+    assert_eq!(os.project_name, "ci-project");
+    assert_eq!(cfg.tailscale.unwrap().api_key, "tskey-ci");
+
+    drop(temp_dir);
+}
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_env_invalid_bool() {
+    let tfvars = load_fixture("minimal_terraform.tfvars");
+    let (temp_dir, _) = create_temp_terraform_dir(&tfvars);
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+
+    // SAFETY: tests touching the environment are serialized
+    unsafe { env::set_var("OS_INSECURE", "maybe") };
+
+    let result = config::load_config(false);
+
+    unsafe { env::remove_var("OS_INSECURE") };
+    env::set_current_dir(original_dir).unwrap();
+
+    assert!(result.is_err());
+    let err_msg = result.unwrap_err().to_string();
+    assert!(err_msg.contains("OS_INSECURE"));
+
+    drop(temp_dir);
+}
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_env_terraform_dir() {
+    let tfvars = load_fixture("minimal_terraform.tfvars");
+    let (temp_dir, terraform_dir) = create_temp_terraform_dir(&tfvars);
+    let elsewhere = tempfile::TempDir::new().unwrap();
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(elsewhere.path()).unwrap();
+
+    // SAFETY: tests touching the environment are serialized
+    unsafe { env::set_var("IM_DEPLOY_TERRAFORM_DIR", &terraform_dir) };
+
+    let result = config::load_config(false);
+
+    unsafe { env::remove_var("IM_DEPLOY_TERRAFORM_DIR") };
+    env::set_current_dir(original_dir).unwrap();
+
+    let cfg = result.unwrap();
+    assert_eq!(cfg.terraform_dir, terraform_dir);
+    assert_eq!(cfg.cluster_name, "minimal-cluster");
+
+    drop(temp_dir);
+}
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_file_terraform_dir_relative_to_file() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let terraform_dir = temp_dir.path().join("infra").join("k3s");
+    std::fs::create_dir_all(&terraform_dir).unwrap();
+    std::fs::write(terraform_dir.join("main.tf"), "# test").unwrap();
+    std::fs::write(
+        terraform_dir.join("terraform.tfvars"),
+        load_fixture("minimal_terraform.tfvars"),
+    )
+    .unwrap();
+    std::fs::write(
+        temp_dir.path().join("im-deploy.toml"),
+        "terraform_dir = \"infra/k3s\"\n",
+    )
+    .unwrap();
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+
+    let result = config::load_config(false);
+
+    env::set_current_dir(original_dir).unwrap();
+
+    let cfg = result.unwrap();
+    assert_eq!(cfg.terraform_dir, terraform_dir);
+    assert_eq!(cfg.cluster_name, "minimal-cluster");
+}
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_file_terraform_bin_relative_to_file() {
+    let tfvars = load_fixture("minimal_terraform.tfvars");
+    let (temp_dir, terraform_dir) = create_temp_terraform_dir(&tfvars);
+    let fake_bin = temp_dir.path().join("bin").join("tofu");
+    std::fs::create_dir_all(fake_bin.parent().unwrap()).unwrap();
+    std::fs::write(&fake_bin, "").unwrap();
+    std::fs::write(temp_dir.path().join("im-deploy.toml"), "terraform_bin = \"bin/tofu\"\n").unwrap();
+
+    // Found from the terraform directory, one level below the config file
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(&terraform_dir).unwrap();
+
+    let result = config::load_config(false);
+
+    env::set_current_dir(original_dir).unwrap();
+
+    let cfg = result.unwrap();
+    assert_eq!(cfg.terraform_bin, fake_bin.display().to_string());
+
+    drop(temp_dir);
+}
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_cli_overrides_take_precedence() {
+    let tfvars = load_fixture("minimal_terraform.tfvars");
+    let (temp_dir, terraform_dir) = create_temp_terraform_dir(&tfvars);
+    let (other_dir, _) = create_temp_terraform_dir("cluster_name = \"other\"\n");
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+
+    // SAFETY: tests touching the environment are serialized
+    unsafe { env::set_var("IM_DEPLOY_TERRAFORM_DIR", other_dir.path().join("terraform")) };
+
+    let overrides = config::ConfigOverrides {
+        terraform_dir: Some(terraform_dir.clone()),
+        terraform_bin: None,
+        ..Default::default()
+    };
+    let result = config::load_config_with_overrides(false, &overrides);
+
+    unsafe { env::remove_var("IM_DEPLOY_TERRAFORM_DIR") };
+    env::set_current_dir(original_dir).unwrap();
+
+    let cfg = result.unwrap();
+    assert_eq!(cfg.terraform_dir, terraform_dir);
+    assert_eq!(cfg.cluster_name, "minimal-cluster");
+}
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_invalid_terraform_overrides() {
+    let tfvars = load_fixture("minimal_terraform.tfvars");
+    let (temp_dir, _) = create_temp_terraform_dir(&tfvars);
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+
+    let missing_bin = config::ConfigOverrides {
+        terraform_dir: None,
+        terraform_bin: Some("/nonexistent/bin/tofu".to_string()),
+        ..Default::default()
+    };
+    let bin_result = config::load_config_with_overrides(false, &missing_bin);
+
+    let missing_dir = config::ConfigOverrides {
+        terraform_dir: Some(temp_dir.path().join("does-not-exist")),
+        terraform_bin: None,
+        ..Default::default()
+    };
+    let dir_result = config::load_config_with_overrides(false, &missing_dir);
+
+    env::set_current_dir(original_dir).unwrap();
+
+    let err_msg = bin_result.unwrap_err().to_string();
+    assert!(err_msg.contains("terraform_bin"));
+    assert!(err_msg.contains("/nonexistent/bin/tofu"));
+
+    let err_msg = dir_result.unwrap_err().to_string();
+    assert!(err_msg.contains("Terraform directory not found"));
+}
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_terraform_required_version() {
+    let tfvars = load_fixture("minimal_terraform.tfvars");
+    let (temp_dir, _) = create_temp_terraform_dir(&tfvars);
+    std::fs::write(
+        temp_dir.path().join("im-deploy.toml"),
+        "terraform_required_version = \">= 1.6, < 2.0\"\ndestroy_timeout_minutes = 45\n",
+    )
+    .unwrap();
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+
+    let pinned = config::load_config(false);
+
+    // SAFETY: tests touching the environment are serialized
+    unsafe { env::set_var("IM_DEPLOY_TERRAFORM_REQUIRED_VERSION", ">= banana") };
+    let invalid = config::load_config(false);
+    unsafe { env::remove_var("IM_DEPLOY_TERRAFORM_REQUIRED_VERSION") };
+
+    env::set_current_dir(original_dir).unwrap();
+
+    let pinned = pinned.unwrap();
+    assert_eq!(pinned.destroy_timeout_mins, 45);
+    let constraint = pinned.terraform_required_version.unwrap();
+    assert_eq!(constraint.to_string(), ">= 1.6, < 2.0");
+
+    let err_msg = invalid.unwrap_err().to_string();
+    assert!(err_msg.contains("IM_DEPLOY_TERRAFORM_REQUIRED_VERSION"));
+}
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_preserve_on_destroy() {
+    let tfvars = load_fixture("minimal_terraform.tfvars");
+    let (temp_dir, _) = create_temp_terraform_dir(&tfvars);
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+
+    let default = config::load_config(false);
+    std::fs::write(
+        temp_dir.path().join("im-deploy.toml"),
+        "preserve_on_destroy = [\"module.*.openstack_blockstorage_volume_v3.data*\"]\n",
+    )
+    .unwrap();
+    let configured = config::load_config(false);
+
+    env::set_current_dir(original_dir).unwrap();
+
+    assert!(!default.unwrap().preserve_on_destroy.is_empty());
+    assert_eq!(configured.unwrap().preserve_on_destroy, ["module.*.openstack_blockstorage_volume_v3.data*"]);
+}
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_ip_family() {
+    let tfvars = load_fixture("minimal_terraform.tfvars");
+    let (temp_dir, _) = create_temp_terraform_dir(&tfvars);
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+
+    let default = config::load_config(false);
+    std::fs::write(temp_dir.path().join("im-deploy.toml"), "ip_family = \"ipv6\"\n").unwrap();
+    let ipv6 = config::load_config(false);
+
+    env::set_current_dir(original_dir).unwrap();
+
+    assert_eq!(default.unwrap().ip_family, IpFamily::Ipv4);
+    assert_eq!(ipv6.unwrap().ip_family, IpFamily::Ipv6);
+}
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_terraform_init_settings() {
+    let tfvars = load_fixture("minimal_terraform.tfvars");
+    let (temp_dir, _) = create_temp_terraform_dir(&tfvars);
+    std::fs::write(
+        temp_dir.path().join("im-deploy.toml"),
+        "init_upgrade = true\nplugin_cache_dir = \"cache/plugins\"\n",
+    )
+    .unwrap();
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+
+    let from_file = config::load_config(false);
+
+    // SAFETY: tests touching the environment are serialized
+    unsafe { env::set_var("TF_PLUGIN_CACHE_DIR", "/srv/terraform-plugins") };
+    let from_env = config::load_config(false);
+    unsafe { env::remove_var("TF_PLUGIN_CACHE_DIR") };
+
+    env::set_current_dir(original_dir).unwrap();
+
+    let from_file = from_file.unwrap();
+    assert!(from_file.terraform_init.upgrade);
+    assert!(!from_file.terraform_init.reconfigure);
+    assert_eq!(
+        from_file.plugin_cache_dir,
+        Some(temp_dir.path().join("cache").join("plugins"))
+    );
+
+    assert_eq!(
+        from_env.unwrap().plugin_cache_dir,
+        Some(std::path::PathBuf::from("/srv/terraform-plugins"))
+    );
+}
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_log_ignore_patterns() {
+    let tfvars = load_fixture("minimal_terraform.tfvars");
+    let (temp_dir, _) = create_temp_terraform_dir(&tfvars);
+    std::fs::write(
+        temp_dir.path().join("im-deploy.toml"),
+        "[log_ignore_patterns]\n\"*\" = [\"known flake\"]\n\"k3s-server.log\" = [\"metrics-server not ready\"]\n",
+    )
+    .unwrap();
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+    let result = config::load_config(false);
+    env::set_current_dir(original_dir).unwrap();
+
+    let config = result.unwrap();
+    let server_log = config.log_filter("k3s-server.log");
+    assert!(!server_log.is_failure("ERROR: metrics-server not ready yet"));
+    assert!(!server_log.is_failure("ERROR: known flake"));
+    assert!(server_log.is_failure("ERROR: k3s failed to start"));
+
+    let argocd_log = config.log_filter("argocd-install.log");
+    assert!(argocd_log.is_failure("ERROR: metrics-server not ready yet"));
+    assert!(!argocd_log.is_failure("ERROR: known flake"));
+}
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_hooks() {
+    let tfvars = load_fixture("minimal_terraform.tfvars");
+    let (temp_dir, _) = create_temp_terraform_dir(&tfvars);
+    std::fs::write(
+        temp_dir.path().join("im-deploy.toml"),
+        "[hooks]\npost_deploy = \"./scripts/update-dns.sh\"\npre_destroy = \"echo bye\"\n",
+    )
+    .unwrap();
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+    let result = config::load_config(false);
+    env::set_current_dir(original_dir).unwrap();
+
+    let config = result.unwrap();
+    assert_eq!(config.hooks.command(HookPoint::PostDeploy), Some("./scripts/update-dns.sh"));
+    assert_eq!(config.hooks.command(HookPoint::PreDestroy), Some("echo bye"));
+    assert_eq!(config.hooks.command(HookPoint::PreDeploy), None);
+    // Hook scripts are resolved relative to the config file
+    assert_eq!(
+        config.hooks.working_dir.as_deref().map(|d| d.canonicalize().unwrap()),
+        Some(temp_dir.path().canonicalize().unwrap())
+    );
+}
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_dns() {
+    let tfvars = load_fixture("minimal_terraform.tfvars");
+    let (temp_dir, _) = create_temp_terraform_dir(&tfvars);
+    std::fs::write(
+        temp_dir.path().join("im-deploy.toml"),
+        "[dns]\nname = \"k3s.example.com\"\nprovider = \"rfc2136\"\nrfc2136_server = \"ns1.example.com\"\nrfc2136_key_file = \"keys/update.key\"\n",
+    )
+    .unwrap();
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+    let result = config::load_config(false);
+    env::set_current_dir(original_dir).unwrap();
+
+    let dns = result.unwrap().dns.unwrap();
+    assert_eq!(dns.name, "k3s.example.com");
+    assert_eq!(dns.ttl, 300);
+    match dns.provider {
+        config::DnsProvider::Rfc2136 { server, key_file, .. } => {
+            assert_eq!(server, "ns1.example.com");
+            // Relative to the config file
+            let key_file = key_file.unwrap();
+            assert!(key_file.is_absolute());
+            assert!(key_file.ends_with("keys/update.key"));
+        }
+        other => panic!("unexpected provider {:?}", other),
+    }
+}
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_monitor_logs() {
+    let tfvars = load_fixture("minimal_terraform.tfvars");
+    let (temp_dir, _) = create_temp_terraform_dir(&tfvars);
+    std::fs::write(
+        temp_dir.path().join("im-deploy.toml"),
+        "[monitor]\nserver_log = \"/var/log/k3s-init.log\"\n\n[monitor.phases.argocd]\nlog_file = \"/opt/argocd/install.log\"\nsuccess_marker = \"argocd ready\"\n",
+    )
+    .unwrap();
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+    let result = config::load_config(false);
+    env::set_current_dir(&original_dir).unwrap();
+
+    let logs = result.unwrap().monitor_logs;
+    assert_eq!(logs.server_log, "/var/log/k3s-init.log");
+    let argocd = logs.phase(MonitorPhase::Argocd).unwrap();
+    assert_eq!(argocd.log_file, "/opt/argocd/install.log");
+    assert_eq!(argocd.log_name(), "install.log");
+    assert_eq!(argocd.success_marker, "argocd ready");
+    // Unset fields and other phases keep the defaults
+    assert_eq!(argocd.start_marker, "Installing ArgoCD...");
+    assert_eq!(logs.phase(MonitorPhase::Gpu).unwrap().log_file, "/var/log/gpu-operator-install.log");
+
+    // Only log-driven phases can be configured
+    std::fs::write(temp_dir.path().join("im-deploy.toml"), "[monitor.phases.nodes]\nlog_file = \"/tmp/x.log\"\n").unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+    let result = config::load_config(false);
+    env::set_current_dir(original_dir).unwrap();
+    assert!(result.unwrap_err().to_string().contains("monitor.phases.nodes"));
+}
+
+#[test]
+#[serial_test::serial]
+fn test_load_config_tailscale_hostname_prefix() {
+    let tfvars = r#"
+cluster_name = "demo"
+user_name = "user"
+user_password = "pass"
+tenant_name = "project"
+enable_tailscale = true
+tailscale_api_key = "tskey-test"
+tailscale_tailnet = "myorg.ts.net"
+"#;
+    let (temp_dir, _) = create_temp_terraform_dir(tfvars);
+    let prefixed = format!("{}tailscale_hostname_prefix = \"k3s\"\n", tfvars);
+    let (prefixed_dir, _) = create_temp_terraform_dir(&prefixed);
+
+    let original_dir = env::current_dir().unwrap();
+    env::set_current_dir(temp_dir.path()).unwrap();
+    let default_prefix = config::load_config(false);
+    env::set_current_dir(prefixed_dir.path()).unwrap();
+    let tfvar_prefix = config::load_config(false);
+    env::set_current_dir(original_dir).unwrap();
+
+    // The module gets `<cluster>-openstack` as its cluster name and names the nodes after it
+    assert_eq!(default_prefix.unwrap().tailscale.unwrap().hostname_prefix, "demo-openstack");
+    assert_eq!(tfvar_prefix.unwrap().tailscale.unwrap().hostname_prefix, "k3s");
+}
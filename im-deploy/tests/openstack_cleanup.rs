@@ -0,0 +1,353 @@
+use im_deploy::domain::inventory::ResourceKind;
+use im_deploy::openstack::{snapshots_to_prune, ClusterScope, FloatingIpScope, OpenStackClient, ServiceEndpoints};
+use mockito::{Matcher, Mock, Server, ServerGuard};
+use serde_json::json;
+
+const CLUSTER: ClusterScope = ClusterScope {
+    name: "demo",
+    network_id: Some("net-demo"),
+};
+
+fn client(server: &ServerGuard) -> OpenStackClient {
+    let endpoints = ServiceEndpoints {
+        neutron: format!("{}/v2.0", server.url()),
+        octavia: Some(format!("{}/lb/v2.0", server.url())),
+        nova: format!("{}/compute/v2.1", server.url()),
+        glance: Some(format!("{}/image/v2", server.url())),
+        cinder: Some(format!("{}/volume/v3/p1", server.url())),
+        ..Default::default()
+    };
+    OpenStackClient::with_endpoints(endpoints, "token", Some("p1")).unwrap()
+}
+
+fn list(server: &mut ServerGuard, path: &str, body: serde_json::Value) -> Mock {
+    server
+        .mock("GET", path)
+        .match_query(Matcher::Any)
+        .match_header("X-Auth-Token", "token")
+        .with_header("content-type", "application/json")
+        .with_body(body.to_string())
+        .create()
+}
+
+fn delete(server: &mut ServerGuard, path: &str, hits: usize) -> Mock {
+    server.mock("DELETE", path).with_status(204).expect(hits).create()
+}
+
+fn mock_floating_ips(server: &mut ServerGuard) {
+    list(
+        server,
+        "/v2.0/floatingips",
+        json!({"floatingips": [
+            {"id": "fip-service", "floating_ip_address": "203.0.113.1", "status": "DOWN", "port_id": null,
+             "project_id": "p1", "description": "Floating IP for Kubernetes external service default/web from cluster demo"},
+            {"id": "fip-tagged", "floating_ip_address": "203.0.113.2", "status": "DOWN", "port_id": null,
+             "project_id": "p1", "tags": ["cluster=demo"]},
+            {"id": "fip-other-cluster", "floating_ip_address": "203.0.113.3", "status": "DOWN", "port_id": null,
+             "project_id": "p1", "tags": ["cluster=prod"], "description": "from cluster demo"},
+            {"id": "fip-unrelated", "floating_ip_address": "203.0.113.4", "status": "DOWN", "port_id": null,
+             "project_id": "p1", "description": ""},
+            {"id": "fip-attached", "floating_ip_address": "203.0.113.5", "status": "ACTIVE", "port_id": "port-1",
+             "project_id": "p1", "description": "from cluster demo"},
+            {"id": "fip-other-project", "floating_ip_address": "203.0.113.6", "status": "DOWN", "port_id": null,
+             "project_id": "p2", "description": "from cluster demo"}
+        ]}),
+    );
+}
+
+fn mock_ports(server: &mut ServerGuard) {
+    list(
+        server,
+        "/v2.0/ports",
+        json!({"ports": [
+            {"id": "port-lb", "name": "octavia-lb-vrrp-1", "status": "DOWN", "device_owner": "Octavia",
+             "device_id": "", "network_id": "net-demo", "project_id": "p1"},
+            {"id": "port-lb-elsewhere", "name": "octavia-lb-vrrp-2", "status": "DOWN", "device_owner": "Octavia",
+             "device_id": "", "network_id": "net-prod", "project_id": "p1"},
+            {"id": "port-server", "name": "demo-openstack-server-1", "status": "ACTIVE", "device_owner": "compute:nova",
+             "device_id": "vm-1", "network_id": "net-demo", "project_id": "p1"}
+        ]}),
+    );
+}
+
+fn mock_security_groups(server: &mut ServerGuard) {
+    list(
+        server,
+        "/v2.0/security-groups",
+        json!({"security_groups": [
+            {"id": "sg-service", "name": "lb-sg-1234", "project_id": "p1",
+             "description": "Security Group for default/web Service LoadBalancer in cluster demo"},
+            {"id": "sg-service-prod", "name": "lb-sg-5678", "project_id": "p1",
+             "description": "Security Group for default/web Service LoadBalancer in cluster prod"},
+            {"id": "sg-server", "name": "demo-server", "project_id": "p1", "description": ""},
+            {"id": "sg-tagged-prod", "name": "demo-agent", "project_id": "p1", "description": "", "tags": ["cluster=prod"]},
+            {"id": "sg-default", "name": "default", "project_id": "p1", "description": "Default security group"}
+        ]}),
+    );
+}
+
+#[test]
+fn test_cleanup_after_destroy_deletes_only_cluster_resources() {
+    let mut server = Server::new();
+    mock_floating_ips(&mut server);
+    mock_ports(&mut server);
+    mock_security_groups(&mut server);
+
+    let deleted = [
+        delete(&mut server, "/v2.0/floatingips/fip-service", 1),
+        delete(&mut server, "/v2.0/floatingips/fip-tagged", 1),
+        delete(&mut server, "/v2.0/ports/port-lb", 1),
+        delete(&mut server, "/v2.0/security-groups/sg-service", 1),
+        delete(&mut server, "/v2.0/security-groups/sg-server", 1),
+    ];
+    let kept = [
+        delete(&mut server, "/v2.0/floatingips/fip-other-cluster", 0),
+        delete(&mut server, "/v2.0/floatingips/fip-unrelated", 0),
+        delete(&mut server, "/v2.0/floatingips/fip-attached", 0),
+        delete(&mut server, "/v2.0/floatingips/fip-other-project", 0),
+        delete(&mut server, "/v2.0/ports/port-lb-elsewhere", 0),
+        delete(&mut server, "/v2.0/ports/port-server", 0),
+        delete(&mut server, "/v2.0/security-groups/sg-service-prod", 0),
+        delete(&mut server, "/v2.0/security-groups/sg-tagged-prod", 0),
+        delete(&mut server, "/v2.0/security-groups/sg-default", 0),
+    ];
+
+    client(&server).cleanup_after_destroy(&CLUSTER, FloatingIpScope::Cluster).unwrap();
+
+    for mock in deleted.iter().chain(&kept) {
+        mock.assert();
+    }
+}
+
+#[test]
+fn test_all_orphans_scope_includes_foreign_floating_ips() {
+    let mut server = Server::new();
+    mock_floating_ips(&mut server);
+    list(&mut server, "/v2.0/ports", json!({"ports": []}));
+    list(&mut server, "/v2.0/security-groups", json!({"security_groups": []}));
+    for path in ["/lb/v2.0/lbaas/loadbalancers", "/lb/v2.0/lbaas/listeners", "/lb/v2.0/lbaas/pools"] {
+        let collection = path.rsplit('/').next().unwrap();
+        list(&mut server, path, json!({ collection: [] }));
+    }
+    list(&mut server, "/lb/v2.0/lbaas/healthmonitors", json!({"healthmonitors": []}));
+    let no_deletes = server.mock("DELETE", Matcher::Any).expect(0).create();
+
+    let plan = client(&server)
+        .deletion_plan(&ClusterScope { network_id: None, ..CLUSTER }, Some(FloatingIpScope::AllOrphans))
+        .unwrap();
+
+    let fips: Vec<&str> = plan
+        .iter()
+        .filter(|item| item.kind == ResourceKind::FloatingIp)
+        .map(|item| item.id.as_str())
+        .collect();
+    assert_eq!(fips, ["fip-service", "fip-tagged", "fip-other-cluster", "fip-unrelated"]);
+    no_deletes.assert();
+}
+
+#[test]
+fn test_inventory_lists_only_cluster_floating_ips() {
+    let mut server = Server::new();
+    list(&mut server, "/compute/v2.1/servers/detail", json!({"servers": []}));
+    list(&mut server, "/volume/v3/p1/volumes/detail", json!({"volumes": []}));
+    list(&mut server, "/lb/v2.0/lbaas/loadbalancers", json!({"loadbalancers": []}));
+    mock_ports(&mut server);
+    mock_floating_ips(&mut server);
+    mock_security_groups(&mut server);
+
+    let inventory = client(&server).inventory("demo", Some("net-demo")).unwrap();
+
+    let fips: Vec<&str> = inventory
+        .items
+        .iter()
+        .filter(|item| item.kind == ResourceKind::FloatingIp)
+        .map(|item| item.id.as_str())
+        .collect();
+    assert_eq!(fips, ["fip-service", "fip-tagged"]);
+    assert!(inventory.unchecked.is_empty(), "{:?}", inventory.unchecked);
+}
+
+#[test]
+fn test_client_from_mock_catalog() {
+    let mut server = Server::new();
+    let token = json!({"token": {
+        "project": {"id": "p1"},
+        "catalog": [
+            {"type": "network", "endpoints": [
+                {"url": format!("{}/network", server.url()), "interface": "public", "region_id": "RegionOne"},
+                {"url": "http://unreachable.invalid:9696", "interface": "public", "region_id": "RegionTwo"}
+            ]},
+            {"type": "load-balancer", "endpoints": [
+                {"url": format!("{}/lb", server.url()), "interface": "public", "region_id": "RegionOne"}
+            ]},
+            {"type": "compute", "endpoints": [
+                {"url": format!("{}/compute/v2.1", server.url()), "interface": "public", "region_id": "RegionOne"}
+            ]}
+        ]
+    }});
+    list(
+        &mut server,
+        "/network/v2.0/security-groups",
+        json!({"security_groups": [
+            {"id": "sg-server", "name": "demo-server", "project_id": "p1", "description": ""},
+            {"id": "sg-foreign", "name": "demo-agent", "project_id": "p2", "description": ""}
+        ]}),
+    );
+    let deleted = delete(&mut server, "/network/v2.0/security-groups/sg-server", 1);
+    let foreign = delete(&mut server, "/network/v2.0/security-groups/sg-foreign", 0);
+
+    let client = OpenStackClient::from_catalog(&token.to_string(), "token", &server.url(), "RegionOne", "public").unwrap();
+    assert_eq!(client.project_label(), "p1 (p1)");
+    client.cleanup_security_groups(&CLUSTER).unwrap();
+
+    deleted.assert();
+    foreign.assert();
+}
+
+#[test]
+fn test_delete_fault_is_reported_not_fatal() {
+    let mut server = Server::new();
+    list(
+        &mut server,
+        "/v2.0/floatingips",
+        json!({"floatingips": [
+            {"id": "fip-tagged", "floating_ip_address": "203.0.113.2", "status": "DOWN", "port_id": null,
+             "project_id": "p1", "tags": ["cluster=demo"]}
+        ]}),
+    );
+    let rejected = server
+        .mock("DELETE", "/v2.0/floatingips/fip-tagged")
+        .with_status(409)
+        .with_header("x-openstack-request-id", "req-123")
+        .with_body(r#"{"NeutronError": {"type": "Conflict", "message": "Floating IP is in use", "detail": ""}}"#)
+        .expect(1)
+        .create();
+    list(&mut server, "/v2.0/ports", json!({"ports": []}));
+    list(&mut server, "/v2.0/security-groups", json!({"security_groups": []}));
+
+    client(&server).cleanup_after_destroy(&CLUSTER, FloatingIpScope::Cluster).unwrap();
+    rejected.assert();
+}
+
+#[test]
+fn test_security_group_in_use_is_not_a_failed_deletion() {
+    let mut server = Server::new();
+    list(
+        &mut server,
+        "/v2.0/security-groups",
+        json!({"security_groups": [
+            {"id": "sg-server", "name": "demo-server", "project_id": "p1", "description": ""},
+            {"id": "sg-agent", "name": "demo-agent", "project_id": "p1", "description": ""}
+        ]}),
+    );
+    let in_use = server
+        .mock("DELETE", "/v2.0/security-groups/sg-server")
+        .with_status(409)
+        .with_body(r#"{"NeutronError": {"type": "SecurityGroupInUse", "message": "in use", "detail": ""}}"#)
+        .expect(1)
+        .create();
+    let broken = server.mock("DELETE", "/v2.0/security-groups/sg-agent").with_status(500).expect(1).create();
+    list(&mut server, "/v2.0/ports", json!({"ports": []}));
+    list(&mut server, "/v2.0/security-group-rules", json!({"security_group_rules": []}));
+
+    let client = client(&server);
+    client.cleanup_security_groups(&CLUSTER).unwrap();
+
+    in_use.assert();
+    broken.assert();
+    assert_eq!(client.failed_deletions(), 1);
+}
+
+#[test]
+fn test_snapshot_labels_node_and_prunes_volume_snapshots() {
+    let mut server = Server::new();
+    list(
+        &mut server,
+        "/compute/v2.1/servers/detail",
+        json!({"servers": [{"id": "vm-0", "name": "demo-server-0", "status": "ACTIVE"}]}),
+    );
+    let create = server
+        .mock("POST", "/compute/v2.1/servers/vm-0/action")
+        .match_body(Matcher::PartialJson(json!({"createImage": {
+            "name": "demo-server-0-snapshot-1",
+            "metadata": {"im_deploy_cluster": "demo", "im_deploy_node": "demo-server-0"}
+        }})))
+        .with_status(202)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"image_id": "img-new"}"#)
+        .expect(1)
+        .create();
+    list(
+        &mut server,
+        "/image/v2/images",
+        json!({"images": [
+            {"id": "img-new", "name": "demo-server-0-snapshot-1", "status": "queued",
+             "created_at": "2025-01-02T00:00:00Z", "im_deploy_cluster": "demo", "im_deploy_node": "demo-server-0"},
+            {"id": "img-old", "name": "demo-server-0-snapshot-0", "status": "active",
+             "created_at": "2025-01-01T00:00:00Z", "im_deploy_cluster": "demo", "im_deploy_node": "demo-server-0",
+             "block_device_mapping": "[{\"snapshot_id\": \"vs-old\"}]"},
+            {"id": "img-base", "name": "ubuntu-24.04", "status": "active", "created_at": "2024-06-01T00:00:00Z"}
+        ]}),
+    );
+    let deleted = [
+        delete(&mut server, "/image/v2/images/img-old", 1),
+        delete(&mut server, "/volume/v3/p1/snapshots/vs-old", 1),
+    ];
+
+    let client = client(&server);
+    let id = client.create_snapshot("demo", "demo-server-0", "demo-server-0-snapshot-1").unwrap();
+    assert_eq!(id, "img-new");
+
+    let snapshots = client.snapshots("demo").unwrap();
+    let ids: Vec<&str> = snapshots.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, ["img-old", "img-new"]);
+    for snapshot in snapshots_to_prune(&snapshots, 1) {
+        client.delete_snapshot(snapshot).unwrap();
+    }
+
+    create.assert();
+    for mock in &deleted {
+        mock.assert();
+    }
+}
+
+#[test]
+fn test_rebuild_server_from_snapshot() {
+    let mut server = Server::new();
+    list(
+        &mut server,
+        "/compute/v2.1/servers/detail",
+        json!({"servers": [{"id": "vm-1", "name": "demo-openstack-server-1", "status": "ACTIVE"}]}),
+    );
+    let rebuild = server
+        .mock("POST", "/compute/v2.1/servers/vm-1/action")
+        .match_body(Matcher::Json(json!({"rebuild": {"imageRef": "snap-1"}})))
+        .with_status(202)
+        .expect(1)
+        .create();
+    list(
+        &mut server,
+        "/compute/v2.1/servers/vm-1",
+        json!({"server": {"id": "vm-1", "name": "demo-openstack-server-1", "status": "ACTIVE"}}),
+    );
+
+    client(&server)
+        .rebuild_server("demo-openstack-server-1", "snap-1", std::time::Duration::from_secs(60))
+        .unwrap();
+    rebuild.assert();
+}
+
+#[test]
+fn test_load_balancer_cleanup_skipped_without_octavia() {
+    let mut server = Server::new();
+    let endpoints = ServiceEndpoints {
+        neutron: format!("{}/v2.0", server.url()),
+        nova: format!("{}/compute/v2.1", server.url()),
+        ..Default::default()
+    };
+    let client = OpenStackClient::with_endpoints(endpoints, "token", Some("p1")).unwrap();
+    let untouched = server.mock("GET", Matcher::Any).expect(0).create();
+
+    client.cleanup_before_destroy("net-demo", "demo").unwrap();
+    untouched.assert();
+}
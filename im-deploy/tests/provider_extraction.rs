@@ -0,0 +1,126 @@
+mod common;
+
+use common::{load_fixture, mock_terraform_output, mock_terraform_output_no_tailscale};
+use im_deploy::domain::cluster::{cloud_providers_from_outputs, ClusterInfo};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+/// Terraform outputs in `fixtures/providers/<case>.json`, extracted providers in
+/// `<case>.expected.json`. Run with UPDATE_GOLDEN=1 to rewrite the expected files.
+const CASES: [&str; 12] = [
+    "../terraform_outputs",
+    "../terraform_outputs_no_tailscale",
+    "short_hostnames",
+    "unordered_hostnames",
+    "custom_hostnames",
+    "null_bastion",
+    "zero_agents",
+    "openstack_disabled",
+    "role_ssh_users",
+    "dual_stack",
+    "public_ips",
+    "multi_provider",
+];
+
+fn check_golden(case: &str) {
+    let outputs: Value = serde_json::from_str(&load_fixture(&format!("providers/{}.json", case))).unwrap();
+    let actual = serde_json::to_string_pretty(&cloud_providers_from_outputs(&outputs)).unwrap() + "\n";
+
+    let expected_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/providers")
+        .join(format!("{}.expected.json", case.trim_start_matches("../")));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&expected_path, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&expected_path)
+        .unwrap_or_else(|_| panic!("Missing {:?}; run with UPDATE_GOLDEN=1", expected_path));
+    assert_eq!(actual, expected, "providers extracted from {} changed", case);
+}
+
+#[test]
+fn test_provider_extraction_golden() {
+    for case in CASES {
+        check_golden(case);
+    }
+}
+
+#[test]
+fn test_short_hostname_list_is_not_shifted() {
+    let outputs: Value = serde_json::from_str(&load_fixture("providers/short_hostnames.json")).unwrap();
+    let providers = cloud_providers_from_outputs(&outputs);
+    let hostnames: Vec<(&str, Option<&str>)> = providers[0]
+        .servers
+        .iter()
+        .map(|s| (s.name.as_str(), s.tailscale_hostname.as_deref()))
+        .collect();
+
+    assert_eq!(
+        hostnames,
+        [
+            ("k3s-server-0", Some("demo-server-0")),
+            ("k3s-server-1", None),
+            ("k3s-server-2", Some("demo-server-2")),
+            ("k3s-agent-0", None),
+            ("k3s-agent-1", Some("demo-agent-1")),
+        ]
+    );
+}
+
+#[test]
+fn test_multiple_providers_keep_their_own_hostnames() {
+    let outputs: Value = serde_json::from_str(&load_fixture("providers/multi_provider.json")).unwrap();
+    let providers = cloud_providers_from_outputs(&outputs);
+    let hosts: Vec<(&str, &str, Option<&str>, Option<&str>)> = providers
+        .iter()
+        .flat_map(|provider| {
+            provider.servers.iter().map(move |s| {
+                (provider.name.as_str(), s.name.as_str(), s.tailscale_hostname.as_deref(), provider.bastion_ip.as_deref())
+            })
+        })
+        .collect();
+
+    assert_eq!(
+        hosts,
+        [
+            ("OpenStack", "k3s-server-0", Some("demo-server-0"), Some("203.0.113.5")),
+            ("OpenStack", "k3s-agent-0", Some("demo-agent-0"), Some("203.0.113.5")),
+            ("OpenStack", "k3s-agent-1", Some("demo-agent-1"), Some("203.0.113.5")),
+            ("hetzner", "k3s-hetzner-server-0", Some("demo-hetzner-server-0"), None),
+            ("hetzner", "k3s-hetzner-agent-0", Some("demo-hetzner-agent-0"), None),
+        ]
+    );
+}
+
+#[test]
+fn test_cluster_info_from_terraform_outputs() {
+    let outputs: Value = serde_json::from_str(&mock_terraform_output()).unwrap();
+    let info = ClusterInfo::from_terraform_outputs(&outputs);
+
+    assert_eq!(info.cluster_name.as_deref(), Some("test-cluster"));
+    assert_eq!(info.primary_api_endpoint.as_deref(), Some("https://5.6.7.8:6443"));
+    assert!(info.gpu_enabled);
+    assert!(info.argocd_enabled);
+    assert_eq!(info.total_expected_nodes(), 5);
+
+    let outputs: Value = serde_json::from_str(&mock_terraform_output_no_tailscale()).unwrap();
+    let info = ClusterInfo::from_terraform_outputs(&outputs);
+    assert!(!info.gpu_enabled);
+    assert!(info.primary_provider().unwrap().servers.iter().all(|s| s.tailscale_hostname.is_none()));
+
+    assert_eq!(info.network_id.as_deref(), Some("net-67890"));
+    assert_eq!(info.longhorn_backup_container, None);
+
+    let info = ClusterInfo::from_terraform_outputs(&serde_json::json!({}));
+    assert!(!info.deployed);
+    assert!(info.providers.is_empty());
+    assert_eq!(info.all_server_ips, None);
+    assert_eq!(info.primary_api_endpoint, None);
+    assert_eq!(info.kubeconfig_source, None);
+
+    let info = ClusterInfo::from_terraform_outputs(&serde_json::json!({
+        "kubeconfig_source": {"value": "cp-0.tailnet.ts.net"}
+    }));
+    assert_eq!(info.kubeconfig_source.as_deref(), Some("cp-0.tailnet.ts.net"));
+}
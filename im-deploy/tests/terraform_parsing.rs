@@ -1,6 +1,6 @@
 mod common;
 
-use common::{load_fixture, mock_terraform_output, mock_terraform_output_no_tailscale};
+use common::{mock_terraform_output, mock_terraform_output_no_tailscale};
 use serde_json::Value;
 
 #[test]
@@ -26,12 +26,14 @@ module "openstack_k3s" {
   ssh_public_key_path = var.ssh_key_path
   # OpenStack authentication
   openstack_auth = {
-    auth_url    = var.openstack_auth_url
-    username    = var.user_name
-    password    = var.user_password
-    tenant_name = var.tenant_name
-    region      = var.openstack_region
-    cacert_file = var.openstack_cacert_file
+    auth_url       = var.openstack_auth_url
+    username       = var.user_name
+    password       = var.user_password
+    tenant_name    = var.tenant_name
+    user_domain    = var.openstack_user_domain
+    project_domain = var.openstack_project_domain
+    region         = var.openstack_region
+    cacert_file    = var.openstack_cacert_file
   }
   # Cluster sizing
   server_count = var.openstack_server_count
@@ -40,36 +42,44 @@ module "openstack_k3s" {
   server_flavor  = var.openstack_server_flavor
   agent_flavor   = var.openstack_agent_flavor
   bastion_flavor = var.openstack_bastion_flavor
+  # Images and their login users per role; the bastion keeps the default Ubuntu image
+  server_image_name = var.openstack_server_image_name
+  agent_image_name  = var.openstack_agent_image_name
+  server_ssh_user   = var.openstack_server_ssh_user
+  agent_ssh_user    = var.openstack_agent_ssh_user
   # Network configuration
   network_cidr     = var.openstack_network_cidr
   dns_servers      = var.openstack_dns_servers
   floating_ip_pool = var.openstack_floating_ip_pool
+  resource_tags    = var.openstack_resource_tags
   # Security configuration
   external_ssh_cidrs = var.external_ssh_cidrs
   external_api_cidrs = var.external_api_cidrs
   # Feature flags
-  enable_bastion       = var.enable_bastion
-  enable_load_balancer = var.enable_load_balancer
+  enable_bastion           = var.enable_bastion
+  enable_load_balancer     = var.enable_load_balancer
+  enable_node_floating_ips = var.enable_node_floating_ips
 
   # Cloud controller configuration
   insecure    = var.openstack_insecure
   lb_provider = var.openstack_lb_provider
 
   # Tailscale configuration
-  enable_tailscale             = var.enable_tailscale
-  tailscale_api_key            = var.tailscale_api_key
-  tailscale_tailnet            = var.tailscale_tailnet
-  tailscale_hostname_prefix    = var.tailscale_hostname_prefix
-  tailscale_key_expiry         = var.tailscale_key_expiry
-  tailscale_ip_update_interval = var.tailscale_ip_update_interval
+  enable_tailscale              = var.enable_tailscale
+  tailscale_api_key             = var.tailscale_api_key
+  tailscale_tailnet             = var.tailscale_tailnet
+  tailscale_hostname_prefix     = var.tailscale_hostname_prefix
+  tailscale_key_expiry          = var.tailscale_key_expiry
+  tailscale_ip_update_interval  = var.tailscale_ip_update_interval
+  tailscale_subnet_router       = var.tailscale_subnet_router
   tailscale_oauth_client_id     = var.tailscale_oauth_client_id
   tailscale_oauth_client_secret = var.tailscale_oauth_client_secret
 
   # Cloudflare Tunnel
-  enable_cloudflare_tunnel  = var.enable_cloudflare_tunnel
-  cloudflare_account_id     = var.cloudflare_account_id
-  cloudflare_tunnel_id      = var.cloudflare_tunnel_id
-  cloudflare_tunnel_secret  = var.cloudflare_tunnel_secret
+  enable_cloudflare_tunnel = var.enable_cloudflare_tunnel
+  cloudflare_account_id    = var.cloudflare_account_id
+  cloudflare_tunnel_id     = var.cloudflare_tunnel_id
+  cloudflare_tunnel_secret = var.cloudflare_tunnel_secret
 
   # Longhorn distributed storage
   enable_longhorn             = var.enable_longhorn
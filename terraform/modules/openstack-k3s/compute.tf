@@ -5,7 +5,7 @@
 resource "openstack_compute_instance_v2" "k3s_server" {
   count           = var.server_count
   name            = "${local.resource_prefix}-server-${count.index}"
-  image_name      = local.image_name
+  image_name      = local.server_image_name
   flavor_name     = var.server_flavor
   key_pair        = openstack_compute_keypair_v2.keypair.name
   security_groups = [openstack_networking_secgroup_v2.server.name]
@@ -17,6 +17,7 @@ resource "openstack_compute_instance_v2" "k3s_server" {
 
   user_data = templatefile("${path.root}/templates/k3s-server.tpl", {
     is_first_server                = count.index == 0
+    ssh_user                       = var.server_ssh_user
     token                          = var.k3s_token
     first_server_ip                = count.index == 0 ? "" : openstack_networking_port_v2.server_port[0].all_fixed_ips[0]
     floating_ip                    = var.enable_load_balancer ? openstack_networking_floatingip_v2.fip_lb[0].address : ""
@@ -24,6 +25,8 @@ resource "openstack_compute_instance_v2" "k3s_server" {
     cloud_config_auth_url          = local.auth_url
     cloud_config_username          = local.username
     cloud_config_password          = local.password
+    cloud_config_user_domain       = local.user_domain
+    cloud_config_project_domain    = local.project_domain
     cloud_config_region            = local.region
     cloud_config_subnet_id         = openstack_networking_subnet_v2.subnet.id
     cloud_config_floating_net      = data.openstack_networking_network_v2.fip_network.id
@@ -44,12 +47,21 @@ resource "openstack_compute_instance_v2" "k3s_server" {
     tailscale_script = var.enable_tailscale ? templatefile("${path.root}/templates/tailscale-install.tpl", {
       tailscale_auth_key = tailscale_tailnet_key.server[count.index].key
       tailscale_hostname = "${local.tailscale_prefix}-server-${count.index}"
+      advertise_routes   = var.tailscale_subnet_router && count.index == 0 ? local.subnet_cidr : ""
     }) : ""
     tailscale_ip_updater_files = var.enable_tailscale ? templatefile("${path.root}/templates/tailscale-ip-updater.tpl", {
       tailscale_ip_update_interval = var.tailscale_ip_update_interval
       k3s_service                  = "k3s"
     }) : ""
   })
+
+  # user_data only runs on first boot, so a new k3s_token (im-deploy rotate-token) must not
+  # rebuild nodes, and a node rebuilt from a snapshot (im-deploy restore-node) reports the
+  # snapshot as image_name. image_id is not set here, so its drift never shows in a plan.
+  # To move nodes to a new image, replace them with terraform apply -replace.
+  lifecycle {
+    ignore_changes = [user_data, image_name]
+  }
 }
 ###############################################################################
 # K3s Agent Instances (Workers)
@@ -57,7 +69,7 @@ resource "openstack_compute_instance_v2" "k3s_server" {
 resource "openstack_compute_instance_v2" "k3s_agent" {
   count           = var.agent_count
   name            = "${local.resource_prefix}-agent-${count.index}"
-  image_name      = local.image_name
+  image_name      = local.agent_image_name
   flavor_name     = var.agent_flavor
   key_pair        = openstack_compute_keypair_v2.keypair.name
   security_groups = [openstack_networking_secgroup_v2.agent.name]
@@ -76,12 +88,18 @@ resource "openstack_compute_instance_v2" "k3s_agent" {
     tailscale_script = var.enable_tailscale ? templatefile("${path.root}/templates/tailscale-install.tpl", {
       tailscale_auth_key = tailscale_tailnet_key.agent[count.index].key
       tailscale_hostname = "${local.tailscale_prefix}-agent-${count.index}"
+      advertise_routes   = ""
     }) : ""
     tailscale_ip_updater_files = var.enable_tailscale ? templatefile("${path.root}/templates/tailscale-ip-updater.tpl", {
       tailscale_ip_update_interval = var.tailscale_ip_update_interval
       k3s_service                  = "k3s-agent"
     }) : ""
   })
+
+  # Same drift as the servers, see k3s_server
+  lifecycle {
+    ignore_changes = [user_data, image_name]
+  }
 }
 ###############################################################################
 # Bastion Host
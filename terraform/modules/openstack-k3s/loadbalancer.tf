@@ -4,6 +4,7 @@ resource "openstack_lb_loadbalancer_v2" "k3s_lb" {
   name          = "${local.resource_prefix}-lb"
   vip_subnet_id = openstack_networking_subnet_v2.subnet.id
   depends_on    = [openstack_networking_subnet_v2.subnet]
+  tags          = local.resource_tags
 }
 resource "openstack_lb_listener_v2" "k3s_listener" {
   count           = var.enable_load_balancer ? 1 : 0
@@ -53,10 +54,32 @@ resource "openstack_networking_floatingip_v2" "fip_lb" {
   pool       = var.floating_ip_pool
   port_id    = openstack_lb_loadbalancer_v2.k3s_lb[0].vip_port_id
   depends_on = [openstack_networking_router_interface_v2.router_interface]
+  tags       = local.resource_tags
 }
 resource "openstack_networking_floatingip_v2" "fip_bastion" {
   count      = var.enable_bastion ? 1 : 0
   pool       = var.floating_ip_pool
   port_id    = openstack_networking_port_v2.bastion_port[0].id
   depends_on = [openstack_networking_router_interface_v2.router_interface]
+  tags       = local.resource_tags
+}
+resource "openstack_networking_floatingip_v2" "fip_server" {
+  count      = var.enable_node_floating_ips ? var.server_count : 0
+  pool       = var.floating_ip_pool
+  port_id    = openstack_networking_port_v2.server_port[count.index].id
+  depends_on = [openstack_networking_router_interface_v2.router_interface]
+  tags       = local.resource_tags
+}
+# Agents get their port from Nova, so it is looked up through the instance
+data "openstack_networking_port_v2" "agent_port" {
+  count      = var.enable_node_floating_ips ? var.agent_count : 0
+  device_id  = openstack_compute_instance_v2.k3s_agent[count.index].id
+  network_id = openstack_networking_network_v2.network.id
+}
+resource "openstack_networking_floatingip_v2" "fip_agent" {
+  count      = var.enable_node_floating_ips ? var.agent_count : 0
+  pool       = var.floating_ip_pool
+  port_id    = data.openstack_networking_port_v2.agent_port[count.index].id
+  depends_on = [openstack_networking_router_interface_v2.router_interface]
+  tags       = local.resource_tags
 }
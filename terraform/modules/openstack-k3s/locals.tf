@@ -5,19 +5,25 @@ locals {
   # Network configuration  
   subnet_cidr = var.network_cidr
   # OpenStack-specific settings from auth object
-  auth_url    = var.openstack_auth.auth_url
-  username    = var.openstack_auth.username
-  password    = var.openstack_auth.password
-  tenant_name = var.openstack_auth.tenant_name
-  region      = var.openstack_auth.region
-  cacert_file = var.openstack_auth.cacert_file
+  auth_url       = var.openstack_auth.auth_url
+  username       = var.openstack_auth.username
+  password       = var.openstack_auth.password
+  tenant_name    = var.openstack_auth.tenant_name
+  user_domain    = var.openstack_auth.user_domain
+  project_domain = var.openstack_auth.project_domain
+  region         = var.openstack_auth.region
+  cacert_file    = var.openstack_auth.cacert_file
   # Instance configuration
-  image_name = var.image_name
+  image_name        = var.image_name
+  server_image_name = coalesce(var.server_image_name, var.image_name)
+  agent_image_name  = coalesce(var.agent_image_name, var.image_name)
   # Tags
   common_tags = merge(var.tags, {
     module       = "openstack-k3s"
     cluster_name = var.cluster_name
   })
+  # Neutron/Octavia tags, used by im-deploy to tell this cluster's resources apart in shared projects
+  resource_tags = concat(["cluster=${var.cluster_name}"], var.resource_tags)
 
   # Tailscale configuration
   tailscale_prefix = var.tailscale_hostname_prefix != "" ? var.tailscale_hostname_prefix : var.cluster_name
@@ -2,6 +2,7 @@
 resource "openstack_networking_network_v2" "network" {
   name           = "${local.resource_prefix}-network"
   admin_state_up = true
+  tags           = local.resource_tags
 }
 resource "openstack_networking_subnet_v2" "subnet" {
   name            = "${local.resource_prefix}-subnet"
@@ -9,11 +10,13 @@ resource "openstack_networking_subnet_v2" "subnet" {
   cidr            = local.subnet_cidr
   ip_version      = 4
   dns_nameservers = var.dns_servers
+  tags            = local.resource_tags
 }
 resource "openstack_networking_router_v2" "router" {
   name                = "${local.resource_prefix}-router"
   admin_state_up      = true
   external_network_id = data.openstack_networking_network_v2.fip_network.id
+  tags                = local.resource_tags
 }
 resource "openstack_networking_router_interface_v2" "router_interface" {
   router_id = openstack_networking_router_v2.router.id
@@ -31,6 +34,7 @@ resource "openstack_networking_port_v2" "server_port" {
   fixed_ip {
     subnet_id = openstack_networking_subnet_v2.subnet.id
   }
+  tags = local.resource_tags
 }
 ###############################################################################
 # Port for Bastion Host
@@ -44,4 +48,5 @@ resource "openstack_networking_port_v2" "bastion_port" {
   fixed_ip {
     subnet_id = openstack_networking_subnet_v2.subnet.id
   }
+  tags = local.resource_tags
 }
@@ -19,6 +19,10 @@ output "server_ips" {
   description = "Private IP addresses of server nodes"
   value       = openstack_compute_instance_v2.k3s_server[*].access_ip_v4
 }
+output "server_ipv6s" {
+  description = "IPv6 addresses of server nodes (empty for nodes without one)"
+  value       = openstack_compute_instance_v2.k3s_server[*].access_ip_v6
+}
 output "server_ids" {
   description = "Instance IDs of server nodes"
   value       = openstack_compute_instance_v2.k3s_server[*].id
@@ -27,6 +31,18 @@ output "agent_ips" {
   description = "Private IP addresses of agent nodes"
   value       = openstack_compute_instance_v2.k3s_agent[*].access_ip_v4
 }
+output "agent_ipv6s" {
+  description = "IPv6 addresses of agent nodes (empty for nodes without one)"
+  value       = openstack_compute_instance_v2.k3s_agent[*].access_ip_v6
+}
+output "server_public_ips" {
+  description = "Floating IPs of server nodes (empty without enable_node_floating_ips)"
+  value       = openstack_networking_floatingip_v2.fip_server[*].address
+}
+output "agent_public_ips" {
+  description = "Floating IPs of agent nodes (empty without enable_node_floating_ips)"
+  value       = openstack_networking_floatingip_v2.fip_agent[*].address
+}
 output "agent_ids" {
   description = "Instance IDs of agent nodes"
   value       = openstack_compute_instance_v2.k3s_agent[*].id
@@ -49,7 +65,7 @@ output "security_group_ids" {
 }
 output "kubeconfig_command" {
   description = "Command to fetch kubeconfig from the first server"
-  value       = var.enable_bastion ? "ssh -J ubuntu@${openstack_networking_floatingip_v2.fip_bastion[0].address} ubuntu@${openstack_compute_instance_v2.k3s_server[0].access_ip_v4} 'sudo cat /etc/rancher/k3s/k3s.yaml'" : null
+  value       = var.enable_bastion ? "ssh -J ubuntu@${openstack_networking_floatingip_v2.fip_bastion[0].address} ${var.server_ssh_user}@${openstack_compute_instance_v2.k3s_server[0].access_ip_v4} 'sudo cat /etc/rancher/k3s/k3s.yaml'" : null
 }
 
 ###############################################################################
@@ -88,16 +104,21 @@ output "tailscale_agent_hostnames" {
   ] : []
 }
 
+output "tailscale_subnet_routes" {
+  description = "Private subnets the first server advertises to the tailnet"
+  value       = var.enable_tailscale && var.tailscale_subnet_router ? [local.subnet_cidr] : []
+}
+
 output "kubeconfig_tailscale_command" {
   description = "Command to fetch kubeconfig via Tailscale SSH (when Tailscale is enabled)"
-  value       = var.enable_tailscale ? "ssh ubuntu@${local.tailscale_prefix}-server-0 'sudo cat /etc/rancher/k3s/k3s.yaml'" : null
+  value       = var.enable_tailscale ? "ssh ${var.server_ssh_user}@${local.tailscale_prefix}-server-0 'sudo cat /etc/rancher/k3s/k3s.yaml'" : null
 }
 
 output "tailscale_ssh_examples" {
   description = "Example SSH commands using Tailscale hostnames"
   value = var.enable_tailscale ? {
-    first_server = "ssh ubuntu@${local.tailscale_prefix}-server-0"
-    first_agent  = var.agent_count > 0 ? "ssh ubuntu@${local.tailscale_prefix}-agent-0" : null
+    first_server = "ssh ${var.server_ssh_user}@${local.tailscale_prefix}-server-0"
+    first_agent  = var.agent_count > 0 ? "ssh ${var.agent_ssh_user}@${local.tailscale_prefix}-agent-0" : null
   } : null
 }
 
@@ -7,12 +7,14 @@ resource "openstack_networking_secgroup_v2" "server" {
   name                 = "${local.resource_prefix}-server"
   description          = "Security group for K3s server nodes"
   delete_default_rules = true
+  tags                 = local.resource_tags
 }
 # Security group for K3s agent nodes (workers)
 resource "openstack_networking_secgroup_v2" "agent" {
   name                 = "${local.resource_prefix}-agent"
   description          = "Security group for K3s agent nodes"
   delete_default_rules = true
+  tags                 = local.resource_tags
 }
 # Security group for bastion host
 resource "openstack_networking_secgroup_v2" "bastion" {
@@ -20,6 +22,7 @@ resource "openstack_networking_secgroup_v2" "bastion" {
   name                 = "${local.resource_prefix}-bastion"
   description          = "Security group for bastion/jump host"
   delete_default_rules = true
+  tags                 = local.resource_tags
 }
 ###############################################################################
 # Egress Rules - Allow all outbound traffic
@@ -55,6 +58,17 @@ resource "openstack_networking_secgroup_rule_v2" "server_ssh_external" {
   remote_ip_prefix  = each.value
   security_group_id = openstack_networking_secgroup_v2.server.id
 }
+# SSH access to agents from external CIDRs, only through their floating IPs
+resource "openstack_networking_secgroup_rule_v2" "agent_ssh_external" {
+  for_each          = var.enable_node_floating_ips ? toset(var.external_ssh_cidrs) : []
+  direction         = "ingress"
+  ethertype         = "IPv4"
+  protocol          = "tcp"
+  port_range_min    = 22
+  port_range_max    = 22
+  remote_ip_prefix  = each.value
+  security_group_id = openstack_networking_secgroup_v2.agent.id
+}
 # K8s API access from external CIDRs
 resource "openstack_networking_secgroup_rule_v2" "server_k8s_api_external" {
   for_each          = toset(var.external_api_cidrs)
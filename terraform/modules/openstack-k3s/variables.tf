@@ -21,12 +21,14 @@ variable "ssh_public_key_path" {
 variable "openstack_auth" {
   description = "OpenStack authentication credentials"
   type = object({
-    auth_url    = string
-    username    = string
-    password    = string
-    tenant_name = string
-    region      = string
-    cacert_file = optional(string, null)
+    auth_url       = string
+    username       = string
+    password       = string
+    tenant_name    = string
+    user_domain    = optional(string, "Default")
+    project_domain = optional(string, "Default")
+    region         = string
+    cacert_file    = optional(string, null)
   })
   sensitive = true
 }
@@ -67,6 +69,26 @@ variable "image_name" {
   type        = string
   default     = "ubuntu-24.04-noble-server-cloud-image-amd64"
 }
+variable "server_image_name" {
+  description = "OpenStack image name for server nodes (defaults to image_name)"
+  type        = string
+  default     = null
+}
+variable "agent_image_name" {
+  description = "OpenStack image name for agent nodes (defaults to image_name)"
+  type        = string
+  default     = null
+}
+variable "server_ssh_user" {
+  description = "Login user of the server image"
+  type        = string
+  default     = "ubuntu"
+}
+variable "agent_ssh_user" {
+  description = "Login user of the agent image"
+  type        = string
+  default     = "ubuntu"
+}
 variable "network_cidr" {
   description = "CIDR block for the private network"
   type        = string
@@ -92,6 +114,11 @@ variable "enable_load_balancer" {
   type        = bool
   default     = true
 }
+variable "enable_node_floating_ips" {
+  description = "Whether to give every node a floating IP, reachable over SSH from external_ssh_cidrs"
+  type        = bool
+  default     = false
+}
 variable "external_ssh_cidrs" {
   description = "CIDR blocks allowed to SSH to servers (0.0.0.0/0 for any)"
   type        = list(string)
@@ -107,6 +134,11 @@ variable "tags" {
   type        = map(string)
   default     = {}
 }
+variable "resource_tags" {
+  description = "Extra Neutron/Octavia tags, in addition to cluster=<cluster_name>"
+  type        = list(string)
+  default     = []
+}
 
 ###############################################################################
 # Tailscale Configuration
@@ -143,6 +175,12 @@ variable "tailscale_key_expiry" {
   default     = 7200
 }
 
+variable "tailscale_subnet_router" {
+  description = "Advertise the cluster subnet from the first server so tailnet devices reach private IPs directly. Set in cloud-init, so only a newly created server 0 advertises it."
+  type        = bool
+  default     = false
+}
+
 variable "tailscale_ip_update_interval" {
   description = "Interval in seconds for checking Tailscale IP changes (default: 300 = 5 minutes)"
   type        = number
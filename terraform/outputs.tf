@@ -1,17 +1,32 @@
 # Aggregated Outputs from All Clouds
+# im-deploy checks this against the outputs it reads; bump it with every change to
+# their names or types and update OUTPUTS_SCHEMA_VERSION in im-deploy to match
+output "outputs_schema_version" {
+  description = "Version of the outputs im-deploy reads"
+  value       = 1
+}
 ###############################################################################
 # OpenStack Outputs
 ###############################################################################
 output "openstack_cluster" {
   description = "OpenStack K3s cluster information"
   value = var.enable_openstack ? {
-    cluster_name       = module.openstack_k3s[0].cluster_name
-    bastion_ip         = module.openstack_k3s[0].bastion_ip
-    loadbalancer_ip    = module.openstack_k3s[0].loadbalancer_ip
-    server_ips         = module.openstack_k3s[0].server_ips
-    agent_ips          = module.openstack_k3s[0].agent_ips
-    network_id         = module.openstack_k3s[0].network_id
-    kubeconfig_command = module.openstack_k3s[0].kubeconfig_command
+    cluster_name            = module.openstack_k3s[0].cluster_name
+    bastion_ip              = module.openstack_k3s[0].bastion_ip
+    loadbalancer_ip         = module.openstack_k3s[0].loadbalancer_ip
+    server_ips              = module.openstack_k3s[0].server_ips
+    agent_ips               = module.openstack_k3s[0].agent_ips
+    server_ipv6s            = module.openstack_k3s[0].server_ipv6s
+    agent_ipv6s             = module.openstack_k3s[0].agent_ipv6s
+    server_public_ips       = module.openstack_k3s[0].server_public_ips
+    agent_public_ips        = module.openstack_k3s[0].agent_public_ips
+    network_id              = module.openstack_k3s[0].network_id
+    tailscale_subnet_routes = module.openstack_k3s[0].tailscale_subnet_routes
+    kubeconfig_command      = module.openstack_k3s[0].kubeconfig_command
+    ssh_users = {
+      server = var.openstack_server_ssh_user
+      agent  = var.openstack_agent_ssh_user
+    }
   } : null
 }
 ###############################################################################
@@ -55,6 +70,11 @@ output "primary_api_endpoint" {
   value       = var.enable_openstack ? "https://${module.openstack_k3s[0].loadbalancer_ip}:6443" : null
 }
 
+output "kubeconfig_source" {
+  description = "Host to fetch the kubeconfig from when it is not the first server"
+  value       = var.kubeconfig_source
+}
+
 ###############################################################################
 # Tailscale Outputs
 ###############################################################################
@@ -15,12 +15,14 @@ terraform {
 
 # OpenStack Provider
 provider "openstack" {
-  user_name   = var.user_name
-  tenant_name = var.tenant_name
-  password    = var.user_password
-  auth_url    = var.openstack_auth_url
-  region      = var.openstack_region
-  cacert_file = var.openstack_cacert_file
+  user_name           = var.user_name
+  user_domain_name    = var.openstack_user_domain
+  tenant_name         = var.tenant_name
+  project_domain_name = var.openstack_project_domain
+  password            = var.user_password
+  auth_url            = var.openstack_auth_url
+  region              = var.openstack_region
+  cacert_file         = var.openstack_cacert_file
 }
 
 # Tailscale Provider
@@ -13,6 +13,8 @@ write_files:
       auth-url=${cloud_config_auth_url}
       username=${cloud_config_username}
       password=${cloud_config_password}
+      user-domain-name=${cloud_config_user_domain}
+      tenant-domain-name=${cloud_config_project_domain}
       region=${cloud_config_region}
       tenant-id=
       domain-id=
@@ -123,15 +125,15 @@ runcmd:
       echo "Waiting for k3s to be ready (attempt $i/30)..." >> /var/log/k3s-server.log
       sleep 5
     done
-  - mkdir -p /home/ubuntu/.kube
+  - mkdir -p /home/${ssh_user}/.kube
   - |
     #!/bin/bash
-    if cp /etc/rancher/k3s/k3s.yaml /home/ubuntu/.kube/config 2>> /var/log/k3s-server.log; then
+    if cp /etc/rancher/k3s/k3s.yaml /home/${ssh_user}/.kube/config 2>> /var/log/k3s-server.log; then
       echo "Kubeconfig copied successfully" >> /var/log/k3s-server.log
     else
       echo "Failed to copy kubeconfig" >> /var/log/k3s-server.log
     fi
-  - chown ubuntu:ubuntu /home/ubuntu/.kube/config
+  - chown ${ssh_user}:${ssh_user} /home/${ssh_user}/.kube/config
   - curl -fsSL -o /tmp/get_helm.sh https://raw.githubusercontent.com/helm/helm/master/scripts/get-helm-3
   - chmod 700 /tmp/get_helm.sh
   - /tmp/get_helm.sh
@@ -19,11 +19,23 @@ for i in $(seq 1 30); do
   sleep 2
 done
 
+%{ if advertise_routes != "" ~}
+log "Enabling IP forwarding for the subnet router..."
+cat > /etc/sysctl.d/99-tailscale.conf <<'EOFSYSCTL'
+net.ipv4.ip_forward = 1
+net.ipv6.conf.all.forwarding = 1
+EOFSYSCTL
+sysctl -p /etc/sysctl.d/99-tailscale.conf >> /var/log/tailscale-setup.log 2>&1
+
+%{ endif ~}
 log "Connecting to Tailscale network..."
 tailscale up \
   --authkey="${tailscale_auth_key}" \
   --hostname="${tailscale_hostname}" \
   --accept-routes=true \
+%{ if advertise_routes != "" ~}
+  --advertise-routes="${advertise_routes}" \
+%{ endif ~}
   --ssh >> /var/log/tailscale-setup.log 2>&1
 
 log "Waiting for Tailscale connection..."
@@ -50,6 +50,16 @@ variable "openstack_region" {
   type        = string
   default     = "RegionOne"
 }
+variable "openstack_user_domain" {
+  description = "Keystone domain of the OpenStack user"
+  type        = string
+  default     = "Default"
+}
+variable "openstack_project_domain" {
+  description = "Keystone domain of the OpenStack project"
+  type        = string
+  default     = "Default"
+}
 variable "openstack_cacert_file" {
   description = "Path to OpenStack CA certificate file"
   type        = string
@@ -65,6 +75,11 @@ variable "openstack_agent_count" {
   type        = number
   default     = 3
 }
+variable "kubeconfig_source" {
+  description = "Host im-deploy fetches the kubeconfig from and runs kubectl on, for control planes not managed here (defaults to the first server)"
+  type        = string
+  default     = null
+}
 variable "openstack_server_flavor" {
   description = "OpenStack flavor for server nodes"
   type        = string
@@ -80,6 +95,26 @@ variable "openstack_bastion_flavor" {
   type        = string
   default     = "m1.small"
 }
+variable "openstack_server_image_name" {
+  description = "OpenStack image for server nodes (null for the module default). Only new nodes pick up a change; replace existing ones with terraform apply -replace."
+  type        = string
+  default     = null
+}
+variable "openstack_agent_image_name" {
+  description = "OpenStack image for agent nodes (null for the module default). Only new nodes pick up a change; replace existing ones with terraform apply -replace."
+  type        = string
+  default     = null
+}
+variable "openstack_server_ssh_user" {
+  description = "Login user of the server image"
+  type        = string
+  default     = "ubuntu"
+}
+variable "openstack_agent_ssh_user" {
+  description = "Login user of the agent image"
+  type        = string
+  default     = "ubuntu"
+}
 variable "openstack_network_cidr" {
   description = "CIDR for OpenStack private network"
   type        = string
@@ -95,6 +130,11 @@ variable "openstack_floating_ip_pool" {
   type        = string
   default     = "ext_net"
 }
+variable "openstack_resource_tags" {
+  description = "Extra tags for OpenStack network and load balancer resources (cluster=<name> is always set)"
+  type        = list(string)
+  default     = []
+}
 ###############################################################################
 # Common Configuration
 ###############################################################################
@@ -108,6 +148,11 @@ variable "enable_load_balancer" {
   type        = bool
   default     = true
 }
+variable "enable_node_floating_ips" {
+  description = "Give every node a floating IP for direct SSH without bastion or Tailscale"
+  type        = bool
+  default     = false
+}
 variable "external_ssh_cidrs" {
   description = "CIDR blocks allowed to SSH (0.0.0.0/0 = any, TODO: restrict)"
   type        = list(string)
@@ -181,6 +226,12 @@ variable "tailscale_key_expiry" {
   }
 }
 
+variable "tailscale_subnet_router" {
+  description = "Advertise the OpenStack subnet from the first server as a Tailscale subnet router. Approve the route in the admin console or with autoApprovers in the ACL. Only a newly created server 0 picks this up; replace an existing one (terraform apply -replace)."
+  type        = bool
+  default     = false
+}
+
 variable "tailscale_ip_update_interval" {
   description = "Interval in seconds for checking Tailscale IP changes (for ephemeral reconnects). Default: 300 (5 minutes)."
   type        = number